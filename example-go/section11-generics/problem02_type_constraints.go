@@ -0,0 +1,27 @@
+// Problem: Type Constraints Basic Practice
+// Topic: Type Constraints
+// Difficulty: 1
+// learn:requires go>=1.21
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a basic level problem focusing on type constraints
+// Section: Generics with type parameters and constraints
+// Syntax elements to practice: constraint interfaces, comparable, cmp.Ordered, ~ underlying types
+
+    fmt.Println("Problem 2: Type Constraints - Basic Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: constraint interfaces, comparable, cmp.Ordered, ~ underlying types
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to type constraints
+// 2. Implement logic using constraint interfaces, comparable, cmp.Ordered, ~ underlying types
+// 3. Display results using fmt package
+}
@@ -0,0 +1,27 @@
+// Problem: Generic Types Basic Practice
+// Topic: Generic Types
+// Difficulty: 1
+// learn:requires go>=1.21
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a basic level problem focusing on generic types
+// Section: Generics with type parameters and constraints
+// Syntax elements to practice: generic structs, methods on generic types, explicit instantiation
+
+    fmt.Println("Problem 3: Generic Types - Basic Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: generic structs, methods on generic types, explicit instantiation
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to generic types
+// 2. Implement logic using generic structs, methods on generic types, explicit instantiation
+// 3. Display results using fmt package
+}
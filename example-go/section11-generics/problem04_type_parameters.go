@@ -0,0 +1,27 @@
+// Problem: Type Parameters Intermediate Practice
+// Topic: Type Parameters
+// Difficulty: 2
+// learn:requires go>=1.21
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a intermediate level problem focusing on type parameters
+// Section: Generics with type parameters and constraints
+// Syntax elements to practice: type parameters, generic functions, type inference
+
+    fmt.Println("Problem 4: Type Parameters - Intermediate Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: type parameters, generic functions, type inference
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to type parameters
+// 2. Implement logic using type parameters, generic functions, type inference
+// 3. Display results using fmt package
+}
@@ -0,0 +1,26 @@
+// Problem: Context Deadlines Basic Practice
+// Topic: Context Deadlines
+// Difficulty: 1
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a basic level problem focusing on context deadlines
+// Section: Cancellation, deadlines and request-scoped values with context
+// Syntax elements to practice: context.WithTimeout, context.WithDeadline, select with ctx.Done()
+
+    fmt.Println("Problem 3: Context Deadlines - Basic Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: context.WithTimeout, context.WithDeadline, select with ctx.Done()
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to context deadlines
+// 2. Implement logic using context.WithTimeout, context.WithDeadline, select with ctx.Done()
+// 3. Display results using fmt package
+}
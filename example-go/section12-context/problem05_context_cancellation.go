@@ -0,0 +1,26 @@
+// Problem: Context Cancellation Intermediate Practice
+// Topic: Context Cancellation
+// Difficulty: 2
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a intermediate level problem focusing on context cancellation
+// Section: Cancellation, deadlines and request-scoped values with context
+// Syntax elements to practice: context.WithCancel, ctx.Done(), ctx.Err(), cancel functions
+
+    fmt.Println("Problem 5: Context Cancellation - Intermediate Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: context.WithCancel, ctx.Done(), ctx.Err(), cancel functions
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to context cancellation
+// 2. Implement logic using context.WithCancel, ctx.Done(), ctx.Err(), cancel functions
+// 3. Display results using fmt package
+}
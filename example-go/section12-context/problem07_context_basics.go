@@ -0,0 +1,26 @@
+// Problem: Context Basics Advanced Practice
+// Topic: Context Basics
+// Difficulty: 3
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a advanced level problem focusing on context basics
+// Section: Cancellation, deadlines and request-scoped values with context
+// Syntax elements to practice: context.Background, context.TODO, ctx as first parameter, context.WithValue
+
+    fmt.Println("Problem 7: Context Basics - Advanced Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: context.Background, context.TODO, ctx as first parameter, context.WithValue
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to context basics
+// 2. Implement logic using context.Background, context.TODO, ctx as first parameter, context.WithValue
+// 3. Display results using fmt package
+}
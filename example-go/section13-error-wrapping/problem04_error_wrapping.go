@@ -0,0 +1,27 @@
+// Problem: Error Wrapping Intermediate Practice
+// Topic: Error Wrapping
+// Difficulty: 2
+// learn:requires go>=1.20
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a intermediate level problem focusing on error wrapping
+// Section: Wrapping and inspecting errors with errors.Is and errors.As
+// Syntax elements to practice: fmt.Errorf with %w, errors.Unwrap, wrapped error chains
+
+    fmt.Println("Problem 4: Error Wrapping - Intermediate Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: fmt.Errorf with %w, errors.Unwrap, wrapped error chains
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to error wrapping
+// 2. Implement logic using fmt.Errorf with %w, errors.Unwrap, wrapped error chains
+// 3. Display results using fmt package
+}
@@ -0,0 +1,27 @@
+// Problem: Joined Errors Intermediate Practice
+// Topic: Joined Errors
+// Difficulty: 2
+// learn:requires go>=1.20
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a intermediate level problem focusing on joined errors
+// Section: Wrapping and inspecting errors with errors.Is and errors.As
+// Syntax elements to practice: errors.Join, multiple %w verbs, Unwrap() []error
+
+    fmt.Println("Problem 6: Joined Errors - Intermediate Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: errors.Join, multiple %w verbs, Unwrap() []error
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to joined errors
+// 2. Implement logic using errors.Join, multiple %w verbs, Unwrap() []error
+// 3. Display results using fmt package
+}
@@ -0,0 +1,27 @@
+// Problem: Errors Is And As Advanced Practice
+// Topic: Errors Is And As
+// Difficulty: 3
+// learn:requires go>=1.20
+
+package main
+
+import "fmt"
+
+func main() {
+// TODO: This is a advanced level problem focusing on errors is and as
+// Section: Wrapping and inspecting errors with errors.Is and errors.As
+// Syntax elements to practice: errors.Is, errors.As, sentinel errors, custom error types
+
+    fmt.Println("Problem 8: Errors Is And As - Advanced Level")
+
+// TODO: Implement your solution here
+// Focus on practicing: errors.Is, errors.As, sentinel errors, custom error types
+
+// TODO: Add appropriate variable declarations, control structures, or function calls
+// based on the topic and difficulty level
+
+// Example structure - modify as needed:
+// 1. Declare variables related to errors is and as
+// 2. Implement logic using errors.Is, errors.As, sentinel errors, custom error types
+// 3. Display results using fmt package
+}
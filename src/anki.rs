@@ -0,0 +1,138 @@
+use crate::files;
+use crate::problem;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// Anki に取り込むカード1枚分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+}
+
+// ディレクトリ配下の問題ファイルからトピックごとのカードを作る
+// 同じセクション・同じトピックの問題は1枚にまとめる
+pub fn collect_cards(dir: &Path) -> io::Result<Vec<Card>> {
+    let mut cards: BTreeMap<(String, String), Card> = BTreeMap::new();
+
+    for path in files::collect_files(dir)? {
+        let language = match path.extension().and_then(|s| s.to_str()) {
+            Some("go") => "Go",
+            Some("py") => "Python",
+            _ => continue,
+        };
+
+        let source = fs::read_to_string(&path)?;
+        let header = problem::parse(&source);
+        let Some(topic) = header.topic else {
+            continue;
+        };
+
+        let section_dir = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // 練習する構文が書かれていなければ TODO の内容を裏面に使う
+        let mut points = header.syntax_elements;
+        if points.is_empty() {
+            points = header.todos;
+        }
+
+        let card = cards
+            .entry((section_dir.clone(), topic.clone()))
+            .or_insert_with(|| Card {
+                front: format!("[{}] {}", language, topic),
+                back: header.section.clone().unwrap_or_default(),
+                tags: vec![section_dir, language.to_lowercase()],
+            });
+
+        for point in points {
+            if !card.back.contains(&point) {
+                if !card.back.is_empty() {
+                    card.back.push_str("<br>");
+                }
+                card.back.push_str(&point);
+            }
+        }
+    }
+
+    Ok(cards.into_values().collect())
+}
+
+// Anki の「ファイルから読み込む」で取り込めるタブ区切り形式で書き出す
+pub fn write_tsv<W: Write>(cards: &[Card], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "#separator:tab")?;
+    writeln!(writer, "#html:true")?;
+    writeln!(writer, "#tags column:3")?;
+
+    for card in cards {
+        writeln!(
+            writer,
+            "{}\t{}\t{}",
+            escape(&card.front),
+            escape(&card.back),
+            escape(&card.tags.join(" "))
+        )?;
+    }
+
+    Ok(())
+}
+
+fn escape(field: &str) -> String {
+    field.replace(['\t', '\r'], " ").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_cards_merges_same_topic() {
+        let tmp = tempfile::tempdir().unwrap();
+        let section = tmp.path().join("section7-concurrency");
+        fs::create_dir_all(&section).unwrap();
+        fs::write(
+            section.join("problem01_goroutines.go"),
+            "// Topic: Goroutines\n// Section: Goroutines and channels\n// Syntax elements to practice: go keyword\n",
+        )
+        .unwrap();
+        fs::write(
+            section.join("problem04_goroutines.go"),
+            "// Topic: Goroutines\n// Syntax elements to practice: go keyword, sync.WaitGroup\n",
+        )
+        .unwrap();
+        fs::write(section.join("notes.txt"), "// Topic: Ignored\n").unwrap();
+
+        let cards = collect_cards(tmp.path()).unwrap();
+
+        assert_eq!(
+            cards,
+            [Card {
+                front: "[Go] Goroutines".to_string(),
+                back: "Goroutines and channels<br>go keyword<br>sync.WaitGroup".to_string(),
+                tags: vec!["section7-concurrency".to_string(), "go".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_write_tsv_escapes_fields() {
+        let cards = [Card {
+            front: "[Python] Loops".to_string(),
+            back: "for\tin\nrange".to_string(),
+            tags: vec!["section2-control-flow".to_string()],
+        }];
+        let mut out = Vec::new();
+
+        write_tsv(&cards, &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("[Python] Loops\tfor in<br>range\tsection2-control-flow\n"));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::directive;
+use std::fmt;
+use std::time::Duration;
+
+// "learn:serve <ポート>" の問題はプログラムをサーバーとして起動し、ポートが開いたら
+// "learn:request <メソッド> <パス> [ボディ] => <ステータス> [文字列]" を順に送って応答を調べる
+// 文字列を書いた場合は応答のボディにその文字列が含まれるかも調べる
+// 例:
+//   // learn:serve 8080
+//   // learn:request GET /health => 200
+//   // learn:request POST /items {"name": "apple"} => 201
+//   // learn:request GET /items => 200 apple
+//   // learn:request DELETE /items/99 => 404
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Option<String>,
+    pub status: u16,
+    pub contains: Option<String>,
+}
+
+// サーバーが起動してポートが開くまで待つ時間 (go run のビルドを含む)
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+impl ApiRequest {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (request, expected) = value.split_once("=>")?;
+        let mut request = request.trim().splitn(3, char::is_whitespace);
+        let method = request.next()?.to_uppercase();
+        let path = request.next()?.to_string();
+        if !METHODS.contains(&method.as_str()) || !path.starts_with('/') {
+            return None;
+        }
+        let body = request
+            .next()
+            .map(str::trim)
+            .filter(|body| !body.is_empty())
+            .map(str::to_string);
+
+        let expected = expected.trim();
+        let (status, contains) = expected
+            .split_once(char::is_whitespace)
+            .map(|(status, contains)| (status, Some(contains.trim().to_string())))
+            .unwrap_or((expected, None));
+        let status: u16 = status.parse().ok().filter(|s| (100..600).contains(s))?;
+        Some(Self {
+            method,
+            path,
+            body,
+            status,
+            contains,
+        })
+    }
+
+    // 応答が期待どおりか (違う場合は理由)
+    pub fn verify(&self, response: &Response) -> Result<(), String> {
+        if response.status != self.status {
+            return Err(format!(
+                "ステータスが {} です (期待: {})",
+                response.status, self.status
+            ));
+        }
+        match &self.contains {
+            Some(text) if !response.body.contains(text.as_str()) => {
+                Err(format!("ボディに \"{}\" が含まれていません", text))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ApiRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)
+    }
+}
+
+// 解釈できない learn:request は別に返す
+pub fn requests(source: &str) -> (Vec<ApiRequest>, Vec<String>) {
+    let mut requests = Vec::new();
+    let mut invalid = Vec::new();
+    for value in directive::find_all(source, "request") {
+        match ApiRequest::parse(&value) {
+            Some(request) => requests.push(request),
+            None => invalid.push(value),
+        }
+    }
+    (requests, invalid)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+// ローカルのサーバーにリクエストを送る (同期処理なので spawn_blocking から呼ぶ)
+// 4xx / 5xx もエラーにせず応答として返す
+pub fn send(port: u16, request: &ApiRequest) -> Result<Response, ureq::Error> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into();
+    let builder = ureq::http::Request::builder()
+        .method(request.method.as_str())
+        .uri(format!("http://127.0.0.1:{}{}", port, request.path));
+    let mut response = match &request.body {
+        Some(body) => {
+            // JSON らしいボディは Content-Type を付けて送る
+            let content_type = if body.starts_with(['{', '[']) {
+                "application/json"
+            } else {
+                "text/plain; charset=utf-8"
+            };
+            agent.run(
+                builder
+                    .header("Content-Type", content_type)
+                    .body(body.clone())?,
+            )?
+        }
+        None => agent.run(builder.body(())?)?,
+    };
+    Ok(Response {
+        status: response.status().as_u16(),
+        body: response.body_mut().read_to_string().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        let source = "// learn:request GET /health => 200\n// learn:request post /items {\"name\": \"apple\"} => 201 apple\n// learn:request GET /items\n// learn:request FETCH /items => 200\n// learn:request GET items => 200\n";
+
+        let (requests, invalid) = requests(source);
+
+        assert_eq!(
+            requests,
+            [
+                ApiRequest {
+                    method: "GET".to_string(),
+                    path: "/health".to_string(),
+                    body: None,
+                    status: 200,
+                    contains: None,
+                },
+                ApiRequest {
+                    method: "POST".to_string(),
+                    path: "/items".to_string(),
+                    body: Some("{\"name\": \"apple\"}".to_string()),
+                    status: 201,
+                    contains: Some("apple".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            invalid,
+            ["GET /items", "FETCH /items => 200", "GET items => 200"]
+        );
+    }
+
+    #[test]
+    fn test_verify_response() {
+        let request = ApiRequest::parse("GET /items => 200 apple").unwrap();
+        let response = |status, body: &str| Response {
+            status,
+            body: body.to_string(),
+        };
+
+        assert_eq!(request.verify(&response(200, "[\"apple\"]")), Ok(()));
+        assert_eq!(
+            request.verify(&response(500, "")),
+            Err("ステータスが 500 です (期待: 200)".to_string())
+        );
+        assert_eq!(
+            request.verify(&response(200, "[]")),
+            Err("ボディに \"apple\" が含まれていません".to_string())
+        );
+        assert_eq!(request.to_string(), "GET /items");
+    }
+}
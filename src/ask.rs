@@ -0,0 +1,94 @@
+use crate::history::ExecutionRecord;
+use crate::problem::ProblemHeader;
+
+pub const SYSTEM_PROMPT: &str = "You are a patient programming tutor helping a learner with one exercise. \
+Answer in Japanese. Explain the cause and guide the learner toward the fix \
+instead of handing over a complete solution.";
+
+// 質問に、問題文・現在のコード・直近のエラー出力を添える
+pub fn user_prompt(
+    question: &str,
+    header: &ProblemHeader,
+    language: &str,
+    source: &str,
+    last_failure: Option<&ExecutionRecord>,
+) -> String {
+    let mut prompt = String::new();
+    if let Some(title) = &header.title {
+        prompt.push_str(&format!("Problem: {}\n", title));
+    }
+    if let Some(topic) = &header.topic {
+        prompt.push_str(&format!("Topic: {}\n", topic));
+    }
+    for todo in &header.todos {
+        prompt.push_str(&format!("Task: {}\n", todo));
+    }
+    prompt.push_str(&format!(
+        "\nCurrent code:\n```{}\n{}\n```\n",
+        language,
+        source.trim_end()
+    ));
+    if let Some(failure) = last_failure {
+        prompt.push_str(&format!(
+            "\nLast error output (exit code {}):\n```\n{}\n```\n",
+            failure
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            failure.output_preview
+        ));
+    }
+    prompt.push_str(&format!("\nQuestion: {}\n", question));
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+
+    #[test]
+    fn test_user_prompt_includes_last_error() {
+        let header = ProblemHeader {
+            title: Some("Pointer Basics".to_string()),
+            ..Default::default()
+        };
+        let failure = ExecutionRecord {
+            id: 1,
+            file_path: "a.go".to_string(),
+            section: "section9-pointers".to_string(),
+            success: false,
+            exit_code: Some(2),
+            duration_ms: 10,
+            executed_at: 0,
+            output_preview:
+                "panic: runtime error: invalid memory address or nil pointer dereference"
+                    .to_string(),
+            environment: Environment {
+                tool_version: None,
+                os: "linux".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: None,
+            todos_remaining: None,
+            score: None,
+            content_hash: None,
+            error_signature: None,
+            language: None,
+            coverage: None,
+        };
+
+        let prompt = user_prompt(
+            "なぜ nil ポインタで panic するの?",
+            &header,
+            "go",
+            "package main\n",
+            Some(&failure),
+        );
+
+        assert!(prompt.starts_with("Problem: Pointer Basics\n"));
+        assert!(prompt.contains("exit code 2"));
+        assert!(prompt.contains("nil pointer dereference"));
+        assert!(prompt.ends_with("Question: なぜ nil ポインタで panic するの?\n"));
+    }
+}
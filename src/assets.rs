@@ -0,0 +1,141 @@
+use crate::link;
+use include_dir::{Dir, DirEntry, include_dir};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// バイナリに埋め込んだGoのカリキュラム (example-go)
+static GO_CURRICULUM: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/example-go");
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExtractSummary {
+    pub written: usize,
+    // 既に存在していたため書き込まなかったファイル数
+    pub skipped: usize,
+}
+
+// 埋め込みカリキュラムを dest 以下に書き出す
+// 学習者の解答を消さないよう、overwrite が false の場合は既存ファイルを残す
+pub fn extract_go_curriculum(dest: &Path, overwrite: bool) -> io::Result<ExtractSummary> {
+    let mut summary = ExtractSummary::default();
+    extract_dir(&GO_CURRICULUM, dest, overwrite, &mut summary)?;
+    Ok(summary)
+}
+
+// 埋め込みカリキュラムからセクションと問題の短い名前 (section3/problem05) に一致するファイルを探す
+// 戻り値のパスはカリキュラムのルートからの相対パス
+pub fn find_go_problem(section: &str, problem: &str) -> Option<(&'static Path, &'static [u8])> {
+    let section_dir = GO_CURRICULUM.dirs().find(|dir| {
+        dir.path()
+            .file_name()
+            .and_then(|s| s.to_str())
+            .is_some_and(|name| link::matches_short_name(name, section, '-'))
+    })?;
+    let file = section_dir.files().find(|file| {
+        file.path()
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|stem| link::matches_short_name(stem, problem, '_'))
+    })?;
+    Some((file.path(), file.contents()))
+}
+
+// 埋め込みカリキュラムのすべてのファイル (パスはカリキュラムのルートからの相対パス)
+pub fn go_curriculum_files() -> Vec<(&'static Path, &'static [u8])> {
+    let mut files = Vec::new();
+    let mut dirs = vec![&GO_CURRICULUM];
+    while let Some(dir) = dirs.pop() {
+        files.extend(dir.files().map(|file| (file.path(), file.contents())));
+        dirs.extend(dir.dirs());
+    }
+    files.sort_by_key(|(path, _)| *path);
+    files
+}
+
+fn extract_dir(
+    dir: &Dir<'_>,
+    dest: &Path,
+    overwrite: bool,
+    summary: &mut ExtractSummary,
+) -> io::Result<()> {
+    for entry in dir.entries() {
+        match entry {
+            DirEntry::Dir(child) => extract_dir(child, dest, overwrite, summary)?,
+            DirEntry::File(file) => {
+                let path = dest.join(file.path());
+                if path.exists() && !overwrite {
+                    summary.skipped += 1;
+                    continue;
+                }
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, file.contents())?;
+                summary.written += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_go_curriculum() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let summary = extract_go_curriculum(tmp.path(), false).unwrap();
+
+        assert!(summary.written > 0);
+        assert!(
+            tmp.path()
+                .join("section1-basics")
+                .join("problem01_variables.go")
+                .is_file()
+        );
+    }
+
+    #[test]
+    fn test_find_go_problem() {
+        let (path, contents) = find_go_problem("section3", "problem05").unwrap();
+
+        assert_eq!(
+            path,
+            Path::new("section3-functions").join("problem05_closures.go")
+        );
+        assert!(!contents.is_empty());
+        assert!(find_go_problem("section3", "problem99").is_none());
+    }
+
+    #[test]
+    fn test_go_curriculum_files() {
+        let files = go_curriculum_files();
+
+        assert!(
+            files
+                .iter()
+                .any(|(path, _)| *path
+                    == Path::new("section1-basics").join("problem01_variables.go"))
+        );
+        assert!(files.is_sorted_by_key(|(path, _)| *path));
+    }
+
+    #[test]
+    fn test_extract_keeps_existing_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let first = extract_go_curriculum(tmp.path(), false).unwrap();
+        let solution = tmp
+            .path()
+            .join("section1-basics")
+            .join("problem01_variables.go");
+        fs::write(&solution, "// my solution\n").unwrap();
+
+        let second = extract_go_curriculum(tmp.path(), false).unwrap();
+
+        assert_eq!(second.written, 0);
+        assert_eq!(second.skipped, first.written);
+        assert_eq!(fs::read_to_string(&solution).unwrap(), "// my solution\n");
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use which::which;
+
+// 実行コマンドがインストールされているかの確認結果の控え (データディレクトリの availability.json)
+// 保存のたびに PATH を探さず、確認してから TTL の間は控えを使う
+// doctor --refresh で確認し直すと、監視中のプロセスも次の実行から新しい結果を使う
+
+// 控えを使う時間 (秒)
+pub const TTL_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+    // 見つからなければ None
+    pub path: Option<PathBuf>,
+    // 確認した時刻 (UNIX 秒)
+    pub probed_at: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct Availability {
+    // None なら控えを保存しない
+    file: Option<PathBuf>,
+    probes: BTreeMap<String, Probe>,
+    // 読み込んだときの控えの更新時刻 (他のプロセスが書き換えたら読み直す)
+    modified: Option<SystemTime>,
+    // 見つからないと警告済みのコマンド (見つかるまで同じ警告を繰り返さない)
+    warned: HashSet<String>,
+}
+
+impl Availability {
+    pub fn open(file: PathBuf) -> Self {
+        let mut availability = Self {
+            file: Some(file),
+            ..Default::default()
+        };
+        availability.reload_if_changed();
+        availability
+    }
+
+    // コマンドのパス (控えが古ければ確認し直す)
+    pub fn find(&mut self, command: &str, now: i64) -> Option<PathBuf> {
+        self.lookup(command, now, |command| which(command).ok())
+    }
+
+    // 控えを使わずにすべて確認し直す
+    pub fn refresh(&mut self, commands: &[String], now: i64) {
+        self.reload_if_changed();
+        for command in commands {
+            self.record(command, which(command).ok(), now);
+        }
+        self.save();
+    }
+
+    // 見つからないことを初めて警告するときだけ true
+    pub fn warn_missing(&mut self, command: &str) -> bool {
+        self.warned.insert(command.to_string())
+    }
+
+    fn lookup(
+        &mut self,
+        command: &str,
+        now: i64,
+        probe: impl FnOnce(&str) -> Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        self.reload_if_changed();
+        let path = match self.probes.get(command) {
+            Some(cached) if now - cached.probed_at < TTL_SECONDS => cached.path.clone(),
+            _ => {
+                let path = probe(command);
+                self.record(command, path.clone(), now);
+                self.save();
+                path
+            }
+        };
+        if path.is_some() {
+            self.warned.remove(command);
+        }
+        path
+    }
+
+    fn record(&mut self, command: &str, path: Option<PathBuf>, now: i64) {
+        self.probes.insert(
+            command.to_string(),
+            Probe {
+                path,
+                probed_at: now,
+            },
+        );
+    }
+
+    fn reload_if_changed(&mut self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let modified = std::fs::metadata(file).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        if let Some(probes) = read(file) {
+            self.probes = probes;
+        }
+    }
+
+    // 控えを書けなくても実行は続ける
+    fn save(&mut self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let Ok(text) = serde_json::to_string_pretty(&self.probes) else {
+            return;
+        };
+        if let Err(e) = std::fs::write(file, text) {
+            log::warn!(
+                "コマンドの確認結果を保存できません: {} ({})",
+                file.display(),
+                e
+            );
+            return;
+        }
+        self.modified = std::fs::metadata(file).and_then(|m| m.modified()).ok();
+    }
+}
+
+fn read(file: &Path) -> Option<BTreeMap<String, Probe>> {
+    let text = std::fs::read_to_string(file).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_is_cached_until_ttl() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("availability.json");
+        let mut availability = Availability::open(file.clone());
+        let go = Some(PathBuf::from("/usr/bin/go"));
+
+        assert_eq!(availability.lookup("go", 100, |_| go.clone()), go);
+        // TTL の間は確認しない
+        assert_eq!(
+            availability.lookup("go", 100 + TTL_SECONDS - 1, |_| unreachable!()),
+            go
+        );
+        assert_eq!(availability.lookup("go", 100 + TTL_SECONDS, |_| None), None);
+
+        // 他のプロセス (doctor --refresh) の書き込みは控えのファイルから読む
+        let mut other = Availability::open(file);
+        assert_eq!(
+            other.lookup("go", 100 + TTL_SECONDS, |_| unreachable!()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_warn_missing_once_until_found() {
+        let mut availability = Availability::default();
+        assert!(availability.warn_missing("python"));
+        assert!(!availability.warn_missing("python"));
+
+        availability.lookup("python", 0, |_| Some(PathBuf::from("/usr/bin/python")));
+        assert!(availability.warn_missing("python"));
+    }
+}
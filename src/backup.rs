@@ -0,0 +1,273 @@
+use crate::history::HistoryStore;
+use chrono::NaiveDate;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OpenFlags, params_from_iter};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// 履歴 DB のバックアップと修復
+// 監視を始めるときに1日1回 backups/history-YYYY-MM-DD.db を作り、新しいものから KEEP_DAILY 個だけ残す
+// db backup で作る手動のバックアップは消さない
+pub const KEEP_DAILY: usize = 7;
+
+pub fn daily_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("history-{}.db", date.format("%Y-%m-%d")))
+}
+
+fn is_daily(name: &str) -> bool {
+    name.strip_prefix("history-")
+        .and_then(|rest| rest.strip_suffix(".db"))
+        .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+}
+
+// 今日のバックアップがまだなければ作る (作成したパスを返す)
+pub fn daily_backup(
+    store: &HistoryStore,
+    dir: &Path,
+    today: NaiveDate,
+) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let path = daily_path(dir, today);
+    if path.exists() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    store.backup_to(&path)?;
+    rotate(dir, KEEP_DAILY)?;
+    Ok(Some(path))
+}
+
+// 日ごとのバックアップを新しいものから keep 個残して削除する (削除したパスを返す)
+pub fn rotate(dir: &Path, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let mut daily: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_daily)
+        })
+        .collect();
+    // 名前の日付順 = 作成日順
+    daily.sort();
+    let remove = daily.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = daily.into_iter().take(remove).collect();
+    for path in &removed {
+        fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+// 復元に使える (壊れていない SQLite の DB) か確かめる
+pub fn verify(path: &Path) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result != "ok" {
+        return Err(result);
+    }
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'executions'",
+        [],
+        |_| Ok(()),
+    )
+    .map_err(|_| "履歴DBではありません".to_string())
+}
+
+// PRAGMA integrity_check で見つかった問題 (壊れていなければ空)
+// 壊れ方によってはチェック自体が失敗するので、そのエラーも問題として返す
+pub fn integrity_problems(path: &Path) -> rusqlite::Result<Vec<String>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let check = || -> rusqlite::Result<Vec<String>> {
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        stmt.query_map([], |row| row.get(0))?.collect()
+    };
+    Ok(match check() {
+        Ok(lines) => lines.into_iter().filter(|line| line != "ok").collect(),
+        Err(e) => vec![e.to_string()],
+    })
+}
+
+// 読み出せた行と読み出せなかった範囲の数 (テーブルごと)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Salvage {
+    pub table: String,
+    pub rows: usize,
+    pub skipped: usize,
+}
+
+// 読み出しに続けて失敗したらそのテーブルはあきらめる
+const MAX_SKIPS: usize = 32;
+
+// 壊れた DB (corrupt) から読み出せる行を、マイグレーション済みの新しい DB (dest) にコピーする
+// 壊れたページで読み出しが止まったら、rowid を少しずつ大きく飛ばして続きを読む
+pub fn salvage(corrupt: &Path, dest: &Path) -> Result<Vec<Salvage>, Box<dyn Error>> {
+    drop(HistoryStore::open(dest)?);
+    let conn = Connection::open(dest)?;
+    conn.execute("ATTACH DATABASE ?1 AS old", [corrupt.display().to_string()])?;
+
+    // 新しい DB の通常のテーブルだけを移す (問題の検索インデックスのような仮想テーブルは検索時に作り直される)
+    let tables: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM pragma_table_list
+             WHERE schema = 'main' AND type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )?;
+        stmt.query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    let mut results = Vec::new();
+    for table in tables {
+        // 両方にある列だけをコピーする (古い DB にないテーブルは空のまま)
+        let columns = |schema: &str| -> rusqlite::Result<Vec<String>> {
+            let mut stmt = conn.prepare(&format!("PRAGMA {}.table_info(\"{}\")", schema, table))?;
+            stmt.query_map([], |row| row.get(1))?.collect()
+        };
+        let new_columns = columns("main")?;
+        let columns: Vec<String> = match columns("old") {
+            Ok(old) => old
+                .into_iter()
+                .filter(|c| new_columns.contains(c))
+                .collect(),
+            Err(_) => continue,
+        };
+        if columns.is_empty() {
+            continue;
+        }
+        let list = columns
+            .iter()
+            .map(|c| format!("\"{}\"", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let select = format!(
+            "SELECT rowid, {} FROM old.\"{}\" WHERE rowid > ?1 ORDER BY rowid",
+            list, table
+        );
+        let insert = format!(
+            "INSERT OR IGNORE INTO main.\"{}\" (rowid, {}) VALUES ({})",
+            table,
+            list,
+            vec!["?"; columns.len() + 1].join(", ")
+        );
+
+        let mut salvage = Salvage {
+            table: table.clone(),
+            rows: 0,
+            skipped: 0,
+        };
+        let mut after = i64::MIN;
+        let mut jump: i64 = 1;
+        loop {
+            let copied = copy_rows(&conn, &select, &insert, &mut after, columns.len() + 1);
+            salvage.rows += copied.0;
+            if copied.1 {
+                break;
+            }
+            salvage.skipped += 1;
+            if salvage.skipped >= MAX_SKIPS || after == i64::MAX {
+                break;
+            }
+            after = after.saturating_add(jump);
+            jump = jump.saturating_mul(2);
+        }
+        results.push(salvage);
+    }
+    conn.execute("DETACH DATABASE old", [])?;
+    Ok(results)
+}
+
+// after より後の行を読めるところまでコピーする (コピーした行数と、最後まで読めたか)
+fn copy_rows(
+    conn: &Connection,
+    select: &str,
+    insert: &str,
+    after: &mut i64,
+    width: usize,
+) -> (usize, bool) {
+    let (Ok(mut select), Ok(mut insert)) = (conn.prepare(select), conn.prepare(insert)) else {
+        return (0, false);
+    };
+    let Ok(mut rows) = select.query([*after]) else {
+        return (0, false);
+    };
+    let mut copied = 0;
+    loop {
+        match rows.next() {
+            Ok(Some(row)) => {
+                let values: rusqlite::Result<Vec<Value>> = (0..width).map(|i| row.get(i)).collect();
+                let Ok(values) = values else {
+                    return (copied, false);
+                };
+                if let Value::Integer(rowid) = values[0] {
+                    *after = rowid;
+                }
+                if insert.execute(params_from_iter(values)).is_ok() {
+                    copied += 1;
+                }
+            }
+            Ok(None) => return (copied, true),
+            Err(_) => return (copied, false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_backup_and_rotate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = HistoryStore::open(&tmp.path().join("history.db")).unwrap();
+        let dir = tmp.path().join("backups");
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+
+        for day in 1..=9 {
+            assert!(daily_backup(&store, &dir, date(day)).unwrap().is_some());
+        }
+        assert_eq!(daily_backup(&store, &dir, date(9)).unwrap(), None);
+        fs::write(dir.join("manual-20261001-120000.db"), "").unwrap();
+
+        assert!(!daily_path(&dir, date(2)).exists());
+        assert!(daily_path(&dir, date(3)).exists());
+        assert_eq!(rotate(&dir, 2).unwrap().len(), 5);
+        assert!(dir.join("manual-20261001-120000.db").exists());
+        assert!(verify(&daily_path(&dir, date(9))).is_ok());
+        assert!(verify(&dir.join("manual-20261001-120000.db")).is_err());
+    }
+
+    #[test]
+    fn test_salvage_corrupted_database() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("history.db");
+        let store = HistoryStore::open(&path).unwrap();
+        for i in 0..2000 {
+            store
+                .save_reference_output(&format!("{:04}", i), &"x".repeat(200), i)
+                .unwrap();
+        }
+        drop(store);
+        assert!(integrity_problems(&path).unwrap().is_empty());
+
+        // 途中のページを壊す
+        let mut bytes = fs::read(&path).unwrap();
+        let page = bytes.len() / 4096 / 2;
+        bytes[page * 4096..(page + 4) * 4096].fill(0xff);
+        fs::write(&path, bytes).unwrap();
+        assert!(!integrity_problems(&path).unwrap().is_empty());
+
+        let recovered = tmp.path().join("recovered.db");
+        let results = salvage(&path, &recovered).unwrap();
+
+        let outputs = results
+            .iter()
+            .find(|result| result.table == "reference_outputs")
+            .unwrap();
+        assert!(outputs.rows > 0 && outputs.rows < 2000, "{:?}", outputs);
+        assert!(integrity_problems(&recovered).unwrap().is_empty());
+        let store = HistoryStore::open(&recovered).unwrap();
+        assert!(store.reference_output("0000").unwrap().is_some());
+    }
+}
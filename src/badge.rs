@@ -0,0 +1,191 @@
+use chrono::{Days, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// badges: 進み具合を shields.io 風の SVG バッジにしてディレクトリに書き出す
+// README やプロフィールに貼れるように、外部のサービスを使わずに手元で作る
+
+const GREEN: &str = "#4c1";
+const YELLOW: &str = "#dfb317";
+const GRAY: &str = "#9f9f9f";
+
+// 課題の問題1つ分 (language は runner::language_for_extension の名前)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemStatus {
+    pub language: String,
+    pub section: String,
+    pub solved: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Badge {
+    // 書き出すファイル名 (拡張子なし)
+    pub name: String,
+    pub label: String,
+    pub message: String,
+    pub color: &'static str,
+}
+
+// 文字幅の目安 (Verdana 11px で ASCII は約7px、全角は約11px)
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c.is_ascii() { 7 } else { 11 })
+        .sum::<usize>()
+        + 10
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Badge {
+    pub fn svg(&self) -> String {
+        let label_width = text_width(&self.label);
+        let message_width = text_width(&self.message);
+        let width = label_width + message_width;
+        let label = escape(&self.label);
+        let message = escape(&self.message);
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text>
+<text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text>
+</g>
+</svg>
+"##,
+            color = self.color,
+            label_x = label_width / 2,
+            message_x = label_width + message_width / 2,
+        )
+    }
+}
+
+// 全部の問題を解き終えたセクションの数を言語ごとに数える ("Go sections: 7/10")
+pub fn section_badges(problems: &[ProblemStatus]) -> Vec<Badge> {
+    // 言語 -> セクション -> すべて解いたか
+    let mut languages: BTreeMap<&str, BTreeMap<&str, bool>> = BTreeMap::new();
+    for problem in problems {
+        let done = languages
+            .entry(&problem.language)
+            .or_default()
+            .entry(&problem.section)
+            .or_insert(true);
+        *done &= problem.solved;
+    }
+    languages
+        .into_iter()
+        .map(|(language, sections)| {
+            let completed = sections.values().filter(|done| **done).count();
+            let total = sections.len();
+            Badge {
+                name: format!("sections-{}", language.to_lowercase()),
+                label: format!("{} sections", language),
+                message: format!("{}/{}", completed, total),
+                color: if completed == total {
+                    GREEN
+                } else if completed > 0 {
+                    YELLOW
+                } else {
+                    GRAY
+                },
+            }
+        })
+        .collect()
+}
+
+// 今日まで (今日まだ学習していなければ昨日まで) 続けて学習した日数
+// days は学習した日 (重複なし、順不同)
+pub fn streak(days: &[NaiveDate], today: NaiveDate) -> u32 {
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        match today.checked_sub_days(Days::new(1)) {
+            Some(yesterday) => yesterday,
+            None => return 0,
+        }
+    };
+    let mut count = 0;
+    while days.contains(&day) {
+        count += 1;
+        match day.checked_sub_days(Days::new(1)) {
+            Some(previous) => day = previous,
+            None => break,
+        }
+    }
+    count
+}
+
+pub fn streak_badge(days: u32) -> Badge {
+    Badge {
+        name: "streak".to_string(),
+        label: "streak".to_string(),
+        message: format!("{} {}", days, if days == 1 { "day" } else { "days" }),
+        color: if days > 0 { GREEN } else { GRAY },
+    }
+}
+
+// out/<name>.svg に書き出して、書き出したパスを返す
+pub fn write(out: &Path, badges: &[Badge]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(out)?;
+    badges
+        .iter()
+        .map(|badge| {
+            let path = out.join(format!("{}.svg", badge.name));
+            fs::write(&path, badge.svg())?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(language: &str, section: &str, solved: bool) -> ProblemStatus {
+        ProblemStatus {
+            language: language.to_string(),
+            section: section.to_string(),
+            solved,
+        }
+    }
+
+    #[test]
+    fn test_section_badges() {
+        let badges = section_badges(&[
+            problem("Go", "section1-basics", true),
+            problem("Go", "section1-basics", true),
+            problem("Go", "section2-types", true),
+            problem("Go", "section2-types", false),
+            problem("Python", "section1-basics", false),
+        ]);
+
+        assert_eq!(badges.len(), 2);
+        assert_eq!(badges[0].name, "sections-go");
+        assert_eq!(badges[0].label, "Go sections");
+        assert_eq!(badges[0].message, "1/2");
+        assert_eq!(badges[0].color, YELLOW);
+        assert_eq!(badges[1].message, "0/1");
+        assert!(badges[0].svg().contains("aria-label=\"Go sections: 1/2\""));
+    }
+
+    #[test]
+    fn test_streak() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let days = [date(1), date(3), date(4), date(5)];
+
+        assert_eq!(streak(&days, date(5)), 3);
+        // 今日はまだ学習していなくても昨日まで続いていれば途切れない
+        assert_eq!(streak(&days, date(6)), 3);
+        assert_eq!(streak(&days, date(7)), 0);
+        assert_eq!(streak_badge(1).message, "1 day");
+    }
+}
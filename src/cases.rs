@@ -0,0 +1,111 @@
+use crate::directive;
+use std::path::{Path, PathBuf};
+
+// 問題ファイルの "learn:case <入力ファイル> <期待する出力のファイル>" で定義する入出力のケース
+// パスは問題ファイルからの相対パスで、ケースごとに入力を標準入力に渡して実行する
+// 例:
+//   // learn:case cases/1.in cases/1.out
+//   // learn:case cases/empty.in cases/empty.out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    // 表示と履歴に使う名前 (入力ファイルの名前)
+    pub name: String,
+    pub input: PathBuf,
+    pub expected: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseResult {
+    pub name: String,
+    pub passed: bool,
+    pub duration_ms: i64,
+    // 失敗の理由 (出力が違う・異常終了したなど)
+    pub detail: Option<String>,
+}
+
+// 解釈できない learn:case は別に返す
+pub fn cases(source: &str, dir: &Path) -> (Vec<Case>, Vec<String>) {
+    let mut cases = Vec::new();
+    let mut invalid = Vec::new();
+    for value in directive::find_all(source, "case") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        match parts[..] {
+            [input, expected] => cases.push(Case {
+                name: input.to_string(),
+                input: dir.join(input),
+                expected: dir.join(expected),
+            }),
+            _ => invalid.push(value),
+        }
+    }
+    (cases, invalid)
+}
+
+// ケースごとの結果の表 (名前をそろえて1ケース1行)
+pub fn format_table(results: &[CaseResult]) -> String {
+    let width = results
+        .iter()
+        .map(|result| result.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut table = String::new();
+    for result in results {
+        table.push_str(&format!(
+            "{} {:<width$}  {:>5}ms",
+            if result.passed { "✅" } else { "❌" },
+            result.name,
+            result.duration_ms,
+            width = width
+        ));
+        if let Some(detail) = &result.detail {
+            table.push_str(&format!("  {}", detail));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cases() {
+        let source = "# learn:case cases/1.in cases/1.out\n# learn:case cases/2.in\n";
+
+        let (cases, invalid) = cases(source, Path::new("/work/section1"));
+
+        assert_eq!(
+            cases,
+            [Case {
+                name: "cases/1.in".to_string(),
+                input: PathBuf::from("/work/section1/cases/1.in"),
+                expected: PathBuf::from("/work/section1/cases/1.out"),
+            }]
+        );
+        assert_eq!(invalid, ["cases/2.in"]);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let results = [
+            CaseResult {
+                name: "1.in".to_string(),
+                passed: true,
+                duration_ms: 12,
+                detail: None,
+            },
+            CaseResult {
+                name: "large.in".to_string(),
+                passed: false,
+                duration_ms: 40,
+                detail: Some("出力が違います".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            format_table(&results),
+            "✅ 1.in         12ms\n❌ large.in     40ms  出力が違います\n"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+// stats charts: 実行履歴をターミナルに描くグラフ (スパークライン・棒グラフ・練習した日のカレンダー)
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// カレンダーの濃さ (0回は ·)
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+const WEEKDAYS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+
+// 値ごとに1文字 (0 から最大値までを8段階に、値がない日は空白)
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    let max = values.iter().flatten().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| match value {
+            None => ' ',
+            Some(_) if max <= 0.0 => SPARKS[0],
+            Some(value) => {
+                let level = (value / max * (SPARKS.len() - 1) as f64).round() as usize;
+                SPARKS[level.min(SPARKS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+// 1行1項目の横棒グラフ (最大値を width 文字にする、note は棒の後ろに付ける)
+pub fn bar_chart(rows: &[(String, f64, String)], width: usize) -> String {
+    let max = rows.iter().map(|(_, value, _)| *value).fold(0.0, f64::max);
+    let label_width = rows
+        .iter()
+        .map(|(label, _, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut text = String::new();
+    for (label, value, note) in rows {
+        let length = match max > 0.0 {
+            true => ((value / max) * width as f64).round().max(1.0) as usize,
+            false => 0,
+        };
+        text.push_str(&format!(
+            "{}{}  {}{}  {}\n",
+            label,
+            " ".repeat(label_width - label.chars().count()),
+            "█".repeat(length),
+            " ".repeat(width - length.min(width)),
+            note
+        ));
+    }
+    text
+}
+
+// from から to までの日ごとの実行回数を、曜日を行・週を列にしたカレンダーにする
+pub fn heatmap(runs: &BTreeMap<NaiveDate, u64>, from: NaiveDate, to: NaiveDate) -> String {
+    let max = runs
+        .range(from..=to)
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+    let first_monday = from - Duration::days(i64::from(from.weekday().num_days_from_monday()));
+    let weeks = (to - first_monday).num_days() / 7 + 1;
+
+    let mut text = String::new();
+    for (weekday, name) in WEEKDAYS.iter().enumerate() {
+        text.push_str(name);
+        for week in 0..weeks {
+            let day = first_monday + Duration::days(week * 7 + weekday as i64);
+            let cell = match runs.get(&day).copied().unwrap_or(0) {
+                _ if day < from || day > to => ' ',
+                0 => '·',
+                count => {
+                    let level = ((count * SHADES.len() as u64).div_ceil(max.max(1))) as usize;
+                    SHADES[level.clamp(1, SHADES.len()) - 1]
+                }
+            };
+            text.push(' ');
+            text.push(cell);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_and_bar_chart() {
+        assert_eq!(sparkline(&[Some(0.0), Some(4.0), None, Some(8.0)]), "▁▅ █");
+        assert_eq!(sparkline(&[Some(0.0), Some(0.0)]), "▁▁");
+
+        let rows = [
+            ("section1".to_string(), 10.0, "10回".to_string()),
+            ("section10".to_string(), 5.0, "5回".to_string()),
+        ];
+        assert_eq!(
+            bar_chart(&rows, 4),
+            "section1   ████  10回\nsection10  ██    5回\n"
+        );
+    }
+
+    #[test]
+    fn test_heatmap_places_days_by_weekday() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        // 2026-10-07 は水曜日
+        let runs = BTreeMap::from([(date(7), 1), (date(8), 4), (date(12), 2)]);
+
+        let lines: Vec<String> = heatmap(&runs, date(7), date(12))
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(lines[0], "月   ▒");
+        assert_eq!(lines[2], "水 ░  ");
+        assert_eq!(lines[3], "木 █  ");
+        assert_eq!(lines[4], "金 ·  ");
+    }
+}
@@ -0,0 +1,217 @@
+use crate::badge;
+use crate::config::{ChatConfig, ChatService};
+use crate::history::HistoryStore;
+use crate::outbox::{self, Message};
+use crate::solve_time;
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+// integrations chat: 1日のまとめ (解いた問題数・連続記録・学習時間) を Discord / Slack の Webhook に送る
+// 送るのは outbox の待ち行列 (送れなければ送り直す)。監視中は日付が変わったら前日の分を1回だけ入れる
+// [chat] の quiet_hours の間は送らずに待たせる
+
+pub const DEFAULT_TEMPLATE: &str = "📚 {date} の学習: {solved}問クリア / 連続{streak}日 / {time}";
+const PLACEHOLDERS: [&str; 4] = ["date", "solved", "streak", "time"];
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub date: NaiveDate,
+    pub runs: usize,
+    pub solved: usize,
+    pub streak: u32,
+    pub study_seconds: i64,
+}
+
+impl Summary {
+    // 実行も学習時間もない日は送らない
+    pub fn is_empty(&self) -> bool {
+        self.runs == 0 && self.study_seconds == 0
+    }
+}
+
+pub fn summary(store: &HistoryStore, date: NaiveDate) -> rusqlite::Result<Summary> {
+    let timestamp = |day: NaiveDate| {
+        day.and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map_or(0, |t| t.timestamp())
+    };
+    let (since, until) = (timestamp(date), timestamp(date + Days::new(1)) - 1);
+    let solved = store
+        .solve_times()?
+        .iter()
+        .filter(|solve| (since..=until).contains(&solve.solved_at))
+        .count();
+    let days: Vec<NaiveDate> = store
+        .study_days()?
+        .iter()
+        .filter_map(|day| day.parse().ok())
+        .collect();
+    Ok(Summary {
+        date,
+        runs: store.executions_between(since, until)?.len(),
+        solved,
+        streak: badge::streak(&days, date),
+        study_seconds: store.study_seconds_between(since, until)?,
+    })
+}
+
+pub fn render(template: &str, summary: &Summary) -> String {
+    let time =
+        solve_time::format_duration(Duration::from_secs(summary.study_seconds.max(0) as u64));
+    template
+        .replace("{date}", &summary.date.to_string())
+        .replace("{solved}", &summary.solved.to_string())
+        .replace("{streak}", &summary.streak.to_string())
+        .replace("{time}", &time)
+}
+
+// Webhook に送る JSON
+pub fn body(service: ChatService, text: &str) -> Value {
+    match service {
+        ChatService::Discord => json!({"content": text}),
+        ChatService::Slack => json!({"text": text}),
+    }
+}
+
+// 送らない時間帯 ("22:00-07:00" のように日付をまたいでもよい)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (start, end) = text.split_once('-')?;
+        let time = |text: &str| NaiveTime::parse_from_str(text.trim(), "%H:%M").ok();
+        Some(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match self.start <= self.end {
+            true => self.start <= time && time < self.end,
+            false => self.start <= time || time < self.end,
+        }
+    }
+}
+
+// config validate で出す問題
+pub fn check(config: &ChatConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !config.webhook_url.starts_with("https://") && !config.webhook_url.starts_with("http://") {
+        problems
+            .push("chat.webhook_url: http:// か https:// の URL を指定してください".to_string());
+    }
+    if let Some(quiet_hours) = &config.quiet_hours
+        && QuietHours::parse(quiet_hours).is_none()
+    {
+        problems.push(format!(
+            "chat.quiet_hours: \"22:00-07:00\" の形で指定してください: {}",
+            quiet_hours
+        ));
+    }
+    for name in config.template.iter().flat_map(|t| placeholders(t)) {
+        if !PLACEHOLDERS.contains(&name) {
+            problems.push(format!(
+                "chat.template: {{{}}} は使えません ({} のどれかを使ってください)",
+                name,
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(" ")
+            ));
+        }
+    }
+    problems
+}
+
+// 文面の中の {名前}
+fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+pub fn message(config: &ChatConfig, summary: &Summary) -> Message {
+    let text = render(
+        config.template.as_deref().unwrap_or(DEFAULT_TEMPLATE),
+        summary,
+    );
+    Message::Chat {
+        date: summary.date.to_string(),
+        webhook_url: config.webhook_url.clone(),
+        body: body(config.service, &text),
+        quiet_hours: config.quiet_hours.clone(),
+    }
+}
+
+// 前日のまとめをまだ送っていなければ待ち行列に入れる (入れたら true)
+pub fn queue_yesterday(
+    store: &HistoryStore,
+    config: &ChatConfig,
+    now: DateTime<Local>,
+) -> rusqlite::Result<bool> {
+    let Some(yesterday) = now.date_naive().checked_sub_days(Days::new(1)) else {
+        return Ok(false);
+    };
+    let summary = summary(store, yesterday)?;
+    if summary.is_empty() || !store.mark_chat_summary(&yesterday.to_string(), now.timestamp())? {
+        return Ok(false);
+    }
+    outbox::enqueue(store, &message(config, &summary), now.timestamp())
+}
+
+// Webhook に POST する (ブロックするので spawn_blocking の中で呼ぶ)
+pub fn post(webhook_url: &str, body: &Value) -> Result<(), String> {
+    ureq::post(webhook_url)
+        .config()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .send_json(body)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template_and_quiet_hours() {
+        let summary = Summary {
+            date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            runs: 12,
+            solved: 3,
+            streak: 5,
+            study_seconds: 3725,
+        };
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &summary),
+            "📚 2026-10-16 の学習: 3問クリア / 連続5日 / 1時間2分5秒"
+        );
+        assert_eq!(body(ChatService::Slack, "hi"), json!({"text": "hi"}));
+
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let night = QuietHours::parse("22:00-07:00").unwrap();
+        assert!(night.contains(at(23, 30)) && night.contains(at(6, 59)));
+        assert!(!night.contains(at(7, 0)) && !night.contains(at(21, 59)));
+        assert!(
+            QuietHours::parse("12:00-13:00")
+                .unwrap()
+                .contains(at(12, 30))
+        );
+        assert_eq!(QuietHours::parse("22-7"), None);
+
+        let config = ChatConfig {
+            service: ChatService::Discord,
+            webhook_url: "discord.com/api/webhooks/1".to_string(),
+            template: Some("{date} {minutes}".to_string()),
+            quiet_hours: Some("夜".to_string()),
+        };
+        assert_eq!(check(&config).len(), 3, "{:?}", check(&config));
+    }
+}
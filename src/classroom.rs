@@ -0,0 +1,285 @@
+use crate::history::{HistoryStore, ProblemOutcome, SolveRecord};
+use crate::link;
+use crate::solve_time;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+// teacher aggregate: 学生ごとの履歴DBを集めてクラス全体の傾向をまとめる
+// 学生ごとに問題のパスが違っても同じ問題として数えられるよう、問題は section3/problem05 のような ID で突き合わせる
+
+// レポートに載せるよくあるエラーの数
+const TOP_ERRORS: usize = 10;
+
+// 1人分の履歴
+pub struct Student {
+    pub name: String,
+    pub outcomes: Vec<ProblemOutcome>,
+    // エラーの種類と回数
+    pub errors: Vec<(String, i64)>,
+    pub solve_times: Vec<SolveRecord>,
+}
+
+// 履歴DBを読み取り専用で読む (名前は submissions/<学生名>/history.db のディレクトリ名)
+pub fn load(path: &Path) -> rusqlite::Result<Student> {
+    let store = HistoryStore::open_read_only(path)?;
+    let name = path
+        .parent()
+        .and_then(Path::file_name)
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned();
+    Ok(Student {
+        name,
+        outcomes: store.problem_outcomes()?,
+        errors: store.frequent_errors(usize::MAX)?,
+        solve_times: store.solve_times()?,
+    })
+}
+
+// カリキュラムの問題なら ID、そうでなければパスの末尾2つ (ディレクトリ/ファイル名)
+pub fn problem_id(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    link::id_for(path).unwrap_or_else(|| {
+        let names: Vec<_> = path
+            .iter()
+            .rev()
+            .take(2)
+            .map(|name| name.to_string_lossy())
+            .collect();
+        names.into_iter().rev().collect::<Vec<_>>().join("/")
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ProblemSummary {
+    pub id: String,
+    // 実行したことのある学生の数
+    pub attempted: usize,
+    pub passed: usize,
+    pub runs: i64,
+    // 解けた学生の解答時間 (秒、短い順)
+    pub solve_seconds: Vec<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ErrorSummary {
+    pub signature: String,
+    // そのエラーを起こした学生の数
+    pub students: usize,
+    pub occurrences: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassReport {
+    pub students: Vec<String>,
+    pub problems: Vec<ProblemSummary>,
+    pub errors: Vec<ErrorSummary>,
+}
+
+pub fn aggregate(students: &[Student]) -> ClassReport {
+    let mut problems: BTreeMap<String, ProblemSummary> = BTreeMap::new();
+    let mut errors: BTreeMap<&str, ErrorSummary> = BTreeMap::new();
+
+    for student in students {
+        // 同じ問題を別のパスで2回解いていても1人として数える
+        let mut outcomes: BTreeMap<String, (i64, bool)> = BTreeMap::new();
+        for outcome in &student.outcomes {
+            let entry = outcomes.entry(problem_id(&outcome.file_path)).or_default();
+            entry.0 += outcome.runs;
+            entry.1 |= outcome.passed;
+        }
+        for (id, (runs, passed)) in outcomes {
+            let problem = problems
+                .entry(id.clone())
+                .or_insert_with(|| ProblemSummary {
+                    id,
+                    ..Default::default()
+                });
+            problem.attempted += 1;
+            problem.passed += usize::from(passed);
+            problem.runs += runs;
+        }
+
+        let mut solved = HashSet::new();
+        for solve in &student.solve_times {
+            let id = problem_id(&solve.file_path);
+            if solved.insert(id.clone())
+                && let Some(problem) = problems.get_mut(&id)
+            {
+                problem.solve_seconds.push(solve.active_seconds);
+            }
+        }
+
+        for (signature, count) in &student.errors {
+            let error = errors.entry(signature).or_insert_with(|| ErrorSummary {
+                signature: signature.clone(),
+                students: 0,
+                occurrences: 0,
+            });
+            error.students += 1;
+            error.occurrences += count;
+        }
+    }
+
+    let mut problems: Vec<ProblemSummary> = problems.into_values().collect();
+    for problem in &mut problems {
+        problem.solve_seconds.sort_unstable();
+    }
+    let mut errors: Vec<ErrorSummary> = errors.into_values().collect();
+    errors.sort_by(|a, b| {
+        (b.students, b.occurrences)
+            .cmp(&(a.students, a.occurrences))
+            .then_with(|| a.signature.cmp(&b.signature))
+    });
+    errors.truncate(TOP_ERRORS);
+
+    ClassReport {
+        students: students.iter().map(|s| s.name.clone()).collect(),
+        problems,
+        errors,
+    }
+}
+
+fn duration(seconds: i64) -> String {
+    solve_time::format_duration(Duration::from_secs(seconds.max(0) as u64))
+}
+
+fn percent(part: usize, whole: usize) -> String {
+    match whole {
+        0 => "-".to_string(),
+        _ => format!("{:.1}%", part as f64 * 100.0 / whole as f64),
+    }
+}
+
+// 講師向けの Markdown レポート
+pub fn render(report: &ClassReport) -> String {
+    let mut md = String::from("# クラスレポート\n\n");
+    md.push_str(&format!("- 学生数: {}\n\n", report.students.len()));
+
+    md.push_str("## 問題ごとの通過率\n\n");
+    md.push_str("| 問題 | 取り組んだ人数 | 解けた人数 | 通過率 | 平均実行回数 | 解答時間 (最短 / 中央値 / 最長) |\n");
+    md.push_str("| --- | ---: | ---: | ---: | ---: | --- |\n");
+    for problem in &report.problems {
+        let times = match problem.solve_seconds.as_slice() {
+            [] => "-".to_string(),
+            seconds => format!(
+                "{} / {} / {}",
+                duration(seconds[0]),
+                duration(seconds[seconds.len() / 2]),
+                duration(seconds[seconds.len() - 1])
+            ),
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {:.1} | {} |\n",
+            problem.id,
+            problem.attempted,
+            problem.passed,
+            percent(problem.passed, report.students.len()),
+            problem.runs as f64 / problem.attempted.max(1) as f64,
+            times
+        ));
+    }
+
+    md.push_str("\n## よくあるエラー\n\n");
+    if report.errors.is_empty() {
+        md.push_str("エラーの記録はありません\n");
+    }
+    for error in &report.errors {
+        md.push_str(&format!(
+            "- {} ({}人、{}回)\n",
+            error.signature, error.students, error.occurrences
+        ));
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(file_path: &str, runs: i64, passed: bool) -> ProblemOutcome {
+        ProblemOutcome {
+            file_path: file_path.to_string(),
+            runs,
+            passed,
+        }
+    }
+
+    fn solve(file_path: &str, active_seconds: i64) -> SolveRecord {
+        SolveRecord {
+            file_path: file_path.to_string(),
+            section: "section1".to_string(),
+            first_modified_at: 0,
+            solved_at: active_seconds,
+            active_seconds,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_matches_problems_across_students() {
+        let students = [
+            Student {
+                name: "alice".to_string(),
+                outcomes: vec![
+                    outcome("/home/alice/go/section1-basics/problem01_hello.go", 3, true),
+                    outcome("/home/alice/go/section1-basics/problem02_loop.go", 5, false),
+                ],
+                errors: vec![("undefined: x".to_string(), 4)],
+                solve_times: vec![solve(
+                    "/home/alice/go/section1-basics/problem01_hello.go",
+                    300,
+                )],
+            },
+            Student {
+                name: "bob".to_string(),
+                outcomes: vec![outcome(
+                    "C:/work/section1-basics/problem01_hello.go",
+                    1,
+                    true,
+                )],
+                errors: vec![
+                    ("undefined: x".to_string(), 1),
+                    ("missing return".to_string(), 2),
+                ],
+                solve_times: vec![solve("C:/work/section1-basics/problem01_hello.go", 60)],
+            },
+        ];
+
+        let report = aggregate(&students);
+        assert_eq!(report.students, ["alice", "bob"]);
+        assert_eq!(
+            report.problems,
+            [
+                ProblemSummary {
+                    id: "section1/problem01".to_string(),
+                    attempted: 2,
+                    passed: 2,
+                    runs: 4,
+                    solve_seconds: vec![60, 300],
+                },
+                ProblemSummary {
+                    id: "section1/problem02".to_string(),
+                    attempted: 1,
+                    passed: 0,
+                    runs: 5,
+                    solve_seconds: vec![],
+                },
+            ]
+        );
+        assert_eq!(report.errors[0].signature, "undefined: x");
+        assert_eq!(
+            (report.errors[0].students, report.errors[0].occurrences),
+            (2, 5)
+        );
+
+        let md = render(&report);
+        assert!(
+            md.contains("| section1/problem01 | 2 | 2 | 100.0% | 2.0 | 1分0秒 / 5分0秒 / 5分0秒 |"),
+            "{}",
+            md
+        );
+        assert!(md.contains("- missing return (1人、2回)"));
+        assert_eq!(problem_id("/tmp/extra/a.py"), "extra/a.py");
+    }
+}
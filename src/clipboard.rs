@@ -0,0 +1,81 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use which::which;
+
+// copy コマンドでコピーするもの
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CopyTarget {
+    // 直前の実行のエラー出力 (stderr)
+    Error,
+    // 直前の実行の標準出力
+    Output,
+    // 直前に実行したファイルの内容
+    File,
+}
+
+// OS ごとのクリップボードに書き込むコマンドの候補 (先に見つかったものを使う)
+fn candidates(os: &str, wayland: bool) -> Vec<(&'static str, &'static [&'static str])> {
+    match os {
+        "macos" => vec![("pbcopy", &[])],
+        "windows" => vec![("clip", &[])],
+        _ => {
+            let mut candidates: Vec<(&'static str, &'static [&'static str])> = vec![
+                ("xclip", &["-selection", "clipboard"]),
+                ("xsel", &["--clipboard", "--input"]),
+            ];
+            if wayland {
+                candidates.insert(0, ("wl-copy", &[]));
+            }
+            candidates
+        }
+    }
+}
+
+pub fn copy(text: &str) -> io::Result<()> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let Some((program, args)) = candidates(std::env::consts::OS, wayland)
+        .into_iter()
+        .find(|(program, _)| which(program).is_ok())
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "クリップボードのコマンドが見つかりません (pbcopy / wl-copy / xclip / xsel / clip)",
+        ));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} が失敗しました ({})",
+            program, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_prefer_wayland() {
+        let names = |os, wayland| {
+            candidates(os, wayland)
+                .into_iter()
+                .map(|(program, _)| program)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("linux", true), ["wl-copy", "xclip", "xsel"]);
+        assert_eq!(names("linux", false), ["xclip", "xsel"]);
+        assert_eq!(names("macos", false), ["pbcopy"]);
+    }
+}
@@ -0,0 +1,117 @@
+use super::exam::ensure_not_in_exam;
+use super::{Result, current_problem};
+use crate::data_dir::DataDir;
+use crate::history::{self, HintKind, HistoryStore};
+use crate::llm::{self, LlmConfig};
+use crate::{ask, knowledge, problem, review};
+use std::path::Path;
+use std::sync::Mutex;
+
+// 答えにつながる助け (レビュー・質問・ヒント)。どれも試験中は使えない
+
+pub async fn review(file: &Path, data_dir: &DataDir) -> Result {
+    ensure_not_in_exam(&HistoryStore::open(&data_dir.history_db())?)?;
+    let config = LlmConfig::from_env().ok_or_else(LlmConfig::not_configured_message)?;
+    let history = Mutex::new(HistoryStore::open(&data_dir.history_db())?);
+    let source = std::fs::read_to_string(file)?;
+    let result = review::run(&config, Some(&history), file, &source)
+        .await
+        .map_err(|e| e.to_string())?;
+    review::print(&result);
+    Ok(())
+}
+
+// question がなければ過去の質問と回答を表示する
+pub async fn ask(question: Option<&str>, file: Option<&Path>, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    ensure_not_in_exam(&store)?;
+    let Some(question) = question else {
+        for q in store.questions(20)? {
+            println!("Q: {} ({})", q.question, q.file_path);
+            println!("A: {}\n", q.answer);
+        }
+        return Ok(());
+    };
+
+    let file_path = current_problem(&store, file)?;
+    let source = std::fs::read_to_string(&file_path)?;
+    let last_failure = store.last_failure(&file_path)?;
+    store.record_hint(&file_path, HintKind::Ask, chrono::Utc::now().timestamp())?;
+
+    // LLM が設定されていなければ同梱のヒント集から探す
+    let Some(config) = LlmConfig::from_env() else {
+        println!(
+            "{}。オフラインのヒントを表示します\n",
+            LlmConfig::not_configured_message()
+        );
+        let text = format!(
+            "{}\n{}",
+            question,
+            last_failure
+                .as_ref()
+                .map(|r| r.output_preview.as_str())
+                .unwrap_or_default()
+        );
+        print_hints(&knowledge::lookup(
+            &text,
+            problem::parse(&source).topic.as_deref(),
+        ));
+        return Ok(());
+    };
+    let language = Path::new(&file_path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let prompt = ask::user_prompt(
+        question,
+        &problem::parse(&source),
+        language,
+        &source,
+        last_failure.as_ref(),
+    );
+
+    println!("=== {} ===", file_path);
+    let answer = tokio::task::spawn_blocking(move || {
+        llm::stream(&config, ask::SYSTEM_PROMPT, &prompt, |chunk| {
+            print!("{}", chunk);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        })
+    })
+    .await?
+    .map_err(|e| e.to_string())?;
+    println!();
+
+    store.record_question(&history::Question {
+        file_path,
+        question: question.to_string(),
+        answer,
+        asked_at: chrono::Utc::now().timestamp(),
+    })?;
+    Ok(())
+}
+
+pub fn hint(file: Option<&Path>, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    ensure_not_in_exam(&store)?;
+    let file_path = current_problem(&store, file)?;
+    let header = problem::parse(&std::fs::read_to_string(&file_path)?);
+    let last_failure = store.last_failure(&file_path)?;
+    store.record_hint(&file_path, HintKind::Hint, chrono::Utc::now().timestamp())?;
+    print_hints(&knowledge::lookup(
+        last_failure
+            .as_ref()
+            .map(|r| r.output_preview.as_str())
+            .unwrap_or_default(),
+        header.topic.as_deref(),
+    ));
+    Ok(())
+}
+
+fn print_hints(entries: &[&knowledge::Entry]) {
+    if entries.is_empty() {
+        println!("該当するヒントが見つかりませんでした");
+    }
+    for entry in entries {
+        println!("{}\n", entry.body);
+    }
+}
@@ -0,0 +1,156 @@
+use super::{Result, load_config};
+use crate::config::{self, Languages};
+use crate::data_dir::DataDir;
+use crate::{dom, handler, link};
+use clap::Subcommand;
+use std::path::Path;
+use which::which;
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// 実際に使われる設定を表示する
+    Show {
+        /// 値をどこから読んだか (既定値・config.toml・環境変数・.env) も表示する
+        #[arg(long)]
+        origin: bool,
+    },
+    /// 1つの項目の値を表示する (例: config get languages.go.flags)
+    Get { key: String },
+    /// 設定ファイルの項目を書き換える (例: config set languages.go.flags "-race -v")
+    Set {
+        key: String,
+
+        /// フラグは空白区切り
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// 設定ファイルを $VISUAL / $EDITOR で開き、閉じたら内容を確かめる
+    Edit,
+    /// 設定ファイルと環境変数の設定に誤りがないか確かめる
+    Validate,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HandlersCommand {
+    /// 拡張子ごとに、この環境で実際に使うコマンド・引数と設定元を表示する
+    List,
+}
+
+pub fn config(action: &ConfigCommand, data_dir: &DataDir) -> Result {
+    match action {
+        ConfigCommand::Show { origin } => show(data_dir, *origin),
+        ConfigCommand::Get { key } => {
+            let loaded = config::load_with_origins(&data_dir.config_file())?;
+            match config::get(&loaded, key)? {
+                Some(toml::Value::String(value)) => println!("{}", value),
+                Some(value) => println!("{}", value),
+                None => return Err(format!("設定されていません: {}", key).into()),
+            }
+            Ok(())
+        }
+        ConfigCommand::Set { key, value } => {
+            let path = data_dir.config_file();
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e.into()),
+            };
+            std::fs::write(&path, config::set(&path, &text, key, value)?)?;
+            println!("{} を書き換えました: {}", path.display(), key);
+            Ok(())
+        }
+        ConfigCommand::Edit => {
+            let path = data_dir.config_file();
+            if !path.exists() {
+                std::fs::write(&path, "")?;
+            }
+            link::launch_editor(&path)?;
+            validate(&path)
+        }
+        ConfigCommand::Validate => validate(&data_dir.config_file()),
+    }
+}
+
+pub fn handlers(action: &HandlersCommand, data_dir: &DataDir) -> Result {
+    let HandlersCommand::List = action;
+    let config = load_config(data_dir);
+    let (_, languages) = config.for_dir(Path::new("."));
+    for handler in handler::Registry::new(&config.handlers).handlers() {
+        println!("{}", format_handler(handler, &languages));
+    }
+    Ok(())
+}
+
+fn show(data_dir: &DataDir, origin: bool) -> Result {
+    let loaded = config::load_with_origins(&data_dir.config_file())?;
+    for entry in &loaded.entries {
+        let value = entry
+            .value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "(未設定)".to_string());
+        if origin {
+            println!("{} = {}  # {}", entry.key, value, entry.origin);
+        } else {
+            println!("{} = {}", entry.key, value);
+        }
+    }
+    for (name, profile) in &loaded.config.profiles {
+        println!(
+            "profiles.{}.dir = {:?}",
+            name,
+            profile.dir.display().to_string()
+        );
+    }
+    Ok(())
+}
+
+fn validate(path: &Path) -> Result {
+    let loaded = config::load_with_origins(path)?;
+    let problems = config::check(&loaded.config);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("❌ {}", problem);
+        }
+        return Err(format!("設定に誤りがあります ({}件)", problems.len()).into());
+    }
+    println!("✅ 設定に誤りはありません: {}", path.display());
+    Ok(())
+}
+
+// .go  go (/usr/local/go/bin/go)  引数: -race  [組み込み]
+fn format_handler(handler: &handler::Handler, languages: &Languages) -> String {
+    let args = match (handler.source, handler.extension.as_str()) {
+        (handler::Source::BuiltIn, "go") => languages.go.flags.clone().unwrap_or_default(),
+        (handler::Source::BuiltIn, "py") => languages.python.flags.clone().unwrap_or_default(),
+        _ => handler.args.clone(),
+    };
+    // ブラウザはディストリビューションによってコマンド名が違う
+    let mut candidates = vec![handler.command.as_str()];
+    if handler.source == handler::Source::BuiltIn && handler.extension == "html" {
+        candidates.extend(dom::BROWSERS);
+    }
+    let (command, path) = candidates
+        .iter()
+        .find_map(|name| {
+            which(name)
+                .ok()
+                .map(|path| (*name, path.display().to_string()))
+        })
+        .unwrap_or((handler.command.as_str(), "見つかりません".to_string()));
+    format!(
+        ".{}  {} ({}){}  [{}]",
+        handler.extension,
+        command,
+        path,
+        if args.is_empty() {
+            String::new()
+        } else {
+            format!("  引数: {}", args.join(" "))
+        },
+        match handler.source {
+            handler::Source::BuiltIn => "組み込み",
+            handler::Source::Config => "config.toml",
+        }
+    )
+}
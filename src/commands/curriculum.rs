@@ -0,0 +1,129 @@
+use super::Result;
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use crate::{assets, link, problem, upgrade};
+use log::info;
+use std::path::Path;
+
+// 埋め込みのカリキュラムから問題を書き出す・作り直す・新しい版を取り込む
+
+pub fn init(dir: &Path, force: bool, data_dir: &DataDir) -> Result {
+    let summary = assets::extract_go_curriculum(dir, force)?;
+    info!(
+        "カリキュラムを書き出しました: {} (作成 {}件, スキップ {}件)",
+        dir.display(),
+        summary.written,
+        summary.skipped
+    );
+    // 問題の版を記録しておく (カリキュラムが更新されたときの upgrade-problem に使う)
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    upgrade::record_extracted(&store, dir, chrono::Utc::now().timestamp())?;
+    Ok(())
+}
+
+pub fn restore_problem(id: &str, dir: &Path, force: bool, data_dir: &DataDir) -> Result {
+    let (section, problem) =
+        link::parse(id).ok_or_else(|| format!("問題の指定が正しくありません: {}", id))?;
+    let (template, contents) = assets::find_go_problem(&section, &problem)
+        .ok_or_else(|| format!("カリキュラムにない問題です: {}/{}", section, problem))?;
+    // 既存のファイル、なければ同じセクションのディレクトリ (名前を変えていてもよい) に書く
+    let path = match link::resolve(dir, &section, &problem) {
+        Some(path) if !force => {
+            return Err(format!(
+                "ファイルが残っています: {} (--force で上書きできます)",
+                path.display()
+            )
+            .into());
+        }
+        Some(path) => path,
+        None => link::section_dir(dir, &section)
+            .unwrap_or_else(|| dir.join(template.parent().unwrap_or(Path::new(""))))
+            .join(template.file_name().unwrap_or_default()),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, contents)?;
+
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let file_path = std::path::absolute(&path)?.display().to_string();
+    store.clear_removed(&file_path)?;
+    store.record_problem_version(
+        &file_path,
+        &upgrade::problem_version(contents),
+        chrono::Utc::now().timestamp(),
+    )?;
+    println!("元の問題を書き出しました: {}", path.display());
+    Ok(())
+}
+
+pub async fn upgrade_problem(id: &str, dir: &Path, data_dir: &DataDir) -> Result {
+    let (section, problem) =
+        link::parse(id).ok_or_else(|| format!("問題の指定が正しくありません: {}", id))?;
+    let (_, contents) = assets::find_go_problem(&section, &problem)
+        .ok_or_else(|| format!("カリキュラムにない問題です: {}/{}", section, problem))?;
+    let path = link::resolve(dir, &section, &problem).ok_or_else(|| {
+        format!(
+            "問題が見つかりません: {}/{} ({})",
+            section,
+            problem,
+            dir.display()
+        )
+    })?;
+    let file_path = std::path::absolute(&path)?.display().to_string();
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let base = store.problem_version(&file_path)?.ok_or_else(|| {
+        format!(
+            "書き出したときの版の記録がありません: {} (init か restore-problem で書き出した問題だけ更新できます)",
+            path.display()
+        )
+    })?;
+    let latest = upgrade::problem_version(contents);
+    if base.version == latest.version {
+        println!("最新の版です (v{}): {}", latest.version, path.display());
+        return Ok(());
+    }
+
+    let mine = std::fs::read_to_string(&path)?;
+    let merged = upgrade::merge(&mine, &base.template, &latest.template).await?;
+    std::fs::write(&path, &merged.text)?;
+    store.record_problem_version(&file_path, &latest, chrono::Utc::now().timestamp())?;
+    if merged.conflicts == 0 {
+        println!(
+            "✅ v{} → v{} に更新しました: {}",
+            base.version,
+            latest.version,
+            path.display()
+        );
+    } else {
+        println!(
+            "⚠ v{} → v{} に更新しました。{}箇所で解答と新しい版の変更が重なっています (<<<<<<< から >>>>>>> の間を直してください): {}",
+            base.version,
+            latest.version,
+            merged.conflicts,
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+pub fn open(target: &str, dir: &Path) -> Result {
+    let (section, problem) =
+        link::parse(target).ok_or_else(|| format!("問題の指定が正しくありません: {}", target))?;
+    let path = link::resolve(dir, &section, &problem).ok_or_else(|| {
+        format!(
+            "問題が見つかりません: {}/{} ({})",
+            section,
+            problem,
+            dir.display()
+        )
+    })?;
+    let header = problem::parse(&std::fs::read_to_string(&path)?);
+    println!(
+        "=== {} ===",
+        header.title.as_deref().unwrap_or("(タイトルなし)")
+    );
+    println!("{}", path.display());
+    link::launch_editor(&path)?;
+    Ok(())
+}
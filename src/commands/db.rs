@@ -0,0 +1,119 @@
+use super::Result;
+use crate::backup;
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// 履歴DBをバックアップする (監視中でもよい)
+    Backup {
+        /// 書き出し先 (省略するとデータディレクトリの backups/manual-<日時>.db)
+        path: Option<PathBuf>,
+    },
+    /// バックアップから履歴DBを復元する (今の履歴DBは backups/before-restore-<日時>.db に残す)
+    Restore { path: PathBuf },
+    /// 履歴DBが壊れていないか調べる (PRAGMA integrity_check)
+    Check {
+        /// 壊れていれば読み出せる行を新しい DB に移して置き換える (壊れた DB は backups/corrupt-<日時>.db に残す)
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+pub fn db(action: &DbCommand, data_dir: &DataDir) -> Result {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    match action {
+        DbCommand::Backup { path } => backup(path.as_deref(), data_dir, &stamp),
+        DbCommand::Restore { path } => restore(path, data_dir, &stamp),
+        // 壊れた DB はマイグレーションで失敗することがあるので HistoryStore を通さずに開く
+        DbCommand::Check { repair } => check(data_dir, *repair, &stamp),
+    }
+}
+
+fn backup(path: Option<&Path>, data_dir: &DataDir, stamp: &str) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            std::fs::create_dir_all(data_dir.backups_dir())?;
+            data_dir.backups_dir().join(format!("manual-{}.db", stamp))
+        }
+    };
+    if path.exists() {
+        return Err(format!("ファイルが既にあります: {}", path.display()).into());
+    }
+    store.backup_to(&path)?;
+    println!("履歴DBをバックアップしました: {}", path.display());
+    Ok(())
+}
+
+fn restore(path: &Path, data_dir: &DataDir, stamp: &str) -> Result {
+    let mut store = HistoryStore::open(&data_dir.history_db())?;
+    backup::verify(path)
+        .map_err(|e| format!("バックアップとして使えません: {} ({})", path.display(), e))?;
+    std::fs::create_dir_all(data_dir.backups_dir())?;
+    let previous = data_dir
+        .backups_dir()
+        .join(format!("before-restore-{}.db", stamp));
+    store.backup_to(&previous)?;
+    store.restore_from(path)?;
+    println!(
+        "履歴DBを復元しました: {} (復元前の履歴DB: {})",
+        path.display(),
+        previous.display()
+    );
+    Ok(())
+}
+
+// 履歴DBの整合性を調べ、repair なら読み出せる行を新しい DB に移して置き換える
+fn check(data_dir: &DataDir, repair: bool, stamp: &str) -> Result {
+    let path = data_dir.history_db();
+    if !path.exists() {
+        println!("履歴DBはまだありません: {}", path.display());
+        return Ok(());
+    }
+    let problems = backup::integrity_problems(&path)?;
+    if problems.is_empty() {
+        println!("✅ 履歴DBは壊れていません: {}", path.display());
+        return Ok(());
+    }
+    println!("❌ 履歴DBが壊れています: {}", path.display());
+    for problem in problems.iter().take(10) {
+        println!("  {}", problem);
+    }
+    if problems.len() > 10 {
+        println!("  ほか {}件", problems.len() - 10);
+    }
+    if !repair {
+        println!(
+            "db check --repair で読み出せる記録を新しい DB に移せます (監視を止めてから実行してください)"
+        );
+        return Ok(());
+    }
+
+    let recovered = path.with_extension("db.recovered");
+    if recovered.exists() {
+        std::fs::remove_file(&recovered)?;
+    }
+    let results = backup::salvage(&path, &recovered)?;
+    std::fs::create_dir_all(data_dir.backups_dir())?;
+    let corrupt = data_dir.backups_dir().join(format!("corrupt-{}.db", stamp));
+    std::fs::rename(&path, &corrupt)?;
+    std::fs::rename(&recovered, &path)?;
+    for result in &results {
+        if result.skipped > 0 {
+            println!(
+                "  {}: {}行を移しました (読み出せない箇所 {}件)",
+                result.table, result.rows, result.skipped
+            );
+        }
+    }
+    println!(
+        "✅ {}行を新しい履歴DBに移しました (壊れた DB: {})",
+        results.iter().map(|result| result.rows).sum::<usize>(),
+        corrupt.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use super::Result;
+use crate::data_dir::DataDir;
+use std::path::Path;
+
+// config が true ならデータディレクトリの設定ファイルも配る
+pub async fn deploy(target: &str, dir: &Path, config: bool, data_dir: &DataDir) -> Result {
+    let config_file = data_dir.config_file();
+    let config_file = match config {
+        true if config_file.is_file() => Some(config_file.as_path()),
+        true => {
+            return Err(format!("設定ファイルがありません: {}", config_file.display()).into());
+        }
+        false => None,
+    };
+    let entries = crate::deploy::entries(dir, config_file)?;
+    let problems = match crate::deploy::Target::parse(target) {
+        crate::deploy::Target::Local(path) => crate::deploy::deploy_local(&entries, &path)?,
+        crate::deploy::Target::Remote(remote) => {
+            crate::deploy::deploy_remote(&entries, dir, &remote, config_file).await?
+        }
+    };
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("❌ {}", problem);
+        }
+        return Err(format!(
+            "配り先のファイルがマニフェストと一致しません ({}件)",
+            problems.len()
+        )
+        .into());
+    }
+    println!(
+        "📦 {}ファイルを配りました: {} ({} で確認済み)",
+        entries.len(),
+        target,
+        crate::deploy::MANIFEST
+    );
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use super::{Result, load_config};
+use crate::availability::Availability;
+use crate::data_dir::DataDir;
+use crate::handler;
+use std::path::Path;
+
+pub async fn doctor(dir: Option<&Path>, refresh: bool, data_dir: &DataDir) -> Result {
+    let handlers = handler::Registry::new(&load_config(data_dir).handlers);
+    let mut availability = Availability::open(data_dir.availability_file());
+    crate::doctor::run(dir, &handlers, &mut availability, refresh).await?;
+    Ok(())
+}
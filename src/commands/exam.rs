@@ -0,0 +1,183 @@
+use super::{Result, load_config, problem_files};
+use crate::data_dir::DataDir;
+use crate::history::{self, HistoryStore};
+use crate::{handler, sandbox, solve_time};
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Subcommand, Debug)]
+pub enum ExamCommand {
+    /// 試験を始める (試験中は hint / ask / review / 模範解答の表示を使えない)
+    Start {
+        /// 問題のあるディレクトリ
+        assignment: PathBuf,
+
+        /// 制限時間 (分、1〜1440)
+        #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(i64).range(1..=crate::exam::MAX_MINUTES))]
+        minutes: i64,
+    },
+    /// 試験中なら残り時間を表示する
+    Status,
+    /// 試験を終えて、結果レポートを書き出す (--key-file なら講師の鍵で封印する)
+    ///
+    /// レポートは学習者が書き込める履歴 DB から作るので、封印で確かめられるのは終了後に書き換えられていないことだけ。
+    /// 講師が自分で鍵を持ち込んで実行する
+    Finish {
+        /// 講師が持つ鍵ファイル (学習者が読み書きできる場所に置かない。データディレクトリ・課題のディレクトリにあるものはエラー)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+    /// 結果レポートが書き換えられていないか、封印に使った鍵で確かめる
+    Verify {
+        report: PathBuf,
+
+        #[arg(long)]
+        key_file: PathBuf,
+    },
+}
+
+pub fn exam(action: &ExamCommand, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let now = chrono::Utc::now().timestamp();
+    match action {
+        ExamCommand::Start {
+            assignment,
+            minutes,
+        } => start(&store, assignment, *minutes, now),
+        ExamCommand::Status => {
+            status(&store, now)?;
+            Ok(())
+        }
+        ExamCommand::Finish { key_file } => finish(&store, key_file.as_deref(), data_dir, now),
+        ExamCommand::Verify { report, key_file } => verify(report, key_file),
+    }
+}
+
+// 試験中は答えにつながる機能を使えない
+pub fn ensure_not_in_exam(store: &HistoryStore) -> Result {
+    match store.active_exam()? {
+        Some(_) => Err("試験中は使えません (exam finish で試験を終了できます)".into()),
+        None => Ok(()),
+    }
+}
+
+fn start(store: &HistoryStore, assignment: &Path, minutes: i64, now: i64) -> Result {
+    if let Some(exam) = store.active_exam()? {
+        return Err(format!(
+            "試験中です: {} (exam finish で終了してください)",
+            exam.assignment
+        )
+        .into());
+    }
+    if !assignment.is_dir() {
+        return Err(format!("ディレクトリが存在しません: {}", assignment.display()).into());
+    }
+    let assignment = std::path::absolute(assignment)?.display().to_string();
+    store.start_exam(&history::Exam {
+        id: 0,
+        assignment: assignment.clone(),
+        started_at: now,
+        deadline: now + minutes * 60,
+        finished_at: None,
+    })?;
+    println!("試験を開始しました (制限時間 {}分)", minutes);
+    println!("試験中は hint / ask / review / show --solution を使えません");
+    if sandbox::is_available() {
+        println!("課題の問題はネットワークに接続できない状態で実行します");
+    } else {
+        println!("⚠ このマシンではサンドボックスを使えないため、課題の問題は通常どおり実行します");
+    }
+    println!(
+        "監視を始めてください: learning-programming --dir {}",
+        assignment
+    );
+    Ok(())
+}
+
+fn status(store: &HistoryStore, now: i64) -> rusqlite::Result<()> {
+    match store.active_exam()? {
+        Some(exam) if crate::exam::is_over(&exam, now) => {
+            println!(
+                "時間切れです: {} (exam finish で結果を確定してください)",
+                exam.assignment
+            )
+        }
+        Some(exam) => println!(
+            "試験中: {} (残り {})",
+            exam.assignment,
+            solve_time::format_duration(Duration::from_secs(crate::exam::remaining_seconds(
+                &exam, now
+            ) as u64))
+        ),
+        None => println!("試験中ではありません"),
+    }
+    Ok(())
+}
+
+fn finish(store: &HistoryStore, key_file: Option<&Path>, data_dir: &DataDir, now: i64) -> Result {
+    let mut exam = store.active_exam()?.ok_or("試験中ではありません")?;
+    if let Some(key_file) = key_file {
+        crate::exam::check_key_location(key_file, &[data_dir.root(), Path::new(&exam.assignment)])?;
+    }
+    // 鍵ファイルを読めないまま試験を終わらせない
+    let key = key_file.map(crate::exam::read_key).transpose()?;
+    store.finish_exam(exam.id, now)?;
+    exam.finished_at = Some(now);
+
+    let problems = problem_files(
+        Path::new(&exam.assignment),
+        &handler::Registry::new(&load_config(data_dir).handlers),
+    )?;
+    let records = store.executions_between(exam.started_at, exam.deadline)?;
+    let report = crate::exam::report(&exam, &crate::exam::results(&exam, &problems, &records));
+    let report = match &key {
+        Some(key) => crate::exam::seal(&report, key),
+        None => report,
+    };
+
+    std::fs::create_dir_all(data_dir.exams_dir())?;
+    let path = data_dir.exams_dir().join(format!("exam-{}.txt", exam.id));
+    std::fs::write(&path, &report)?;
+    print!("{}", report);
+    println!("\nレポートを保存しました: {}", path.display());
+    if key.is_none() {
+        println!(
+            "鍵を指定していないので封印していません (書き換えを検出するには --key-file を指定してください)"
+        );
+    }
+    Ok(())
+}
+
+fn verify(report: &Path, key_file: &Path) -> Result {
+    let key = crate::exam::read_key(key_file)?;
+    match crate::exam::verify(&std::fs::read_to_string(report)?, &key) {
+        Some(true) => {
+            println!("✅ レポートは書き換えられていません");
+            Ok(())
+        }
+        Some(false) => Err("レポートが書き換えられているか、封印した鍵と違います".into()),
+        None => Err("封印されたレポートではありません".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_blocks_only_during_exam() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert!(ensure_not_in_exam(&store).is_ok());
+
+        let assignment = tempfile::tempdir().unwrap();
+        start(&store, assignment.path(), 30, 1_000).unwrap();
+        assert!(ensure_not_in_exam(&store).is_err());
+        // 2つ目の試験は始められない
+        assert!(start(&store, assignment.path(), 30, 1_100).is_err());
+
+        let exam = store.active_exam().unwrap().unwrap();
+        store.finish_exam(exam.id, 2_000).unwrap();
+        assert!(ensure_not_in_exam(&store).is_ok());
+    }
+}
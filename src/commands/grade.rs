@@ -0,0 +1,150 @@
+use super::exam::ensure_not_in_exam;
+use super::{Result, load_config, problem_files, replay_journal, write_summary};
+use crate::availability::Availability;
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use crate::journal::Journal;
+use crate::runner::{self, RunContext, run_if_target_file};
+use crate::{handler, junit, mutation};
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub async fn grade(
+    dir: &Path,
+    junit: Option<&Path>,
+    github_annotations: bool,
+    summary: Option<&Path>,
+    data_dir: &DataDir,
+) -> Result {
+    let history = match HistoryStore::open(&data_dir.history_db()) {
+        Ok(store) => Some(Mutex::new(store)),
+        Err(e) => {
+            error!("履歴DBを開けません: {} (実行履歴は保存されません)", e);
+            None
+        }
+    };
+    let journal = Journal::new(data_dir.journal_dir());
+    if let Some(store) = history.as_ref().and_then(|h| h.lock().ok()) {
+        replay_journal(&journal, &store);
+    }
+    let config = load_config(data_dir);
+    let (profile, languages) = config.for_dir(dir);
+    if let Some(profile) = profile {
+        info!("プロファイル: {}", profile);
+    }
+    let ctx = Arc::new(RunContext {
+        history,
+        journal: Some(journal),
+        batch: true,
+        go_flags: languages.go.flags.clone().unwrap_or_default(),
+        python_args: languages.python.flags.clone().unwrap_or_default(),
+        go_timeout: languages.go.timeout(),
+        python_timeout: languages.python.timeout(),
+        handlers: handler::Registry::new(&config.handlers),
+        availability: Mutex::new(Availability::open(data_dir.availability_file())),
+        ..Default::default()
+    });
+    let started_at = chrono::Utc::now().timestamp();
+
+    let mut problems = Vec::new();
+    let root = std::path::absolute(dir)?;
+    for path in problem_files(dir, &ctx.handlers)? {
+        let path = PathBuf::from(path);
+        let outcome = run_if_target_file(path.clone(), ctx.clone()).await;
+        // レポートには課題ディレクトリからの相対パスを書く
+        let path = path
+            .strip_prefix(&root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        problems.push(junit::GradedProblem { path, outcome });
+    }
+
+    write_summary(&ctx, summary, "grade", started_at);
+
+    let passed = problems
+        .iter()
+        .filter(|p| p.outcome.as_ref().is_some_and(|o| o.success))
+        .count();
+    println!("=== 採点: {}/{}問 成功 ===", passed, problems.len());
+
+    if let Some(junit) = junit {
+        let suite = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.display().to_string());
+        std::fs::write(junit, junit::junit_xml(&suite, &problems))?;
+        println!("JUnit XML を書き出しました: {}", junit.display());
+    }
+    if github_annotations {
+        for annotation in junit::github_annotations(&problems) {
+            println!("{}", annotation);
+        }
+    }
+    if passed < problems.len() {
+        return Err(format!("{}問が成功しませんでした", problems.len() - passed).into());
+    }
+    Ok(())
+}
+
+// 解けた解答にバグを1つ入れて、採点で見つかるものをバグ探しの問題にする
+pub async fn challenge(problem: &Path, hint: bool, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    ensure_not_in_exam(&store)?;
+    let problem = std::path::absolute(problem)?;
+    let extension = problem
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let source = std::fs::read_to_string(&problem)?;
+    let Some(mutants) = mutation::mutants(&source, extension) else {
+        return Err("バグ探しの問題を作れるのは Go と Python の解答だけです".into());
+    };
+
+    // 元の解答は変えず、問題のディレクトリごと複製した先にバグを入れる
+    let dir = problem.parent().unwrap_or(Path::new("."));
+    let workspace = data_dir
+        .challenges_dir()
+        .join(dir.file_name().unwrap_or_default());
+    if workspace.exists() {
+        std::fs::remove_dir_all(&workspace)?;
+    }
+    mutation::copy_dir(dir, &workspace)?;
+    let target = workspace.join(problem.file_name().unwrap_or_default());
+
+    let config = load_config(data_dir);
+    let (_, languages) = config.for_dir(dir);
+    let ctx = RunContext {
+        history: Some(Mutex::new(store)),
+        go_flags: languages.go.flags.clone().unwrap_or_default(),
+        python_args: languages.python.flags.clone().unwrap_or_default(),
+        go_timeout: languages.go.timeout(),
+        python_timeout: languages.python.timeout(),
+        handlers: handler::Registry::new(&config.handlers),
+        availability: Mutex::new(Availability::open(data_dir.availability_file())),
+        ..Default::default()
+    };
+    println!("元の解答を採点しています: {}", problem.display());
+    if runner::passes(&target, &ctx).await != Some(true) {
+        return Err("元の解答が採点を通りません (バグ探しの問題は解けた問題から作ります)".into());
+    }
+
+    // 毎回同じバグにならないよう、試す順番の始まりを変える
+    let start = chrono::Utc::now().timestamp_subsec_nanos() as usize;
+    println!("バグを入れた解答を {}個まで試します", mutation::TRIES);
+    for index in (0..mutants.len()).take(mutation::TRIES) {
+        let mutant = &mutants[(start + index) % mutants.len()];
+        std::fs::write(&target, &mutant.source)?;
+        if runner::passes(&target, &ctx).await == Some(false) {
+            println!("🐛 バグを1つ入れた解答を作りました: {}", target.display());
+            println!("focus {} で実行しながら直してください", target.display());
+            if hint {
+                println!("ヒント: {}行目", mutant.line);
+            }
+            return Ok(());
+        }
+    }
+    std::fs::remove_dir_all(&workspace)?;
+    Err("採点で見つかるバグを作れませんでした (learn:check や learn:case を増やすと作れることがあります)".into())
+}
@@ -0,0 +1,220 @@
+use super::{Result, format_time};
+use crate::clipboard::{self, CopyTarget};
+use crate::data_dir::DataDir;
+use crate::history::{self, HistoryStore};
+use crate::{problem, snapshot, story};
+use clap::Subcommand;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommand {
+    /// 実行記録にメモを付ける (例: history note 12 "ポインタレシーバを理解した")
+    Note {
+        /// history で表示される実行番号
+        id: i64,
+        text: String,
+    },
+    /// 実行記録を1件表示する
+    Show {
+        /// history で表示される実行番号
+        id: i64,
+
+        /// 出力の先頭部分の代わりに全文を表示する (監視時に --store-output で保存した場合)
+        #[arg(long)]
+        full: bool,
+    },
+    /// メモ・ファイルパス・出力から実行記録を探す
+    Search {
+        query: String,
+
+        /// 表示する件数
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+
+        /// この位置より古い記録を表示する
+        #[arg(long)]
+        before: Option<history::Cursor>,
+    },
+}
+
+pub fn history(
+    limit: usize,
+    before: Option<history::Cursor>,
+    action: Option<&HistoryCommand>,
+    data_dir: &DataDir,
+) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    match action {
+        None => print_page(&store.page(limit, before)?, limit),
+        Some(HistoryCommand::Note { id, text }) => {
+            if !store.set_note(*id, text)? {
+                return Err(format!("実行 #{} が見つかりません", id).into());
+            }
+            println!("#{} にメモを保存しました", id);
+        }
+        Some(HistoryCommand::Show { id, full }) => show(&store, *id, *full)?,
+        Some(HistoryCommand::Search {
+            query,
+            limit,
+            before,
+        }) => print_page(&store.search(query, *limit, *before)?, *limit),
+    }
+    Ok(())
+}
+
+fn show(store: &HistoryStore, id: i64, full: bool) -> Result {
+    let record = store
+        .execution(id)?
+        .ok_or_else(|| format!("実行 #{} が見つかりません", id))?;
+    print_record(&record);
+    match store.full_output(id)?.filter(|_| full) {
+        Some((stdout, stderr)) => {
+            println!("=== 標準出力 ===============\n");
+            println!("{}", stdout);
+            println!("=== 標準エラー出力 =========\n");
+            println!("{}", stderr);
+        }
+        None => {
+            if full {
+                println!(
+                    "出力の全文は保存されていません (監視時に --store-output を付けると保存されます)"
+                );
+            }
+            println!("=== 出力 (先頭) ============\n");
+            println!("{}", record.output_preview);
+        }
+    }
+    Ok(())
+}
+
+pub async fn playback(problem: &Path, delay: u64, full: bool, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let file_path = std::path::absolute(problem)?.display().to_string();
+    let snapshots = store.snapshots(&file_path)?;
+    if snapshots.is_empty() {
+        return Err(format!(
+            "記録がありません: {} (--snapshots を付けて監視すると記録されます)",
+            file_path
+        )
+        .into());
+    }
+
+    let versions = snapshot::replay(&snapshots);
+    for (i, (snapshot, version)) in snapshots.iter().zip(&versions).enumerate() {
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+        let taken_at = format_time(snapshot.taken_at);
+        let delta = &snapshot.delta;
+        println!(
+            "=== {}/{} {} (+{} -{}行) ===",
+            i + 1,
+            snapshots.len(),
+            taken_at,
+            delta.inserted.len(),
+            delta.removed.len()
+        );
+        if full {
+            println!("{}", version.trim_end());
+            continue;
+        }
+        for line in &delta.removed {
+            println!("{:>4} - {}", delta.start + 1, line.trim_end_matches('\n'));
+        }
+        for (offset, line) in delta.inserted.iter().enumerate() {
+            println!(
+                "{:>4} + {}",
+                delta.start + offset + 1,
+                line.trim_end_matches('\n')
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn story(problem: &Path, output: Option<&Path>, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let file_path = std::path::absolute(problem)?.display().to_string();
+    let source = std::fs::read_to_string(problem)?;
+    let solve = store
+        .solve_times()?
+        .into_iter()
+        .find(|record| record.file_path == file_path);
+    let hints = store.hints_used(&file_path, solve.as_ref().map_or(i64::MAX, |s| s.solved_at))?;
+
+    let markdown = story::render(&story::Story {
+        file_path: &file_path,
+        header: &problem::parse(&source),
+        source: &source,
+        language: problem
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default(),
+        executions: &store.executions_for(&file_path)?,
+        snapshots: &store.snapshots(&file_path)?,
+        solve: solve.as_ref(),
+        hints: &hints,
+    });
+    match output {
+        Some(output) => {
+            std::fs::write(output, markdown)?;
+            println!("書き出しました: {}", output.display());
+        }
+        None => print!("{}", markdown),
+    }
+    Ok(())
+}
+
+pub fn copy(what: CopyTarget, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let last = store.last_output()?.ok_or("まだ実行の記録がありません")?;
+    let text = match what {
+        CopyTarget::Error => last.stderr,
+        CopyTarget::Output => last.stdout,
+        CopyTarget::File => std::fs::read_to_string(&last.file_path)?,
+    };
+    if text.trim().is_empty() {
+        return Err(format!("コピーする内容がありません ({})", last.file_path).into());
+    }
+    clipboard::copy(&text)?;
+    println!(
+        "クリップボードにコピーしました: {} ({}行)",
+        last.file_path,
+        text.lines().count()
+    );
+    Ok(())
+}
+
+// 1ページ分の実行記録 (件数いっぱいなら続きを表示するための位置も表示する)
+fn print_page(records: &[history::ExecutionRecord], limit: usize) {
+    records.iter().for_each(print_record);
+    if let Some(last) = records.last().filter(|_| records.len() == limit) {
+        println!("続き: --before {}", history::Cursor::of(last));
+    }
+}
+
+fn print_record(record: &history::ExecutionRecord) {
+    let executed_at = format_time(record.executed_at);
+    println!(
+        "#{} {} {} {} [{}] {}ms ({}, {}, v{})",
+        record.id,
+        executed_at,
+        if record.success { "✅" } else { "❌" },
+        record.file_path,
+        record.section,
+        record.duration_ms,
+        record.environment.tool_version.as_deref().unwrap_or("不明"),
+        record.environment.os,
+        record.environment.app_version
+    );
+    if let Some(score) = record.score {
+        println!("    採点 {}/100", score);
+    }
+    if let Some(remaining) = record.todos_remaining.filter(|n| *n > 0) {
+        println!("    TODO 残り {}", remaining);
+    }
+    if let Some(note) = &record.note {
+        println!("    📝 {}", note);
+    }
+}
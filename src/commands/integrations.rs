@@ -0,0 +1,106 @@
+use super::{Result, load_config};
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use crate::{chat, outbox, vscode};
+use clap::Subcommand;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Subcommand, Debug)]
+pub enum IntegrationsCommand {
+    /// Visual Studio Code
+    Vscode {
+        #[command(subcommand)]
+        action: VscodeCommand,
+    },
+    /// Discord / Slack (config.toml の [chat] に Webhook を書く)
+    Chat {
+        #[command(subcommand)]
+        action: ChatCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ChatCommand {
+    /// 1日のまとめ (解いた問題数・連続記録・学習時間) を送る (監視中は日付が変わると前日の分を自動で送る)
+    Send {
+        /// まとめる日 (YYYY-MM-DD、省略すると今日)
+        #[arg(long)]
+        date: Option<chrono::NaiveDate>,
+
+        /// 送らずに文面だけ表示する
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum VscodeCommand {
+    /// .vscode/ に監視・集中モードのタスク、デバッグの設定、推奨の設定を書き出す
+    Init {
+        /// 課題のディレクトリ
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// 既存のファイルも上書きする
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub async fn integrations(action: &IntegrationsCommand, data_dir: &DataDir) -> Result {
+    match action {
+        IntegrationsCommand::Vscode {
+            action: VscodeCommand::Init { dir, force },
+        } => {
+            let summary = vscode::init(dir, *force)?;
+            for path in &summary.written {
+                println!("作成: {}", path.display());
+            }
+            for path in &summary.skipped {
+                println!(
+                    "スキップ: {} (既にあります、--force で上書きできます)",
+                    path.display()
+                );
+            }
+            Ok(())
+        }
+        IntegrationsCommand::Chat {
+            action: ChatCommand::Send { date, dry_run },
+        } => send_chat(*date, *dry_run, data_dir).await,
+    }
+}
+
+async fn send_chat(date: Option<chrono::NaiveDate>, dry_run: bool, data_dir: &DataDir) -> Result {
+    let chat = load_config(data_dir).chat;
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+    let summary = chat::summary(&store, date)?;
+    if dry_run {
+        let template = chat.as_ref().and_then(|chat| chat.template.as_deref());
+        println!(
+            "{}",
+            chat::render(template.unwrap_or(chat::DEFAULT_TEMPLATE), &summary)
+        );
+        return Ok(());
+    }
+    let chat = chat.ok_or("config.toml に [chat] の service と webhook_url を書いてください")?;
+    let now = chrono::Utc::now().timestamp();
+    if !outbox::enqueue(&store, &chat::message(&chat, &summary), now)? {
+        return Err(format!(
+            "送信待ちが {}件あるため入れられません (outbox で確認できます)",
+            outbox::CAPACITY
+        )
+        .into());
+    }
+    store.mark_chat_summary(&date.to_string(), now)?;
+    let store = Mutex::new(store);
+    match outbox::deliver_due(&store, None).await {
+        0 => println!(
+            "{} のまとめを送信待ちに入れました (静かな時間帯か送れなかったため、監視中か outbox retry で送ります)",
+            date
+        ),
+        _ => println!("{} のまとめを送りました", date),
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// サブコマンドの処理 (main.rs はコマンドラインの定義と振り分けだけを持つ)
+// 1つのサブコマンド (または関係の深いいくつか) ごとに1つのモジュールにする
+
+pub mod assist;
+pub mod config;
+pub mod curriculum;
+pub mod db;
+pub mod deploy;
+pub mod doctor;
+pub mod exam;
+pub mod grade;
+pub mod history;
+pub mod integrations;
+pub mod outbox;
+pub mod privacy;
+pub mod problems;
+pub mod report;
+pub mod stats;
+pub mod submit;
+pub mod teacher;
+pub mod watch;
+
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use crate::journal::Journal;
+use crate::runner::RunContext;
+use crate::{files, handler, problem, summary};
+use log::{error, info};
+use std::path::Path;
+
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+// 監視・採点では設定ファイルが読めなくても既定の設定で続ける
+pub fn load_config(data_dir: &DataDir) -> crate::config::Config {
+    crate::config::load(&data_dir.config_file()).unwrap_or_else(|e| {
+        error!("設定ファイルを読み込めません: {} (既定の設定で続けます)", e);
+        crate::config::Config::default()
+    })
+}
+
+// ディレクトリ内の実行できる問題ファイル (絶対パス)
+pub fn problem_files(dir: &Path, handlers: &handler::Registry) -> std::io::Result<Vec<String>> {
+    Ok(files::collect_files(&std::path::absolute(dir)?)?
+        .into_iter()
+        .filter(|path| handlers.is_runnable(path))
+        .map(|path| path.display().to_string())
+        .collect())
+}
+
+// 指定がなければ最後に実行したファイルを対象にする
+pub fn current_problem(store: &HistoryStore, file: Option<&Path>) -> Result<String> {
+    match file {
+        Some(file) => Ok(std::path::absolute(file)?.display().to_string()),
+        None => Ok(store
+            .recent(1)?
+            .pop()
+            .map(|record| record.file_path)
+            .ok_or("まだ実行した問題がありません (--file で指定してください)")?),
+    }
+}
+
+// 問題ファイルの "Tags:" とコマンドで付けたタグを合わせたもの
+pub fn problem_tags(store: &HistoryStore, path: &Path) -> Vec<String> {
+    let mut tags = std::fs::read_to_string(path)
+        .map(|source| problem::parse(&source).tags)
+        .unwrap_or_default();
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match store.tags(&absolute.display().to_string()) {
+        Ok(extra) => tags.extend(extra),
+        Err(e) => error!("タグを読み込めません: {}", e),
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+// 前回保存できなかった実行記録を履歴に入れる
+pub fn replay_journal(journal: &Journal, store: &HistoryStore) {
+    match journal.replay(store) {
+        Ok(0) => {}
+        Ok(count) => info!(
+            "保存されていなかった実行記録を履歴に入れました ({}件)",
+            count
+        ),
+        Err(e) => error!("ジャーナルの実行記録を履歴に入れられません: {}", e),
+    }
+}
+
+// --summary が指定されていれば、起動してからの集計を JSON で書き出す
+pub fn write_summary(ctx: &RunContext, path: Option<&Path>, mode: &str, started_at: i64) {
+    let Some(path) = path else {
+        return;
+    };
+    let Ok(stats) = ctx.stats.lock() else {
+        return;
+    };
+    let summary = stats.to_json(mode, started_at, chrono::Utc::now().timestamp());
+    if let Err(e) = summary::write(path, &summary) {
+        error!("サマリーを書き出せません: {} ({})", path.display(), e);
+    }
+}
+
+// UNIX時間をローカル時刻で表示する
+pub fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
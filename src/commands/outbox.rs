@@ -0,0 +1,51 @@
+use super::{Result, format_time};
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use crate::llm::LlmConfig;
+use clap::Subcommand;
+use std::sync::Mutex;
+
+#[derive(Subcommand, Debug)]
+pub enum OutboxCommand {
+    /// あきらめたものも含めて、すべて今すぐ送り直す
+    Retry,
+}
+
+pub async fn outbox(action: Option<&OutboxCommand>, data_dir: &DataDir) -> Result {
+    let store = Mutex::new(HistoryStore::open(&data_dir.history_db())?);
+    if let Some(OutboxCommand::Retry) = action {
+        let count = store
+            .lock()
+            .map_err(|_| "履歴DBを使えません")?
+            .retry_outbound(chrono::Utc::now().timestamp())?;
+        println!("{}件を送り直します", count);
+        let llm = LlmConfig::from_env();
+        while crate::outbox::deliver_due(&store, llm.as_ref()).await > 0 {}
+    }
+    let outbound = store.lock().map_err(|_| "履歴DBを使えません")?.outbound()?;
+    if outbound.is_empty() {
+        println!("送信待ちはありません");
+    }
+    for item in &outbound {
+        let state = match item.next_attempt_at {
+            Some(at) if item.attempts == 0 => format!("送信待ち ({})", format_time(at)),
+            Some(at) => format!(
+                "再送待ち ({}回失敗、次は {})",
+                item.attempts,
+                format_time(at)
+            ),
+            None => format!("あきらめた ({}回失敗)", item.attempts),
+        };
+        println!(
+            "#{} {} {} {}",
+            item.id,
+            format_time(item.created_at),
+            crate::outbox::Message::describe(item),
+            state
+        );
+        if let Some(error) = &item.last_error {
+            println!("    {}", error);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use super::Result;
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use clap::Subcommand;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum PrivacyCommand {
+    /// 集計に使う値だけを JSON で書き出す (コード・出力・メモ・ファイルの場所は含めない)
+    Export {
+        /// 出力先 (省略時は標準出力)
+        output: Option<PathBuf>,
+    },
+    /// 履歴からコード・出力・メモ・質問を消す (実行回数・成功率・解答時間などの集計は残る)
+    Purge {
+        /// 消す (付けなければ消す件数を表示するだけ)
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+pub fn privacy(action: &PrivacyCommand, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    match action {
+        PrivacyCommand::Export { output } => export(&store, output.as_deref()),
+        PrivacyCommand::Purge { yes } => purge(&store, *yes),
+    }
+}
+
+fn export(store: &HistoryStore, output: Option<&Path>) -> Result {
+    let exported = crate::privacy::export(store, chrono::Utc::now().timestamp())?;
+    let json = serde_json::to_string_pretty(&exported)?;
+    match output {
+        Some(output) => {
+            std::fs::write(output, json)?;
+            println!("書き出しました: {}", output.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+// --yes がなければ消す件数を表示するだけ
+fn purge(store: &HistoryStore, yes: bool) -> Result {
+    if !yes {
+        for (name, count) in store.stored_content()? {
+            println!("{}: {}件", name, count);
+        }
+        println!("--yes を付けると上の記録を消します (元に戻せません)");
+        return Ok(());
+    }
+    for (name, count) in store.purge_content()? {
+        println!("{}: {}件を消しました", name, count);
+    }
+    Ok(())
+}
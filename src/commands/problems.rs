@@ -0,0 +1,226 @@
+use super::exam::ensure_not_in_exam;
+use super::stats::print_deferred;
+use super::{Result, load_config, problem_files, problem_tags};
+use crate::data_dir::DataDir;
+use crate::history::{self, HintKind, HistoryStore};
+use crate::{badge, directive, docs, files, handler, problem, project, runner, search};
+use std::collections::HashSet;
+use std::path::Path;
+
+// 問題を探す・見る・整理するコマンド
+
+pub fn browse(dir: &Path, tag: Option<&str>, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let handlers = handler::Registry::new(&load_config(data_dir).handlers);
+    for path in files::collect_files(dir)? {
+        if !handlers.is_runnable(&path) {
+            continue;
+        }
+        let tags = problem_tags(&store, &path);
+        if let Some(tag) = tag
+            && !tags.contains(&tag.to_lowercase())
+        {
+            continue;
+        }
+
+        let header = problem::parse(&std::fs::read_to_string(&path)?);
+        println!(
+            "{} [{}] 難易度{} {}",
+            path.display(),
+            header.topic.as_deref().unwrap_or("-"),
+            header
+                .difficulty
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            tags.iter()
+                .map(|t| format!("#{}", t))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    Ok(())
+}
+
+pub fn search(query: &str, dir: &Path, limit: usize, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let handlers = handler::Registry::new(&load_config(data_dir).handlers);
+    search::update_index(&store, &handlers, dir)?;
+    let hits = store.search_problems(query, limit)?;
+    if hits.is_empty() {
+        println!("見つかりませんでした: {}", query);
+    }
+    for hit in hits {
+        println!("{} {}", hit.file_path, hit.title);
+        println!("    {}", hit.snippet.replace('\n', " "));
+    }
+    Ok(())
+}
+
+// solution なら模範解答も表示する (試験中は使えない)
+pub fn show(problem: &Path, solution: bool, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let source = std::fs::read_to_string(problem)?;
+    let header = problem::parse(&source);
+    let language = problem
+        .extension()
+        .and_then(|s| s.to_str())
+        .and_then(docs::Language::from_extension);
+
+    println!(
+        "=== {} ===",
+        header.title.as_deref().unwrap_or("(タイトルなし)")
+    );
+    println!("トピック: {}", header.topic.as_deref().unwrap_or("-"));
+    if let Some(difficulty) = header.difficulty {
+        println!("難易度: {}", difficulty);
+    }
+    if let Some(section) = &header.section {
+        println!("セクション: {}", section);
+    }
+    if !header.syntax_elements.is_empty() {
+        println!("練習する構文: {}", header.syntax_elements.join(", "));
+    }
+    let tags = problem_tags(&store, problem);
+    if !tags.is_empty() {
+        println!("タグ: {}", tags.join(", "));
+    }
+    for todo in &header.todos {
+        println!("TODO: {}", todo);
+    }
+    if let Some(language) = language {
+        for url in docs::for_problem(&header, language) {
+            println!("参考: {}", url);
+        }
+    }
+    if solution {
+        ensure_not_in_exam(&store)?;
+        let reference = directive::find(&source, "reference")
+            .ok_or("この問題には模範解答 (learn:reference) がありません")?;
+        let reference_path = problem.parent().unwrap_or(Path::new(".")).join(reference);
+        let reference_source = std::fs::read_to_string(&reference_path)?;
+        store.record_hint(
+            &std::path::absolute(problem)?.display().to_string(),
+            HintKind::Solution,
+            chrono::Utc::now().timestamp(),
+        )?;
+        println!("\n=== 模範解答: {} ===", reference_path.display());
+        println!("{}", reference_source.trim_end());
+    }
+    Ok(())
+}
+
+pub fn docs(topic: &str, lang: Option<docs::Language>, print: bool) -> Result {
+    let urls = doc_urls(topic, lang)?;
+    for url in &urls {
+        println!("{}", url);
+    }
+    if !print && let Some(url) = urls.first() {
+        docs::open_in_browser(url)?;
+    }
+    Ok(())
+}
+
+// 問題ファイルならその問題の、そうでなければトピック名で参考ドキュメントを探す
+fn doc_urls(topic: &str, lang: Option<docs::Language>) -> Result<Vec<String>> {
+    let path = Path::new(topic);
+    if path.is_file() {
+        let language = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .and_then(docs::Language::from_extension)
+            .ok_or_else(|| format!("対応していない問題ファイルです: {}", topic))?;
+        let header = problem::parse(&std::fs::read_to_string(path)?);
+        return Ok(docs::for_problem(&header, language));
+    }
+
+    let languages = match lang {
+        Some(language) => vec![language],
+        None => vec![docs::Language::Go, docs::Language::Python],
+    };
+    let url = languages
+        .iter()
+        .find_map(|language| docs::for_topic(topic, *language))
+        .map(str::to_string)
+        .unwrap_or_else(|| docs::search_url(topic, languages[0]));
+    Ok(vec![url])
+}
+
+pub fn tag(problem: &Path, add: &[String], remove: &[String], data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let file_path = std::path::absolute(problem)?.display().to_string();
+    store.add_tags(&file_path, add)?;
+    store.remove_tags(&file_path, remove)?;
+    println!(
+        "{}: {}",
+        file_path,
+        problem_tags(&store, Path::new(&file_path)).join(", ")
+    );
+    Ok(())
+}
+
+// problem がなければ保留中の問題を一覧表示する
+pub fn skip(
+    problem: Option<&Path>,
+    reason: Option<&str>,
+    undo: bool,
+    data_dir: &DataDir,
+) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let Some(problem) = problem else {
+        print_deferred(&store.deferred()?);
+        return Ok(());
+    };
+    let file_path = std::path::absolute(problem)?.display().to_string();
+    if undo {
+        if !store.undefer(&file_path)? {
+            return Err(format!("保留中ではありません: {}", file_path).into());
+        }
+        println!("保留を解除しました: {}", file_path);
+    } else {
+        store.defer(&history::DeferredProblem {
+            file_path: file_path.clone(),
+            reason: reason.map(str::to_string),
+            deferred_at: chrono::Utc::now().timestamp(),
+        })?;
+        println!("後回しにしました: {}", file_path);
+    }
+    Ok(())
+}
+
+pub fn badges(dir: &Path, out: &Path, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let solved: HashSet<String> = store
+        .solve_times()?
+        .into_iter()
+        .map(|record| record.file_path)
+        .collect();
+    let handlers = handler::Registry::new(&load_config(data_dir).handlers);
+    let problems: Vec<badge::ProblemStatus> = problem_files(dir, &handlers)?
+        .into_iter()
+        .filter_map(|file_path| {
+            let path = Path::new(&file_path);
+            let language =
+                runner::language_for_extension(path.extension().and_then(|s| s.to_str())?)?;
+            Some(badge::ProblemStatus {
+                language: language.to_string(),
+                section: project::detect(path).section,
+                solved: solved.contains(&file_path),
+            })
+        })
+        .collect();
+    let days: Vec<chrono::NaiveDate> = store
+        .study_days()?
+        .iter()
+        .filter_map(|day| day.parse().ok())
+        .collect();
+
+    let mut badges = badge::section_badges(&problems);
+    badges.push(badge::streak_badge(badge::streak(
+        &days,
+        chrono::Local::now().date_naive(),
+    )));
+    for path in badge::write(out, &badges)? {
+        println!("作成: {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use super::Result;
+use crate::data_dir::DataDir;
+use crate::history::HistoryStore;
+use clap::Subcommand;
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommand {
+    /// 直近7日の解いた問題・連続記録・苦手なセクション・来週やることをまとめる
+    Weekly {
+        /// 出力形式
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: crate::report::Format,
+        /// 出力先 (省略時は標準出力)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn report(action: &ReportCommand, data_dir: &DataDir) -> Result {
+    let ReportCommand::Weekly { format, output } = action;
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let weekly = crate::report::weekly(&store, chrono::Local::now().date_naive())?;
+    let rendered = crate::report::render(&weekly, *format);
+    match output {
+        Some(output) => {
+            std::fs::write(output, rendered)?;
+            println!("書き出しました: {}", output.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
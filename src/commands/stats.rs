@@ -0,0 +1,373 @@
+use super::{Result, format_time, problem_tags};
+use crate::data_dir::DataDir;
+use crate::history::{self, HistoryStore};
+use crate::mastery::{self, Mastery};
+use crate::query::{self, StatsQuery};
+use crate::{charts, config, goal, link, runner, solve_time, xp};
+use clap::Subcommand;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Subcommand, Debug)]
+pub enum StatsCommand {
+    /// 実行履歴を観点ごとに集計する (例: stats query --group-by section,language --metric avg_time,success_rate --since 30d)
+    Query {
+        /// 集計の観点 (カンマ区切り)
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "section")]
+        group_by: Vec<query::Dimension>,
+
+        /// 集計する指標 (カンマ区切り)
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "runs,success_rate"
+        )]
+        metric: Vec<query::Metric>,
+
+        /// 直近の期間だけを集計する (例: 30d, 2w, 12h)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// 言語ごとの実行回数・成功率・解答時間を表示する
+    Languages,
+    /// 日ごとの推移・セクションごとの棒グラフ・練習した日のカレンダーを表示する
+    Charts {
+        /// 今日から遡って表示する日数
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..=366))]
+        days: u32,
+    },
+    /// レベルと XP を得た記録を表示する
+    Xp {
+        /// 表示する件数
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+pub fn stats(
+    tag: Option<&str>,
+    collapse_repeats: Option<u64>,
+    action: Option<&StatsCommand>,
+    data_dir: &DataDir,
+) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    match action {
+        Some(StatsCommand::Query {
+            group_by,
+            metric,
+            since,
+        }) => {
+            let since = match since {
+                Some(value) => Some(
+                    chrono::Utc::now().timestamp()
+                        - query::parse_since(value).ok_or_else(|| {
+                            format!("期間を解釈できません: {} (例: 30d, 2w, 12h)", value)
+                        })?,
+                ),
+                None => None,
+            };
+            let query = StatsQuery {
+                group_by: group_by.clone(),
+                metrics: metric.clone(),
+                since,
+                collapse_within: collapse_repeats,
+            };
+            let rows = store.query_stats(&query)?;
+            if rows.is_empty() {
+                println!("該当する実行記録はありません");
+            } else {
+                print!("{}", query::format_rows(&query, &rows));
+            }
+        }
+        Some(StatsCommand::Xp { limit }) => {
+            println!("{}", xp::Level::from_xp(store.total_xp()?));
+            for event in store.xp_events(*limit)? {
+                println!(
+                    "{} +{} XP ({}) {}",
+                    format_time(event.awarded_at),
+                    event.xp,
+                    event.reason.label(),
+                    event.file_path
+                );
+            }
+        }
+        Some(StatsCommand::Languages) => languages(&store, collapse_repeats)?,
+        Some(StatsCommand::Charts { days }) => charts(&store, *days, collapse_repeats)?,
+        None => overview(&store, tag, collapse_repeats, data_dir)?,
+    }
+    Ok(())
+}
+
+fn languages(store: &HistoryStore, collapse_repeats: Option<u64>) -> Result {
+    let query = StatsQuery {
+        group_by: vec![query::Dimension::Language],
+        metrics: vec![
+            query::Metric::Runs,
+            query::Metric::SuccessRate,
+            query::Metric::Problems,
+        ],
+        since: None,
+        collapse_within: collapse_repeats,
+    };
+    // 解答時間は解けた問題の拡張子で言語に振り分ける
+    let mut solved: HashMap<&str, (usize, i64)> = HashMap::new();
+    for record in store.solve_times()? {
+        let language = Path::new(&record.file_path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .and_then(runner::language_for_extension)
+            .unwrap_or("その他");
+        let entry = solved.entry(language).or_default();
+        entry.0 += 1;
+        entry.1 += record.active_seconds.max(0);
+    }
+
+    println!("=== 言語別 ===");
+    let rows = store.query_stats(&query)?;
+    if rows.is_empty() {
+        println!("実行記録がありません");
+    }
+    for row in rows {
+        let (count, seconds) = solved
+            .get(row.keys[0].as_str())
+            .copied()
+            .unwrap_or_default();
+        println!(
+            "{}  解答 {}問 ({})",
+            query::format_rows(&query, std::slice::from_ref(&row)).trim_end(),
+            count,
+            solve_time::format_duration(Duration::from_secs(seconds as u64))
+        );
+    }
+    Ok(())
+}
+
+fn charts(store: &HistoryStore, days: u32, collapse_repeats: Option<u64>) -> Result {
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Days::new(u64::from(days) - 1);
+    let since = from
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map(|t| t.timestamp());
+    let query = |group_by| StatsQuery {
+        group_by: vec![group_by],
+        metrics: vec![query::Metric::Runs, query::Metric::SuccessRate],
+        since,
+        collapse_within: collapse_repeats,
+    };
+
+    // 日ごとの実行回数と成功率 (実行しなかった日は回数 0、成功率なし)
+    let mut daily: BTreeMap<chrono::NaiveDate, (u64, f64)> = BTreeMap::new();
+    for row in store.query_stats(&query(query::Dimension::Day))? {
+        if let Ok(day) = chrono::NaiveDate::parse_from_str(&row.keys[0], "%Y-%m-%d") {
+            daily.insert(
+                day,
+                (
+                    row.values[0].unwrap_or(0.0) as u64,
+                    row.values[1].unwrap_or(0.0),
+                ),
+            );
+        }
+    }
+    if daily.is_empty() {
+        println!("直近{}日の実行記録はありません", days);
+        return Ok(());
+    }
+    let dates: Vec<chrono::NaiveDate> = from.iter_days().take_while(|d| *d <= today).collect();
+    let runs: Vec<Option<f64>> = dates
+        .iter()
+        .map(|day| Some(daily.get(day).map_or(0.0, |(runs, _)| *runs as f64)))
+        .collect();
+    let rates: Vec<Option<f64>> = dates
+        .iter()
+        .map(|day| daily.get(day).map(|(_, rate)| *rate))
+        .collect();
+    println!("=== 直近{}日 ({} 〜 {}) ===", days, from, today);
+    println!(
+        "実行   {}  合計 {}回",
+        charts::sparkline(&runs),
+        daily.values().map(|(runs, _)| runs).sum::<u64>()
+    );
+    println!("成功率 {}", charts::sparkline(&rates));
+
+    println!("\n=== セクション別 ===");
+    let sections: Vec<(String, f64, String)> = store
+        .query_stats(&query(query::Dimension::Section))?
+        .into_iter()
+        .map(|row| {
+            let runs = row.values[0].unwrap_or(0.0);
+            let note = format!(
+                "{} (成功率 {})",
+                query::Metric::Runs.format(row.values[0]),
+                query::Metric::SuccessRate.format(row.values[1])
+            );
+            (row.keys[0].clone(), runs, note)
+        })
+        .collect();
+    print!("{}", charts::bar_chart(&sections, 30));
+
+    println!("\n=== 練習した日 ===");
+    let counts = daily.iter().map(|(day, (runs, _))| (*day, *runs)).collect();
+    print!("{}", charts::heatmap(&counts, from, today));
+    Ok(())
+}
+
+// サブコマンドなしの stats
+fn overview(
+    store: &HistoryStore,
+    tag: Option<&str>,
+    collapse_repeats: Option<u64>,
+    data_dir: &DataDir,
+) -> Result {
+    let summary = store.summary(collapse_repeats)?;
+    let rate = if summary.executions > 0 {
+        summary.successes as f64 * 100.0 / summary.executions as f64
+    } else {
+        0.0
+    };
+    println!("=== 統計 ===");
+    println!(
+        "実行回数: {} (成功 {}, 成功率 {:.1}%)",
+        summary.executions, summary.successes, rate
+    );
+    println!(
+        "学習時間: {} ({}セッション)",
+        solve_time::format_duration(Duration::from_secs(summary.study_seconds.max(0) as u64)),
+        summary.sessions
+    );
+    println!("レベル: {}", xp::Level::from_xp(store.total_xp()?));
+
+    if summary.unchanged_resubmissions > 0 {
+        println!("変更なしの再提出: {}回", summary.unchanged_resubmissions);
+    }
+
+    let deferred = store.deferred()?;
+    if !deferred.is_empty() {
+        println!("保留中: {}問", deferred.len());
+    }
+
+    let removed = store.removed_problems()?;
+    if !removed.is_empty() {
+        println!("削除済み (リセット): {}問", removed.len());
+        for problem in &removed {
+            let restore = link::id_for(Path::new(&problem.file_path))
+                .map(|id| format!(" → restore-problem {}", id))
+                .unwrap_or_default();
+            println!(
+                "  {} {}{}",
+                format_time(problem.removed_at),
+                problem.file_path,
+                restore
+            );
+        }
+    }
+
+    let config = config::load(&data_dir.config_file())?;
+    if !config.goals.is_empty() {
+        println!();
+        print_goals(&config.goals, store)?;
+    }
+
+    println!("\n=== 解答時間 ===");
+    let mut masteries = Vec::new();
+    for record in store.solve_times()? {
+        if let Some(tag) = tag
+            && !problem_tags(store, Path::new(&record.file_path)).contains(&tag.to_lowercase())
+        {
+            continue;
+        }
+        // 解けるまでに見た助けで習熟度を判定する
+        let mastery = Mastery::classify(&store.hints_used(&record.file_path, record.solved_at)?);
+        masteries.push(mastery);
+        println!(
+            "[{}] {} {} ({})",
+            record.section,
+            record.file_path,
+            solve_time::format_duration(Duration::from_secs(record.active_seconds.max(0) as u64)),
+            mastery.label()
+        );
+    }
+
+    if let Some(score) = mastery::score(&masteries) {
+        let count = |m: Mastery| masteries.iter().filter(|x| **x == m).count();
+        println!(
+            "\n習熟度: {}/100 (自力 {}問, ヒントあり {}問, 解答参照 {}問)",
+            score,
+            count(Mastery::Independent),
+            count(Mastery::Hinted),
+            count(Mastery::PeekedSolution)
+        );
+    }
+
+    let frequent_errors = store.frequent_errors(5)?;
+    if !frequent_errors.is_empty() {
+        println!("\n=== よくあるエラー ===");
+        for (signature, count) in &frequent_errors {
+            println!("{:>4}回  {}", count, signature);
+        }
+    }
+
+    // 最後の実行で通らなかったケースが先に並ぶ
+    let case_stats = store.case_stats()?;
+    if !case_stats.is_empty() {
+        println!("\n=== テストケース ===");
+        for stats in &case_stats {
+            println!(
+                "{} {} {} ({}/{}回 成功)",
+                if stats.last_passed { "✅" } else { "❌" },
+                stats.file_path,
+                stats.case_name,
+                stats.passed,
+                stats.runs
+            );
+        }
+    }
+
+    // --coverage で計測した問題ごとの推移 (最初 → 最新)
+    let coverage_trends = store.coverage_trends()?;
+    if !coverage_trends.is_empty() {
+        println!("\n=== カバレッジ ===");
+        for (file_path, values) in &coverage_trends {
+            let line: Vec<Option<f64>> = values.iter().copied().map(Some).collect();
+            println!(
+                "{} {:.1}% → {:.1}% ({}回) {}",
+                charts::sparkline(&line),
+                values[0],
+                values[values.len() - 1],
+                values.len(),
+                file_path
+            );
+        }
+    }
+    Ok(())
+}
+
+// 今週の目標の進み具合 (目標が設定されていなければ何も表示しない)
+pub fn print_goals(goals: &config::Goals, store: &HistoryStore) -> rusqlite::Result<()> {
+    if goals.is_empty() {
+        return Ok(());
+    }
+    let (problems_solved, study_seconds) =
+        store.progress_since(goal::week_start(chrono::Local::now()))?;
+    let progress = goal::WeeklyProgress {
+        problems_solved,
+        study_seconds,
+    };
+    println!("=== 今週の目標 ===");
+    for line in goal::format_goals(goals, &progress) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+pub fn print_deferred(problems: &[history::DeferredProblem]) {
+    for problem in problems {
+        println!(
+            "⏭ {} ({})",
+            problem.file_path,
+            problem.reason.as_deref().unwrap_or("理由なし")
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use super::{Result, format_time, load_config, problem_files};
+use crate::data_dir::DataDir;
+use crate::history::{self, HistoryStore};
+use crate::{exam, handler};
+use std::path::Path;
+
+// dir がなければ過去の提出を一覧表示する
+pub async fn submit(dir: Option<&Path>, github: bool, remote: &str, data_dir: &DataDir) -> Result {
+    let store = HistoryStore::open(&data_dir.history_db())?;
+    let Some(dir) = dir else {
+        for submission in store.submissions()? {
+            println!(
+                "{} {} {}/{}問 合格{}",
+                format_time(submission.submitted_at),
+                submission.assignment,
+                submission.passed,
+                submission.total,
+                submission
+                    .commit_sha
+                    .map(|sha| format!(" (commit {})", &sha[..sha.len().min(7)]))
+                    .unwrap_or_default()
+            );
+        }
+        return Ok(());
+    };
+
+    let assignment = std::path::absolute(dir)?.display().to_string();
+    let now = chrono::Utc::now().timestamp();
+    let problems = problem_files(
+        dir,
+        &handler::Registry::new(&load_config(data_dir).handlers),
+    )?;
+    let results = exam::results_between(&problems, &store.executions_between(0, now)?, 0, now);
+    let report = crate::submit::report(&assignment, &format_time(now), &results);
+    print!("{}", report);
+
+    let commit_sha = if github {
+        let sha = crate::submit::push(dir, remote, &report).await?;
+        println!("\n{} に push しました (commit {})", remote, &sha[..7]);
+        Some(sha)
+    } else {
+        None
+    };
+    store.record_submission(&history::Submission {
+        assignment,
+        submitted_at: now,
+        passed: results.iter().filter(|r| r.passed).count() as i64,
+        total: results.len() as i64,
+        commit_sha,
+    })?;
+    Ok(())
+}
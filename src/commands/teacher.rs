@@ -0,0 +1,87 @@
+use super::Result;
+use crate::{anki, classroom, similarity};
+use clap::Subcommand;
+use log::{info, warn};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum TeacherCommand {
+    /// 問題ごとの通過率・よくあるエラー・解答時間の分布を Markdown にまとめる (学生の履歴DBは書き換えない)
+    Aggregate {
+        /// 学生の履歴DB (例: "submissions/*/history.db"、学生名は DB のあるディレクトリ名)
+        #[arg(long)]
+        glob: String,
+
+        /// 出力先 (省略時は標準出力)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn teacher(action: &TeacherCommand) -> Result {
+    let TeacherCommand::Aggregate { glob, output } = action;
+    let paths =
+        ::glob::glob(glob).map_err(|e| format!("パターンを解釈できません: {} ({})", glob, e))?;
+    let mut students = Vec::new();
+    for path in paths {
+        let path = path?;
+        match classroom::load(&path) {
+            Ok(student) => students.push(student),
+            Err(e) => warn!("履歴DBを読めません: {} ({})", path.display(), e),
+        }
+    }
+    if students.is_empty() {
+        return Err(format!("{} に一致する履歴DBがありません", glob).into());
+    }
+
+    let markdown = classroom::render(&classroom::aggregate(&students));
+    match output {
+        Some(output) => {
+            std::fs::write(output, markdown)?;
+            println!(
+                "{}人分の履歴を集計しました: {}",
+                students.len(),
+                output.display()
+            );
+        }
+        None => print!("{}", markdown),
+    }
+    Ok(())
+}
+
+pub fn anki(dir: &Path, output: Option<&Path>) -> Result {
+    let cards = anki::collect_cards(dir)?;
+    match output {
+        Some(path) => {
+            anki::write_tsv(&cards, File::create(path)?)?;
+            info!(
+                "{}枚のカードを書き出しました: {}",
+                cards.len(),
+                path.display()
+            );
+        }
+        None => anki::write_tsv(&cards, std::io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+pub fn similarity(dir: &Path, min_score: f64) -> Result {
+    let matches = similarity::analyze(dir, min_score)?;
+    println!("=== 類似度レポート ({}件) ===", matches.len());
+    for m in &matches {
+        println!(
+            "{:5.1}%  {} <-> {}",
+            m.score * 100.0,
+            m.left.display(),
+            m.right.display()
+        );
+        let regions: Vec<String> = m
+            .regions
+            .iter()
+            .map(|(l1, l2, r1, r2)| format!("{}-{} <-> {}-{}", l1, l2, r1, r2))
+            .collect();
+        println!("        一致箇所: {}", regions.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,632 @@
+use super::stats::{print_deferred, print_goals};
+use super::{Result, load_config, replay_journal, write_summary};
+use crate::Args;
+use crate::availability::Availability;
+use crate::data_dir::DataDir;
+use crate::dispatch::{Batch, WorkerPool};
+use crate::history::HistoryStore;
+use crate::instance::{self, InstanceLock};
+use crate::journal::Journal;
+use crate::llm::LlmConfig;
+use crate::reflect::ReflectGate;
+use crate::remote::Remote;
+use crate::runner::{self, RunContext};
+use crate::session::{SessionClock, SessionRecord};
+use crate::snapshot::SnapshotRecorder;
+use crate::solve_time::SolveTracker;
+use crate::sound::Sounds;
+use crate::{
+    backup, config, events, file_watcher, files, handler, hotkey, link, outbox, rerun, search, xp,
+};
+use log::{error, info, warn};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode};
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use which::which;
+
+// ディレクトリ (focus のときはそのファイルだけ) を監視して、変更されたファイルを実行する
+pub async fn watch(args: &Args, data_dir: &DataDir, focus: Option<PathBuf>) -> notify::Result<()> {
+    info!("データディレクトリ: {}", data_dir.root().display());
+
+    if which("mise").is_err() {
+        error!("miseコマンドが見つかりません(必要な実行環境がインストールされていません)",);
+        std::process::exit(1);
+    }
+
+    let (focus, watch_dir) = watch_target(args, focus);
+    let os_type = env::consts::OS;
+    let remote = start_remote(args, &watch_dir).await;
+
+    // 同じディレクトリを監視するのは1つのプロセスだけ
+    let lock = match InstanceLock::acquire(&data_dir.locks_dir(), &watch_dir, args.takeover) {
+        Ok(lock) => lock,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // イベントを受け取るチャンネル
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mode = match focus {
+        Some(_) => RecursiveMode::NonRecursive,
+        None => RecursiveMode::Recursive,
+    };
+    let poll_interval = Duration::from_millis(args.poll_interval);
+    // OS の監視が使えなければポーリングに切り替える
+    let mut polling = args.poll;
+    // 監視を続けるために保持しておく
+    let mut _watcher = if polling {
+        file_watcher::polling(tx.clone(), &watch_dir, mode, poll_interval)?
+    } else {
+        match file_watcher::native(tx.clone(), &watch_dir, mode) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!(
+                    "OSのファイル監視を使えません: {} (ポーリングに切り替えます)",
+                    e
+                );
+                polling = true;
+                file_watcher::polling(tx.clone(), &watch_dir, mode, poll_interval)?
+            }
+        }
+    };
+
+    info!(
+        "監視を開始: {}{}",
+        watch_dir.display(),
+        if polling { " (ポーリング)" } else { "" }
+    );
+
+    listen_for_rerun(&lock, &tx).await;
+    // 監視を終えるまで登録しておく
+    let _hotkey = args
+        .hotkey
+        .as_deref()
+        .and_then(|keys| register_hotkey(keys, &tx));
+
+    let config = load_config(data_dir);
+    let ctx = Arc::new(run_context(
+        args,
+        data_dir,
+        &config,
+        &watch_dir,
+        remote,
+        focus.is_some(),
+    ));
+
+    // レビューなどの送信待ちは監視と別のタスクで送る (前回の監視で送れなかった分も)
+    if ctx.history.is_some() {
+        outbox::spawn_worker(
+            data_dir.history_db(),
+            LlmConfig::from_env(),
+            config.chat.clone(),
+            &ctx.events,
+        );
+    }
+
+    if let Some(sounds) = prepare_sounds(args, data_dir) {
+        events::spawn_subscriber(&ctx.events, move |event| {
+            if let events::Event::ExecutionFinished { success, .. } = event {
+                sounds.play(success);
+            }
+        });
+    }
+
+    let idle_timeout = Duration::from_secs(args.idle_timeout);
+    let session = Arc::new(Mutex::new(SessionClock::new(idle_timeout)));
+
+    let started_at = chrono::Utc::now().timestamp();
+    save_on_ctrlc(&session, &ctx, args.summary.as_deref(), &lock, started_at);
+
+    let mut last_modified: HashMap<PathBuf, Instant> = HashMap::new();
+    let debounce_duration = Duration::from_millis(300);
+
+    // エディタの保存で一度に届くイベントを tick ごとにまとめてワーカーに渡す
+    let pool = WorkerPool::start(
+        ctx.clone(),
+        usize::from(args.workers),
+        usize::from(args.queue_size),
+    );
+    let tick = Duration::from_millis(50);
+    let mut batch = Batch::default();
+    let mut created: Vec<PathBuf> = Vec::new();
+    let mut flush_at: Option<Instant> = None;
+    // rerun で再実行するファイル
+    let mut last_run: Option<PathBuf> = focus.clone();
+
+    loop {
+        let timeout = flush_at.map_or(Duration::from_secs(1), |at| {
+            at.saturating_duration_since(Instant::now())
+        });
+        let res = match rx.recv_timeout(timeout) {
+            Ok(res) => Some(res),
+            Err(RecvTimeoutError::Timeout) if flush_at.is_some() => None,
+            Err(RecvTimeoutError::Timeout) => {
+                let paused = session
+                    .lock()
+                    .ok()
+                    .and_then(|mut s| s.pause_if_idle(Instant::now()));
+                if let Some(record) = paused {
+                    println!(
+                        "⏸ {}秒間変更がないためセッションを一時停止しました",
+                        args.idle_timeout
+                    );
+                    save_session(&ctx, &record);
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        match res {
+            None => {}
+            Some(Ok(event)) if event.kind == EventKind::Other && event.paths.is_empty() => {
+                match &last_run {
+                    Some(path) => {
+                        println!("🔁 再実行: {}", path.display());
+                        batch.push(path.clone());
+                        flush_at.get_or_insert(Instant::now());
+                    }
+                    None => println!("再実行するファイルがありません (まだ何も実行していません)"),
+                }
+            }
+            Some(Ok(event)) => {
+                // 名前の変更・移動では履歴などを新しいパスに引き継ぐ (実行はしない)
+                if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind
+                    && let [from, to] = event.paths.as_slice()
+                {
+                    rename_problem(&ctx, from, to);
+                    continue;
+                }
+                if let EventKind::Remove(_) = event.kind {
+                    for path in &event.paths {
+                        remove_problem(&ctx, path);
+                    }
+                    continue;
+                }
+                for path in event.paths {
+                    if !path.is_file() || files::in_environment_dir(&path) {
+                        continue;
+                    }
+                    if let Some(focus) = &focus
+                        && path.canonicalize().ok().as_ref() != Some(focus)
+                    {
+                        continue;
+                    }
+
+                    let now = Instant::now();
+                    if let Ok(mut tracker) = ctx.solve_tracker.lock() {
+                        tracker.touch(&path, now);
+                    }
+                    if let Ok(mut session) = session.lock() {
+                        let resumed = !session.is_active();
+                        if let Some(record) = session.touch(now, chrono::Utc::now().timestamp()) {
+                            save_session(&ctx, &record);
+                        }
+                        if resumed {
+                            println!("▶ セッションを開始しました");
+                        }
+                    }
+
+                    // 新しく作られたファイルは中身が書き込まれるのを待ってから登録する
+                    if let EventKind::Create(_) = event.kind
+                        && !created.contains(&path)
+                    {
+                        created.push(path.clone());
+                        flush_at.get_or_insert(now + tick);
+                    }
+
+                    // ポーリングでは保存1回につきイベントが1回なので間引かない
+                    if !polling {
+                        let entry = last_modified.entry(path.clone()).or_insert(now);
+                        if now.duration_since(*entry) < debounce_duration {
+                            continue;
+                        }
+                        *entry = now;
+                    }
+
+                    if focus.is_none() {
+                        println!("event.kind={:?}, path={}", event.kind, path.display());
+                    }
+                    ctx.events
+                        .publish(events::Event::FileChanged { path: path.clone() });
+
+                    if file_watcher::triggers_run(&event.kind, os_type, polling) {
+                        last_run = Some(path.clone());
+                        batch.push(path);
+                        flush_at.get_or_insert(now + tick);
+                    }
+                }
+            }
+            Some(Err(e)) if !polling => {
+                error!("watch error: {:?} (ポーリングに切り替えます)", e);
+                match file_watcher::polling(tx.clone(), &watch_dir, mode, poll_interval) {
+                    Ok(poll_watcher) => {
+                        _watcher = poll_watcher;
+                        polling = true;
+                    }
+                    Err(e) => error!("ポーリングを開始できません: {:?}", e),
+                }
+            }
+            Some(Err(e)) => error!("watch error: {:?}", e),
+        }
+
+        if flush_at.is_some_and(|at| Instant::now() >= at) {
+            // --run-new なら登録した問題をすぐ実行する
+            for path in created.drain(..) {
+                if register_problem(&ctx, &path) && args.run_new {
+                    batch.push(path);
+                }
+            }
+            pool.dispatch(&mut batch);
+            flush_at = None;
+        }
+    }
+
+    write_summary(&ctx, args.summary.as_deref(), "watch", started_at);
+    lock.release();
+    Ok(())
+}
+
+// 監視中のプロセスに最後に実行したファイルをもう一度実行させる
+pub async fn rerun(dir: Option<&Path>, data_dir: &DataDir) -> Result {
+    let watcher = find_watcher(&data_dir.locks_dir(), dir)?;
+    let endpoint = watcher.endpoint.ok_or_else(|| {
+        format!(
+            "監視中のプロセス (PID {}) は rerun の指示を待ち受けていません",
+            watcher.pid
+        )
+    })?;
+    if let Err(e) = rerun::request(&endpoint).await {
+        return Err(format!(
+            "監視中のプロセス (PID {}) に再実行を指示できません: {}",
+            watcher.pid, e
+        )
+        .into());
+    }
+    Ok(())
+}
+
+// rerun で再実行を指示する監視中のプロセス (ディレクトリを省略したら監視中のプロセスが1つのときだけ選ぶ)
+fn find_watcher(locks_dir: &Path, dir: Option<&Path>) -> Result<instance::Watcher> {
+    if let Some(dir) = dir {
+        return instance::watcher(locks_dir, dir)
+            .ok_or_else(|| format!("{} を監視中のプロセスがありません", dir.display()).into());
+    }
+    let mut watchers = instance::watchers(locks_dir);
+    match watchers.len() {
+        0 => Err("監視中のプロセスがありません".into()),
+        1 => Ok(watchers.remove(0)),
+        _ => Err(format!(
+            "複数のディレクトリを監視中です。ディレクトリを指定してください:\n{}",
+            watchers
+                .iter()
+                .map(|watcher| format!("  {}", watcher.dir.display()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+        .into()),
+    }
+}
+
+// 集中モードの対象ファイルと監視するディレクトリ (どちらも絶対パス、なければ終了する)
+fn watch_target(args: &Args, focus: Option<PathBuf>) -> (Option<PathBuf>, PathBuf) {
+    // 集中モードでは対象ファイルのあるディレクトリだけを監視し、他のファイルは無視する
+    let focus = match focus {
+        Some(file) => match file.canonicalize() {
+            Ok(file) if file.is_file() => Some(file),
+            _ => {
+                error!("ファイルが存在しません: {}", file.display());
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let watch_dir = match &focus {
+        Some(file) => file.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        None => PathBuf::from(args.dir.as_deref().unwrap_or_default()),
+    };
+    if !watch_dir.is_dir() {
+        error!("ディレクトリが存在しません: {}", watch_dir.display());
+        std::process::exit(1);
+    }
+    // ポーリングのイベントも OS の監視と同じく絶対パスにする
+    let watch_dir = std::path::absolute(&watch_dir).unwrap_or(watch_dir);
+    (focus, watch_dir)
+}
+
+// リモートで実行するときは ssh と rsync が必要で、最初にディレクトリ全体を送っておく
+async fn start_remote(args: &Args, watch_dir: &Path) -> Option<Remote> {
+    let remote = args.remote.clone()?.with_local_root(watch_dir);
+    for tool in ["ssh", "rsync"] {
+        if which(tool).is_err() {
+            error!("{}コマンドが見つかりません (--remote に必要です)", tool);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = remote.sync().await {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+    info!("リモートで実行します: {}:{}", remote.host, remote.dir);
+    Some(remote)
+}
+
+// rerun コマンドの指示はファイルのイベントと同じチャンネルに中身のないイベントとして流す
+async fn listen_for_rerun(lock: &InstanceLock, tx: &Sender<notify::Result<Event>>) {
+    match rerun::bind().await {
+        Ok((listener, endpoint)) => {
+            if let Err(e) = lock.publish(&endpoint) {
+                error!("ロックファイルに書き込めません: {} (rerun は使えません)", e);
+            }
+            let tx = tx.clone();
+            rerun::spawn_server(listener, endpoint.token, move || {
+                tx.send(Ok(Event::new(EventKind::Other))).is_ok()
+            });
+        }
+        Err(e) => error!(
+            "rerun の指示を待ち受けられません: {} (rerun は使えません)",
+            e
+        ),
+    }
+}
+
+fn register_hotkey(keys: &str, tx: &Sender<notify::Result<Event>>) -> Option<hotkey::Hotkey> {
+    let tx = tx.clone();
+    match hotkey::register(keys, move || {
+        let _ = tx.send(Ok(Event::new(EventKind::Other)));
+    }) {
+        Ok(hotkey) => {
+            info!("{} で最後のファイルを再実行できます", keys);
+            Some(hotkey)
+        }
+        Err(e) => {
+            error!("{} (--hotkey なしで続けます)", e);
+            None
+        }
+    }
+}
+
+// 履歴DBを開いて、起動時のお知らせ・ジャーナルの取り込み・日ごとのバックアップを済ませる
+fn open_history(data_dir: &DataDir, config: &config::Config) -> Option<Mutex<HistoryStore>> {
+    let store = match HistoryStore::open(&data_dir.history_db()) {
+        Ok(store) => store,
+        Err(e) => {
+            error!("履歴DBを開けません: {} (実行履歴は保存されません)", e);
+            return None;
+        }
+    };
+    // 後回しにした問題を忘れないように起動時に表示する
+    match store.deferred() {
+        Ok(deferred) if !deferred.is_empty() => {
+            println!("=== 保留中の問題 ({}件) ===", deferred.len());
+            print_deferred(&deferred);
+        }
+        Ok(_) => {}
+        Err(e) => error!("保留中の問題を読み込めません: {}", e),
+    }
+    if let Err(e) = print_goals(&config.goals, &store) {
+        error!("今週の目標の進み具合を読み込めません: {}", e);
+    }
+    match store.total_xp() {
+        Ok(total) if total > 0 => println!("{}", xp::Level::from_xp(total)),
+        Ok(_) => {}
+        Err(e) => error!("XP を読み込めません: {}", e),
+    }
+    replay_journal(&Journal::new(data_dir.journal_dir()), &store);
+    match backup::daily_backup(
+        &store,
+        &data_dir.backups_dir(),
+        chrono::Local::now().date_naive(),
+    ) {
+        Ok(Some(path)) => info!("履歴DBをバックアップしました: {}", path.display()),
+        Ok(None) => {}
+        Err(e) => error!("履歴DBをバックアップできません: {}", e),
+    }
+    Some(Mutex::new(store))
+}
+
+// コマンドラインの指定が設定ファイル ([languages]) より優先する
+fn run_context(
+    args: &Args,
+    data_dir: &DataDir,
+    config: &config::Config,
+    watch_dir: &Path,
+    remote: Option<Remote>,
+    focus: bool,
+) -> RunContext {
+    let (profile, languages) = config.for_dir(watch_dir);
+    if let Some(profile) = profile {
+        info!("プロファイル: {}", profile);
+    }
+    let history = open_history(data_dir, config);
+
+    let review = match (args.review, LlmConfig::from_env()) {
+        (true, None) => {
+            error!("{}", LlmConfig::not_configured_message());
+            None
+        }
+        (true, config) => config,
+        (false, _) => None,
+    };
+
+    RunContext {
+        target: args.target.clone(),
+        coverage: args.coverage,
+        complexity: args.complexity,
+        review,
+        history,
+        journal: Some(Journal::new(data_dir.journal_dir())),
+        preview_chars: Some(args.preview_chars),
+        store_output: args.store_output,
+        solve_tracker: Mutex::new(SolveTracker::new(Duration::from_secs(args.idle_timeout))),
+        reflect: args
+            .reflect
+            .map(|seconds| Mutex::new(ReflectGate::new(Duration::from_secs(seconds)))),
+        snapshots: args
+            .snapshots
+            .then(|| Mutex::new(SnapshotRecorder::default())),
+        batch: false,
+        stats: Mutex::default(),
+        events: events::EventBus::default(),
+        focus,
+        go_flags: args
+            .go_flags
+            .as_deref()
+            .map(runner::split_flags)
+            .or(languages.go.flags.clone())
+            .unwrap_or_default(),
+        python_args: args
+            .python_args
+            .as_deref()
+            .map(runner::split_flags)
+            .or(languages.python.flags.clone())
+            .unwrap_or_default(),
+        go_timeout: languages.go.timeout(),
+        python_timeout: languages.python.timeout(),
+        handlers: handler::Registry::new(&config.handlers),
+        availability: Mutex::new(Availability::open(data_dir.availability_file())),
+        remote,
+        jump_to_error: args.jump_to_error,
+        hyperlinks: args.hyperlinks,
+    }
+}
+
+fn prepare_sounds(args: &Args, data_dir: &DataDir) -> Option<Sounds> {
+    if args.silent || args.volume == 0 {
+        return None;
+    }
+    match Sounds::prepare(
+        &data_dir.sounds_dir(),
+        args.volume,
+        args.success_sound.as_deref(),
+        args.failure_sound.as_deref(),
+    ) {
+        Ok(sounds) => Some(sounds),
+        Err(e) => {
+            error!("効果音を用意できません: {} (効果音なしで続けます)", e);
+            None
+        }
+    }
+}
+
+// Ctrl+C で終了するときも作業中のセッションを保存する
+fn save_on_ctrlc(
+    session: &Arc<Mutex<SessionClock>>,
+    ctx: &Arc<RunContext>,
+    summary: Option<&Path>,
+    lock: &InstanceLock,
+    started_at: i64,
+) {
+    let session = session.clone();
+    let ctx = ctx.clone();
+    let summary = summary.map(Path::to_path_buf);
+    let lock_file = lock.path().to_path_buf();
+    let result = ctrlc::set_handler(move || {
+        if let Some(record) = session.lock().ok().and_then(|mut s| s.pause()) {
+            save_session(&ctx, &record);
+        }
+        write_summary(&ctx, summary.as_deref(), "watch", started_at);
+        let _ = std::fs::remove_file(&lock_file);
+        std::process::exit(0);
+    });
+    if let Err(e) = result {
+        error!("Ctrl+C ハンドラを設定できません: {}", e);
+    }
+}
+
+// 問題ファイル (またはセクションのディレクトリ) の名前変更・移動を記録に反映する
+fn rename_problem(ctx: &RunContext, from: &Path, to: &Path) {
+    // エディタが保存時に元のファイルを退避する名前変更 (a.go → a.go~ など) は引き継がない
+    if !to.is_dir() && from.extension() != to.extension() {
+        return;
+    }
+    if let Ok(mut tracker) = ctx.solve_tracker.lock() {
+        tracker.rename(from, to);
+    }
+    let Some(Ok(store)) = ctx.history.as_ref().map(|h| h.lock()) else {
+        return;
+    };
+    match store.rename_path(&from.display().to_string(), &to.display().to_string()) {
+        Ok(0) => {}
+        Ok(_) => println!(
+            "📁 名前の変更を履歴に反映しました: {} → {}",
+            from.display(),
+            to.display()
+        ),
+        Err(e) => error!("名前の変更を履歴に反映できません: {}", e),
+    }
+}
+
+// 監視中に作られた問題ファイルを検索の索引に登録する (実行できるファイルなら true)
+fn register_problem(ctx: &RunContext, path: &Path) -> bool {
+    if !ctx.handlers.is_runnable(path) {
+        return false;
+    }
+    if let Some(Ok(store)) = ctx.history.as_ref().map(|h| h.lock()) {
+        match search::index_file(&store, path) {
+            Ok(header) => println!(
+                "🆕 新しい問題を登録しました: {}{}",
+                path.display(),
+                header
+                    .title
+                    .map(|title| format!(" ({})", title))
+                    .unwrap_or_default()
+            ),
+            Err(e) => error!("問題を登録できません: {} ({})", path.display(), e),
+        }
+        if let Err(e) = store.clear_removed(&path.display().to_string()) {
+            error!("削除済みの記録を更新できません: {}", e);
+        }
+    }
+    true
+}
+
+// 削除された問題ファイルをリセット扱いにする (restore-problem で作り直せる)
+fn remove_problem(ctx: &RunContext, path: &Path) {
+    if !ctx.handlers.is_runnable(path) {
+        return;
+    }
+    let Some(Ok(store)) = ctx.history.as_ref().map(|h| h.lock()) else {
+        return;
+    };
+    if let Err(e) = store.mark_removed(&path.display().to_string(), chrono::Utc::now().timestamp())
+    {
+        error!("削除を記録できません: {}", e);
+        return;
+    }
+    match link::id_for(path) {
+        Some(id) => println!(
+            "🗑 問題ファイルが削除されました: {} (restore-problem {} で元に戻せます)",
+            path.display(),
+            id
+        ),
+        None => println!("🗑 問題ファイルが削除されました: {}", path.display()),
+    }
+}
+
+// 一時停止した学習セッションを履歴DBに保存する (イベント1回だけの区間は保存しない)
+fn save_session(ctx: &RunContext, record: &SessionRecord) {
+    if record.active_seconds() == 0 {
+        return;
+    }
+    if let Ok(mut stats) = ctx.stats.lock() {
+        stats.record_session(record);
+    }
+    ctx.events.publish(events::Event::SessionEnded {
+        active_seconds: record.active_seconds(),
+    });
+    let Some(Ok(store)) = ctx.history.as_ref().map(|h| h.lock()) else {
+        return;
+    };
+    if let Err(e) = store.record_session(record) {
+        error!("学習セッションを保存できません: {}", e);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::stacktrace::Frame;
+use std::time::Duration;
+
+// 並行処理の問題 (section7-concurrency など) では -race を付け、短い時間で打ち切って
+// データ競合・デッドロック・終わらない実行をわかりやすく説明する
+
+// learn:timeout がない場合に並行処理の問題を打ち切るまでの時間
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn is_concurrency_section(section: &str) -> bool {
+    section.contains("concurrency")
+}
+
+// データ競合で衝突したアクセスの一方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Access {
+    pub write: bool,
+    // "goroutine 7" / "main goroutine"
+    pub goroutine: String,
+    pub frame: Option<Frame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    // 最初に報告された競合のアクセス (今回のアクセス、以前のアクセスの順) と報告の数
+    DataRace {
+        accesses: Vec<Access>,
+        count: usize,
+    },
+    // 止まっている goroutine の状態 ("chan send" など) と場所
+    Deadlock {
+        blocked: Vec<(String, Option<Frame>)>,
+    },
+    Timeout(Duration),
+}
+
+// 実行のエラー出力から並行処理の問題を読み取る (timed_out は打ち切ったときの制限時間)
+pub fn diagnose(stderr: &str, timed_out: Option<Duration>) -> Option<Diagnosis> {
+    if let Some(limit) = timed_out {
+        return Some(Diagnosis::Timeout(limit));
+    }
+    if stderr.contains("WARNING: DATA RACE") {
+        return Some(parse_race(stderr));
+    }
+    if stderr.contains("all goroutines are asleep - deadlock") {
+        return Some(parse_deadlock(stderr));
+    }
+    None
+}
+
+// "\t/work/main.go:12 +0x44" → Frame
+fn parse_location(line: &str, function: Option<&str>) -> Option<Frame> {
+    let location = line.trim();
+    let location = location.split(" +0x").next().unwrap_or(location);
+    let (file, line) = location.rsplit_once(':')?;
+    Some(Frame {
+        file: file.to_string(),
+        line: line.parse().ok()?,
+        column: None,
+        function: function.map(|name| {
+            name.rsplit_once('(')
+                .map_or(name, |(name, _)| name)
+                .to_string()
+        }),
+    })
+}
+
+fn parse_race(stderr: &str) -> Diagnosis {
+    let lines: Vec<&str> = stderr.lines().collect();
+    let mut accesses = Vec::new();
+    // 最初の報告 ("==================" で区切られる) だけを読む
+    let start = lines
+        .iter()
+        .position(|line| line.starts_with("WARNING: DATA RACE"))
+        .unwrap_or(0);
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.starts_with("==================") {
+            break;
+        }
+        // "Write at 0x00c000012108 by goroutine 7:" / "Previous read at ... by main goroutine:"
+        let access = line
+            .strip_prefix("Previous ")
+            .unwrap_or(line)
+            .to_lowercase();
+        let Some(write) = (if access.starts_with("write at ") {
+            Some(true)
+        } else if access.starts_with("read at ") {
+            Some(false)
+        } else {
+            None
+        }) else {
+            continue;
+        };
+        let goroutine = line
+            .rsplit_once(" by ")
+            .map(|(_, by)| by.trim_end_matches(':').to_string())
+            .unwrap_or_default();
+        let function = lines.get(index + 1).map(|line| line.trim());
+        let frame = lines
+            .get(index + 2)
+            .and_then(|line| parse_location(line, function));
+        accesses.push(Access {
+            write,
+            goroutine,
+            frame,
+        });
+    }
+    let count = lines
+        .iter()
+        .find_map(|line| {
+            line.strip_prefix("Found ")?
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()
+        })
+        .unwrap_or(1);
+    Diagnosis::DataRace { accesses, count }
+}
+
+fn parse_deadlock(stderr: &str) -> Diagnosis {
+    let lines: Vec<&str> = stderr.lines().collect();
+    let mut blocked = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        // "goroutine 1 [chan send]:" / "goroutine 6 [sync.WaitGroup.Wait, 2 minutes]:"
+        let Some(state) = line
+            .strip_prefix("goroutine ")
+            .and_then(|rest| rest.split_once(" ["))
+            .and_then(|(_, state)| state.strip_suffix("]:"))
+        else {
+            continue;
+        };
+        let state = state.split(", ").next().unwrap_or(state).to_string();
+        // 標準ライブラリの中で止まっていることが多いので、最初のユーザーのコードの場所を使う
+        let frame = lines[index + 1..]
+            .chunks(2)
+            .take_while(|chunk| !chunk[0].is_empty())
+            .filter_map(|chunk| parse_location(chunk.get(1)?, Some(chunk[0])))
+            .find(|frame| {
+                !frame.file.contains("/src/runtime/") && !frame.file.contains("/src/sync/")
+            });
+        blocked.push((state, frame));
+    }
+    Diagnosis::Deadlock { blocked }
+}
+
+fn describe_state(state: &str) -> &'static str {
+    match state {
+        "chan send" => "チャネルへの送信で止まっています (受け取る側がいません)",
+        "chan receive" => {
+            "チャネルからの受信で止まっています (送る側がいないか close されていません)"
+        }
+        "chan send (nil chan)" | "chan receive (nil chan)" => {
+            "nil のチャネルを使っています (make で作成していません)"
+        }
+        "select" => "select のどのケースも進めません",
+        "select (no cases)" => "空の select {} で止まっています",
+        "sync.WaitGroup.Wait" => "wg.Wait() で止まっています (Add と Done の回数が合っていません)",
+        "sync.Mutex.Lock" | "sync.RWMutex.Lock" | "sync.RWMutex.RLock" => {
+            "ロックの取得で止まっています (Unlock し忘れているか、二重にロックしています)"
+        }
+        "semacquire" => "WaitGroup かロックの待ちで止まっています",
+        _ => "待ち状態で止まっています",
+    }
+}
+
+fn location(frame: &Option<Frame>) -> String {
+    match frame {
+        Some(frame) => {
+            let file = frame.file.rsplit(['/', '\\']).next().unwrap_or(&frame.file);
+            format!(" ({}:{})", file, frame.line)
+        }
+        None => String::new(),
+    }
+}
+
+// 学習者向けの説明
+pub fn explain(diagnosis: &Diagnosis) -> Vec<String> {
+    match diagnosis {
+        Diagnosis::DataRace { accesses, count } => {
+            let mut lines = vec![format!(
+                "データ競合が {} 件見つかりました: 複数の goroutine が同じ変数を同時に読み書きしています",
+                count
+            )];
+            for access in accesses {
+                lines.push(format!(
+                    "  {} が{}{}",
+                    access.goroutine,
+                    if access.write { "書き込み" } else { "読み込み" },
+                    location(&access.frame)
+                ));
+            }
+            lines.push(
+                "sync.Mutex で囲むか、値をチャネルで受け渡すか、sync/atomic を使ってください"
+                    .to_string(),
+            );
+            lines
+        }
+        Diagnosis::Deadlock { blocked } => {
+            let mut lines = vec![
+                "デッドロック: すべての goroutine が待ち状態になり、先に進めなくなりました".to_string(),
+            ];
+            for (state, frame) in blocked {
+                lines.push(format!("  {}{}", describe_state(state), location(frame)));
+            }
+            lines
+        }
+        Diagnosis::Timeout(limit) => vec![
+            format!(
+                "{}秒以内に終了しなかったため打ち切りました",
+                limit.as_secs_f64()
+            ),
+            "終わらない goroutine を待ち続けているか、無限ループになっていないか確認してください (制限時間は learn:timeout で変えられます)".to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_data_race() {
+        let stderr = "==================
+WARNING: DATA RACE
+Write at 0x00c000012108 by goroutine 7:
+  main.main.func1()
+      /work/section7-concurrency/main.go:12 +0x44
+
+Previous read at 0x00c000012108 by main goroutine:
+  main.main()
+      /work/section7-concurrency/main.go:15 +0xd0
+
+Goroutine 7 (running) created at:
+  main.main()
+      /work/section7-concurrency/main.go:11 +0xbc
+==================
+Found 2 data race(s)
+exit status 66
+";
+
+        let diagnosis = diagnose(stderr, None).unwrap();
+
+        let Diagnosis::DataRace { accesses, count } = &diagnosis else {
+            panic!("{:?}", diagnosis);
+        };
+        assert_eq!(*count, 2);
+        assert_eq!(accesses.len(), 2);
+        assert!(accesses[0].write);
+        assert_eq!(accesses[1].goroutine, "main goroutine");
+        assert_eq!(accesses[1].frame.as_ref().unwrap().line, 15);
+        let lines = explain(&diagnosis);
+        assert_eq!(lines[1], "  goroutine 7 が書き込み (main.go:12)");
+    }
+
+    #[test]
+    fn test_diagnose_deadlock_and_timeout() {
+        let stderr = "fatal error: all goroutines are asleep - deadlock!
+
+goroutine 1 [semacquire]:
+sync.runtime_Semacquire(0xc000012108?)
+\t/usr/local/go/src/runtime/sema.go:62 +0x25
+sync.(*WaitGroup).Wait(0x0?)
+\t/usr/local/go/src/sync/waitgroup.go:116 +0x48
+main.main()
+\t/work/main.go:20 +0x8c
+exit status 2
+";
+
+        let diagnosis = diagnose(stderr, None).unwrap();
+
+        assert_eq!(
+            explain(&diagnosis)[1],
+            "  WaitGroup かロックの待ちで止まっています (main.go:20)"
+        );
+        assert_eq!(
+            diagnose("", Some(Duration::from_secs(5))),
+            Some(Diagnosis::Timeout(Duration::from_secs(5)))
+        );
+        assert_eq!(diagnose("panic: boom", None), None);
+    }
+}
@@ -0,0 +1,848 @@
+use crate::chat;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// <データディレクトリ>/config.toml の設定 (ファイルがなければすべて既定値)
+// 例:
+// [goals]
+// weekly_problems = 5
+// weekly_minutes = 120
+//
+// [languages.go]
+// flags = ["-race"]
+// timeout = 10
+//
+// [profiles.python-course]
+// dir = "~/learning/python"
+// [profiles.python-course.languages.python]
+// flags = ["-X", "dev"]
+//
+// [chat]
+// service = "discord"
+// webhook_url = "https://discord.com/api/webhooks/..."
+// quiet_hours = "22:00-07:00"
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub goals: Goals,
+    // すべての課題で使う言語ごとの設定
+    pub languages: Languages,
+    // 課題のディレクトリごとの設定 (監視・採点するディレクトリが dir の中にあれば languages を上書きする)
+    pub profiles: BTreeMap<String, Profile>,
+    // 拡張子ごとの実行コマンド (組み込みのものより優先する)
+    pub handlers: BTreeMap<String, HandlerConfig>,
+    // 1日のまとめを送るチャットの Webhook (なければ送らない)
+    pub chat: Option<ChatConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatService {
+    Discord,
+    Slack,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChatConfig {
+    pub service: ChatService,
+    pub webhook_url: String,
+    // 送る文面 ({date} {solved} {streak} {time} を置き換える、なければ chat::DEFAULT_TEMPLATE)
+    pub template: Option<String>,
+    // 送らない時間帯 ("22:00-07:00"、この間に送るものは終わるまで待たせる)
+    pub quiet_hours: Option<String>,
+}
+
+// [handlers.rb]
+// command = "ruby"
+// args = ["-w"]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HandlerConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Languages {
+    pub go: LanguageSettings,
+    pub python: LanguageSettings,
+}
+
+impl Languages {
+    // 設定されていない項目を fallback で埋める
+    fn or(&self, fallback: &Languages) -> Languages {
+        Languages {
+            go: self.go.or(&fallback.go),
+            python: self.python.or(&fallback.python),
+        }
+    }
+}
+
+// CLI 引数 (--go-flags / --python-args) とファイル中の learn:flags / learn:timeout が優先される
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageSettings {
+    // 実行時に付けるフラグ
+    pub flags: Option<Vec<String>>,
+    // 実行の制限時間 (秒)
+    pub timeout: Option<f64>,
+}
+
+impl LanguageSettings {
+    fn or(&self, fallback: &LanguageSettings) -> LanguageSettings {
+        LanguageSettings {
+            flags: self.flags.clone().or_else(|| fallback.flags.clone()),
+            timeout: self.timeout.or(fallback.timeout),
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+            .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    // 課題のディレクトリ (~/ はホームディレクトリ)
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub languages: Languages,
+}
+
+impl Config {
+    // dir を監視・採点するときのプロファイル名と言語ごとの設定
+    // 複数のプロファイルに当てはまる場合は dir が一番深いものを使う
+    pub fn for_dir(&self, dir: &Path) -> (Option<&str>, Languages) {
+        let dir = resolve_path(dir);
+        let profile = self
+            .profiles
+            .iter()
+            .map(|(name, profile)| (name, profile, resolve_path(&expand_home(&profile.dir))))
+            .filter(|(_, _, root)| dir.starts_with(root))
+            .max_by_key(|(_, _, root)| root.components().count());
+        match profile {
+            Some((name, profile, _)) => {
+                (Some(name.as_str()), profile.languages.or(&self.languages))
+            }
+            None => (None, self.languages.clone()),
+        }
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (
+        path.strip_prefix("~"),
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf()),
+    ) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+// シンボリックリンク越しに指定しても同じディレクトリとみなす
+fn resolve_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+// 週ごとの目標 (月曜始まり、指定しなければその目標は表示しない)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Goals {
+    // 解く問題数
+    pub weekly_problems: Option<u32>,
+    // 学習時間 (分)
+    pub weekly_minutes: Option<u32>,
+}
+
+impl Goals {
+    pub fn is_empty(&self) -> bool {
+        self.weekly_problems.is_none() && self.weekly_minutes.is_none()
+    }
+}
+
+// 環境変数の名前の接頭辞 (goals.weekly_problems は LEARNAPP_GOALS_WEEKLY_PROBLEMS)
+pub const ENV_PREFIX: &str = "LEARNAPP_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    // 0 以上の整数
+    Count,
+    // 秒数 (小数も可)
+    Seconds,
+    // 空白区切りのフラグ (ファイルでは文字列の配列)
+    Flags,
+    // ディレクトリのパス (profiles.<名前>.dir)
+    Path,
+}
+
+// 環境変数でも指定できる項目 (profiles はファイルにだけ書ける)
+pub const KEYS: &[(&str, ValueKind)] = &[
+    ("goals.weekly_problems", ValueKind::Count),
+    ("goals.weekly_minutes", ValueKind::Count),
+    ("languages.go.flags", ValueKind::Flags),
+    ("languages.go.timeout", ValueKind::Seconds),
+    ("languages.python.flags", ValueKind::Flags),
+    ("languages.python.timeout", ValueKind::Seconds),
+];
+
+pub fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+// 値をどこから読んだか
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File,
+    // 環境変数の名前
+    Env(String),
+    // .env に書かれた環境変数の名前
+    DotEnv(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "既定値"),
+            Origin::File => write!(f, "config.toml"),
+            Origin::Env(name) => write!(f, "環境変数 {}", name),
+            Origin::DotEnv(name) => write!(f, ".env の {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: &'static str,
+    // 設定されていなければ None
+    pub value: Option<toml::Value>,
+    pub origin: Origin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loaded {
+    pub config: Config,
+    // KEYS の順
+    pub entries: Vec<Entry>,
+    // 環境変数を重ねたあとの設定 (プロファイルも含む)
+    pub table: toml::Table,
+}
+
+// 項目の値の種類 (KEYS と、プロファイルの dir・languages.*)
+pub fn key_kind(key: &str) -> Option<ValueKind> {
+    let known = |key: &str| {
+        KEYS.iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, kind)| *kind)
+    };
+    if let Some(kind) = known(key) {
+        return Some(kind);
+    }
+    let (name, rest) = key.strip_prefix("profiles.")?.split_once('.')?;
+    match rest {
+        _ if name.is_empty() => None,
+        "dir" => Some(ValueKind::Path),
+        _ if rest.starts_with("languages.") => known(rest),
+        _ => None,
+    }
+}
+
+// key と同じ表にありうる項目 (プロファイルならそのプロファイルの項目)
+fn keys_like(key: &str) -> Vec<String> {
+    let mut keys: Vec<String> = KEYS.iter().map(|(name, _)| name.to_string()).collect();
+    if let Some((name, _)) = key
+        .strip_prefix("profiles.")
+        .and_then(|rest| rest.split_once('.'))
+    {
+        let prefix = format!("profiles.{}.", name);
+        keys = std::iter::once(format!("{}dir", prefix))
+            .chain(
+                KEYS.iter()
+                    .filter(|(key, _)| key.starts_with("languages."))
+                    .map(|(key, _)| format!("{}{}", prefix, key)),
+            )
+            .collect();
+    }
+    keys
+}
+
+fn unknown_key(key: &str) -> String {
+    match suggest(key, keys_like(key).iter().map(String::as_str)) {
+        Some(candidate) => format!("不明な項目です: {} (もしかして {}?)", key, candidate),
+        None => format!("不明な項目です: {} (config show で項目を確認できます)", key),
+    }
+}
+
+// レーベンシュタイン距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// 打ち間違いと思われる場合だけ一番近い候補を返す (3文字に1文字程度の違いまで)
+fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// 表ごとに書ける項目 (None ならどんな名前でもよい: プロファイル名)
+fn allowed_keys(path: &[&str]) -> Option<&'static [&'static str]> {
+    match path {
+        [] => Some(&["goals", "languages", "profiles", "handlers", "chat"]),
+        ["chat"] => Some(&["service", "webhook_url", "template", "quiet_hours"]),
+        ["handlers", _] => Some(&["command", "args"]),
+        ["goals"] => Some(&["weekly_problems", "weekly_minutes"]),
+        ["languages"] | ["profiles", _, "languages"] => Some(&["go", "python"]),
+        ["languages", _] | ["profiles", _, "languages", _] => Some(&["flags", "timeout"]),
+        ["profiles", _] => Some(&["dir", "languages"]),
+        _ => None,
+    }
+}
+
+// 設定ファイルにある不明な項目 (打ち間違いなら候補も付ける)
+fn unknown_keys(table: &toml::Table, path: &mut Vec<String>, problems: &mut Vec<String>) {
+    let allowed = allowed_keys(&path.iter().map(String::as_str).collect::<Vec<_>>());
+    let prefix: String = path.iter().map(|part| format!("{}.", part)).collect();
+    for (name, value) in table {
+        if let Some(allowed) = allowed
+            && !allowed.contains(&name.as_str())
+        {
+            problems.push(match suggest(name, allowed.iter().copied()) {
+                _ if path.is_empty() && name == "kiosk" => format!(
+                    "kiosk はシステムの設定ファイル ({}) に書いてください",
+                    system_file().display()
+                ),
+                Some(candidate) => format!(
+                    "不明な項目です: {}{} (もしかして {}{}?)",
+                    prefix, name, prefix, candidate
+                ),
+                None if path.is_empty() => format!(
+                    "不明な項目です: {} (書けるのは {})",
+                    name,
+                    allowed.join(", ")
+                ),
+                None => format!(
+                    "不明な項目です: {}{} ([{}] に書けるのは {})",
+                    prefix,
+                    name,
+                    path.join("."),
+                    allowed.join(", ")
+                ),
+            });
+            continue;
+        }
+        if let Some(table) = value.as_table() {
+            path.push(name.clone());
+            unknown_keys(table, path, problems);
+            path.pop();
+        }
+    }
+}
+
+// 環境変数や config set の値をファイルに書くときと同じ形の値にする
+fn parse_value(kind: ValueKind, text: &str) -> Result<toml::Value, String> {
+    let text = text.trim();
+    match kind {
+        ValueKind::Count => text
+            .parse::<u32>()
+            .map(|n| toml::Value::Integer(n.into()))
+            .map_err(|_| format!("0 以上の整数を指定してください: {}", text)),
+        ValueKind::Seconds => text
+            .parse::<f64>()
+            .map(toml::Value::Float)
+            .map_err(|_| format!("秒数を指定してください: {}", text)),
+        ValueKind::Flags => Ok(toml::Value::Array(
+            text.split_whitespace()
+                .map(|flag| toml::Value::String(flag.to_string()))
+                .collect(),
+        )),
+        ValueKind::Path => Ok(toml::Value::String(text.to_string())),
+    }
+}
+
+fn edit_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::Integer(n) => (*n).into(),
+        toml::Value::Float(n) => (*n).into(),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(edit_value)
+            .collect::<toml_edit::Array>()
+            .into(),
+        value => value.as_str().unwrap_or_default().into(),
+    }
+}
+
+// config set: text (設定ファイルの内容) の key を value にした内容を返す
+// コメントや項目の順番はそのまま残し、書き換えた結果が設定として正しいことも確かめる
+pub fn set(path: &Path, text: &str, key: &str, value: &str) -> Result<String, String> {
+    let kind = key_kind(key).ok_or_else(|| unknown_key(key))?;
+    let value = parse_value(kind, value).map_err(|e| format!("{}: {}", key, e))?;
+    let mut document: toml_edit::DocumentMut = text
+        .parse()
+        .map_err(|e: toml_edit::TomlError| format!("{}: {}", path.display(), e))?;
+
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let name = parts.pop().unwrap_or_default();
+    let mut table = document.as_table_mut();
+    for part in parts {
+        let item = table.entry(part).or_insert_with(|| {
+            // 途中の表は見出しを書かない ([profiles.x.languages.go] だけを書く)
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            toml_edit::Item::Table(table)
+        });
+        table = item
+            .as_table_mut()
+            .ok_or_else(|| format!("{}: {} は表ではありません", path.display(), part))?;
+    }
+    let mut new = edit_value(&value);
+    // 書き換える値の後ろのコメントも残す
+    if let Some(old) = table.get(name).and_then(|item| item.as_value()) {
+        *new.decor_mut() = old.decor().clone();
+    }
+    table[name] = toml_edit::value(new);
+
+    let text = document.to_string();
+    resolve(path, &text, |_| None)?;
+    Ok(text)
+}
+
+// config get: 環境変数を重ねたあとの値 (設定されていなければ None)
+pub fn get<'a>(loaded: &'a Loaded, key: &str) -> Result<Option<&'a toml::Value>, String> {
+    key_kind(key).ok_or_else(|| unknown_key(key))?;
+    Ok(lookup(&loaded.table, key))
+}
+
+// config validate: 読み込めるが使われない値 (0 以下の制限時間・存在しないディレクトリ)
+pub fn check(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut scopes = vec![(String::new(), &config.languages)];
+    scopes.extend(
+        config
+            .profiles
+            .iter()
+            .map(|(name, profile)| (format!("profiles.{}.", name), &profile.languages)),
+    );
+    for (prefix, languages) in scopes {
+        for (language, settings) in [("go", &languages.go), ("python", &languages.python)] {
+            if settings.timeout.is_some() && settings.timeout().is_none() {
+                problems.push(format!(
+                    "{}languages.{}.timeout: 正の秒数を指定してください",
+                    prefix, language
+                ));
+            }
+        }
+    }
+    for (name, profile) in &config.profiles {
+        if !expand_home(&profile.dir).is_dir() {
+            problems.push(format!(
+                "profiles.{}.dir: ディレクトリが存在しません: {}",
+                name,
+                profile.dir.display()
+            ));
+        }
+    }
+    if let Some(chat) = &config.chat {
+        problems.extend(chat::check(chat));
+    }
+    problems
+}
+
+// "languages.go.flags" のような名前で値を引く
+pub fn lookup<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (parents, name) = match key.rsplit_once('.') {
+        Some((parents, name)) => (Some(parents), name),
+        None => (None, key),
+    };
+    let mut table = table;
+    for part in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        table = table.get(part)?.as_table()?;
+    }
+    table.get(name)
+}
+
+// 途中の表がなければ作る
+pub fn insert(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let Some(name) = parts.pop() else {
+        return;
+    };
+    let mut table = table;
+    for part in parts {
+        let entry = table
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let Some(next) = entry.as_table_mut() else {
+            return;
+        };
+        table = next;
+    }
+    table.insert(name.to_string(), value);
+}
+
+// 設定ファイル (path の内容が text) に環境変数を重ねる (環境変数 > 設定ファイル > 既定値)
+// env は環境変数の名前から値とその出どころを返す
+pub fn resolve(
+    path: &Path,
+    text: &str,
+    env: impl Fn(&str) -> Option<(String, Origin)>,
+) -> Result<Loaded, String> {
+    let mut table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| format!("{}: {}", path.display(), e))?;
+    let mut unknown = Vec::new();
+    unknown_keys(&table, &mut Vec::new(), &mut unknown);
+    if !unknown.is_empty() {
+        return Err(unknown
+            .iter()
+            .map(|problem| format!("{}: {}", path.display(), problem))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+    let mut entries = Vec::new();
+    for (key, kind) in KEYS {
+        let name = env_name(key);
+        let origin = match env(&name) {
+            Some((text, origin)) => {
+                let value = parse_value(*kind, &text).map_err(|e| format!("{}: {}", name, e))?;
+                // 環境変数はファイルに書かれたプロファイルの設定よりも優先する
+                if key.starts_with("languages.") {
+                    let profiles: Vec<String> = table
+                        .get("profiles")
+                        .and_then(|profiles| profiles.as_table())
+                        .map(|profiles| profiles.keys().cloned().collect())
+                        .unwrap_or_default();
+                    for profile in profiles {
+                        insert(
+                            &mut table,
+                            &format!("profiles.{}.{}", profile, key),
+                            value.clone(),
+                        );
+                    }
+                }
+                insert(&mut table, key, value);
+                origin
+            }
+            None if lookup(&table, key).is_some() => Origin::File,
+            None => Origin::Default,
+        };
+        entries.push(Entry {
+            key,
+            value: lookup(&table, key).cloned(),
+            origin,
+        });
+    }
+    let config = toml::Value::Table(table.clone())
+        .try_into()
+        .map_err(|e: toml::de::Error| format!("{}: {}", path.display(), e))?;
+    Ok(Loaded {
+        config,
+        entries,
+        table,
+    })
+}
+
+// 実際の環境変数、なければカレントディレクトリの .env から引く (.env はプロセスの環境変数には入れない)
+fn process_env() -> Result<impl Fn(&str) -> Option<(String, Origin)>, String> {
+    let mut dotenv = HashMap::new();
+    match dotenvy::from_path_iter(".env") {
+        Ok(iter) => {
+            for item in iter {
+                let (name, value) = item.map_err(|e| format!(".env: {}", e))?;
+                dotenv.insert(name, value);
+            }
+        }
+        Err(e) if e.not_found() => {}
+        Err(e) => return Err(format!(".env: {}", e)),
+    }
+    Ok(move |name: &str| match std::env::var(name) {
+        Ok(value) => Some((value, Origin::Env(name.to_string()))),
+        Err(_) => dotenv
+            .get(name)
+            .map(|value| (value.clone(), Origin::DotEnv(name.to_string()))),
+    })
+}
+
+pub fn load_with_origins(path: &Path) -> Result<Loaded, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    resolve(path, &text, process_env()?)
+}
+
+pub fn load(path: &Path) -> Result<Config, String> {
+    load_with_origins(path).map(|loaded| loaded.config)
+}
+
+// 管理者が置くシステム全体の設定ファイル (今は kiosk だけを読む)
+// --data-dir や環境変数では場所を変えられないので、利用者がキオスクモードを外せない
+pub fn system_file() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\learning-programming\system.toml")
+    } else {
+        PathBuf::from("/etc/learning-programming/system.toml")
+    }
+}
+
+// キオスクモードか (ほかの項目に誤りがあって読み込めなくても kiosk = true なら解除しない)
+pub fn kiosk(path: &Path) -> bool {
+    let Ok(text) = fs::read_to_string(path) else {
+        return false;
+    };
+    match text.parse::<toml::Table>() {
+        Ok(table) => table.get("kiosk").and_then(toml::Value::as_bool) == Some(true),
+        Err(_) => text.lines().any(|line| {
+            line.split('#').next().is_some_and(|line| {
+                line.split_once('=')
+                    .is_some_and(|(key, value)| key.trim() == "kiosk" && value.trim() == "true")
+            })
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Config, String> {
+        resolve(Path::new("config.toml"), text, |_| None).map(|loaded| loaded.config)
+    }
+
+    #[test]
+    fn test_kiosk_survives_invalid_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("system.toml");
+        assert!(!kiosk(&path));
+
+        std::fs::write(&path, "kiosk = true\n").unwrap();
+        assert!(kiosk(&path));
+        // データディレクトリの設定ファイルでは切り替えられない (--data-dir で外せてしまうため)
+        assert!(
+            parse("kiosk = false\n")
+                .unwrap_err()
+                .contains("kiosk はシステムの設定ファイル")
+        );
+
+        // 書式の誤りがあってもキオスクモードは解除されない
+        std::fs::write(&path, "kiosk = true # 展示用\n[goals\n").unwrap();
+        assert!(kiosk(&path));
+        std::fs::write(&path, "# kiosk = true\n").unwrap();
+        assert!(!kiosk(&path));
+    }
+
+    #[test]
+    fn test_parse_goals() {
+        let config = parse("[goals]\nweekly_problems = 5\n").unwrap();
+        assert_eq!(
+            config.goals,
+            Goals {
+                weekly_problems: Some(5),
+                weekly_minutes: None,
+            }
+        );
+        assert_eq!(parse("").unwrap(), Config::default());
+        assert!(parse("[goals]\nweekly_problems = \"five\"\n").is_err());
+    }
+
+    #[test]
+    fn test_profile_for_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let course = tmp.path().join("python-course");
+        fs::create_dir_all(course.join("section1-basics")).unwrap();
+        let config = parse(&format!(
+            "[languages.go]\nflags = [\"-race\"]\n\n\
+             [languages.python]\nflags = [\"-X\", \"dev\"]\ntimeout = 5\n\n\
+             [profiles.python-course]\ndir = {:?}\n\
+             [profiles.python-course.languages.python]\ntimeout = 2.5\n",
+            course.display().to_string()
+        ))
+        .unwrap();
+
+        let (profile, languages) = config.for_dir(&course.join("section1-basics"));
+        assert_eq!(profile, Some("python-course"));
+        assert_eq!(
+            languages.python.timeout(),
+            Some(Duration::from_millis(2500))
+        );
+        // プロファイルにない項目は共通の設定を使う
+        assert_eq!(
+            languages.python.flags,
+            Some(vec!["-X".to_string(), "dev".to_string()])
+        );
+        assert_eq!(languages.go.flags, Some(vec!["-race".to_string()]));
+
+        let (profile, languages) = config.for_dir(tmp.path());
+        assert_eq!(profile, None);
+        assert_eq!(languages.python.timeout(), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let env = |name: &str| match name {
+            "LEARNAPP_GOALS_WEEKLY_PROBLEMS" => {
+                Some(("8".to_string(), Origin::Env(name.to_string())))
+            }
+            "LEARNAPP_LANGUAGES_GO_FLAGS" => {
+                Some(("-race -v".to_string(), Origin::DotEnv(name.to_string())))
+            }
+            _ => None,
+        };
+        let loaded = resolve(
+            Path::new("config.toml"),
+            "[goals]\nweekly_problems = 5\nweekly_minutes = 60\n\n\
+             [profiles.go-course]\ndir = \"/work/go\"\n\
+             [profiles.go-course.languages.go]\nflags = [\"-v\"]\n",
+            env,
+        )
+        .unwrap();
+
+        assert_eq!(loaded.config.goals.weekly_problems, Some(8));
+        assert_eq!(loaded.config.goals.weekly_minutes, Some(60));
+        assert_eq!(
+            loaded.config.profiles["go-course"].languages.go.flags,
+            loaded.config.languages.go.flags
+        );
+        assert_eq!(
+            loaded.config.languages.go.flags,
+            Some(vec!["-race".to_string(), "-v".to_string()])
+        );
+        let origin = |key: &str| {
+            loaded
+                .entries
+                .iter()
+                .find(|entry| entry.key == key)
+                .map(|entry| entry.origin.to_string())
+                .unwrap()
+        };
+        assert_eq!(
+            origin("goals.weekly_problems"),
+            "環境変数 LEARNAPP_GOALS_WEEKLY_PROBLEMS"
+        );
+        assert_eq!(origin("goals.weekly_minutes"), "config.toml");
+        assert_eq!(
+            origin("languages.go.flags"),
+            ".env の LEARNAPP_LANGUAGES_GO_FLAGS"
+        );
+        assert_eq!(origin("languages.python.timeout"), "既定値");
+
+        let bad = |name: &str| {
+            (name == "LEARNAPP_GOALS_WEEKLY_MINUTES")
+                .then(|| ("an hour".to_string(), Origin::Env(name.to_string())))
+        };
+        assert!(
+            resolve(Path::new("config.toml"), "", bad)
+                .unwrap_err()
+                .starts_with("LEARNAPP_GOALS_WEEKLY_MINUTES:")
+        );
+    }
+
+    #[test]
+    fn test_set_keeps_comments() {
+        let path = Path::new("config.toml");
+        let text = "# 今週は少なめ\n[goals]\nweekly_problems = 5 # 問題数\n";
+
+        let text = set(path, text, "goals.weekly_problems", "8").unwrap();
+        assert_eq!(
+            text,
+            "# 今週は少なめ\n[goals]\nweekly_problems = 8 # 問題数\n"
+        );
+
+        // dir のないプロファイルは設定として正しくないので書き換えない
+        let error = set(path, &text, "profiles.go.languages.go.flags", "-race").unwrap_err();
+        assert!(error.contains("dir"), "{}", error);
+
+        let text = set(path, &text, "profiles.go.dir", "~/learning/go").unwrap();
+        let text = set(path, &text, "profiles.go.languages.go.flags", "-race -v").unwrap();
+        assert!(text.ends_with(
+            "[profiles.go]\ndir = \"~/learning/go\"\n\n\
+             [profiles.go.languages.go]\nflags = [\"-race\", \"-v\"]\n"
+        ));
+
+        assert!(set(path, "", "goals.weekly_problems", "many").is_err());
+        assert!(
+            set(path, "", "goals.weekly", "1")
+                .unwrap_err()
+                .starts_with("不明な項目です")
+        );
+        assert_eq!(key_kind("profiles.go.dir"), Some(ValueKind::Path));
+        assert_eq!(key_kind("profiles..dir"), None);
+    }
+
+    #[test]
+    fn test_check_reports_unusable_values() {
+        let config = parse(
+            "[languages.go]\ntimeout = 0\n\n\
+             [profiles.missing]\ndir = \"/nonexistent/learning\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            check(&config),
+            [
+                "languages.go.timeout: 正の秒数を指定してください",
+                "profiles.missing.dir: ディレクトリが存在しません: /nonexistent/learning",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_suggest_nearest() {
+        let error = parse(
+            "[goals]\nweekly_problem = 5\n\n\
+             [languages.go]\nflag = [\"-race\"]\n\n\
+             [profiles.go]\ndir = \"/work\"\nsandbox = true\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.lines().collect::<Vec<_>>(),
+            [
+                "config.toml: 不明な項目です: goals.weekly_problem (もしかして goals.weekly_problems?)",
+                "config.toml: 不明な項目です: languages.go.flag (もしかして languages.go.flags?)",
+                "config.toml: 不明な項目です: profiles.go.sandbox ([profiles.go] に書けるのは dir, languages)",
+            ]
+        );
+        assert_eq!(
+            unknown_key("languages.go.timout"),
+            "不明な項目です: languages.go.timout (もしかして languages.go.timeout?)"
+        );
+        assert_eq!(
+            unknown_key("profiles.go.languages.pyhton.flags"),
+            "不明な項目です: profiles.go.languages.pyhton.flags (もしかして profiles.go.languages.python.flags?)"
+        );
+        assert_eq!(edit_distance("debouce_ms", "debounce_ms"), 1);
+    }
+}
@@ -0,0 +1,52 @@
+// テスト実行結果の出力からカバレッジ(%)を読み取る
+// Go:     "coverage: 75.0% of statements"
+// Python: pytest-cov の "TOTAL    20    5    75%"
+pub fn parse(extension: &str, output: &str) -> Option<f64> {
+    match extension {
+        "go" => output.lines().rev().find_map(parse_go_line),
+        "py" => output.lines().rev().find_map(parse_python_line),
+        _ => None,
+    }
+}
+
+fn parse_go_line(line: &str) -> Option<f64> {
+    let rest = &line[line.find("coverage:")? + "coverage:".len()..];
+    let percent = rest.trim_start().split('%').next()?;
+    percent.trim().parse().ok()
+}
+
+fn parse_python_line(line: &str) -> Option<f64> {
+    if !line.starts_with("TOTAL") {
+        return None;
+    }
+    let percent = line.split_whitespace().last()?.strip_suffix('%')?;
+    percent.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_go_coverage() {
+        let output = "=== RUN   TestSum\n--- PASS: TestSum (0.00s)\nPASS\ncoverage: 66.7% of statements\nok  \tcommand-line-arguments\t0.002s\n";
+
+        assert_eq!(parse("go", output), Some(66.7));
+    }
+
+    #[test]
+    fn test_parse_python_coverage() {
+        let output = "Name          Stmts   Miss  Cover\n---------------------------------\nsum.py           10      2    80%\n---------------------------------\nTOTAL            10      2    80%\n";
+
+        assert_eq!(parse("py", output), Some(80.0));
+    }
+
+    #[test]
+    fn test_parse_without_coverage_output() {
+        assert_eq!(
+            parse("go", "PASS\nok  \tcommand-line-arguments\t0.002s\n"),
+            None
+        );
+        assert_eq!(parse("py", "1 passed in 0.01s\n"), None);
+    }
+}
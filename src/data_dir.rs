@@ -0,0 +1,119 @@
+use directories::ProjectDirs;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// アプリケーションが書き込むファイル(DB・ログなど)の置き場所
+// Linux: ~/.local/share/learning-programming
+// Windows: %APPDATA%\learning-programming\data
+// macOS: ~/Library/Application Support/learning-programming
+#[derive(Debug, Clone)]
+pub struct DataDir {
+    root: PathBuf,
+}
+
+impl DataDir {
+    // --data-dir が指定されていればそれを優先し、なければOS標準の場所を使う
+    // ディレクトリが存在しない場合は作成する
+    pub fn resolve(override_dir: Option<&Path>) -> io::Result<Self> {
+        let root = match override_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => ProjectDirs::from("", "", env!("CARGO_PKG_NAME"))
+                .map(|dirs| dirs.data_dir().to_path_buf())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "ホームディレクトリを特定できません (--data-dir で指定してください)",
+                    )
+                })?,
+        };
+
+        let data_dir = Self { root };
+        fs::create_dir_all(data_dir.logs_dir())?;
+        Ok(data_dir)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.root.join("logs")
+    }
+
+    // 設定ファイル (なければすべて既定値)
+    pub fn config_file(&self) -> PathBuf {
+        self.root.join("config.toml")
+    }
+
+    pub fn history_db(&self) -> PathBuf {
+        self.root.join("history.db")
+    }
+
+    // 履歴DBのバックアップの保存先
+    pub fn backups_dir(&self) -> PathBuf {
+        self.root.join("backups")
+    }
+
+    // 試験の結果レポートの保存先
+    pub fn exams_dir(&self) -> PathBuf {
+        self.root.join("exams")
+    }
+
+    // 生成した効果音の保存先
+    pub fn sounds_dir(&self) -> PathBuf {
+        self.root.join("sounds")
+    }
+
+    // challenge で作ったバグ入りの解答の置き場所
+    pub fn challenges_dir(&self) -> PathBuf {
+        self.root.join("challenges")
+    }
+
+    // 履歴に保存する前の実行記録の控え
+    pub fn journal_dir(&self) -> PathBuf {
+        self.root.join("journal")
+    }
+
+    // 実行コマンドがインストールされているかの確認結果の控え
+    pub fn availability_file(&self) -> PathBuf {
+        self.root.join("availability.json")
+    }
+
+    // 監視するディレクトリごとのロックファイル (同じディレクトリを2つのプロセスで監視しない)
+    pub fn locks_dir(&self) -> PathBuf {
+        self.root.join("locks")
+    }
+
+    pub fn log_file(&self) -> PathBuf {
+        self.logs_dir()
+            .join(concat!(env!("CARGO_PKG_NAME"), ".log"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_with_override_creates_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("nested").join("data");
+
+        let data_dir = DataDir::resolve(Some(&root)).unwrap();
+
+        assert_eq!(data_dir.root(), root.as_path());
+        assert!(data_dir.logs_dir().is_dir());
+    }
+
+    #[test]
+    fn test_log_file_is_inside_logs_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::resolve(Some(tmp.path())).unwrap();
+
+        assert_eq!(
+            data_dir.log_file().parent(),
+            Some(data_dir.logs_dir().as_path())
+        );
+    }
+}
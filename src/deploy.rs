@@ -0,0 +1,233 @@
+use crate::files;
+use crate::remote::Remote;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+// 問題のディレクトリを教室の PC や USB メモリに配る (deploy コマンド)
+// 配り先には sha256sum -c で確かめられる形式のマニフェストを置き、コピーした後に全ファイルのハッシュを確かめる
+// --config ならデータディレクトリの設定ファイルを配り先の .learning-programming/config.toml に置く
+// (配り先では --data-dir <配り先>/.learning-programming で使える)
+
+pub const MANIFEST: &str = "MANIFEST.sha256";
+pub const CONFIG_DIR: &str = ".learning-programming";
+
+// 配り先 (user@host:/path ならリモート、それ以外はローカルのディレクトリ)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Local(PathBuf),
+    Remote(Remote),
+}
+
+impl Target {
+    pub fn parse(value: &str) -> Self {
+        // C:\... のようなドライブ名や ./a:b はローカルのパス
+        match value.split_once(':') {
+            Some((host, _))
+                if host.len() > 1 && !host.contains(['/', '\\']) && !host.starts_with('.') =>
+            {
+                match value.parse() {
+                    Ok(remote) => Target::Remote(remote),
+                    Err(_) => Target::Local(PathBuf::from(value)),
+                }
+            }
+            _ => Target::Local(PathBuf::from(value)),
+        }
+    }
+}
+
+// マニフェストの1行 (配り先のディレクトリからの相対パス、区切りは /)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: String,
+    pub hash: String,
+    // 配る元のファイル
+    source: PathBuf,
+}
+
+fn file_hash(path: &Path) -> io::Result<String> {
+    Ok(Sha256::digest(fs::read(path)?)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+// 配るファイル (.git と仮想環境は配らない)
+pub fn entries(dir: &Path, config: Option<&Path>) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for path in files::collect_files(dir)? {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        if relative.components().any(|c| c.as_os_str() == ".git") || relative == Path::new(MANIFEST)
+        {
+            continue;
+        }
+        entries.push(Entry {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            hash: file_hash(&path)?,
+            source: path,
+        });
+    }
+    if let Some(config) = config {
+        entries.push(Entry {
+            path: format!("{}/config.toml", CONFIG_DIR),
+            hash: file_hash(config)?,
+            source: config.to_path_buf(),
+        });
+    }
+    Ok(entries)
+}
+
+// sha256sum と同じ形式 ("<ハッシュ>  <パス>")
+pub fn format_manifest(entries: &[Entry]) -> String {
+    entries
+        .iter()
+        .map(|entry| format!("{}  {}\n", entry.hash, entry.path))
+        .collect()
+}
+
+// 配り先のファイルがマニフェストと違えばその理由を1件ずつ返す
+pub fn verify_local(target: &Path) -> io::Result<Vec<String>> {
+    let manifest = fs::read_to_string(target.join(MANIFEST))?;
+    let mut problems = Vec::new();
+    for line in manifest.lines() {
+        let Some((hash, path)) = line.split_once("  ") else {
+            problems.push(format!("マニフェストを読めません: {}", line));
+            continue;
+        };
+        match file_hash(&target.join(path)) {
+            Ok(actual) if actual == hash => {}
+            Ok(_) => problems.push(format!("内容が違います: {}", path)),
+            Err(_) => problems.push(format!("見つかりません: {}", path)),
+        }
+    }
+    Ok(problems)
+}
+
+pub fn deploy_local(entries: &[Entry], target: &Path) -> io::Result<Vec<String>> {
+    for entry in entries {
+        let destination = target.join(&entry.path);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&entry.source, destination)?;
+    }
+    fs::write(target.join(MANIFEST), format_manifest(entries))?;
+    verify_local(target)
+}
+
+// rsync で送り、リモートにマニフェストを書いて sha256sum -c で確かめる
+pub async fn deploy_remote(
+    entries: &[Entry],
+    dir: &Path,
+    remote: &Remote,
+    config: Option<&Path>,
+) -> Result<Vec<String>, String> {
+    remote.clone().with_local_root(dir).sync().await?;
+    if let Some(config) = config {
+        let output = Command::new("rsync")
+            .args(["-az", "-e", "ssh -o BatchMode=yes"])
+            .arg(config)
+            .arg(format!("{}:{}/{}/", remote.host, remote.dir, CONFIG_DIR))
+            .output()
+            .await
+            .map_err(|e| format!("rsync を実行できません: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "設定ファイルを送れません: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+
+    let script = format!(
+        "cd '{}' && cat > {} && sha256sum -c --quiet {}",
+        remote.dir.replace('\'', r"'\''"),
+        MANIFEST,
+        MANIFEST
+    );
+    let mut child = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", &remote.host, "--", &script])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("ssh を実行できません: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(format_manifest(entries).as_bytes())
+            .await
+            .map_err(|e| format!("マニフェストを送れません: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("ssh を実行できません: {}", e))?;
+    if output.status.success() {
+        return Ok(Vec::new());
+    }
+    // sha256sum -c --quiet は一致しなかったファイルだけを "<パス>: FAILED" と出す
+    let problems: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| format!("内容が違います: {}", line))
+        .collect();
+    if problems.is_empty() {
+        return Err(format!(
+            "リモートで確かめられません: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deploy_local_writes_manifest_and_verifies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("example-go");
+        fs::create_dir_all(dir.join("section1/.git")).unwrap();
+        fs::create_dir_all(dir.join(".venv")).unwrap();
+        fs::write(dir.join("section1/problem01.go"), "package main\n").unwrap();
+        fs::write(dir.join("section1/.git/HEAD"), "ref\n").unwrap();
+        fs::write(dir.join(".venv/pyvenv.cfg"), "").unwrap();
+        let config = tmp.path().join("config.toml");
+        fs::write(&config, "[goals]\nweekly_problems = 3\n").unwrap();
+
+        let target = tmp.path().join("usb");
+        let entries = entries(&dir, Some(&config)).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.path.as_str()).collect::<Vec<_>>(),
+            ["section1/problem01.go", ".learning-programming/config.toml"]
+        );
+        assert!(deploy_local(&entries, &target).unwrap().is_empty());
+        assert!(
+            fs::read_to_string(target.join(MANIFEST))
+                .unwrap()
+                .ends_with("  .learning-programming/config.toml\n")
+        );
+
+        fs::write(target.join("section1/problem01.go"), "changed\n").unwrap();
+        fs::remove_file(target.join(".learning-programming/config.toml")).unwrap();
+        assert_eq!(
+            verify_local(&target).unwrap(),
+            [
+                "内容が違います: section1/problem01.go",
+                "見つかりません: .learning-programming/config.toml",
+            ]
+        );
+
+        assert!(matches!(Target::parse("me@lab:/srv/go"), Target::Remote(_)));
+        assert!(matches!(Target::parse("/media/usb"), Target::Local(_)));
+        assert!(matches!(Target::parse(r"E:\learn"), Target::Local(_)));
+    }
+}
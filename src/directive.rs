@@ -0,0 +1,73 @@
+// ソースコード中の "learn:<name> <value>" 形式のコメントを読み取る
+// 例: Go の場合 "// learn:target TestFoo"、Python の場合 "# learn:target test_foo"
+// HTML の場合 "<!-- learn:check dom h1 -->"
+pub fn find(source: &str, name: &str) -> Option<String> {
+    find_all(source, name).into_iter().next()
+}
+
+// 同じ名前のコメントが複数ある場合 (例: learn:check) はすべて返す
+pub fn find_all(source: &str, name: &str) -> Vec<String> {
+    let key = format!("learn:{}", name);
+
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let comment = line
+                .strip_prefix("//")
+                .or_else(|| line.strip_prefix('#'))
+                .or_else(|| line.strip_prefix("<!--"))?
+                .trim_start();
+            let value = comment.strip_prefix(&key)?;
+            let value = value.trim_end().strip_suffix("-->").unwrap_or(value);
+
+            // "learn:target" と "learn:targets" を区別する
+            if !value.is_empty() && !value.starts_with(char::is_whitespace) {
+                return None;
+            }
+
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_go_comment() {
+        let source = "package main\n\n// learn:target TestSum\nfunc TestSum(t *testing.T) {}\n";
+
+        assert_eq!(find(source, "target"), Some("TestSum".to_string()));
+    }
+
+    #[test]
+    fn test_find_python_comment() {
+        let source = "# learn:target test_sum\ndef test_sum():\n    pass\n";
+
+        assert_eq!(find(source, "target"), Some("test_sum".to_string()));
+    }
+
+    #[test]
+    fn test_find_html_comment() {
+        let source = "<body>\n  <!-- learn:check dom ul li -->\n  <!-- learn:check -->\n</body>\n";
+
+        assert_eq!(find_all(source, "check"), ["dom ul li"]);
+    }
+
+    #[test]
+    fn test_find_ignores_other_names_and_empty_values() {
+        let source = "// learn:targets TestA\n// learn:target\n";
+
+        assert_eq!(find(source, "target"), None);
+    }
+
+    #[test]
+    fn test_find_all_returns_every_occurrence() {
+        let source = "// learn:check success\nfunc main() {}\n// learn:check 2 output ok\n";
+
+        assert_eq!(find_all(source, "check"), ["success", "2 output ok"]);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::runner::{RunContext, run_if_target_file};
+use log::warn;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+// 1 tick の間に届いたイベントのファイル (同じファイルは1つにまとめる)
+#[derive(Debug, Default)]
+pub struct Batch {
+    paths: Vec<PathBuf>,
+    coalesced: usize,
+}
+
+impl Batch {
+    pub fn push(&mut self, path: PathBuf) {
+        if self.paths.contains(&path) {
+            self.coalesced += 1;
+        } else {
+            self.paths.push(path);
+        }
+    }
+}
+
+// ファイルごとの状態
+#[derive(Debug, Default)]
+struct Files {
+    // 実行待ち (同じファイルを二重に積まない)
+    pending: HashSet<PathBuf>,
+    // 実行中 (同じファイルを別のワーカーで同時に実行しない)
+    running: HashSet<PathBuf>,
+    // 実行中に保存された (終わったら同じワーカーでもう一度だけ実行する)
+    rerun: HashSet<PathBuf>,
+}
+
+// 監視中の実行を決まった数のワーカーで処理する
+// 実行待ちの数には上限があり、あふれたイベントは捨てる
+pub struct WorkerPool {
+    ctx: Arc<RunContext>,
+    sender: mpsc::Sender<PathBuf>,
+    receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<PathBuf>>>,
+    files: Arc<Mutex<Files>>,
+}
+
+impl WorkerPool {
+    pub fn start(ctx: Arc<RunContext>, workers: usize, capacity: usize) -> Self {
+        let pool = Self::new(ctx, capacity);
+        for _ in 0..workers {
+            pool.spawn_worker();
+        }
+        pool
+    }
+
+    fn new(ctx: Arc<RunContext>, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        Self {
+            ctx,
+            sender,
+            receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+            files: Arc::default(),
+        }
+    }
+
+    fn spawn_worker(&self) {
+        let ctx = self.ctx.clone();
+        let receiver = self.receiver.clone();
+        let files = self.files.clone();
+        tokio::spawn(async move {
+            loop {
+                let Some(path) = receiver.lock().await.recv().await else {
+                    break;
+                };
+                if let Ok(mut files) = files.lock() {
+                    files.pending.remove(&path);
+                    files.running.insert(path.clone());
+                }
+                loop {
+                    run_if_target_file(path.clone(), ctx.clone()).await;
+                    let Ok(mut files) = files.lock() else {
+                        break;
+                    };
+                    if !files.rerun.remove(&path) {
+                        files.running.remove(&path);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn dispatch(&self, batch: &mut Batch) {
+        let paths = std::mem::take(&mut batch.paths);
+        let mut coalesced = std::mem::take(&mut batch.coalesced);
+        let mut queued = 0;
+        let mut dropped = 0;
+        if let Ok(mut files) = self.files.lock() {
+            for path in paths {
+                if files.pending.contains(&path) || files.rerun.contains(&path) {
+                    coalesced += 1;
+                    continue;
+                }
+                if files.running.contains(&path) {
+                    files.rerun.insert(path);
+                    queued += 1;
+                    continue;
+                }
+                match self.sender.try_send(path.clone()) {
+                    Ok(()) => {
+                        files.pending.insert(path);
+                        queued += 1;
+                    }
+                    Err(TrySendError::Full(_)) => {
+                        warn!("実行待ちがいっぱいのため無視しました: {}", path.display());
+                        dropped += 1;
+                    }
+                    Err(TrySendError::Closed(_)) => dropped += 1,
+                }
+            }
+        }
+
+        let depth = self.sender.max_capacity() - self.sender.capacity();
+        if let Ok(mut stats) = self.ctx.stats.lock() {
+            let queue = &mut stats.queue;
+            queue.queued += queued;
+            queue.coalesced += coalesced;
+            queue.dropped += dropped;
+            queue.max_depth = queue.max_depth.max(depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_dispatch_coalesces_and_drops() {
+        // ワーカーなしなら積んだものがそのまま残る
+        let pool = WorkerPool::new(Arc::new(RunContext::default()), 2);
+        let mut batch = Batch::default();
+        for path in ["a.go", "b.go", "a.go"] {
+            batch.push(PathBuf::from(path));
+        }
+        pool.dispatch(&mut batch);
+        assert!(batch.paths.is_empty());
+
+        // a.go はまだ実行待ち、c.go は上限を超える
+        batch.push(PathBuf::from("a.go"));
+        batch.push(PathBuf::from("c.go"));
+        pool.dispatch(&mut batch);
+
+        // 実行中のファイルは2つ目のワーカーに渡さず、終わった後の1回にまとめる
+        pool.files
+            .lock()
+            .unwrap()
+            .running
+            .insert(PathBuf::from("d.go"));
+        batch.push(PathBuf::from("d.go"));
+        pool.dispatch(&mut batch);
+        batch.push(PathBuf::from("d.go"));
+        pool.dispatch(&mut batch);
+        assert!(pool.files.lock().unwrap().rerun.contains(Path::new("d.go")));
+
+        let stats = pool.ctx.stats.lock().unwrap();
+        assert_eq!(stats.queue.queued, 3);
+        assert_eq!(stats.queue.coalesced, 3);
+        assert_eq!(stats.queue.dropped, 1);
+        assert_eq!(stats.queue.max_depth, 2);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::problem::ProblemHeader;
+use std::io;
+use std::process::Command;
+
+// カリキュラムのトピックごとの参考ドキュメント
+const GO_TOPIC_DOCS: &[(&str, &str)] = &[
+    ("Package Declaration", "https://go.dev/tour/basics/1"),
+    ("Import Statements", "https://go.dev/tour/basics/2"),
+    ("Function Basics", "https://go.dev/tour/basics/4"),
+    ("Multiple Returns", "https://go.dev/tour/basics/6"),
+    ("Variables", "https://go.dev/tour/basics/8"),
+    ("Data Types", "https://go.dev/tour/basics/11"),
+    ("Constants", "https://go.dev/tour/basics/15"),
+    ("For Loops", "https://go.dev/tour/flowcontrol/1"),
+    ("If Statements", "https://go.dev/tour/flowcontrol/5"),
+    ("Control Flow", "https://go.dev/tour/flowcontrol/1"),
+    ("Switch Statements", "https://go.dev/tour/flowcontrol/9"),
+    (
+        "Variadic Functions",
+        "https://go.dev/ref/spec#Passing_arguments_to_..._parameters",
+    ),
+    ("Pointer Basics", "https://go.dev/tour/moretypes/1"),
+    ("Pointer Usage", "https://go.dev/tour/methods/4"),
+    ("Memory Management", "https://go.dev/doc/gc-guide"),
+    ("Struct Definition", "https://go.dev/tour/moretypes/2"),
+    ("Arrays", "https://go.dev/tour/moretypes/6"),
+    ("Slices", "https://go.dev/tour/moretypes/7"),
+    ("Maps", "https://go.dev/tour/moretypes/19"),
+    ("Methods", "https://go.dev/tour/methods/1"),
+    ("Embedding", "https://go.dev/doc/effective_go#embedding"),
+    ("Interface Basics", "https://go.dev/tour/methods/9"),
+    ("Empty Interface", "https://go.dev/tour/methods/14"),
+    (
+        "Interface Composition",
+        "https://go.dev/doc/effective_go#embedding",
+    ),
+    ("Goroutines", "https://go.dev/tour/concurrency/1"),
+    ("Channels", "https://go.dev/tour/concurrency/2"),
+    ("Select Statement", "https://go.dev/tour/concurrency/5"),
+    ("Error Interface", "https://go.dev/tour/methods/19"),
+    ("Error Creation", "https://pkg.go.dev/errors#New"),
+    (
+        "Error Handling Patterns",
+        "https://go.dev/blog/error-handling-and-go",
+    ),
+    ("Error Wrapping", "https://go.dev/blog/go1.13-errors"),
+    ("Errors Is And As", "https://pkg.go.dev/errors#Is"),
+    ("Joined Errors", "https://pkg.go.dev/errors#Join"),
+    ("Standard Library", "https://pkg.go.dev/std"),
+    ("Type Parameters", "https://go.dev/tour/generics/1"),
+    ("Generic Types", "https://go.dev/tour/generics/2"),
+    (
+        "Type Constraints",
+        "https://go.dev/ref/spec#Type_constraints",
+    ),
+    ("Context Basics", "https://pkg.go.dev/context"),
+    (
+        "Context Cancellation",
+        "https://pkg.go.dev/context#WithCancel",
+    ),
+    (
+        "Context Deadlines",
+        "https://pkg.go.dev/context#WithDeadline",
+    ),
+];
+
+const PYTHON_TOPIC_DOCS: &[(&str, &str)] = &[
+    (
+        "Variables",
+        "https://docs.python.org/3/tutorial/introduction.html",
+    ),
+    (
+        "If Statements",
+        "https://docs.python.org/3/tutorial/controlflow.html#if-statements",
+    ),
+    (
+        "Loops",
+        "https://docs.python.org/3/tutorial/controlflow.html#for-statements",
+    ),
+    (
+        "Functions",
+        "https://docs.python.org/3/tutorial/controlflow.html#defining-functions",
+    ),
+    (
+        "Data Structures",
+        "https://docs.python.org/3/tutorial/datastructures.html",
+    ),
+    ("Modules", "https://docs.python.org/3/tutorial/modules.html"),
+    ("Classes", "https://docs.python.org/3/tutorial/classes.html"),
+    (
+        "Exceptions",
+        "https://docs.python.org/3/tutorial/errors.html",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Language {
+    Go,
+    Python,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "go" => Some(Self::Go),
+            "py" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Go => GO_TOPIC_DOCS,
+            Self::Python => PYTHON_TOPIC_DOCS,
+        }
+    }
+}
+
+// トピック名 (大文字小文字は区別しない) の参考ドキュメント
+pub fn for_topic(topic: &str, language: Language) -> Option<&'static str> {
+    language
+        .table()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(topic.trim()))
+        .map(|(_, url)| *url)
+}
+
+// 問題の参考ドキュメント
+// "Docs:" コメントに書かれたURLを優先し、なければトピックの既定のページを使う
+pub fn for_problem(header: &ProblemHeader, language: Language) -> Vec<String> {
+    if !header.docs.is_empty() {
+        return header.docs.clone();
+    }
+    header
+        .topic
+        .as_deref()
+        .and_then(|topic| for_topic(topic, language))
+        .map(|url| vec![url.to_string()])
+        .unwrap_or_default()
+}
+
+// 表に載っていないトピックは公式ドキュメントの検索結果を開く
+pub fn search_url(query: &str, language: Language) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join("+");
+    match language {
+        Language::Go => format!("https://pkg.go.dev/search?q={}", query),
+        Language::Python => format!("https://docs.python.org/3/search.html?q={}", query),
+    }
+}
+
+// OS標準のブラウザでURLを開く
+pub fn open_in_browser(url: &str) -> io::Result<()> {
+    let mut command = match std::env::consts::OS {
+        "windows" => {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        "macos" => Command::new("open"),
+        _ => Command::new("xdg-open"),
+    };
+    command.arg(url).spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_topic_ignores_case() {
+        assert_eq!(
+            for_topic("goroutines", Language::Go),
+            Some("https://go.dev/tour/concurrency/1")
+        );
+        assert_eq!(for_topic("Goroutines", Language::Python), None);
+    }
+
+    #[test]
+    fn test_for_problem_prefers_header_docs() {
+        let mut header = ProblemHeader {
+            topic: Some("Maps".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            for_problem(&header, Language::Go),
+            ["https://go.dev/tour/moretypes/19"]
+        );
+
+        header.docs = vec!["https://go.dev/blog/maps".to_string()];
+        assert_eq!(
+            for_problem(&header, Language::Go),
+            ["https://go.dev/blog/maps"]
+        );
+        assert_eq!(
+            search_url("list comprehension", Language::Python),
+            "https://docs.python.org/3/search.html?q=list+comprehension"
+        );
+    }
+}
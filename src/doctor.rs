@@ -0,0 +1,110 @@
+use crate::availability::Availability;
+use crate::directive;
+use crate::dom;
+use crate::environment;
+use crate::files;
+use crate::handler::{Registry, Source};
+use crate::runner;
+use crate::toolchain::{Requirement, Version};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use which::which;
+
+// 実行環境を確認し、dir が指定されていれば learn:requires を満たさない問題を一覧表示する
+// refresh なら実行コマンドの確認結果の控えを使わずに探し直す
+pub async fn run(
+    dir: Option<&Path>,
+    handlers: &Registry,
+    availability: &mut Availability,
+    refresh: bool,
+) -> io::Result<()> {
+    println!("=== 実行環境 ===");
+    println!("{} mise", if which("mise").is_ok() { "✅" } else { "❌" });
+
+    let mut installed = BTreeMap::new();
+    for command_name in ["go", "python"] {
+        let version = environment::tool_version(command_name).await;
+        match &version {
+            Some(version) => println!("✅ {}: {}", command_name, version),
+            None => println!("❌ {}: 見つかりません", command_name),
+        }
+        installed.insert(command_name, version.as_deref().and_then(Version::find_in));
+    }
+
+    println!("\n=== 実行コマンド ===");
+    let now = chrono::Utc::now().timestamp();
+    let mut candidates: Vec<(String, Vec<String>)> = Vec::new();
+    for handler in handlers.handlers() {
+        let mut commands = vec![handler.command.clone()];
+        // ブラウザはディストリビューションによってコマンド名が違う
+        if handler.source == Source::BuiltIn && handler.extension == "html" {
+            for name in dom::BROWSERS {
+                if !commands.iter().any(|command| command == name) {
+                    commands.push(name.to_string());
+                }
+            }
+        }
+        candidates.push((handler.extension.clone(), commands));
+    }
+    if refresh {
+        let commands: Vec<String> = candidates
+            .iter()
+            .flat_map(|(_, commands)| commands.clone())
+            .collect();
+        availability.refresh(&commands, now);
+        println!("🔄 実行コマンドを探し直しました (監視中のプロセスにも反映されます)");
+    }
+    for (extension, commands) in &candidates {
+        let found = commands
+            .iter()
+            .find_map(|command| Some((command, availability.find(command, now)?)));
+        match found {
+            Some((command, path)) => {
+                println!("✅ .{}: {} ({})", extension, command, path.display())
+            }
+            None => println!(
+                "❌ .{}: {} が見つかりません",
+                extension,
+                commands.join(", ")
+            ),
+        }
+    }
+
+    let Some(dir) = dir else {
+        return Ok(());
+    };
+
+    println!("\n=== バージョン要件 ({}) ===", dir.display());
+    let mut unsatisfied = 0;
+    for path in files::collect_files(dir)? {
+        let Some(command_name) = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .and_then(runner::command_for_extension)
+        else {
+            continue;
+        };
+        let Some(raw) = directive::find(&fs::read_to_string(&path)?, "requires") else {
+            continue;
+        };
+
+        let status = match (
+            Requirement::parse(&raw),
+            installed.get(command_name).cloned().flatten(),
+        ) {
+            (None, _) => "⚠️ 書式エラー".to_string(),
+            (Some(_), None) => "⚠️ バージョン不明".to_string(),
+            (Some(requirement), Some(version)) if requirement.matches(&version) => continue,
+            (Some(_), Some(version)) => format!("❌ インストール済み {}", version),
+        };
+        unsatisfied += 1;
+        println!("{} {} ({})", path.display(), raw, status);
+    }
+
+    if unsatisfied == 0 {
+        println!("✅ すべての問題を実行できます");
+    }
+    Ok(())
+}
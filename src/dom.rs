@@ -0,0 +1,321 @@
+// ヘッドレスブラウザが出力した DOM (--dump-dom) を読み、learn:check dom の条件を調べる
+// ブラウザが整形した HTML を読むだけなので、タグの入れ子を追う簡単なパーサで十分
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub tag: String,
+    pub attributes: Vec<(String, String)>,
+    pub parent: Option<usize>,
+    // 子孫のテキストをつなげたもの
+    pub text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attribute("class")
+            .unwrap_or_default()
+            .split_whitespace()
+    }
+}
+
+// HTML の実行に使うブラウザのコマンド (learn:interpreter がなければこの順に探す)
+pub const BROWSERS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+];
+
+// 子要素を持たない要素
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+#[derive(Debug, Default)]
+pub struct Document {
+    pub elements: Vec<Element>,
+}
+
+impl Document {
+    pub fn parse(html: &str) -> Self {
+        let mut elements: Vec<Element> = Vec::new();
+        let mut stack: Vec<usize> = Vec::new();
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                append_text(&mut elements, &stack, rest);
+                break;
+            };
+            append_text(&mut elements, &stack, &rest[..start]);
+            rest = &rest[start..];
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.split_once("-->").map_or("", |(_, after)| after);
+                continue;
+            }
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                // 対応する開始タグまで閉じる
+                let name = name.trim().to_lowercase();
+                if let Some(position) = stack.iter().rposition(|&i| elements[i].tag == name) {
+                    stack.truncate(position);
+                }
+                continue;
+            }
+            if tag.starts_with('!') || tag.starts_with('?') {
+                continue;
+            }
+
+            let (name, attributes) = tag
+                .split_once(|c: char| c.is_whitespace())
+                .unwrap_or((tag, ""));
+            let name = name.trim_end_matches('/').to_lowercase();
+            elements.push(Element {
+                tag: name.clone(),
+                attributes: parse_attributes(attributes),
+                parent: stack.last().copied(),
+                text: String::new(),
+            });
+            let index = elements.len() - 1;
+
+            // script / style の中身はタグとして読まない
+            if name == "script" || name == "style" {
+                let close = format!("</{}", name);
+                let end = rest.to_lowercase().find(&close).unwrap_or(rest.len());
+                rest = &rest[end..];
+                rest = rest.split_once('>').map_or("", |(_, after)| after);
+                continue;
+            }
+            if !VOID_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+                stack.push(index);
+            }
+        }
+        Self { elements }
+    }
+
+    // セレクタに一致する要素
+    pub fn select(&self, selector: &str) -> Vec<&Element> {
+        let Some(compounds) = parse_selector(selector) else {
+            return Vec::new();
+        };
+        (0..self.elements.len())
+            .filter(|&index| self.matches(index, &compounds))
+            .map(|index| &self.elements[index])
+            .collect()
+    }
+
+    // 最後の部分がこの要素に、それより前の部分が祖先に順に一致するか (子孫結合子)
+    fn matches(&self, index: usize, compounds: &[Compound]) -> bool {
+        let Some((last, ancestors)) = compounds.split_last() else {
+            return false;
+        };
+        if !last.matches(&self.elements[index]) {
+            return false;
+        }
+        let mut remaining = ancestors;
+        let mut current = self.elements[index].parent;
+        while let Some((compound, rest)) = remaining.split_last() {
+            let Some(parent) = current else {
+                return false;
+            };
+            if compound.matches(&self.elements[parent]) {
+                remaining = rest;
+            }
+            current = self.elements[parent].parent;
+        }
+        true
+    }
+}
+
+fn append_text(elements: &mut [Element], stack: &[usize], text: &str) {
+    let text = decode_entities(text);
+    for &index in stack {
+        elements[index].text.push_str(&text);
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+// id="title" class='a b' disabled
+fn parse_attributes(text: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = text.trim().trim_end_matches('/');
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        after[1..].split_once(quote).unwrap_or((&after[1..], ""))
+                    }
+                    _ => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+                };
+                rest = remaining.trim_start();
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+    }
+    attributes
+}
+
+// "li.item", "#title", "input[type=checkbox]" のような1つの要素の条件
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        self.tag.as_ref().is_none_or(|tag| *tag == element.tag)
+            && self
+                .id
+                .as_ref()
+                .is_none_or(|id| element.attribute("id") == Some(id.as_str()))
+            && self
+                .classes
+                .iter()
+                .all(|class| element.classes().any(|c| c == class))
+            && self
+                .attributes
+                .iter()
+                .all(|(name, value)| match (element.attribute(name), value) {
+                    (Some(actual), Some(expected)) => actual == expected,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                })
+    }
+}
+
+// 空白区切り (子孫結合子) の単純なセレクタだけを扱う (読めなければ None)
+fn parse_selector(selector: &str) -> Option<Vec<Compound>> {
+    let compounds = selector
+        .split_whitespace()
+        .map(parse_compound)
+        .collect::<Option<Vec<_>>>()?;
+    (!compounds.is_empty()).then_some(compounds)
+}
+
+fn parse_compound(text: &str) -> Option<Compound> {
+    let mut compound = Compound::default();
+    let name_end = text.find(['#', '.', '[']).unwrap_or(text.len());
+    let tag = &text[..name_end];
+    if !tag.is_empty() && tag != "*" {
+        compound.tag = Some(tag.to_lowercase());
+    }
+    let mut rest = &text[name_end..];
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '[' => {
+                let (inner, after) = rest[1..].split_once(']')?;
+                let attribute = match inner.split_once('=') {
+                    Some((name, value)) => (
+                        name.trim().to_lowercase(),
+                        Some(value.trim().trim_matches(['"', '\'']).to_string()),
+                    ),
+                    None => (inner.trim().to_lowercase(), None),
+                };
+                compound.attributes.push(attribute);
+                rest = after;
+            }
+            '#' | '.' => {
+                let end = rest[1..]
+                    .find(['#', '.', '['])
+                    .map_or(rest.len(), |i| i + 1);
+                let name = rest[1..end].to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                if c == '#' {
+                    compound.id = Some(name);
+                } else {
+                    compound.classes.push(name);
+                }
+                rest = &rest[end..];
+            }
+            _ => return None,
+        }
+    }
+    Some(compound)
+}
+
+// 連続する空白を1つにまとめたテキスト
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<!DOCTYPE html><html><head><title>Todo</title>
+<style>li > a { color: red; }</style></head>
+<body>
+<!-- learn:check dom ul#todos li.done -->
+<h1 id="title">やること &amp; メモ</h1>
+<ul id="todos">
+  <li class="item done">牛乳を買う<br></li>
+  <li class="item">宿題</li>
+</ul>
+<input type="checkbox" checked>
+<script>if (a < b) { document.title = "<p>"; }</script>
+</body></html>"#;
+
+    #[test]
+    fn test_select_elements() {
+        let document = Document::parse(HTML);
+
+        assert_eq!(document.select("li").len(), 2);
+        assert_eq!(document.select("ul#todos li.done").len(), 1);
+        assert_eq!(document.select("body li.item").len(), 2);
+        assert_eq!(document.select("input[type=checkbox][checked]").len(), 1);
+        assert!(document.select("ol li").is_empty());
+        assert!(document.select("p").is_empty());
+        assert_eq!(document.select("#title")[0].text, "やること & メモ");
+        assert_eq!(
+            collapse_whitespace(&document.select("#todos")[0].text),
+            "牛乳を買う 宿題"
+        );
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        let document = Document::parse(HTML);
+
+        assert!(document.select("li > a").is_empty());
+        assert!(document.select("li.").is_empty());
+    }
+}
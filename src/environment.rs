@@ -0,0 +1,66 @@
+use std::env;
+use tokio::process::Command;
+
+// 実行時の環境 (「昨日は動いたのに」の調査用に履歴へ保存する)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    // "go version go1.22.1 linux/amd64" / "Python 3.12.2" など
+    pub tool_version: Option<String>,
+    pub os: String,
+    pub app_version: String,
+}
+
+pub async fn capture(command_name: &str) -> Environment {
+    Environment {
+        tool_version: tool_version(command_name).await,
+        os: format!("{} {}", env::consts::OS, env::consts::ARCH),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+// コンパイラ/インタプリタのバージョン表記の1行目を返す
+pub async fn tool_version(command_name: &str) -> Option<String> {
+    let arg = match command_name {
+        "go" => "version",
+        _ => "--version",
+    };
+
+    let output = Command::new(command_name).arg(arg).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // 古いPythonはバージョンを標準エラーに出力する
+    let text = if output.stdout.is_empty() {
+        String::from_utf8_lossy(&output.stderr)
+    } else {
+        String::from_utf8_lossy(&output.stdout)
+    };
+
+    text.lines()
+        .next()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_capture_includes_os_and_app_version() {
+        let environment = capture("python").await;
+
+        assert!(environment.os.starts_with(env::consts::OS));
+        assert_eq!(environment.app_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_version_of_missing_command() {
+        assert_eq!(
+            tool_version("learning-programming-missing-tool").await,
+            None
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+// 監視・実行・採点で起きたことを知らせるイベント
+// 実行する側は publish するだけで、効果音などの受け手は subscribe したタスクで処理する
+// (--summary の集計は終了時に漏れなく書き出すため、イベントを使わずにその場で記録する)
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    // 監視しているファイルが保存された
+    FileChanged {
+        path: PathBuf,
+    },
+    ExecutionStarted {
+        path: PathBuf,
+    },
+    // 実行できなかった場合も success = false で知らせる
+    ExecutionFinished {
+        path: PathBuf,
+        success: bool,
+        duration: Duration,
+    },
+    // カリキュラムの問題を初めて解いた (xp は付与した XP の合計)
+    ProblemPassed {
+        path: PathBuf,
+        xp: i64,
+    },
+    // 学習セッションが一時停止・終了した
+    SessionEnded {
+        active_seconds: i64,
+    },
+}
+
+// 受け手が処理しきれずにたまってよいイベントの数 (超えると古いものから捨てられる)
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    // 受け手がいなくてもよい
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+// 受け手のタスク: 閉じられるまでイベントを handle に渡す (追いつけずに捨てられた分は飛ばす)
+pub fn spawn_subscriber(
+    bus: &EventBus,
+    mut handle: impl FnMut(Event) + Send + 'static,
+) -> tokio::task::JoinHandle<()> {
+    let mut receiver = bus.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => handle(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("イベントを {} 件処理できませんでした", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        let bus = EventBus::default();
+        // 受け手がいなくても publish できる
+        bus.publish(Event::SessionEnded { active_seconds: 1 });
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let task = {
+            let received = received.clone();
+            spawn_subscriber(&bus, move |event| received.lock().unwrap().push(event))
+        };
+        let path = PathBuf::from("section1/a.go");
+        bus.publish(Event::ExecutionStarted { path: path.clone() });
+        bus.publish(Event::ProblemPassed {
+            path: path.clone(),
+            xp: 45,
+        });
+        drop(bus);
+        task.await.unwrap();
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            [
+                Event::ExecutionStarted { path: path.clone() },
+                Event::ProblemPassed { path, xp: 45 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::history::{Exam, ExecutionRecord};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::Path;
+
+// 制限時間の上限 (分)
+pub const MAX_MINUTES: i64 = 24 * 60;
+
+// レポートの最終行 (これより前の本文の HMAC)
+// 鍵は講師が持つ鍵ファイルの内容で、鍵を知らなければ本文を書き換えた後に封印し直せない
+// 封印で確かめられるのは exam finish の後にレポートが書き換えられていないことだけ
+// 本文は学習者が書き込める履歴 DB から作るので、exam finish は講師が自分の鍵を持ち込んで実行する
+const SEAL_PREFIX: &str = "HMAC-SHA256: ";
+
+// 残り時間 (秒、時間切れなら 0)
+pub fn remaining_seconds(exam: &Exam, now: i64) -> i64 {
+    (exam.deadline - now).max(0)
+}
+
+pub fn is_over(exam: &Exam, now: i64) -> bool {
+    now > exam.deadline
+}
+
+// 問題ごとの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemResult {
+    pub file_path: String,
+    pub passed: bool,
+    // learn:check がある問題の最高点
+    pub best_score: Option<i64>,
+    pub runs: usize,
+}
+
+// 試験時間内の実行だけで問題ごとの結果をまとめる
+pub fn results(
+    exam: &Exam,
+    problems: &[String],
+    records: &[ExecutionRecord],
+) -> Vec<ProblemResult> {
+    results_between(problems, records, exam.started_at, exam.deadline)
+}
+
+// from 以上 to 以下に実行された記録で問題ごとの結果をまとめる
+pub fn results_between(
+    problems: &[String],
+    records: &[ExecutionRecord],
+    from: i64,
+    to: i64,
+) -> Vec<ProblemResult> {
+    problems
+        .iter()
+        .map(|file_path| {
+            let runs: Vec<&ExecutionRecord> = records
+                .iter()
+                .filter(|r| &r.file_path == file_path)
+                .filter(|r| r.executed_at >= from && r.executed_at <= to)
+                .collect();
+            ProblemResult {
+                file_path: file_path.clone(),
+                passed: runs.iter().any(|r| r.success),
+                best_score: runs.iter().filter_map(|r| r.score).max(),
+                runs: runs.len(),
+            }
+        })
+        .collect()
+}
+
+pub fn report(exam: &Exam, results: &[ProblemResult]) -> String {
+    let time = |timestamp: i64| {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut report = String::new();
+    report.push_str("=== 試験結果 ===\n");
+    report.push_str(&format!("課題: {}\n", exam.assignment));
+    report.push_str(&format!("開始: {}\n", time(exam.started_at)));
+    report.push_str(&format!("期限: {}\n", time(exam.deadline)));
+    if let Some(finished_at) = exam.finished_at {
+        report.push_str(&format!("終了: {}\n", time(finished_at)));
+    }
+    report.push('\n');
+
+    for result in results {
+        report.push_str(&format!(
+            "[{}] {} 得点 {} 実行 {}回\n",
+            if result.passed { "合格" } else { "不合格" },
+            result.file_path,
+            result
+                .best_score
+                .map(|score| format!("{}/100", score))
+                .unwrap_or_else(|| "-".to_string()),
+            result.runs
+        ));
+    }
+    report.push_str(&format!(
+        "\n合計: {}/{}問 合格\n",
+        results.iter().filter(|r| r.passed).count(),
+        results.len()
+    ));
+    report
+}
+
+// 鍵ファイルの内容 (前後の空白と改行は除く)
+pub fn read_key(path: &Path) -> Result<Vec<u8>, String> {
+    let key = std::fs::read(path)
+        .map_err(|e| format!("鍵ファイルを読めません: {}: {}", path.display(), e))?;
+    let key = key.trim_ascii();
+    if key.is_empty() {
+        return Err(format!("鍵ファイルが空です: {}", path.display()));
+    }
+    Ok(key.to_vec())
+}
+
+// 学習者が書き込める場所 (データディレクトリ・課題のディレクトリ) にある鍵ファイルは使わない
+pub fn check_key_location(path: &Path, learner_dirs: &[&Path]) -> Result<(), String> {
+    let resolve = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let key = resolve(path);
+    match learner_dirs
+        .iter()
+        .find(|dir| key.starts_with(resolve(dir)))
+    {
+        Some(dir) => Err(format!(
+            "鍵ファイルが学習者の書き込める場所にあります: {} ({} の外、講師が持ち込んだ USB メモリなどに置いてください)",
+            path.display(),
+            dir.display()
+        )),
+        None => Ok(()),
+    }
+}
+
+// 本文の HMAC を最終行に付ける (書き換えると同じ鍵を使った verify で検出できる)
+pub fn seal(body: &str, key: &[u8]) -> String {
+    format!("{}{}{}\n", body, SEAL_PREFIX, digest(body, key))
+}
+
+// 封印されていなければ None
+pub fn verify(text: &str, key: &[u8]) -> Option<bool> {
+    let trimmed = text.strip_suffix('\n').unwrap_or(text);
+    let (body, seal_line) = match trimmed.rfind('\n') {
+        Some(i) => (&trimmed[..=i], &trimmed[i + 1..]),
+        None => ("", trimmed),
+    };
+    let expected = seal_line.strip_prefix(SEAL_PREFIX)?;
+    // 比べるのにかかる時間から正しい値を推測されないよう、一定の時間で比べる
+    Some(
+        decode_hex(expected).is_some_and(|expected| mac(body, key).verify_slice(&expected).is_ok()),
+    )
+}
+
+fn mac(body: &str, key: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC は任意の長さの鍵を使える");
+    mac.update(body.as_bytes());
+    mac
+}
+
+fn digest(body: &str, key: &[u8]) -> String {
+    mac(body, key)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+
+    fn exam() -> Exam {
+        Exam {
+            id: 1,
+            assignment: "/exam".to_string(),
+            started_at: 100,
+            deadline: 200,
+            finished_at: Some(190),
+        }
+    }
+
+    fn run(
+        file_path: &str,
+        executed_at: i64,
+        success: bool,
+        score: Option<i64>,
+    ) -> ExecutionRecord {
+        ExecutionRecord {
+            id: 0,
+            file_path: file_path.to_string(),
+            section: String::new(),
+            success,
+            exit_code: Some(if success { 0 } else { 1 }),
+            duration_ms: 10,
+            executed_at,
+            output_preview: String::new(),
+            environment: Environment {
+                tool_version: None,
+                os: "linux x86_64".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: None,
+            todos_remaining: None,
+            score,
+            content_hash: None,
+            error_signature: None,
+            language: None,
+            coverage: None,
+        }
+    }
+
+    #[test]
+    fn test_results_only_count_runs_within_time_limit() {
+        let problems = ["/exam/a.go".to_string(), "/exam/b.go".to_string()];
+        let records = [
+            run("/exam/a.go", 120, false, Some(40)),
+            run("/exam/a.go", 150, true, Some(80)),
+            // 時間切れ後の成功は数えない
+            run("/exam/b.go", 250, true, None),
+        ];
+
+        let results = results(&exam(), &problems, &records);
+
+        assert!(results[0].passed);
+        assert_eq!(results[0].best_score, Some(80));
+        assert_eq!(results[0].runs, 2);
+        assert!(!results[1].passed);
+        assert_eq!(results[1].runs, 0);
+    }
+
+    #[test]
+    fn test_seal_detects_tampering() {
+        let sealed = seal(&report(&exam(), &[]), b"teacher-secret");
+
+        assert_eq!(verify(&sealed, b"teacher-secret"), Some(true));
+        assert_eq!(
+            verify(&sealed.replace("0/0問", "1/0問"), b"teacher-secret"),
+            Some(false)
+        );
+        // 鍵を知らずに封印し直したレポートも通らない
+        let forged = seal(&report(&exam(), &[]).replace("0/0問", "1/0問"), b"guess");
+        assert_eq!(verify(&forged, b"teacher-secret"), Some(false));
+        assert_eq!(verify("unsealed\n", b"teacher-secret"), None);
+        assert_eq!(
+            verify(&format!("body\n{}zz", SEAL_PREFIX), b"teacher-secret"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_key_in_learner_directory_is_rejected() {
+        let data = tempfile::tempdir().unwrap();
+        let teacher = tempfile::tempdir().unwrap();
+        for dir in [&data, &teacher] {
+            std::fs::write(dir.path().join("exam.key"), "secret").unwrap();
+        }
+        let learner_dirs = [data.path()];
+        assert!(check_key_location(&data.path().join("exam.key"), &learner_dirs).is_err());
+        assert!(check_key_location(&teacher.path().join("exam.key"), &learner_dirs).is_ok());
+    }
+}
@@ -0,0 +1,69 @@
+use notify::{
+    Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Result, Watcher,
+};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+// OS のファイル監視 (inotify など) で監視する
+pub fn native(
+    tx: Sender<Result<Event>>,
+    dir: &Path,
+    mode: RecursiveMode,
+) -> Result<Box<dyn Watcher>> {
+    let mut watcher = RecommendedWatcher::new(tx, Config::default())?;
+    watcher.watch(dir, mode)?;
+    Ok(Box::new(watcher))
+}
+
+// 一定間隔でファイルを調べて監視する (NFS・SMB やコンテナなど OS の監視が届かない環境向け)
+// 更新時刻だけでなく内容のハッシュも比べるので、時刻がずれるファイルシステムでも変更を見逃さない
+pub fn polling(
+    tx: Sender<Result<Event>>,
+    dir: &Path,
+    mode: RecursiveMode,
+    interval: Duration,
+) -> Result<Box<dyn Watcher>> {
+    let config = Config::default()
+        .with_poll_interval(interval)
+        .with_compare_contents(true);
+    let mut watcher = PollWatcher::new(tx, config)?;
+    watcher.watch(dir, mode)?;
+    Ok(Box::new(watcher))
+}
+
+// このイベントで実行するか
+// windows: event.kind=Modify(Any)
+// Linux:   event.kind=Access(Open(Any))
+// ポーリングでは保存ごとに Modify (新規なら Create) が1回だけ届く
+pub fn triggers_run(kind: &EventKind, os: &str, polling: bool) -> bool {
+    if polling {
+        return matches!(kind, EventKind::Modify(_) | EventKind::Create(_));
+    }
+    match os {
+        "linux" => matches!(kind, EventKind::Access(_)),
+        "windows" => matches!(kind, EventKind::Modify(_)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    #[test]
+    fn test_triggers_run() {
+        let access = EventKind::Access(AccessKind::Any);
+        let modify = EventKind::Modify(ModifyKind::Any);
+        let create = EventKind::Create(CreateKind::File);
+
+        assert!(triggers_run(&access, "linux", false));
+        assert!(!triggers_run(&modify, "linux", false));
+        assert!(triggers_run(&modify, "windows", false));
+        // ポーリングでは OS によらず変更で実行する
+        assert!(triggers_run(&modify, "linux", true));
+        assert!(triggers_run(&create, "macos", true));
+        assert!(!triggers_run(&access, "linux", true));
+    }
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// ディレクトリ配下のファイルを再帰的に集める (パス順)
+pub fn collect_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_into(dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+// Python の仮想環境やツールが作るディレクトリ (中のファイルは問題として扱わない)
+pub const ENVIRONMENT_DIRS: &[&str] = &[".venv", "venv", "__pycache__", ".ipynb_checkpoints"];
+
+// パスが仮想環境などのディレクトリの中にあるか
+pub fn in_environment_dir(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .is_some_and(|name| ENVIRONMENT_DIRS.contains(&name))
+    })
+}
+
+fn collect_into(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.join("pyvenv.cfg").is_file() || in_environment_dir(&path) {
+                continue;
+            }
+            collect_into(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
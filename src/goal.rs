@@ -0,0 +1,102 @@
+use crate::config::Goals;
+use chrono::{DateTime, Local, TimeZone, Weekday};
+
+// 週ごとの目標の達成状況
+
+// 今週の実績
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WeeklyProgress {
+    // 今週初めて解けた問題の数
+    pub problems_solved: u32,
+    // 今週始めたセッションの学習時間 (秒)
+    pub study_seconds: i64,
+}
+
+// now を含む週の始まり (月曜 0時、ローカル時刻) の UNIX 秒
+pub fn week_start(now: DateTime<Local>) -> i64 {
+    let monday = now.date_naive().week(Weekday::Mon).first_day();
+    let midnight = monday.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|start| start.timestamp())
+        .unwrap_or_else(|| now.timestamp())
+}
+
+const BAR_WIDTH: u32 = 20;
+
+// [########------------] 2/5 (40%)
+pub fn progress_bar(done: u32, goal: u32) -> String {
+    let goal = goal.max(1);
+    let filled = (done.min(goal) * BAR_WIDTH / goal) as usize;
+    format!(
+        "[{}{}] {}/{} ({}%)",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH as usize - filled),
+        done,
+        goal,
+        done as u64 * 100 / goal as u64
+    )
+}
+
+// 目標ごとに1行 (目標が設定されていなければ空)
+pub fn format_goals(goals: &Goals, progress: &WeeklyProgress) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(goal) = goals.weekly_problems {
+        lines.push(format!(
+            "解いた問題: {}{}",
+            progress_bar(progress.problems_solved, goal),
+            if progress.problems_solved >= goal {
+                " 達成!"
+            } else {
+                ""
+            }
+        ));
+    }
+    if let Some(goal) = goals.weekly_minutes {
+        let minutes = (progress.study_seconds.max(0) / 60) as u32;
+        lines.push(format!(
+            "学習時間(分): {}{}",
+            progress_bar(minutes, goal),
+            if minutes >= goal { " 達成!" } else { "" }
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(2, 5), "[########------------] 2/5 (40%)");
+        assert_eq!(progress_bar(7, 5), "[####################] 7/5 (140%)");
+        assert_eq!(progress_bar(0, 0), "[--------------------] 0/1 (0%)");
+    }
+
+    #[test]
+    fn test_format_goals_and_week_start() {
+        let goals = Goals {
+            weekly_problems: Some(3),
+            weekly_minutes: Some(60),
+        };
+        let progress = WeeklyProgress {
+            problems_solved: 3,
+            study_seconds: 45 * 60,
+        };
+        assert_eq!(
+            format_goals(&goals, &progress),
+            [
+                "解いた問題: [####################] 3/3 (100%) 達成!",
+                "学習時間(分): [###############-----] 45/60 (75%)",
+            ]
+        );
+        assert!(format_goals(&Goals::default(), &progress).is_empty());
+
+        // 2024-03-07 は木曜なので週の始まりは 03-04 (月)
+        let now = Local.with_ymd_and_hms(2024, 3, 7, 15, 30, 0).unwrap();
+        let start = Local.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap();
+        assert_eq!(week_start(now), start.timestamp());
+    }
+}
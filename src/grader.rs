@@ -0,0 +1,534 @@
+use crate::directive;
+use crate::dom::{self, Document};
+use crate::normalize::{self, Rule};
+use crate::syntax;
+use regex::Regex;
+use std::cell::LazyCell;
+use std::fmt;
+use std::sync::LazyLock;
+
+// 問題ファイルの "learn:check [重み] <種類> [値]" で定義する採点項目
+// 例:
+//   // learn:check 40 output Hello, World   (標準出力にこの行がある)
+//   // learn:check 30 code range            (コメントを除いたコードに含まれる)
+//   // learn:check 30 success               (正常終了した / テストが通った)
+//   // learn:check 20 uses for-range        (その構文を使っている)
+//   // learn:check 10 avoids goto           (その構文を使っていない)
+//   // learn:check 50 reference-output      (learn:reference の模範解答と同じ出力になる)
+//   // learn:check 20 cell 3 42             (ノートブックの3番目のコードセルの出力にこの行がある)
+//   <!-- learn:check 20 dom ul#todos li -->      (スクリプト実行後の DOM にセレクタに一致する要素がある)
+//   <!-- learn:check 20 dom-text h1 Todo -->     (一致する要素のテキストにこの文字列が含まれる)
+//   <!-- learn:check dom-text #todos li => 牛乳 --> (セレクタに空白を含む場合は => で区切る)
+// uses / avoids に書ける名前は syntax::uses を参照
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub weight: u32,
+    pub kind: CheckKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckKind {
+    Success,
+    Output(String),
+    Code(String),
+    Uses(String),
+    Avoids(String),
+    ReferenceOutput,
+    Cell(usize, String),
+    Dom(String),
+    DomText(String, String),
+}
+
+impl CheckKind {
+    // 採点の観点 (観点ごとにも集計して表示する)
+    pub fn dimension(&self) -> &'static str {
+        match self {
+            Self::Success => "実行",
+            Self::Output(_) | Self::ReferenceOutput | Self::Cell(..) => "出力",
+            Self::Code(_) => "コード",
+            Self::Uses(_) | Self::Avoids(_) => "構文",
+            Self::Dom(_) | Self::DomText(..) => "DOM",
+        }
+    }
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Success => write!(f, "正常終了する"),
+            Self::Output(line) => write!(f, "出力に \"{}\" の行がある", line),
+            Self::Code(text) => write!(f, "コードに \"{}\" が含まれる", text),
+            Self::Uses(construct) => write!(f, "{} を使っている", construct),
+            Self::Avoids(construct) => write!(f, "{} を使っていない", construct),
+            Self::ReferenceOutput => write!(f, "模範解答と同じ出力になる"),
+            Self::Cell(number, line) => {
+                write!(f, "セル {} の出力に \"{}\" の行がある", number, line)
+            }
+            Self::Dom(selector) => write!(f, "{} に一致する要素がある", selector),
+            Self::DomText(selector, text) => {
+                write!(f, "{} のテキストに \"{}\" が含まれる", selector, text)
+            }
+        }
+    }
+}
+
+impl Check {
+    pub fn parse(value: &str) -> Option<Self> {
+        // 重みは省略できる (既定は 1)
+        let (weight, rest) = match value.split_once(char::is_whitespace) {
+            Some((weight, rest)) if weight.parse::<u32>().is_ok() => {
+                (weight.parse().ok()?, rest.trim())
+            }
+            _ => (1, value.trim()),
+        };
+        let (kind, arg) = rest
+            .split_once(char::is_whitespace)
+            .map(|(kind, arg)| (kind, arg.trim()))
+            .unwrap_or((rest, ""));
+
+        let kind = match kind {
+            "success" => CheckKind::Success,
+            "output" if !arg.is_empty() => CheckKind::Output(arg.to_string()),
+            "code" if !arg.is_empty() => CheckKind::Code(arg.to_string()),
+            "uses" if !arg.is_empty() => CheckKind::Uses(arg.to_string()),
+            "avoids" if !arg.is_empty() => CheckKind::Avoids(arg.to_string()),
+            "reference-output" => CheckKind::ReferenceOutput,
+            "cell" => {
+                let (number, line) = arg.split_once(char::is_whitespace)?;
+                let number: usize = number.parse().ok().filter(|n| *n > 0)?;
+                CheckKind::Cell(number, line.trim().to_string())
+            }
+            "dom" if !arg.is_empty() => CheckKind::Dom(arg.to_string()),
+            "dom-text" => {
+                let (selector, text) = arg
+                    .split_once(" => ")
+                    .or_else(|| arg.split_once(char::is_whitespace))?;
+                let (selector, text) = (selector.trim(), text.trim());
+                if selector.is_empty() || text.is_empty() {
+                    return None;
+                }
+                CheckKind::DomText(selector.to_string(), text.to_string())
+            }
+            _ => return None,
+        };
+        (weight > 0).then_some(Self { weight, kind })
+    }
+}
+
+// 採点に使う実行結果
+pub struct Submission<'a> {
+    pub extension: &'a str,
+    pub source: &'a str,
+    pub success: bool,
+    // HTML の場合はヘッドレスブラウザが出力した DOM
+    pub stdout: &'a str,
+    // 模範解答を実行した標準出力 (reference-output の項目があるときだけ使う)
+    pub expected: Option<&'a str>,
+    // 比べる前に実際の出力と期待する出力の両方に適用する learn:normalize
+    pub normalize: &'a [Rule],
+    // learn:tolerance の許容誤差 (指定があれば出力中の数値はこの差まで同じとみなす)
+    pub tolerance: Option<f64>,
+    // ノートブックのコードセルごとの出力 (ノートブック以外は空)
+    pub cells: &'a [String],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub check: Check,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grade {
+    pub results: Vec<CheckResult>,
+    // 0 - 100 (通った項目の重みの割合)
+    pub score: u8,
+}
+
+impl Grade {
+    // 観点ごとの (観点, 通った項目数, 項目数)
+    pub fn by_dimension(&self) -> Vec<(&'static str, usize, usize)> {
+        let mut dimensions: Vec<(&'static str, usize, usize)> = Vec::new();
+        for result in &self.results {
+            let dimension = result.check.kind.dimension();
+            let index = match dimensions.iter().position(|(d, _, _)| *d == dimension) {
+                Some(index) => index,
+                None => {
+                    dimensions.push((dimension, 0, 0));
+                    dimensions.len() - 1
+                }
+            };
+            dimensions[index].1 += result.passed as usize;
+            dimensions[index].2 += 1;
+        }
+        dimensions
+    }
+}
+
+// 解釈できない learn:check は (理由付きで) 別に返す
+pub fn checks(source: &str) -> (Vec<Check>, Vec<String>) {
+    let mut checks = Vec::new();
+    let mut invalid = Vec::new();
+    for value in directive::find_all(source, "check") {
+        match Check::parse(&value) {
+            Some(check) => checks.push(check),
+            None => invalid.push(value),
+        }
+    }
+    (checks, invalid)
+}
+
+pub fn grade(checks: &[Check], submission: &Submission) -> Option<Grade> {
+    if checks.is_empty() {
+        return None;
+    }
+
+    let code = strip_comments(submission.source, submission.extension);
+    let python = matches!(submission.extension, "py" | "ipynb");
+    let stdout = normalize::apply(submission.normalize, submission.stdout);
+    let expected = submission
+        .expected
+        .map(|expected| normalize::apply(submission.normalize, expected));
+    let document = LazyCell::new(|| Document::parse(submission.stdout));
+    let results: Vec<CheckResult> = checks
+        .iter()
+        .map(|check| {
+            let passed = match &check.kind {
+                CheckKind::Success => submission.success,
+                CheckKind::Output(line) => {
+                    let line = normalize::apply(submission.normalize, line);
+                    stdout
+                        .lines()
+                        .any(|l| same_line(l.trim(), &line, submission.tolerance))
+                }
+                CheckKind::Code(text) => code.contains(text.as_str()),
+                CheckKind::Uses(construct) => syntax::uses(submission.source, python, construct),
+                CheckKind::Avoids(construct) => !syntax::uses(submission.source, python, construct),
+                CheckKind::Cell(number, line) => {
+                    let line = normalize::apply(submission.normalize, line);
+                    submission.cells.get(number - 1).is_some_and(|output| {
+                        normalize::apply(submission.normalize, output)
+                            .lines()
+                            .any(|l| same_line(l.trim(), &line, submission.tolerance))
+                    })
+                }
+                CheckKind::Dom(selector) => !document.select(selector).is_empty(),
+                CheckKind::DomText(selector, text) => document
+                    .select(selector)
+                    .iter()
+                    .any(|element| dom::collapse_whitespace(&element.text).contains(text.as_str())),
+                CheckKind::ReferenceOutput => expected
+                    .as_deref()
+                    .is_some_and(|expected| same_output(&stdout, expected, submission.tolerance)),
+            };
+            CheckResult {
+                check: check.clone(),
+                passed,
+            }
+        })
+        .collect();
+
+    let total: u32 = results.iter().map(|r| r.check.weight).sum();
+    let earned: u32 = results
+        .iter()
+        .filter(|r| r.passed)
+        .map(|r| r.check.weight)
+        .sum();
+    Some(Grade {
+        results,
+        score: (earned * 100 / total) as u8,
+    })
+}
+
+// 行末の空白と末尾の空行の違いは無視して出力を比べる
+pub fn same_output(actual: &str, expected: &str, tolerance: Option<f64>) -> bool {
+    let lines = |output: &str| -> Vec<String> {
+        let mut lines: Vec<String> = output.lines().map(|l| l.trim_end().to_string()).collect();
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        lines
+    };
+    let (actual, expected) = (lines(actual), lines(expected));
+    actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(&expected)
+            .all(|(a, e)| same_line(a, e, tolerance))
+}
+
+// learn:normalize を両方に適用してから出力を比べる (learn:case の判定にも使う)
+pub fn matches_expected(
+    actual: &str,
+    expected: &str,
+    rules: &[Rule],
+    tolerance: Option<f64>,
+) -> bool {
+    same_output(
+        &normalize::apply(rules, actual),
+        &normalize::apply(rules, expected),
+        tolerance,
+    )
+}
+
+static NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?").unwrap());
+
+// 許容誤差があれば、数値以外の部分が同じで数値がそれぞれ誤差の範囲内なら同じとみなす
+// (0.30000000000000004 と 0.3、1e-07 と 0.0000001 など)
+// 誤差は絶対誤差と相対誤差の大きい方 (大きな値でも桁数の違いで落ちないように)
+fn same_line(actual: &str, expected: &str, tolerance: Option<f64>) -> bool {
+    let Some(tolerance) = tolerance else {
+        return actual == expected;
+    };
+    if NUMBER.split(actual).ne(NUMBER.split(expected)) {
+        return false;
+    }
+    let numbers = |line: &str| -> Vec<Option<f64>> {
+        NUMBER
+            .find_iter(line)
+            .map(|m| m.as_str().parse().ok())
+            .collect()
+    };
+    let (actual, expected) = (numbers(actual), numbers(expected));
+    actual.len() == expected.len()
+        && actual.iter().zip(&expected).all(|pair| match pair {
+            (Some(a), Some(e)) => (a - e).abs() <= tolerance * a.abs().max(e.abs()).max(1.0),
+            _ => false,
+        })
+}
+
+// 行コメントを取り除く (learn:check 自体に書いた文字列に一致しないように)
+fn strip_comments(source: &str, extension: &str) -> String {
+    let marker = if matches!(extension, "py" | "ipynb") {
+        "#"
+    } else if extension == "html" {
+        "<!--"
+    } else {
+        "//"
+    };
+    source
+        .lines()
+        .map(|line| {
+            line.split_once(marker)
+                .map(|(code, _)| code)
+                .unwrap_or(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_checks() {
+        let source = "// learn:check 40 output Hello, World\n// learn:check code range\n// learn:check 30 unknown x\n";
+
+        let (checks, invalid) = checks(source);
+
+        assert_eq!(
+            checks,
+            [
+                Check {
+                    weight: 40,
+                    kind: CheckKind::Output("Hello, World".to_string())
+                },
+                Check {
+                    weight: 1,
+                    kind: CheckKind::Code("range".to_string())
+                },
+            ]
+        );
+        assert_eq!(invalid, ["30 unknown x"]);
+    }
+
+    #[test]
+    fn test_grade_weights_passed_checks() {
+        let source = "// learn:check 50 code range\n// learn:check 30 output 6\n// learn:check 20 success\nfor i := 0; i < 3; i++ {}\n";
+        let (checks, _) = checks(source);
+
+        let grade = grade(
+            &checks,
+            &Submission {
+                extension: "go",
+                source,
+                success: true,
+                stdout: "6\n",
+                expected: None,
+                normalize: &[],
+                tolerance: None,
+                cells: &[],
+            },
+        )
+        .unwrap();
+
+        // コメント中の "range" は数えない
+        assert!(!grade.results[0].passed);
+        assert_eq!(grade.score, 50);
+    }
+
+    #[test]
+    fn test_grade_without_checks() {
+        let submission = Submission {
+            extension: "py",
+            source: "print(1)\n",
+            success: true,
+            stdout: "1\n",
+            expected: None,
+            normalize: &[],
+            tolerance: None,
+            cells: &[],
+        };
+
+        assert_eq!(grade(&[], &submission), None);
+    }
+
+    #[test]
+    fn test_syntax_checks_are_a_separate_dimension() {
+        let source = "// learn:check uses for-range\n// learn:check avoids goto\n// learn:check success\nfor i := 0; i < 3; i++ {}\n";
+        let (checks, _) = checks(source);
+
+        let grade = grade(
+            &checks,
+            &Submission {
+                extension: "go",
+                source,
+                success: true,
+                stdout: "",
+                expected: None,
+                normalize: &[],
+                tolerance: None,
+                cells: &[],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(grade.by_dimension(), [("構文", 1, 2), ("実行", 1, 1)]);
+        assert_eq!(grade.score, 66);
+    }
+
+    #[test]
+    fn test_reference_output_check() {
+        let source = "# learn:check reference-output\nprint('a')\n";
+        let (checks, _) = checks(source);
+        assert_eq!(checks[0].kind, CheckKind::ReferenceOutput);
+
+        let submission = |stdout, expected| Submission {
+            extension: "py",
+            source,
+            success: true,
+            stdout,
+            expected,
+            normalize: &[],
+            tolerance: None,
+            cells: &[],
+        };
+        // 行末の空白と末尾の空行は無視する
+        let result = grade(&checks, &submission("a  \nb\n\n", Some("a\nb\n"))).unwrap();
+        assert_eq!(result.score, 100);
+        let result = grade(&checks, &submission("a\n", Some("a\nb\n"))).unwrap();
+        assert_eq!(result.score, 0);
+        // 模範解答を実行できなかった場合は通らない
+        let result = grade(&checks, &submission("a\n", None)).unwrap();
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn test_normalize_before_comparing() {
+        let source = "// learn:check reference-output\n// learn:check output took <TIME>\n// learn:normalize sort-lines\n// learn:normalize strip-timestamps\n";
+        let (checks, _) = checks(source);
+        let (rules, _) = normalize::rules(source);
+
+        let result = grade(
+            &checks,
+            &Submission {
+                extension: "go",
+                source,
+                success: true,
+                stdout: "b 2\na 1\ntook 10:00:01\n",
+                expected: Some("a 1\ntook 09:59:58\nb 2\n"),
+                normalize: &rules,
+                tolerance: None,
+                cells: &[],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.score, 100);
+    }
+
+    #[test]
+    fn test_numeric_tolerance() {
+        let tolerance = Some(1e-6);
+
+        assert!(same_line("mean=0.30000000000000004", "mean=0.3", tolerance));
+        assert!(same_line("x 1e-07 y", "x 0.0000001 y", tolerance));
+        assert!(same_line("1000000.5", "1000000", tolerance));
+        assert!(!same_line("mean=0.31", "mean=0.3", tolerance));
+        assert!(!same_line("avg=0.3", "mean=0.3", tolerance));
+        assert!(!same_line("0.30000000000000004", "0.3", None));
+        assert!(same_output("1.0\n2.50\n", "1\n2.5", tolerance));
+        assert!(!same_output("1.0\n", "1\n2.5", tolerance));
+    }
+
+    #[test]
+    fn test_cell_checks() {
+        let source = "# learn:check cell 2 42\n# learn:check cell 3 x\n# learn:check cell 0 y\n";
+        let (checks, invalid) = checks(source);
+        assert_eq!(checks.len(), 2);
+        assert_eq!(invalid, ["cell 0 y"]);
+
+        let cells = ["1\n".to_string(), "42\n".to_string()];
+        let result = grade(
+            &checks,
+            &Submission {
+                extension: "ipynb",
+                source,
+                success: true,
+                stdout: "",
+                expected: None,
+                normalize: &[],
+                tolerance: None,
+                cells: &cells,
+            },
+        )
+        .unwrap();
+
+        // 3番目のセルはない
+        assert!(result.results[0].passed);
+        assert!(!result.results[1].passed);
+    }
+
+    #[test]
+    fn test_dom_checks() {
+        let source = "<!-- learn:check dom ul#todos li.done -->\n<!-- learn:check dom-text h1 Todo -->\n<!-- learn:check dom-text #todos li => 宿題 -->\n<!-- learn:check code <ul -->\n<!-- learn:check dom-text h1 -->\n<ul></ul>\n";
+        let (checks, invalid) = checks(source);
+        assert_eq!(checks.len(), 4);
+        assert_eq!(invalid, ["dom-text h1"]);
+        assert_eq!(
+            checks[2].kind,
+            CheckKind::DomText("#todos li".to_string(), "宿題".to_string())
+        );
+
+        let result = grade(
+            &checks,
+            &Submission {
+                extension: "html",
+                source,
+                success: true,
+                stdout: "<html><body><h1>My Todo</h1><ul id=\"todos\"><li class=\"done\">牛乳</li></ul></body></html>",
+                expected: None,
+                normalize: &[],
+                tolerance: None,
+                cells: &[],
+            },
+        )
+        .unwrap();
+
+        let passed: Vec<bool> = result.results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, [true, true, false, true]);
+        assert_eq!(result.by_dimension(), [("DOM", 2, 3), ("コード", 1, 1)]);
+    }
+}
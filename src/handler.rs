@@ -0,0 +1,132 @@
+use crate::config::HandlerConfig;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// 拡張子ごとの実行方法
+// 組み込みのもの (go run・仮想環境の python・nbconvert など) に、設定ファイルの [handlers.<拡張子>] が優先する
+// 設定ファイルのハンドラは「コマンド 引数... ファイル」で実行する
+
+// 組み込みの拡張子と実行コマンド
+pub const BUILT_IN: &[(&str, &str)] = &[
+    ("go", "go"),
+    ("py", "python"),
+    ("ipynb", "jupyter"),
+    ("html", "chromium"),
+    ("lua", "lua"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    BuiltIn,
+    Config,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handler {
+    pub extension: String,
+    pub command: String,
+    // ファイルの前に付ける引数 (設定ファイルのハンドラだけ)
+    pub args: Vec<String>,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    handlers: BTreeMap<String, Handler>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new())
+    }
+}
+
+impl Registry {
+    // 設定ファイルの [handlers] は "py" でも ".py" でもよい
+    pub fn new(configured: &BTreeMap<String, HandlerConfig>) -> Self {
+        let mut handlers: BTreeMap<String, Handler> = BUILT_IN
+            .iter()
+            .map(|(extension, command)| {
+                (
+                    extension.to_string(),
+                    Handler {
+                        extension: extension.to_string(),
+                        command: command.to_string(),
+                        args: Vec::new(),
+                        source: Source::BuiltIn,
+                    },
+                )
+            })
+            .collect();
+        for (extension, config) in configured {
+            let extension = extension.trim_start_matches('.').to_string();
+            handlers.insert(
+                extension.clone(),
+                Handler {
+                    extension,
+                    command: config.command.clone(),
+                    args: config.args.clone(),
+                    source: Source::Config,
+                },
+            );
+        }
+        Self { handlers }
+    }
+
+    pub fn get(&self, extension: &str) -> Option<&Handler> {
+        self.handlers.get(extension)
+    }
+
+    // 拡張子の順
+    pub fn handlers(&self) -> impl Iterator<Item = &Handler> {
+        self.handlers.values()
+    }
+
+    // 拡張子から実行方法が決まるファイルか
+    pub fn is_runnable(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|extension| self.handlers.contains_key(extension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_handlers_override_built_in() {
+        let configured = BTreeMap::from([
+            (
+                ".rb".to_string(),
+                HandlerConfig {
+                    command: "ruby".to_string(),
+                    args: vec!["-w".to_string()],
+                },
+            ),
+            (
+                "py".to_string(),
+                HandlerConfig {
+                    command: "pypy3".to_string(),
+                    args: Vec::new(),
+                },
+            ),
+        ]);
+        let registry = Registry::new(&configured);
+
+        assert_eq!(registry.get("go").unwrap().source, Source::BuiltIn);
+        let python = registry.get("py").unwrap();
+        assert_eq!(python.command, "pypy3");
+        assert_eq!(python.source, Source::Config);
+        assert_eq!(registry.get("rb").unwrap().args, ["-w"]);
+        assert!(registry.is_runnable(Path::new("section1/a.rb")));
+        assert!(!Registry::default().is_runnable(Path::new("section1/a.rb")));
+        assert_eq!(
+            registry
+                .handlers()
+                .map(|handler| handler.extension.as_str())
+                .collect::<Vec<_>>(),
+            ["go", "html", "ipynb", "lua", "py", "rb"]
+        );
+    }
+}
@@ -0,0 +1,2619 @@
+use crate::cases::CaseResult;
+use crate::environment::Environment;
+use crate::query::{self, QueryRow, StatsQuery};
+use crate::session::SessionRecord;
+use crate::snapshot::{Delta, Snapshot};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use rusqlite::backup::{Backup, Progress};
+use rusqlite::types::Type;
+use rusqlite::{Connection, MAIN_DB, OpenFlags, OptionalExtension, Row, params};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// 出力の先頭何文字を履歴に残すか
+pub const PREVIEW_CHARS: usize = 200;
+
+// スキーマの変更は末尾に追加する (PRAGMA user_version で適用済みの数を管理)
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE executions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        section TEXT NOT NULL,
+        success INTEGER NOT NULL,
+        exit_code INTEGER,
+        duration_ms INTEGER NOT NULL,
+        executed_at INTEGER NOT NULL,
+        output_preview TEXT NOT NULL,
+        tool_version TEXT,
+        os TEXT NOT NULL,
+        app_version TEXT NOT NULL
+    );
+    CREATE INDEX idx_executions_file_path ON executions(file_path);",
+    "CREATE TABLE solve_times (
+        file_path TEXT PRIMARY KEY,
+        section TEXT NOT NULL,
+        first_modified_at INTEGER NOT NULL,
+        solved_at INTEGER NOT NULL,
+        active_seconds INTEGER NOT NULL
+    );",
+    "CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE executions ADD COLUMN note TEXT;",
+    "CREATE TABLE deferred_problems (
+        file_path TEXT PRIMARY KEY,
+        reason TEXT,
+        deferred_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE problem_tags (
+        file_path TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (file_path, tag)
+    );",
+    "CREATE VIRTUAL TABLE problems_fts USING fts5(file_path UNINDEXED, title, topic, body);
+    CREATE TABLE problems_index (
+        file_path TEXT PRIMARY KEY,
+        modified_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE executions ADD COLUMN todos_remaining INTEGER;",
+    "ALTER TABLE executions ADD COLUMN score INTEGER;",
+    "CREATE TABLE llm_cache (
+        key TEXT PRIMARY KEY,
+        response TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE questions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        question TEXT NOT NULL,
+        answer TEXT NOT NULL,
+        asked_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE hint_usage (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        used_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_hint_usage_file_path ON hint_usage (file_path);",
+    "CREATE TABLE exams (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        assignment TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        deadline INTEGER NOT NULL,
+        finished_at INTEGER
+    );",
+    "ALTER TABLE executions ADD COLUMN content_hash TEXT;
+    CREATE TABLE resubmissions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        submitted_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        taken_at INTEGER NOT NULL,
+        start_line INTEGER NOT NULL,
+        removed TEXT NOT NULL,
+        inserted TEXT NOT NULL
+    );
+    CREATE INDEX idx_snapshots_file_path ON snapshots (file_path);",
+    "CREATE TABLE submissions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        assignment TEXT NOT NULL,
+        submitted_at INTEGER NOT NULL,
+        passed INTEGER NOT NULL,
+        total INTEGER NOT NULL,
+        commit_sha TEXT
+    );",
+    "CREATE TABLE last_output (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        file_path TEXT NOT NULL,
+        executed_at INTEGER NOT NULL,
+        stdout TEXT NOT NULL,
+        stderr TEXT NOT NULL
+    );",
+    "CREATE TABLE removed_problems (
+        file_path TEXT PRIMARY KEY,
+        removed_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE executions ADD COLUMN error_signature TEXT;",
+    "CREATE TABLE reference_outputs (
+        content_hash TEXT PRIMARY KEY,
+        stdout TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE case_results (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        case_name TEXT NOT NULL,
+        passed INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        executed_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_case_results_file_path ON case_results (file_path);",
+    "CREATE TABLE problem_versions (
+        file_path TEXT PRIMARY KEY,
+        version TEXT NOT NULL,
+        template TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL
+    );",
+    // 言語はこれまでの実行の分も拡張子から埋める
+    "ALTER TABLE executions ADD COLUMN language TEXT;
+    UPDATE executions SET language = CASE
+        WHEN file_path LIKE '%.go' THEN 'Go'
+        WHEN file_path LIKE '%.py' OR file_path LIKE '%.ipynb' THEN 'Python'
+        WHEN file_path LIKE '%.lua' THEN 'Lua'
+        WHEN file_path LIKE '%.html' THEN 'HTML'
+        END;",
+    "CREATE INDEX idx_executions_executed_at ON executions (executed_at, id);",
+    // --store-output で保存する出力の全文 (zlib で圧縮)
+    "CREATE TABLE execution_outputs (
+        execution_id INTEGER PRIMARY KEY,
+        stdout BLOB NOT NULL,
+        stderr BLOB NOT NULL
+    );",
+    // 付与した XP の記録 (合計はここから計算する)
+    "CREATE TABLE xp_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        xp INTEGER NOT NULL,
+        awarded_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_xp_events_file_path ON xp_events (file_path);",
+    // --coverage で読み取ったテストのカバレッジ (%)
+    "ALTER TABLE executions ADD COLUMN coverage REAL;",
+    // ネットワークに送る前の待ち行列 (next_attempt_at が NULL なら再送をあきらめたもの)
+    "CREATE TABLE outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER,
+        last_error TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_outbox_next_attempt_at ON outbox (next_attempt_at);",
+    // チャットに1日のまとめを送った日 (同じ日を2回送らない)
+    "CREATE TABLE chat_summaries (
+        day TEXT PRIMARY KEY,
+        queued_at INTEGER NOT NULL
+    );",
+];
+
+// file_path で問題を記録しているテーブル (問題の検索インデックスは検索時に作り直される)
+const PATH_TABLES: &[&str] = &[
+    "executions",
+    "solve_times",
+    "deferred_problems",
+    "problem_tags",
+    "questions",
+    "hint_usage",
+    "resubmissions",
+    "snapshots",
+    "last_output",
+    "removed_problems",
+    "case_results",
+    "problem_versions",
+    "xp_events",
+];
+
+// privacy purge で消す、コードや出力の内容を含む記録 (名前, 件数を数える SQL, 消す SQL)
+// 実行の成否・時間・エラーの種類は残すので、消した後も集計は変わらない
+const CONTENT: &[(&str, &str, &str)] = &[
+    (
+        "実行の出力 (先頭部分)",
+        "SELECT COUNT(*) FROM executions WHERE output_preview != ''",
+        "UPDATE executions SET output_preview = '' WHERE output_preview != ''",
+    ),
+    (
+        "実行のメモ",
+        "SELECT COUNT(*) FROM executions WHERE note IS NOT NULL",
+        "UPDATE executions SET note = NULL WHERE note IS NOT NULL",
+    ),
+    (
+        "実行の出力 (全文)",
+        "SELECT COUNT(*) FROM execution_outputs",
+        "DELETE FROM execution_outputs",
+    ),
+    (
+        "直前の実行の出力",
+        "SELECT COUNT(*) FROM last_output",
+        "DELETE FROM last_output",
+    ),
+    (
+        "解答の変化 (スナップショット)",
+        "SELECT COUNT(*) FROM snapshots",
+        "DELETE FROM snapshots",
+    ),
+    (
+        "質問と回答",
+        "SELECT COUNT(*) FROM questions",
+        "DELETE FROM questions",
+    ),
+    (
+        "LLM の応答のキャッシュ",
+        "SELECT COUNT(*) FROM llm_cache",
+        "DELETE FROM llm_cache",
+    ),
+    (
+        "送信待ちのレビュー・通知",
+        "SELECT COUNT(*) FROM outbox",
+        "DELETE FROM outbox",
+    ),
+];
+
+// 1回の実行結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionRecord {
+    // 保存前は 0
+    pub id: i64,
+    pub file_path: String,
+    pub section: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: i64,
+    // UNIX時間 (秒)
+    pub executed_at: i64,
+    pub output_preview: String,
+    pub environment: Environment,
+    // 学習者が後から付けたメモ
+    pub note: Option<String>,
+    // 実行時点で残っていた TODO コメントの数
+    pub todos_remaining: Option<i64>,
+    // learn:check による採点 (0 - 100、採点項目がなければ None)
+    pub score: Option<i64>,
+    // 実行したソースの SHA-256 (変更なしの再提出の検出用)
+    pub content_hash: Option<String>,
+    // 失敗したときのエラーの種類 (よくあるエラーの集計用、読み取れなければ None)
+    pub error_signature: Option<String>,
+    // 問題の言語 ("Go" / "Python" など)
+    pub language: Option<String>,
+    // --coverage で計測したテストのカバレッジ (%、計測していなければ None)
+    pub coverage: Option<f64>,
+}
+
+// 問題ごとの解答時間 (最初に成功したときに確定する)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveRecord {
+    pub file_path: String,
+    pub section: String,
+    pub first_modified_at: i64,
+    pub solved_at: i64,
+    // 離席時間を除いた作業時間
+    pub active_seconds: i64,
+}
+
+// 後回しにした問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredProblem {
+    pub file_path: String,
+    pub reason: Option<String>,
+    pub deferred_at: i64,
+}
+
+// 監視中に削除された問題 (restore-problem で作り直すまでリセット扱い)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedProblem {
+    pub file_path: String,
+    pub removed_at: i64,
+}
+
+// learn:case のケースごとの集計
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseStats {
+    pub file_path: String,
+    pub case_name: String,
+    pub passed: i64,
+    pub runs: i64,
+    pub last_passed: bool,
+}
+
+// 問題ごとの実行回数と、一度でも成功したか
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemOutcome {
+    pub file_path: String,
+    pub runs: i64,
+    pub passed: bool,
+}
+
+// 問題の全文検索の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemHit {
+    pub file_path: String,
+    pub title: String,
+    // 一致した箇所の前後 (一致した語は [] で囲む)
+    pub snippet: String,
+}
+
+// ask コマンドの質問と回答
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    pub file_path: String,
+    pub question: String,
+    pub answer: String,
+    pub asked_at: i64,
+}
+
+// 問題に取り組む中で見た助け
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintKind {
+    // hint コマンド
+    Hint,
+    // ask コマンド
+    Ask,
+    // 模範解答
+    Solution,
+}
+
+impl HintKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            HintKind::Hint => "hint",
+            HintKind::Ask => "ask",
+            HintKind::Solution => "solution",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "hint" => Some(HintKind::Hint),
+            "ask" => Some(HintKind::Ask),
+            "solution" => Some(HintKind::Solution),
+            _ => None,
+        }
+    }
+}
+
+// XP を得た理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XpReason {
+    // 問題を初めて解いた
+    Solve,
+    // 失敗せずに解いたボーナス
+    FirstTry,
+}
+
+impl XpReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            XpReason::Solve => "solve",
+            XpReason::FirstTry => "first_try",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "solve" => Some(XpReason::Solve),
+            "first_try" => Some(XpReason::FirstTry),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            XpReason::Solve => "解答",
+            XpReason::FirstTry => "一発で成功",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XpEvent {
+    // 保存前は 0
+    pub id: i64,
+    pub file_path: String,
+    pub reason: XpReason,
+    pub xp: i64,
+    pub awarded_at: i64,
+}
+
+// 試験 (finished_at が None の間は試験中)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exam {
+    // 保存前は 0
+    pub id: i64,
+    // 問題のあるディレクトリ (絶対パス)
+    pub assignment: String,
+    pub started_at: i64,
+    pub deadline: i64,
+    pub finished_at: Option<i64>,
+}
+
+// 課題の提出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    pub assignment: String,
+    pub submitted_at: i64,
+    // 合格した問題数 / 問題数
+    pub passed: i64,
+    pub total: i64,
+    // GitHub に push したコミット (ローカルだけの提出なら None)
+    pub commit_sha: Option<String>,
+}
+
+// 直前の実行の出力 (履歴のプレビューとは違い全文を1件だけ残す)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastOutput {
+    pub file_path: String,
+    pub executed_at: i64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+// 問題ファイルの元になったカリキュラムの版 (version は内容のハッシュの先頭)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProblemVersion {
+    pub version: String,
+    pub template: String,
+}
+
+// 送信待ちの1件 (outbox::Message を kind と JSON の payload にしたもの)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outbound {
+    pub id: i64,
+    pub kind: String,
+    pub payload: String,
+    pub attempts: i64,
+    // 次に送る時刻 (None なら再送をあきらめた)
+    pub next_attempt_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+}
+
+// 実行記録の一覧の続きの位置 ("<実行時刻>:<実行番号>"、この記録より古いものを表示する)
+// 実行番号だけでなく時刻も使うのは、一覧が実行時刻の順だから
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub executed_at: i64,
+    pub id: i64,
+}
+
+impl Cursor {
+    pub fn of(record: &ExecutionRecord) -> Self {
+        Self {
+            executed_at: record.executed_at,
+            id: record.id,
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.executed_at, self.id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .split_once(':')
+            .and_then(|(executed_at, id)| {
+                Some(Self {
+                    executed_at: executed_at.parse().ok()?,
+                    id: id.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| format!("続きの位置を解釈できません: {} (例: 1760000000:42)", value))
+    }
+}
+
+// 実行回数の集計
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Summary {
+    pub executions: i64,
+    pub successes: i64,
+    pub sessions: i64,
+    // 一時停止中を除いた学習時間の合計
+    pub study_seconds: i64,
+    // 失敗したときと同じ内容のまま保存した回数
+    pub unchanged_resubmissions: i64,
+}
+
+// record_from_row が読む列 (順番を合わせること)
+const RECORD_COLUMNS: &str = "id, file_path, section, success, exit_code, duration_ms,
+    executed_at, output_preview, tool_version, os, app_version, note, todos_remaining, score,
+    content_hash, error_signature, language, coverage";
+
+// 実行履歴 (SQLite)
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    // 他の人の履歴DBを読み取り専用で開き、メモリ上に写して読む (元のファイルは書き換えない)
+    // 古いバージョンの DB でも写した方にマイグレーションを適用するので同じように読める
+    pub fn open_read_only(path: &Path) -> rusqlite::Result<Self> {
+        let source = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        let mut conn = Connection::open_in_memory()?;
+        Backup::new(&source, &mut conn)?.run_to_completion(256, std::time::Duration::ZERO, None)?;
+        Self::with_connection(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    // SQLite のオンラインバックアップで dest に書き出す (監視中でも書き込みを止めずに取れる)
+    pub fn backup_to(&self, dest: &Path) -> rusqlite::Result<()> {
+        self.conn.backup(MAIN_DB, dest, None)
+    }
+
+    // バックアップの内容で置き換える (古いバージョンのバックアップなら続きのマイグレーションを適用する)
+    pub fn restore_from(&mut self, src: &Path) -> rusqlite::Result<()> {
+        self.conn.restore(MAIN_DB, src, None::<fn(Progress)>)?;
+        self.migrate()
+    }
+
+    fn with_connection(conn: Connection) -> rusqlite::Result<Self> {
+        let store = Self { conn };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> rusqlite::Result<()> {
+        self.apply_migrations(MIGRATIONS)
+    }
+
+    fn apply_migrations(&self, migrations: &[&str]) -> rusqlite::Result<()> {
+        let applied: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        // マイグレーションと user_version の更新を同じトランザクションにして、途中で失敗しても半端に適用されないようにする
+        for (version, sql) in migrations.iter().enumerate().skip(applied as usize) {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute_batch(sql)?;
+            tx.pragma_update(None, "user_version", version as i64 + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    pub fn insert(&self, record: &ExecutionRecord) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO executions (file_path, section, success, exit_code, duration_ms,
+                executed_at, output_preview, tool_version, os, app_version, todos_remaining, score,
+                content_hash, error_signature, language, coverage)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            params![
+                record.file_path,
+                record.section,
+                record.success,
+                record.exit_code,
+                record.duration_ms,
+                record.executed_at,
+                record.output_preview,
+                record.environment.tool_version,
+                record.environment.os,
+                record.environment.app_version,
+                record.todos_remaining,
+                record.score,
+                record.content_hash,
+                record.error_signature,
+                record.language,
+                record.coverage,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // 新しい順に最大 limit 件
+    pub fn recent(&self, limit: usize) -> rusqlite::Result<Vec<ExecutionRecord>> {
+        self.page(limit, None)
+    }
+
+    // before より古い記録を新しい順に最大 limit 件 (全件を読み込まずに少しずつ表示する)
+    pub fn page(
+        &self,
+        limit: usize,
+        before: Option<Cursor>,
+    ) -> rusqlite::Result<Vec<ExecutionRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM executions
+             WHERE ?2 IS NULL OR (executed_at, id) < (?2, ?3)
+             ORDER BY executed_at DESC, id DESC LIMIT ?1",
+            RECORD_COLUMNS
+        ))?;
+        let records = stmt
+            .query_map(
+                params![
+                    limit as i64,
+                    before.map(|c| c.executed_at),
+                    before.map(|c| c.id)
+                ],
+                record_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    // そのファイルの実行記録 (古い順)
+    pub fn executions_for(&self, file_path: &str) -> rusqlite::Result<Vec<ExecutionRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM executions WHERE file_path = ?1 ORDER BY executed_at, id",
+            RECORD_COLUMNS
+        ))?;
+        let records = stmt
+            .query_map([file_path], record_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    // そのファイルで記録された TODO の最大数 (最初のひな形に含まれていた数とみなす)
+    pub fn todo_total(&self, file_path: &str) -> rusqlite::Result<Option<i64>> {
+        self.conn.query_row(
+            "SELECT MAX(todos_remaining) FROM executions WHERE file_path = ?1",
+            [file_path],
+            |row| row.get(0),
+        )
+    }
+
+    // ID で実行記録を1件取得する (なければ None)
+    pub fn execution(&self, id: i64) -> rusqlite::Result<Option<ExecutionRecord>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM executions WHERE id = ?1", RECORD_COLUMNS),
+                [id],
+                record_from_row,
+            )
+            .optional()
+    }
+
+    pub fn save_full_output(
+        &self,
+        execution_id: i64,
+        stdout: &[u8],
+        stderr: &[u8],
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO execution_outputs (execution_id, stdout, stderr)
+             VALUES (?1, ?2, ?3)",
+            params![execution_id, compress(stdout)?, compress(stderr)?],
+        )?;
+        Ok(())
+    }
+
+    // 保存した出力の全文 (標準出力, 標準エラー出力)
+    pub fn full_output(&self, execution_id: i64) -> rusqlite::Result<Option<(String, String)>> {
+        let output = self
+            .conn
+            .query_row(
+                "SELECT stdout, stderr FROM execution_outputs WHERE execution_id = ?1",
+                [execution_id],
+                |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()?;
+        output
+            .map(|(stdout, stderr)| Ok((decompress(&stdout)?, decompress(&stderr)?)))
+            .transpose()
+    }
+
+    // 実行記録にメモを付ける (既存のメモは上書き、該当する実行がなければ false)
+    pub fn set_note(&self, id: i64, note: &str) -> rusqlite::Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE executions SET note = ?1 WHERE id = ?2",
+            params![note, id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    // メモ・ファイルパス・出力に query を含む実行を新しい順に最大 limit 件
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        before: Option<Cursor>,
+    ) -> rusqlite::Result<Vec<ExecutionRecord>> {
+        let pattern = format!(
+            "%{}%",
+            query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM executions
+             WHERE (note LIKE ?1 ESCAPE '\\' OR file_path LIKE ?1 ESCAPE '\\'
+                OR output_preview LIKE ?1 ESCAPE '\\')
+                AND (?3 IS NULL OR (executed_at, id) < (?3, ?4))
+             ORDER BY executed_at DESC, id DESC LIMIT ?2",
+            RECORD_COLUMNS
+        ))?;
+        let records = stmt
+            .query_map(
+                params![
+                    pattern,
+                    limit as i64,
+                    before.map(|c| c.executed_at),
+                    before.map(|c| c.id)
+                ],
+                record_from_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    // 既に解答時間が記録されている問題は更新しない (戻り値は記録したかどうか)
+    pub fn record_solve_time(&self, record: &SolveRecord) -> rusqlite::Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO solve_times
+                (file_path, section, first_modified_at, solved_at, active_seconds)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                record.file_path,
+                record.section,
+                record.first_modified_at,
+                record.solved_at,
+                record.active_seconds,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn solve_times(&self) -> rusqlite::Result<Vec<SolveRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, section, first_modified_at, solved_at, active_seconds
+             FROM solve_times ORDER BY section, file_path",
+        )?;
+        let records = stmt
+            .query_map([], |row| {
+                Ok(SolveRecord {
+                    file_path: row.get(0)?,
+                    section: row.get(1)?,
+                    first_modified_at: row.get(2)?,
+                    solved_at: row.get(3)?,
+                    active_seconds: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    // 実行したことのある問題ごとの実行回数と成否
+    pub fn problem_outcomes(&self) -> rusqlite::Result<Vec<ProblemOutcome>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, COUNT(*), MAX(success) FROM executions
+             GROUP BY file_path ORDER BY file_path",
+        )?;
+        let outcomes = stmt
+            .query_map([], |row| {
+                Ok(ProblemOutcome {
+                    file_path: row.get(0)?,
+                    runs: row.get(1)?,
+                    passed: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(outcomes)
+    }
+
+    // since 以降に初めて解けた問題の数と、since 以降に始めたセッションの学習時間 (秒)
+    pub fn progress_since(&self, since: i64) -> rusqlite::Result<(u32, i64)> {
+        self.conn.query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM solve_times WHERE solved_at >= ?1),
+                (SELECT COALESCE(SUM(ended_at - started_at), 0) FROM sessions WHERE started_at >= ?1)",
+            [since],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    // since から until までに始めた学習時間の合計 (秒)
+    pub fn study_seconds_between(&self, since: i64, until: i64) -> rusqlite::Result<i64> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(ended_at - started_at), 0) FROM sessions
+             WHERE started_at BETWEEN ?1 AND ?2",
+            [since, until],
+            |row| row.get(0),
+        )
+    }
+
+    // 1回でも実行した日 (ローカル時刻の "YYYY-MM-DD"、古い順)
+    pub fn study_days(&self) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT date(executed_at, 'unixepoch', 'localtime') AS day
+             FROM executions ORDER BY day",
+        )?;
+        let days = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(days)
+    }
+
+    pub fn record_session(&self, session: &SessionRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO sessions (started_at, ended_at) VALUES (?1, ?2)",
+            params![session.started_at, session.ended_at],
+        )?;
+        Ok(())
+    }
+
+    // 既に保留中なら理由と日時を更新する
+    pub fn defer(&self, problem: &DeferredProblem) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO deferred_problems (file_path, reason, deferred_at)
+             VALUES (?1, ?2, ?3)",
+            params![problem.file_path, problem.reason, problem.deferred_at],
+        )?;
+        Ok(())
+    }
+
+    // 保留を解除する (保留中でなければ false)
+    pub fn undefer(&self, file_path: &str) -> rusqlite::Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM deferred_problems WHERE file_path = ?1",
+            [file_path],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // 古い順
+    pub fn deferred(&self) -> rusqlite::Result<Vec<DeferredProblem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, reason, deferred_at FROM deferred_problems
+             ORDER BY deferred_at, file_path",
+        )?;
+        let problems = stmt
+            .query_map([], |row| {
+                Ok(DeferredProblem {
+                    file_path: row.get(0)?,
+                    reason: row.get(1)?,
+                    deferred_at: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(problems)
+    }
+
+    pub fn mark_removed(&self, file_path: &str, removed_at: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO removed_problems (file_path, removed_at) VALUES (?1, ?2)",
+            params![file_path, removed_at],
+        )?;
+        Ok(())
+    }
+
+    // ファイルが作り直されたら削除済みから外す (削除済みでなければ false)
+    pub fn clear_removed(&self, file_path: &str) -> rusqlite::Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM removed_problems WHERE file_path = ?1",
+            [file_path],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // 古い順
+    pub fn removed_problems(&self) -> rusqlite::Result<Vec<RemovedProblem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, removed_at FROM removed_problems ORDER BY removed_at, file_path",
+        )?;
+        let problems = stmt
+            .query_map([], |row| {
+                Ok(RemovedProblem {
+                    file_path: row.get(0)?,
+                    removed_at: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(problems)
+    }
+
+    pub fn add_tags(&self, file_path: &str, tags: &[String]) -> rusqlite::Result<()> {
+        for tag in tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO problem_tags (file_path, tag) VALUES (?1, ?2)",
+                params![file_path, tag.to_lowercase()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_tags(&self, file_path: &str, tags: &[String]) -> rusqlite::Result<()> {
+        for tag in tags {
+            self.conn.execute(
+                "DELETE FROM problem_tags WHERE file_path = ?1 AND tag = ?2",
+                params![file_path, tag.to_lowercase()],
+            )?;
+        }
+        Ok(())
+    }
+
+    // コマンドで付けたタグ (問題ファイルの "Tags:" は含まない)
+    pub fn tags(&self, file_path: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag FROM problem_tags WHERE file_path = ?1 ORDER BY tag")?;
+        let tags = stmt
+            .query_map([file_path], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    // 索引済みの問題ファイルと索引したときの更新日時
+    pub fn indexed_problems(&self) -> rusqlite::Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT file_path, modified_at FROM problems_index")?;
+        let indexed = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(indexed)
+    }
+
+    pub fn index_problem(
+        &self,
+        file_path: &str,
+        modified_at: i64,
+        title: &str,
+        topic: &str,
+        body: &str,
+    ) -> rusqlite::Result<()> {
+        self.remove_problem(file_path)?;
+        self.conn.execute(
+            "INSERT INTO problems_fts (file_path, title, topic, body) VALUES (?1, ?2, ?3, ?4)",
+            params![file_path, title, topic, body],
+        )?;
+        self.conn.execute(
+            "INSERT INTO problems_index (file_path, modified_at) VALUES (?1, ?2)",
+            params![file_path, modified_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_problem(&self, file_path: &str) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM problems_fts WHERE file_path = ?1", [file_path])?;
+        self.conn.execute(
+            "DELETE FROM problems_index WHERE file_path = ?1",
+            [file_path],
+        )?;
+        Ok(())
+    }
+
+    // 関連度の高い順に最大 limit 件 (空白区切りの語をすべて含むもの)
+    pub fn search_problems(&self, query: &str, limit: usize) -> rusqlite::Result<Vec<ProblemHit>> {
+        // 記号が FTS5 の構文として解釈されないよう、語ごとに引用符で囲む
+        let query = query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, title, snippet(problems_fts, 3, '[', ']', '…', 12)
+             FROM problems_fts WHERE problems_fts MATCH ?1
+             ORDER BY bm25(problems_fts, 0.0, 10.0, 5.0, 1.0) LIMIT ?2",
+        )?;
+        let hits = stmt
+            .query_map(params![query, limit as i64], |row| {
+                Ok(ProblemHit {
+                    file_path: row.get(0)?,
+                    title: row.get(1)?,
+                    snippet: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    // そのファイルの直近の失敗した実行
+    pub fn last_failure(&self, file_path: &str) -> rusqlite::Result<Option<ExecutionRecord>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM executions WHERE file_path = ?1 AND success = 0
+                     ORDER BY executed_at DESC, id DESC LIMIT 1",
+                    RECORD_COLUMNS
+                ),
+                [file_path],
+                record_from_row,
+            )
+            .optional()
+    }
+
+    pub fn record_question(&self, question: &Question) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO questions (file_path, question, answer, asked_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                question.file_path,
+                question.question,
+                question.answer,
+                question.asked_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    // 新しい順に最大 limit 件
+    pub fn questions(&self, limit: usize) -> rusqlite::Result<Vec<Question>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, question, answer, asked_at FROM questions
+             ORDER BY asked_at DESC, id DESC LIMIT ?1",
+        )?;
+        let questions = stmt
+            .query_map([limit as i64], |row| {
+                Ok(Question {
+                    file_path: row.get(0)?,
+                    question: row.get(1)?,
+                    answer: row.get(2)?,
+                    asked_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(questions)
+    }
+
+    // LLM の応答のキャッシュ (キーは問い合わせ内容のハッシュ)
+    pub fn cached_response(&self, key: &str) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT response FROM llm_cache WHERE key = ?1",
+                [key],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn cache_response(
+        &self,
+        key: &str,
+        response: &str,
+        created_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO llm_cache (key, response, created_at) VALUES (?1, ?2, ?3)",
+            params![key, response, created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_hint(
+        &self,
+        file_path: &str,
+        kind: HintKind,
+        used_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO hint_usage (file_path, kind, used_at) VALUES (?1, ?2, ?3)",
+            params![file_path, kind.as_str(), used_at],
+        )?;
+        Ok(())
+    }
+
+    // until 以前に見た助けの種類 (重複なし)
+    pub fn hints_used(&self, file_path: &str, until: i64) -> rusqlite::Result<Vec<HintKind>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT kind FROM hint_usage WHERE file_path = ?1 AND used_at <= ?2",
+        )?;
+        let mut kinds = stmt
+            .query_map(params![file_path, until], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .iter()
+            .filter_map(|kind| HintKind::from_str(kind))
+            .collect::<Vec<_>>();
+        kinds.sort();
+        Ok(kinds)
+    }
+
+    // そのファイルで失敗した実行の回数
+    pub fn failure_count(&self, file_path: &str) -> rusqlite::Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM executions WHERE file_path = ?1 AND success = 0",
+            [file_path],
+            |row| row.get(0),
+        )
+    }
+
+    // 問題を解いたときの XP をまとめて記録する
+    // XP は問題ごとに1回だけなので、既に解答の XP があれば何もせず false を返す
+    pub fn award_xp(&self, events: &[XpEvent]) -> rusqlite::Result<bool> {
+        let Some(first) = events.first() else {
+            return Ok(false);
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        let awarded: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM xp_events WHERE file_path = ?1 AND reason = ?2)",
+            params![first.file_path, XpReason::Solve.as_str()],
+            |row| row.get(0),
+        )?;
+        if awarded {
+            return Ok(false);
+        }
+        for event in events {
+            tx.execute(
+                "INSERT INTO xp_events (file_path, reason, xp, awarded_at) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    event.file_path,
+                    event.reason.as_str(),
+                    event.xp,
+                    event.awarded_at
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    pub fn total_xp(&self) -> rusqlite::Result<i64> {
+        self.conn
+            .query_row("SELECT COALESCE(SUM(xp), 0) FROM xp_events", [], |row| {
+                row.get(0)
+            })
+    }
+
+    // 新しい順
+    pub fn xp_events(&self, limit: usize) -> rusqlite::Result<Vec<XpEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, file_path, reason, xp, awarded_at FROM xp_events
+             ORDER BY awarded_at DESC, id DESC LIMIT ?1",
+        )?;
+        let events = stmt
+            .query_map([limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(id, file_path, reason, xp, awarded_at)| {
+                Some(XpEvent {
+                    id,
+                    file_path,
+                    reason: XpReason::from_str(&reason)?,
+                    xp,
+                    awarded_at,
+                })
+            })
+            .collect();
+        Ok(events)
+    }
+
+    pub fn start_exam(&self, exam: &Exam) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO exams (assignment, started_at, deadline) VALUES (?1, ?2, ?3)",
+            params![exam.assignment, exam.started_at, exam.deadline],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // 終了していない試験 (時間切れでも finish するまでは試験中として扱う)
+    pub fn active_exam(&self) -> rusqlite::Result<Option<Exam>> {
+        self.conn
+            .query_row(
+                "SELECT id, assignment, started_at, deadline, finished_at FROM exams
+                 WHERE finished_at IS NULL ORDER BY id DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(Exam {
+                        id: row.get(0)?,
+                        assignment: row.get(1)?,
+                        started_at: row.get(2)?,
+                        deadline: row.get(3)?,
+                        finished_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn finish_exam(&self, id: i64, finished_at: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE exams SET finished_at = ?2 WHERE id = ?1",
+            params![id, finished_at],
+        )?;
+        Ok(())
+    }
+
+    // from 以上 to 以下に実行された記録 (古い順)
+    pub fn executions_between(&self, from: i64, to: i64) -> rusqlite::Result<Vec<ExecutionRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM executions WHERE executed_at BETWEEN ?1 AND ?2
+             ORDER BY executed_at, id",
+            RECORD_COLUMNS
+        ))?;
+        let records = stmt
+            .query_map(params![from, to], record_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    // そのファイルの最新の実行
+    pub fn latest_execution(&self, file_path: &str) -> rusqlite::Result<Option<ExecutionRecord>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM executions WHERE file_path = ?1
+                     ORDER BY executed_at DESC, id DESC LIMIT 1",
+                    RECORD_COLUMNS
+                ),
+                [file_path],
+                record_from_row,
+            )
+            .optional()
+    }
+
+    pub fn record_resubmission(&self, file_path: &str, submitted_at: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO resubmissions (file_path, submitted_at) VALUES (?1, ?2)",
+            params![file_path, submitted_at],
+        )?;
+        Ok(())
+    }
+
+    // 削除・追加した行は JSON の配列で保存する
+    pub fn record_snapshot(
+        &self,
+        file_path: &str,
+        taken_at: i64,
+        delta: &Delta,
+    ) -> rusqlite::Result<()> {
+        let lines = |lines: &Vec<String>| serde_json::to_string(lines).unwrap_or_default();
+        self.conn.execute(
+            "INSERT INTO snapshots (file_path, taken_at, start_line, removed, inserted)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                file_path,
+                taken_at,
+                delta.start as i64,
+                lines(&delta.removed),
+                lines(&delta.inserted)
+            ],
+        )?;
+        Ok(())
+    }
+
+    // 古い順
+    pub fn snapshots(&self, file_path: &str) -> rusqlite::Result<Vec<Snapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT taken_at, start_line, removed, inserted FROM snapshots
+             WHERE file_path = ?1 ORDER BY id",
+        )?;
+        let lines = |text: String| serde_json::from_str::<Vec<String>>(&text).unwrap_or_default();
+        let snapshots = stmt
+            .query_map([file_path], |row| {
+                Ok(Snapshot {
+                    taken_at: row.get(0)?,
+                    delta: Delta {
+                        start: row.get::<_, i64>(1)? as usize,
+                        removed: lines(row.get(2)?),
+                        inserted: lines(row.get(3)?),
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(snapshots)
+    }
+
+    pub fn record_submission(&self, submission: &Submission) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO submissions (assignment, submitted_at, passed, total, commit_sha)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                submission.assignment,
+                submission.submitted_at,
+                submission.passed,
+                submission.total,
+                submission.commit_sha
+            ],
+        )?;
+        Ok(())
+    }
+
+    // 新しい順
+    pub fn submissions(&self) -> rusqlite::Result<Vec<Submission>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT assignment, submitted_at, passed, total, commit_sha FROM submissions
+             ORDER BY submitted_at DESC, id DESC",
+        )?;
+        let submissions = stmt
+            .query_map([], |row| {
+                Ok(Submission {
+                    assignment: row.get(0)?,
+                    submitted_at: row.get(1)?,
+                    passed: row.get(2)?,
+                    total: row.get(3)?,
+                    commit_sha: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(submissions)
+    }
+
+    pub fn save_last_output(&self, output: &LastOutput) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO last_output (id, file_path, executed_at, stdout, stderr)
+             VALUES (1, ?1, ?2, ?3, ?4)",
+            params![
+                output.file_path,
+                output.executed_at,
+                output.stdout,
+                output.stderr
+            ],
+        )?;
+        Ok(())
+    }
+
+    // learn:case のケースごとの結果 (1回の実行分)
+    pub fn record_case_results(
+        &self,
+        file_path: &str,
+        results: &[CaseResult],
+        executed_at: i64,
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for result in results {
+            tx.execute(
+                "INSERT INTO case_results (file_path, case_name, passed, duration_ms, executed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    file_path,
+                    result.name,
+                    result.passed,
+                    result.duration_ms,
+                    executed_at
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // ケースごとの通った回数と実行回数 (最後の実行で通らなかったケースが先)
+    pub fn case_stats(&self) -> rusqlite::Result<Vec<CaseStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, case_name, SUM(passed), COUNT(*),
+                (SELECT passed FROM case_results AS latest
+                 WHERE latest.file_path = c.file_path AND latest.case_name = c.case_name
+                 ORDER BY executed_at DESC, id DESC LIMIT 1)
+             FROM case_results AS c
+             GROUP BY file_path, case_name
+             ORDER BY 5, file_path, case_name",
+        )?;
+        let stats = stmt
+            .query_map([], |row| {
+                Ok(CaseStats {
+                    file_path: row.get(0)?,
+                    case_name: row.get(1)?,
+                    passed: row.get(2)?,
+                    runs: row.get(3)?,
+                    last_passed: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(stats)
+    }
+
+    // 待ち行列に入れる (capacity 件たまっていれば入れずに false)
+    pub fn enqueue_outbound(
+        &self,
+        kind: &str,
+        payload: &str,
+        now: i64,
+        capacity: usize,
+    ) -> rusqlite::Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT INTO outbox (kind, payload, next_attempt_at, created_at)
+             SELECT ?1, ?2, ?3, ?3
+             WHERE (SELECT COUNT(*) FROM outbox) < ?4",
+            params![kind, payload, now, capacity as i64],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    // day ("YYYY-MM-DD") のまとめを送ることにした記録を付ける (既に付いていれば false)
+    pub fn mark_chat_summary(&self, day: &str, now: i64) -> rusqlite::Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO chat_summaries (day, queued_at) VALUES (?1, ?2)",
+            params![day, now],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    // 送る時刻になったもの (古い順に最大 limit 件)
+    pub fn due_outbound(&self, now: i64, limit: usize) -> rusqlite::Result<Vec<Outbound>> {
+        self.query_outbound(
+            "WHERE next_attempt_at <= ?1 ORDER BY id LIMIT ?2",
+            params![now, limit as i64],
+        )
+    }
+
+    // あきらめたものを含むすべて (古い順)
+    pub fn outbound(&self) -> rusqlite::Result<Vec<Outbound>> {
+        self.query_outbound("ORDER BY id", [])
+    }
+
+    fn query_outbound(
+        &self,
+        condition: &str,
+        params: impl rusqlite::Params,
+    ) -> rusqlite::Result<Vec<Outbound>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, kind, payload, attempts, next_attempt_at, last_error, created_at
+             FROM outbox {}",
+            condition
+        ))?;
+        let outbound = stmt
+            .query_map(params, |row| {
+                Ok(Outbound {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    payload: row.get(2)?,
+                    attempts: row.get(3)?,
+                    next_attempt_at: row.get(4)?,
+                    last_error: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(outbound)
+    }
+
+    // 送れたので消す
+    pub fn finish_outbound(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM outbox WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    // 送れなかった回数を増やし、次に送る時刻を決め直す (None ならあきらめる)
+    pub fn fail_outbound(
+        &self,
+        id: i64,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE outbox SET attempts = attempts + 1, last_error = ?2, next_attempt_at = ?3
+             WHERE id = ?1",
+            params![id, error, next_attempt_at],
+        )?;
+        Ok(())
+    }
+
+    // あきらめたものも含めて、すべてすぐに送り直す (送り直す件数)
+    pub fn retry_outbound(&self, now: i64) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "UPDATE outbox SET next_attempt_at = ?1, attempts = 0",
+            [now],
+        )
+    }
+
+    // カバレッジを計測した問題ごとの推移 (問題はパスの順、カバレッジは古い順)
+    pub fn coverage_trends(&self) -> rusqlite::Result<Vec<(String, Vec<f64>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, coverage FROM executions
+             WHERE coverage IS NOT NULL
+             ORDER BY file_path, executed_at, id",
+        )?;
+        let mut trends: Vec<(String, Vec<f64>)> = Vec::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (file_path, coverage) = row?;
+            match trends.last_mut() {
+                Some((last, values)) if *last == file_path => values.push(coverage),
+                _ => trends.push((file_path, vec![coverage])),
+            }
+        }
+        Ok(trends)
+    }
+
+    // 模範解答を実行した標準出力 (模範解答の内容のハッシュごと)
+    pub fn save_reference_output(
+        &self,
+        content_hash: &str,
+        stdout: &str,
+        created_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO reference_outputs (content_hash, stdout, created_at)
+             VALUES (?1, ?2, ?3)",
+            params![content_hash, stdout, created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn reference_output(&self, content_hash: &str) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT stdout FROM reference_outputs WHERE content_hash = ?1",
+                params![content_hash],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    // 問題ファイルを書き出したときのカリキュラムの版と内容 (upgrade-problem の 3-way マージの元になる)
+    pub fn record_problem_version(
+        &self,
+        file_path: &str,
+        version: &ProblemVersion,
+        recorded_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO problem_versions (file_path, version, template, recorded_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![file_path, version.version, version.template, recorded_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn problem_version(&self, file_path: &str) -> rusqlite::Result<Option<ProblemVersion>> {
+        self.conn
+            .query_row(
+                "SELECT version, template FROM problem_versions WHERE file_path = ?1",
+                params![file_path],
+                |row| {
+                    Ok(ProblemVersion {
+                        version: row.get(0)?,
+                        template: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn last_output(&self) -> rusqlite::Result<Option<LastOutput>> {
+        self.conn
+            .query_row(
+                "SELECT file_path, executed_at, stdout, stderr FROM last_output WHERE id = 1",
+                [],
+                |row| {
+                    Ok(LastOutput {
+                        file_path: row.get(0)?,
+                        executed_at: row.get(1)?,
+                        stdout: row.get(2)?,
+                        stderr: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    // ファイル (またはディレクトリ) の名前変更・移動に合わせて記録のパスを書き換える
+    // 履歴・解答時間・ヒントなどが新しいパスに引き継がれる (戻り値は書き換えた行数)
+    pub fn rename_path(&self, from: &str, to: &str) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut renamed = 0;
+        for table in PATH_TABLES {
+            // 移動先に同じ問題の記録があれば移動してきた方を残す
+            renamed += tx.execute(
+                &format!(
+                    "UPDATE OR REPLACE {} SET file_path = ?2 || substr(file_path, length(?1) + 1)
+                     WHERE file_path = ?1 OR substr(file_path, 1, length(?1) + 1) = ?1 || ?3",
+                    table
+                ),
+                params![from, to, std::path::MAIN_SEPARATOR_STR],
+            )?;
+        }
+        tx.commit()?;
+        Ok(renamed)
+    }
+
+    // よく起きたエラーの種類と回数 (多い順)
+    pub fn frequent_errors(&self, limit: usize) -> rusqlite::Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT error_signature, COUNT(*) AS count FROM executions
+             WHERE error_signature IS NOT NULL
+             GROUP BY error_signature ORDER BY count DESC, error_signature LIMIT ?1",
+        )?;
+        let errors = stmt
+            .query_map([limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(errors)
+    }
+
+    // コードや出力の内容を含む記録の種類ごとの件数
+    pub fn stored_content(&self) -> rusqlite::Result<Vec<(&'static str, i64)>> {
+        CONTENT
+            .iter()
+            .map(|(name, count, _)| Ok((*name, self.conn.query_row(count, [], |row| row.get(0))?)))
+            .collect()
+    }
+
+    // コードや出力の内容を含む記録を消す (戻り値は種類ごとの消した件数)
+    // 消した内容がファイルの空き領域に残らないよう、最後に VACUUM で DB を作り直す
+    pub fn purge_content(&self) -> rusqlite::Result<Vec<(&'static str, usize)>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let purged = CONTENT
+            .iter()
+            .map(|(name, _, purge)| Ok((*name, tx.execute(purge, [])?)))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        tx.commit()?;
+        self.conn.execute_batch("VACUUM")?;
+        Ok(purged)
+    }
+
+    // stats query の集計 (観点ごとに1行)
+    pub fn query_stats(&self, query: &StatsQuery) -> rusqlite::Result<Vec<QueryRow>> {
+        let mut stmt = self.conn.prepare(&query.sql())?;
+        let keys = query.group_by.len();
+        let width = keys + query.metrics.len();
+        let rows = stmt
+            .query_map([query.since.unwrap_or(i64::MIN)], |row| {
+                Ok(QueryRow {
+                    keys: (0..keys)
+                        .map(|i| row.get(i))
+                        .collect::<rusqlite::Result<_>>()?,
+                    values: (keys..width)
+                        .map(|i| row.get(i))
+                        .collect::<rusqlite::Result<_>>()?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    // collapse_within を指定すると、その秒数以内に同じ内容のまま繰り返した実行を1回と数える
+    pub fn summary(&self, collapse_within: Option<u64>) -> rusqlite::Result<Summary> {
+        let executions = query::executions_source(collapse_within);
+        self.conn.query_row(
+            &format!(
+                "SELECT
+                    (SELECT COUNT(*) FROM {executions}),
+                    (SELECT COALESCE(SUM(success), 0) FROM {executions}),
+                    (SELECT COUNT(*) FROM sessions),
+                    (SELECT COALESCE(SUM(ended_at - started_at), 0) FROM sessions),
+                    (SELECT COUNT(*) FROM resubmissions)"
+            ),
+            [],
+            |row| {
+                Ok(Summary {
+                    executions: row.get(0)?,
+                    successes: row.get(1)?,
+                    sessions: row.get(2)?,
+                    study_seconds: row.get(3)?,
+                    unchanged_resubmissions: row.get(4)?,
+                })
+            },
+        )
+    }
+}
+
+fn record_from_row(row: &Row<'_>) -> rusqlite::Result<ExecutionRecord> {
+    Ok(ExecutionRecord {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        section: row.get(2)?,
+        success: row.get(3)?,
+        exit_code: row.get(4)?,
+        duration_ms: row.get(5)?,
+        executed_at: row.get(6)?,
+        output_preview: row.get(7)?,
+        environment: Environment {
+            tool_version: row.get(8)?,
+            os: row.get(9)?,
+            app_version: row.get(10)?,
+        },
+        note: row.get(11)?,
+        todos_remaining: row.get(12)?,
+        score: row.get(13)?,
+        content_hash: row.get(14)?,
+        error_signature: row.get(15)?,
+        language: row.get(16)?,
+        coverage: row.get(17)?,
+    })
+}
+
+// 出力の先頭 chars 文字 (既定は PREVIEW_CHARS、--preview-chars で変えられる)
+pub fn preview(output: &str, chars: usize) -> String {
+    output.trim().chars().take(chars).collect()
+}
+
+fn compress(data: &[u8]) -> rusqlite::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn decompress(data: &[u8]) -> rusqlite::Result<String> {
+    let mut text = Vec::new();
+    ZlibDecoder::new(data)
+        .read_to_end(&mut text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Blob, Box::new(e)))?;
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+pub fn content_hash(source: &str) -> String {
+    Sha256::digest(source)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record(file_path: &str, executed_at: i64) -> ExecutionRecord {
+        ExecutionRecord {
+            id: 0,
+            file_path: file_path.to_string(),
+            section: "section1-basics".to_string(),
+            success: true,
+            exit_code: Some(0),
+            duration_ms: 120,
+            executed_at,
+            output_preview: "hello".to_string(),
+            environment: Environment {
+                tool_version: Some("go version go1.22.1 linux/amd64".to_string()),
+                os: "linux x86_64".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: None,
+            todos_remaining: None,
+            score: None,
+            content_hash: None,
+            error_signature: None,
+            language: None,
+            coverage: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_and_read_back_environment() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let record = sample_record("a.go", 100);
+
+        let id = store.insert(&record).unwrap();
+        let records = store.recent(10).unwrap();
+
+        assert_eq!(records, [ExecutionRecord { id, ..record }]);
+    }
+
+    #[test]
+    fn test_recent_returns_newest_first() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.insert(&sample_record("old.go", 100)).unwrap();
+        store.insert(&sample_record("new.go", 200)).unwrap();
+
+        let records = store.recent(1).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].file_path, "new.go");
+    }
+
+    #[test]
+    fn test_page_with_cursor() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        // 実行番号の順と実行時刻の順が違う記録 (ジャーナルから後で入れた記録など)
+        for (file_path, executed_at) in [("b.go", 200), ("c.go", 300), ("a.go", 100), ("d.go", 300)]
+        {
+            store
+                .insert(&sample_record(file_path, executed_at))
+                .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut before = None;
+        loop {
+            let page = store.page(3, before).unwrap();
+            seen.extend(page.iter().map(|r| r.file_path.clone()));
+            match page.last() {
+                Some(last) if page.len() == 3 => before = Some(Cursor::of(last)),
+                _ => break,
+            }
+        }
+
+        assert_eq!(seen, ["d.go", "c.go", "b.go", "a.go"]);
+        let cursor: Cursor = "300:4".parse().unwrap();
+        assert_eq!(cursor.to_string(), "300:4");
+        assert!("300".parse::<Cursor>().is_err());
+    }
+
+    #[test]
+    fn test_reopen_keeps_records() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("history.db");
+        HistoryStore::open(&path)
+            .unwrap()
+            .insert(&sample_record("a.py", 100))
+            .unwrap();
+
+        let store = HistoryStore::open(&path).unwrap();
+
+        assert_eq!(store.recent(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_preview_truncates_output() {
+        let output = "x".repeat(PREVIEW_CHARS + 50);
+
+        assert_eq!(
+            preview(&output, PREVIEW_CHARS).chars().count(),
+            PREVIEW_CHARS
+        );
+        assert_eq!(preview(&output, 10).chars().count(), 10);
+    }
+
+    #[test]
+    fn test_full_output_round_trip() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = store.insert(&sample_record("a.go", 100)).unwrap();
+        let stdout = "行\n".repeat(10_000);
+
+        store
+            .save_full_output(id, stdout.as_bytes(), b"warning")
+            .unwrap();
+
+        assert_eq!(
+            store.full_output(id).unwrap(),
+            Some((stdout.clone(), "warning".to_string()))
+        );
+        let stored: i64 = store
+            .conn
+            .query_row(
+                "SELECT length(stdout) FROM execution_outputs WHERE execution_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!((stored as usize) < stdout.len() / 10);
+        assert_eq!(store.full_output(id + 1).unwrap(), None);
+        assert_eq!(store.execution(id).unwrap().unwrap().file_path, "a.go");
+    }
+
+    #[test]
+    fn test_solve_time_is_recorded_once() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let record = SolveRecord {
+            file_path: "a.go".to_string(),
+            section: "section1-basics".to_string(),
+            first_modified_at: 100,
+            solved_at: 400,
+            active_seconds: 250,
+        };
+
+        assert!(store.record_solve_time(&record).unwrap());
+        assert!(
+            !store
+                .record_solve_time(&SolveRecord {
+                    active_seconds: 10,
+                    ..record.clone()
+                })
+                .unwrap()
+        );
+        assert_eq!(store.solve_times().unwrap(), [record]);
+    }
+
+    #[test]
+    fn test_summary_counts_successes() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.insert(&sample_record("a.go", 100)).unwrap();
+        store
+            .insert(&ExecutionRecord {
+                success: false,
+                ..sample_record("a.go", 200)
+            })
+            .unwrap();
+
+        assert_eq!(
+            store.summary(None).unwrap(),
+            Summary {
+                executions: 2,
+                successes: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_summary_sums_session_time() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (started_at, ended_at) in [(1000, 1600), (5000, 5300)] {
+            store
+                .record_session(&SessionRecord {
+                    started_at,
+                    ended_at,
+                })
+                .unwrap();
+        }
+
+        let summary = store.summary(None).unwrap();
+
+        assert_eq!(summary.sessions, 2);
+        assert_eq!(summary.study_seconds, 900);
+    }
+
+    #[test]
+    fn test_note_is_searchable() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = store.insert(&sample_record("pointers.go", 100)).unwrap();
+        store.insert(&sample_record("loops.go", 200)).unwrap();
+
+        assert!(
+            store
+                .set_note(id, "finally understood pointer receivers")
+                .unwrap()
+        );
+        assert!(!store.set_note(999, "missing").unwrap());
+
+        let found = store.search("pointer receivers", 10, None).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, id);
+        assert_eq!(
+            found[0].note.as_deref(),
+            Some("finally understood pointer receivers")
+        );
+        // "%" はワイルドカードとして扱わない
+        assert!(store.search("%", 10, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_defer_and_undefer() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let problem = DeferredProblem {
+            file_path: "section3/problem05.go".to_string(),
+            reason: Some("section7 の内容が必要".to_string()),
+            deferred_at: 100,
+        };
+
+        store.defer(&problem).unwrap();
+        store
+            .defer(&DeferredProblem {
+                deferred_at: 200,
+                ..problem.clone()
+            })
+            .unwrap();
+        assert_eq!(store.deferred().unwrap().len(), 1);
+        assert_eq!(store.deferred().unwrap()[0].deferred_at, 200);
+
+        assert!(store.undefer(&problem.file_path).unwrap());
+        assert!(!store.undefer(&problem.file_path).unwrap());
+        assert!(store.deferred().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tags_are_normalized_and_removable() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let tags = ["Recursion".to_string(), "stdlib".to_string()];
+
+        store.add_tags("a.go", &tags).unwrap();
+        store.add_tags("a.go", &tags).unwrap();
+        assert_eq!(store.tags("a.go").unwrap(), ["recursion", "stdlib"]);
+
+        store.remove_tags("a.go", &["STDLIB".to_string()]).unwrap();
+        assert_eq!(store.tags("a.go").unwrap(), ["recursion"]);
+        assert!(store.tags("b.go").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_problems_ranks_title_matches() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store
+            .index_problem(
+                "a.go",
+                1,
+                "Type Assertion Practice",
+                "Interfaces",
+                "// TODO: use a type assertion",
+            )
+            .unwrap();
+        store
+            .index_problem(
+                "b.go",
+                1,
+                "Loops",
+                "Loops",
+                "// mentions type and assertion once",
+            )
+            .unwrap();
+        store
+            .index_problem("c.go", 1, "Maps", "Maps", "m := map[string]int{}")
+            .unwrap();
+
+        let hits = store.search_problems("type assertion", 10).unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].file_path, "a.go");
+        assert!(hits[0].snippet.contains("[type]"));
+        // FTS5 の構文になる記号もそのまま検索できる
+        assert!(store.search_problems("map[string]int", 10).is_ok());
+    }
+
+    #[test]
+    fn test_reindex_replaces_problem() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store
+            .index_problem("a.go", 1, "Old", "", "goroutine")
+            .unwrap();
+        store
+            .index_problem("a.go", 2, "New", "", "channel")
+            .unwrap();
+
+        assert!(store.search_problems("goroutine", 10).unwrap().is_empty());
+        assert_eq!(store.search_problems("channel", 10).unwrap().len(), 1);
+        assert_eq!(store.indexed_problems().unwrap()["a.go"], 2);
+    }
+
+    #[test]
+    fn test_todo_total_is_highest_count() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert_eq!(store.todo_total("a.go").unwrap(), None);
+
+        for (executed_at, remaining) in [(100, 7), (200, 5), (300, 3)] {
+            store
+                .insert(&ExecutionRecord {
+                    todos_remaining: Some(remaining),
+                    ..sample_record("a.go", executed_at)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(store.todo_total("a.go").unwrap(), Some(7));
+        assert_eq!(store.recent(1).unwrap()[0].todos_remaining, Some(3));
+        assert_eq!(store.recent(1).unwrap()[0].score, None);
+    }
+
+    #[test]
+    fn test_llm_cache() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert_eq!(store.cached_response("k").unwrap(), None);
+
+        store.cache_response("k", "{}", 100).unwrap();
+
+        assert_eq!(store.cached_response("k").unwrap().as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn test_last_failure_and_questions() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store
+            .insert(&ExecutionRecord {
+                success: false,
+                output_preview: "panic".to_string(),
+                ..sample_record("a.go", 100)
+            })
+            .unwrap();
+        store.insert(&sample_record("a.go", 200)).unwrap();
+
+        assert_eq!(
+            store.last_failure("a.go").unwrap().unwrap().output_preview,
+            "panic"
+        );
+        assert_eq!(store.last_failure("b.go").unwrap(), None);
+
+        let question = Question {
+            file_path: "a.go".to_string(),
+            question: "why?".to_string(),
+            answer: "because".to_string(),
+            asked_at: 300,
+        };
+        store.record_question(&question).unwrap();
+        assert_eq!(store.questions(10).unwrap(), [question]);
+    }
+
+    #[test]
+    fn test_hints_used_until_solved() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.record_hint("a.go", HintKind::Ask, 100).unwrap();
+        store.record_hint("a.go", HintKind::Hint, 110).unwrap();
+        store.record_hint("a.go", HintKind::Ask, 120).unwrap();
+        // 解いた後に見た模範解答は含めない
+        store.record_hint("a.go", HintKind::Solution, 300).unwrap();
+
+        assert_eq!(
+            store.hints_used("a.go", 200).unwrap(),
+            [HintKind::Hint, HintKind::Ask]
+        );
+        assert!(store.hints_used("b.go", 200).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_exam_lifecycle() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let mut exam = Exam {
+            id: 0,
+            assignment: "/exam".to_string(),
+            started_at: 100,
+            deadline: 200,
+            finished_at: None,
+        };
+        exam.id = store.start_exam(&exam).unwrap();
+        store.insert(&sample_record("/exam/a.go", 50)).unwrap();
+        store.insert(&sample_record("/exam/a.go", 150)).unwrap();
+
+        assert_eq!(store.active_exam().unwrap(), Some(exam.clone()));
+        assert_eq!(store.executions_between(100, 200).unwrap().len(), 1);
+
+        store.finish_exam(exam.id, 180).unwrap();
+        assert_eq!(store.active_exam().unwrap(), None);
+    }
+
+    #[test]
+    fn test_latest_execution_and_resubmissions() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.insert(&sample_record("a.go", 100)).unwrap();
+        store
+            .insert(&ExecutionRecord {
+                success: false,
+                content_hash: Some(content_hash("package main\n")),
+                ..sample_record("a.go", 200)
+            })
+            .unwrap();
+        store.record_resubmission("a.go", 210).unwrap();
+
+        let latest = store.latest_execution("a.go").unwrap().unwrap();
+        assert_eq!(store.executions_for("a.go").unwrap()[1], latest);
+        assert!(!latest.success);
+        assert_eq!(latest.content_hash, Some(content_hash("package main\n")));
+        assert_ne!(
+            content_hash("package main\n"),
+            content_hash("package main\n\n")
+        );
+        assert_eq!(store.summary(None).unwrap().unchanged_resubmissions, 1);
+    }
+
+    #[test]
+    fn test_rename_path_moves_records() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let file = |dir: &str, name: &str| format!("{}{}{}", dir, std::path::MAIN_SEPARATOR, name);
+        store
+            .insert(&sample_record(&file("section1", "a.go"), 100))
+            .unwrap();
+        store
+            .insert(&sample_record(&file("section10", "b.go"), 100))
+            .unwrap();
+        store
+            .add_tags(&file("section1", "a.go"), &["loop".to_string()])
+            .unwrap();
+
+        // ファイルの名前変更
+        store
+            .rename_path(&file("section1", "a.go"), &file("section1", "hello.go"))
+            .unwrap();
+        assert_eq!(store.tags(&file("section1", "hello.go")).unwrap(), ["loop"]);
+
+        // ディレクトリの名前変更 (名前が前方一致するだけの section10 は対象外)
+        assert_eq!(store.rename_path("section1", "section01").unwrap(), 2);
+        assert_eq!(
+            store
+                .executions_for(&file("section01", "hello.go"))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .executions_for(&file("section10", "b.go"))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_purge_content_keeps_stats() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = store.insert(&sample_record("/work/a.go", 100)).unwrap();
+        store.set_note(id, "ポインタを理解した").unwrap();
+        store.save_full_output(id, b"hello\n", b"").unwrap();
+        store
+            .record_question(&Question {
+                file_path: "/work/a.go".to_string(),
+                question: "なぜ?".to_string(),
+                answer: "...".to_string(),
+                asked_at: 100,
+            })
+            .unwrap();
+        let before = store.summary(None).unwrap();
+
+        let purged = store.purge_content().unwrap();
+        assert_eq!(
+            purged.iter().map(|(_, count)| count).sum::<usize>(),
+            4,
+            "{:?}",
+            purged
+        );
+        assert!(
+            store
+                .stored_content()
+                .unwrap()
+                .iter()
+                .all(|(_, count)| *count == 0)
+        );
+        let record = store.execution(id).unwrap().unwrap();
+        assert_eq!((record.output_preview.as_str(), record.note), ("", None));
+        assert_eq!(store.full_output(id).unwrap(), None);
+        assert_eq!(store.summary(None).unwrap(), before);
+    }
+
+    #[test]
+    fn test_frequent_errors() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (executed_at, signature) in [
+            (100, Some("NameError: name '_' is not defined")),
+            (200, None),
+            (300, Some("ZeroDivisionError: division by zero")),
+            (400, Some("NameError: name '_' is not defined")),
+        ] {
+            store
+                .insert(&ExecutionRecord {
+                    success: signature.is_none(),
+                    error_signature: signature.map(str::to_string),
+                    ..sample_record("a.py", executed_at)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            store.frequent_errors(1).unwrap(),
+            [("NameError: name '_' is not defined".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_query_stats() {
+        use crate::query::{Dimension, Metric};
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (file_path, executed_at, success, duration_ms, language) in [
+            ("a.go", 100, true, 100, "Go"),
+            ("b.go", 200, false, 300, "Go"),
+            ("a.py", 300, true, 1000, "Python"),
+            ("old.py", 10, false, 50, "Python"),
+        ] {
+            store
+                .insert(&ExecutionRecord {
+                    success,
+                    duration_ms,
+                    language: Some(language.to_string()),
+                    ..sample_record(file_path, executed_at)
+                })
+                .unwrap();
+        }
+
+        let rows = store
+            .query_stats(&StatsQuery {
+                group_by: vec![Dimension::Language],
+                metrics: vec![Metric::Runs, Metric::SuccessRate, Metric::AvgTime],
+                since: Some(100),
+                collapse_within: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            rows,
+            [
+                QueryRow {
+                    keys: vec!["Go".to_string()],
+                    values: vec![Some(2.0), Some(50.0), Some(200.0)],
+                },
+                QueryRow {
+                    keys: vec!["Python".to_string()],
+                    values: vec![Some(1.0), Some(100.0), Some(1000.0)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summary_collapses_repeated_runs() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (file_path, executed_at, hash, success) in [
+            ("a.go", 100, Some("h1"), false),
+            ("a.go", 110, Some("h1"), false),
+            ("a.go", 150, Some("h1"), true),
+            ("a.go", 160, Some("h2"), true),
+            ("a.go", 500, Some("h2"), true),
+            ("b.go", 105, Some("h1"), true),
+            ("b.go", 106, None, true),
+            ("b.go", 107, None, true),
+        ] {
+            store
+                .insert(&ExecutionRecord {
+                    success,
+                    content_hash: hash.map(str::to_string),
+                    ..sample_record(file_path, executed_at)
+                })
+                .unwrap();
+        }
+
+        let summary = store.summary(Some(60)).unwrap();
+
+        // a.go の 110・150 (直前から 60 秒以内で同じ内容) だけがまとめられる
+        assert_eq!((summary.executions, summary.successes), (6, 5));
+        assert_eq!(store.summary(None).unwrap().executions, 8);
+    }
+
+    #[test]
+    fn test_removed_problems() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.mark_removed("b.go", 200).unwrap();
+        store.mark_removed("a.go", 100).unwrap();
+
+        assert!(store.clear_removed("b.go").unwrap());
+        assert!(!store.clear_removed("b.go").unwrap());
+        assert_eq!(
+            store.removed_problems().unwrap(),
+            [RemovedProblem {
+                file_path: "a.go".to_string(),
+                removed_at: 100,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_submissions_newest_first() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (submitted_at, commit_sha) in [(100, None), (200, Some("abc".to_string()))] {
+            store
+                .record_submission(&Submission {
+                    assignment: "/work/hw1".to_string(),
+                    submitted_at,
+                    passed: 1,
+                    total: 2,
+                    commit_sha,
+                })
+                .unwrap();
+        }
+
+        let submissions = store.submissions().unwrap();
+
+        assert_eq!(submissions.len(), 2);
+        assert_eq!(submissions[0].commit_sha.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_last_output_keeps_only_latest() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        assert_eq!(store.last_output().unwrap(), None);
+
+        for (executed_at, stderr) in [(100, "first"), (200, "second")] {
+            store
+                .save_last_output(&LastOutput {
+                    file_path: "/work/a.py".to_string(),
+                    executed_at,
+                    stdout: String::new(),
+                    stderr: stderr.to_string(),
+                })
+                .unwrap();
+        }
+
+        let last = store.last_output().unwrap().unwrap();
+        assert_eq!(last.executed_at, 200);
+        assert_eq!(last.stderr, "second");
+    }
+
+    #[test]
+    fn test_reference_output_cache() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let hash = content_hash("print('a')\n");
+        assert_eq!(store.reference_output(&hash).unwrap(), None);
+
+        store.save_reference_output(&hash, "a\n", 100).unwrap();
+
+        assert_eq!(
+            store.reference_output(&hash).unwrap().as_deref(),
+            Some("a\n")
+        );
+        assert_eq!(store.reference_output(&content_hash("")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back() {
+        let store = HistoryStore {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        // 2つ目は途中の文で失敗する
+        let migrations = [
+            "CREATE TABLE a (x INTEGER);",
+            "CREATE TABLE b (x INTEGER); CREATE TABLE a (x INTEGER);",
+        ];
+        assert!(store.apply_migrations(&migrations).is_err());
+
+        let version: i64 = store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 1);
+        let tables: Vec<String> = store
+            .conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(tables, ["a"]);
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut store = HistoryStore::open(&tmp.path().join("history.db")).unwrap();
+        store.save_reference_output("a", "1\n", 100).unwrap();
+        let backup = tmp.path().join("backup.db");
+        store.backup_to(&backup).unwrap();
+        store.save_reference_output("b", "2\n", 200).unwrap();
+
+        store.restore_from(&backup).unwrap();
+
+        assert_eq!(store.reference_output("a").unwrap().as_deref(), Some("1\n"));
+        assert_eq!(store.reference_output("b").unwrap(), None);
+    }
+
+    #[test]
+    fn test_open_read_only_leaves_file_untouched() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("history.db");
+        let store = HistoryStore::open(&path).unwrap();
+        store.insert(&sample_record("/work/a.go", 100)).unwrap();
+        store
+            .insert(&ExecutionRecord {
+                success: false,
+                ..sample_record("/work/b.go", 200)
+            })
+            .unwrap();
+        drop(store);
+
+        let copy = HistoryStore::open_read_only(&path).unwrap();
+        assert_eq!(
+            copy.problem_outcomes().unwrap(),
+            [
+                ProblemOutcome {
+                    file_path: "/work/a.go".to_string(),
+                    runs: 1,
+                    passed: true,
+                },
+                ProblemOutcome {
+                    file_path: "/work/b.go".to_string(),
+                    runs: 1,
+                    passed: false,
+                },
+            ]
+        );
+        // 写しへの書き込みは元のファイルに残らない
+        copy.insert(&sample_record("/work/c.go", 300)).unwrap();
+        let original = HistoryStore::open(&path).unwrap();
+        assert_eq!(original.problem_outcomes().unwrap().len(), 2);
+        assert!(HistoryStore::open_read_only(&tmp.path().join("missing.db")).is_err());
+    }
+
+    #[test]
+    fn test_problem_version_follows_rename() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let version = ProblemVersion {
+            version: "1a2b3c4d".to_string(),
+            template: "package main\n".to_string(),
+        };
+        store
+            .record_problem_version("/work/section1/a.go", &version, 100)
+            .unwrap();
+
+        store
+            .rename_path("/work/section1", "/work/section1-basics")
+            .unwrap();
+
+        assert_eq!(store.problem_version("/work/section1/a.go").unwrap(), None);
+        assert_eq!(
+            store.problem_version("/work/section1-basics/a.go").unwrap(),
+            Some(version)
+        );
+    }
+
+    #[test]
+    fn test_award_xp_once_per_problem() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let event = |reason, xp| XpEvent {
+            id: 0,
+            file_path: "a.go".to_string(),
+            reason,
+            xp,
+            awarded_at: 100,
+        };
+
+        assert!(
+            store
+                .award_xp(&[event(XpReason::Solve, 30), event(XpReason::FirstTry, 15)])
+                .unwrap()
+        );
+        assert!(!store.award_xp(&[event(XpReason::Solve, 30)]).unwrap());
+        assert_eq!(store.total_xp().unwrap(), 45);
+        let events = store.xp_events(10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].reason, XpReason::FirstTry);
+
+        store.insert(&sample_record("b.go", 100)).unwrap();
+        store
+            .insert(&ExecutionRecord {
+                success: false,
+                ..sample_record("b.go", 101)
+            })
+            .unwrap();
+        assert_eq!(store.failure_count("b.go").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_coverage_trends_per_problem() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (file_path, executed_at, coverage) in [
+            ("/work/b.go", 300, Some(90.0)),
+            ("/work/a.go", 200, Some(75.5)),
+            ("/work/a.go", 100, Some(40.0)),
+            ("/work/a.go", 150, None),
+        ] {
+            store
+                .insert(&ExecutionRecord {
+                    coverage,
+                    ..sample_record(file_path, executed_at)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(store.recent(1).unwrap()[0].coverage, Some(90.0));
+        assert_eq!(
+            store.coverage_trends().unwrap(),
+            [
+                ("/work/a.go".to_string(), vec![40.0, 75.5]),
+                ("/work/b.go".to_string(), vec![90.0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_case_stats_puts_failing_cases_first() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let result = |name: &str, passed| CaseResult {
+            name: name.to_string(),
+            passed,
+            duration_ms: 10,
+            detail: None,
+        };
+        store
+            .record_case_results(
+                "/work/a.py",
+                &[result("1.in", false), result("2.in", true)],
+                100,
+            )
+            .unwrap();
+        store
+            .record_case_results(
+                "/work/a.py",
+                &[result("1.in", true), result("2.in", false)],
+                200,
+            )
+            .unwrap();
+
+        let stats = store.case_stats().unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[0],
+            CaseStats {
+                file_path: "/work/a.py".to_string(),
+                case_name: "2.in".to_string(),
+                passed: 1,
+                runs: 2,
+                last_passed: false,
+            }
+        );
+        assert!(stats[1].last_passed);
+    }
+}
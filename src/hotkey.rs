@@ -0,0 +1,50 @@
+// --hotkey: 監視中にどのウィンドウにいても押せるショートカットキーで再実行する
+// Linux の X11 (XWayland のウィンドウを含む) だけで使える
+// Wayland のウィンドウや他の OS では rerun コマンドを OS のショートカットキーに割り当てる
+
+// 登録したショートカットキー (落とすと登録を外す)
+#[cfg(target_os = "linux")]
+pub struct Hotkey {
+    _manager: global_hotkey::GlobalHotKeyManager,
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct Hotkey;
+
+// keys は "ctrl+alt+r" のような形 (修飾キー+キー)
+#[cfg(target_os = "linux")]
+pub fn register(keys: &str, on_press: impl Fn() + Send + 'static) -> Result<Hotkey, String> {
+    use global_hotkey::hotkey::HotKey;
+    use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+    let hotkey: HotKey = keys
+        .parse()
+        .map_err(|e| format!("ショートカットキーを解釈できません: {} ({})", keys, e))?;
+    // X11 に接続できなくても登録はエラーにならないので先に確かめる
+    if std::env::var_os("DISPLAY").is_none_or(|display| display.is_empty()) {
+        return Err("X11 のディスプレイがないためショートカットキーを使えません".to_string());
+    }
+    let manager =
+        GlobalHotKeyManager::new().map_err(|e| format!("ショートカットキーを使えません: {}", e))?;
+    manager
+        .register(hotkey)
+        .map_err(|e| format!("{} を登録できません: {}", keys, e))?;
+
+    let id = hotkey.id();
+    std::thread::spawn(move || {
+        while let Ok(event) = GlobalHotKeyEvent::receiver().recv() {
+            if event.id == id && event.state == HotKeyState::Pressed {
+                on_press();
+            }
+        }
+    });
+    Ok(Hotkey { _manager: manager })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn register(_keys: &str, _on_press: impl Fn() + Send + 'static) -> Result<Hotkey, String> {
+    Err(
+        "このOSでは --hotkey を使えません (rerun コマンドを OS のショートカットキーに割り当ててください)"
+            .to_string(),
+    )
+}
@@ -0,0 +1,364 @@
+use crate::history;
+use crate::rerun::Endpoint;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+// 同じディレクトリを監視するプロセスを1つにするためのロックファイル
+// (2つ監視していると1回の保存で2回実行され、履歴DBへの書き込みも競合する)
+// データディレクトリの locks/<監視するディレクトリのハッシュ>.lock に PID と監視するディレクトリを書く
+// 書いたプロセスが終了していればロックは古いものとして取り直す
+// rerun コマンドもこのファイルで再実行を指示する先のプロセスとポートを探す
+
+// --takeover で前のプロセスの終了を待つ時間
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(10);
+// 古いロックの置き換えがこれより長く終わらなければ、置き換えていたプロセスは終了したとみなす
+const GUARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+// 監視中のプロセス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watcher {
+    pub pid: u32,
+    pub dir: PathBuf,
+    // rerun の指示を待ち受けているポート (待ち受けを始める前は None)
+    pub endpoint: Option<Endpoint>,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    // 他のプロセスが監視中 (PID)
+    Running(u32),
+    // --takeover で終了を頼んだが終わらなかった (PID)
+    StillRunning(u32),
+    Io(io::Error),
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Running(pid) => write!(
+                f,
+                "このディレクトリは既に監視されています (PID {}、--takeover で置き換えられます)",
+                pid
+            ),
+            LockError::StillRunning(pid) => {
+                write!(f, "監視中のプロセス (PID {}) が終了しません", pid)
+            }
+            LockError::Io(e) => write!(f, "ロックファイルを作成できません: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        LockError::Io(e)
+    }
+}
+
+// 監視するディレクトリごとのロックファイルのパス
+pub fn lock_file(locks_dir: &Path, watch_dir: &Path) -> PathBuf {
+    let dir = watch_dir
+        .canonicalize()
+        .unwrap_or_else(|_| watch_dir.to_path_buf());
+    let hash = history::content_hash(&dir.display().to_string());
+    locks_dir.join(format!("{}.lock", &hash[..16]))
+}
+
+impl InstanceLock {
+    // takeover なら監視中のプロセスに終了を頼み (Ctrl+C と同じくセッションを保存して終わる)、終わるのを待って取る
+    pub fn acquire(locks_dir: &Path, watch_dir: &Path, takeover: bool) -> Result<Self, LockError> {
+        fs::create_dir_all(locks_dir)?;
+        let path = lock_file(locks_dir, watch_dir);
+        let mut asked = false;
+        loop {
+            match private_file().create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write_holder(&mut file, watch_dir)?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let Some(pid) = holder(&path) else {
+                // 書き込み途中のロックは少し待って読み直す
+                std::thread::sleep(Duration::from_millis(100));
+                if holder(&path).is_none() {
+                    log::warn!("読めないロックファイルを置き換えます: {}", path.display());
+                    if replace_stale(&path, None, watch_dir)? {
+                        return Ok(Self { path });
+                    }
+                }
+                continue;
+            };
+            if pid == std::process::id() || !is_alive(pid) {
+                log::warn!("終了したプロセス (PID {}) のロックを置き換えます", pid);
+                if replace_stale(&path, Some(pid), watch_dir)? {
+                    return Ok(Self { path });
+                }
+                continue;
+            }
+            if !takeover {
+                return Err(LockError::Running(pid));
+            }
+            if asked {
+                return Err(LockError::StillRunning(pid));
+            }
+            println!("監視中のプロセス (PID {}) を終了して置き換えます", pid);
+            interrupt(pid)?;
+            asked = true;
+            let deadline = Instant::now() + TAKEOVER_TIMEOUT;
+            while Instant::now() < deadline && path.exists() && is_alive(pid) {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // rerun の指示を待ち受けているポートを書き足す (読み途中のファイルを見せないよう置き換える)
+    pub fn publish(&self, endpoint: &Endpoint) -> io::Result<()> {
+        let text = fs::read_to_string(&self.path)?;
+        let mut lines: Vec<&str> = text.lines().take(2).collect();
+        let endpoint = endpoint.to_string();
+        lines.push(&endpoint);
+        let temporary = self.path.with_extension("tmp");
+        remove(&temporary)?;
+        private_file()
+            .create_new(true)
+            .open(&temporary)?
+            .write_all((lines.join("\n") + "\n").as_bytes())?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    pub fn release(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// 監視中のプロセスの一覧 (終了したプロセスのロックは含めない)
+pub fn watchers(locks_dir: &Path) -> Vec<Watcher> {
+    let Ok(entries) = fs::read_dir(locks_dir) else {
+        return Vec::new();
+    };
+    let mut watchers: Vec<Watcher> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lock"))
+        .filter_map(|path| read(&path))
+        .filter(|watcher| is_alive(watcher.pid))
+        .collect();
+    watchers.sort_by(|a, b| a.dir.cmp(&b.dir));
+    watchers
+}
+
+// watch_dir を監視しているプロセス
+pub fn watcher(locks_dir: &Path, watch_dir: &Path) -> Option<Watcher> {
+    read(&lock_file(locks_dir, watch_dir)).filter(|watcher| is_alive(watcher.pid))
+}
+
+fn read(path: &Path) -> Option<Watcher> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut lines = text.lines();
+    Some(Watcher {
+        pid: lines.next()?.trim().parse().ok()?,
+        dir: PathBuf::from(lines.next()?),
+        endpoint: lines.next().and_then(Endpoint::parse),
+    })
+}
+
+// ロックを持っているプロセスの PID
+fn holder(path: &Path) -> Option<u32> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// 1行目に PID、2行目に監視するディレクトリ
+fn write_holder(file: &mut fs::File, watch_dir: &Path) -> io::Result<()> {
+    let dir = watch_dir
+        .canonicalize()
+        .unwrap_or_else(|_| watch_dir.to_path_buf());
+    write!(file, "{}\n{}\n", std::process::id(), dir.display())
+}
+
+// 古いロック (持ち主が stale、読めなければ None) を自分のものに置き換える (置き換えたら true)
+// 消してから作り直すと、同時に置き換えようとした別のプロセスのロックまで消してしまうので、
+// 置き換えは .takeover のファイルで1つのプロセスずつにし、一時ファイルを rename で上書きした後に読み直して確かめる
+fn replace_stale(path: &Path, stale: Option<u32>, watch_dir: &Path) -> io::Result<bool> {
+    let guard = path.with_extension("takeover");
+    match private_file().create_new(true).open(&guard) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            // 置き換えの途中で終了したプロセスの .takeover は消す
+            let abandoned = fs::metadata(&guard)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| {
+                    modified
+                        .elapsed()
+                        .is_ok_and(|elapsed| elapsed > GUARD_TIMEOUT)
+                });
+            match abandoned {
+                true => remove(&guard)?,
+                false => std::thread::sleep(Duration::from_millis(100)),
+            }
+            return Ok(false);
+        }
+        Err(e) => return Err(e),
+    }
+
+    let replaced = (|| {
+        // 待っている間に別のプロセスが取っていれば置き換えない
+        if holder(path) != stale {
+            return Ok(false);
+        }
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        remove(&temporary)?;
+        write_holder(
+            &mut private_file().create_new(true).open(&temporary)?,
+            watch_dir,
+        )?;
+        fs::rename(&temporary, path)?;
+        Ok(holder(path) == Some(std::process::id()))
+    })();
+    remove(&guard)?;
+    replaced
+}
+
+// rerun の合言葉を書くので、持ち主だけが読み書きできるファイルにする
+fn private_file() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+// 他のプロセスが先に消していてもよい
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// シグナルを送る権限がない (EPERM) のは別のユーザーのプロセスが生きているので、「No such process」のときだけ終了したとみなす
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    let Ok(output) = std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .env("LC_ALL", "C")
+        .stdout(Stdio::null())
+        .output()
+    else {
+        return false;
+    };
+    output.status.success() || !String::from_utf8_lossy(&output.stderr).contains("No such process")
+}
+
+#[cfg(windows)]
+fn is_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+// Ctrl+C と同じく終了させる
+#[cfg(unix)]
+fn interrupt(pid: u32) -> io::Result<()> {
+    std::process::Command::new("kill")
+        .args(["-INT", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map(|_| ())
+}
+
+#[cfg(windows)]
+fn interrupt(pid: u32) -> io::Result<()> {
+    std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_second_instance_is_rejected_and_stale_lock_is_replaced() {
+        let tmp = tempfile::tempdir().unwrap();
+        let locks = tmp.path().join("locks");
+        let watched = tmp.path().join("work");
+        fs::create_dir(&watched).unwrap();
+
+        // 終了したプロセスのロックは取り直す
+        let path = lock_file(&locks, &watched);
+        fs::create_dir_all(&locks).unwrap();
+        fs::write(&path, "4194305\n").unwrap();
+        let lock = InstanceLock::acquire(&locks, &watched, false).unwrap();
+        assert_eq!(holder(lock.path()), Some(std::process::id()));
+        // rerun はディレクトリごとに監視中のプロセスを探す
+        let mut running = Watcher {
+            pid: std::process::id(),
+            dir: watched.canonicalize().unwrap(),
+            endpoint: None,
+        };
+        assert_eq!(watcher(&locks, &watched), Some(running.clone()));
+        let endpoint = Endpoint {
+            port: 40000,
+            token: "abc".to_string(),
+        };
+        lock.publish(&endpoint).unwrap();
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(lock.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        running.endpoint = Some(endpoint);
+        assert_eq!(watchers(&locks), [running]);
+        assert_eq!(watcher(&locks, tmp.path()), None);
+
+        // 生きているプロセス (ここではテストを実行している親プロセス) のロックは取れない
+        let parent = std::os::unix::process::parent_id();
+        fs::write(&path, format!("{}\n", parent)).unwrap();
+        assert!(matches!(
+            InstanceLock::acquire(&locks, &watched, false),
+            Err(LockError::Running(pid)) if pid == parent
+        ));
+
+        // 別のユーザーのプロセス (シグナルを送れなくても) は生きている
+        assert!(is_alive(1));
+
+        // 置き換えの途中のプロセスがあれば待つ
+        lock.release();
+        fs::write(&path, "4194305\n").unwrap();
+        let guard = path.with_extension("takeover");
+        fs::write(&guard, "").unwrap();
+        assert!(!replace_stale(&path, Some(4194305), &watched).unwrap());
+        fs::remove_file(&guard).unwrap();
+        assert!(replace_stale(&path, Some(4194305), &watched).unwrap());
+        assert_eq!(holder(&path), Some(std::process::id()));
+        assert!(!guard.exists());
+
+        lock.release();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::environment::Environment;
+use crate::history::{ExecutionRecord, HistoryStore};
+use log::{error, warn};
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// 実行記録のジャーナル
+// 実行が終わったらすぐに記録を journal/<PID>-<連番>.json に書き、履歴 DB に保存できたら消す
+// 採点やレビューの途中で終了したり DB に書けなかったりした記録は、次に起動したときに DB に入れる
+pub struct Journal {
+    dir: PathBuf,
+    next: AtomicU64,
+}
+
+impl Journal {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            next: AtomicU64::new(0),
+        }
+    }
+
+    // 書き終わるまで別名にしておき、途中で終了しても壊れたエントリが残らないようにする
+    pub fn write(&self, record: &ExecutionRecord) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let name = format!(
+            "{}-{}",
+            std::process::id(),
+            self.next.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = self.dir.join(format!("{}.json", name));
+        rewrite(&path, record)?;
+        Ok(path)
+    }
+
+    // 書いた後に分かった値 (採点の点数など) を入れて書き直す
+    pub fn rewrite(&self, entry: &Path, record: &ExecutionRecord) -> io::Result<()> {
+        rewrite(entry, record)
+    }
+
+    pub fn remove(&self, entry: &Path) {
+        if let Err(e) = fs::remove_file(entry) {
+            error!("ジャーナルを削除できません: {} ({})", entry.display(), e);
+        }
+    }
+
+    // 残っている記録を DB に入れて消す (入れた件数を返す)
+    // 動いている別のプロセスが書いたエントリはそのプロセスが保存するので触らない
+    pub fn replay(&self, store: &HistoryStore) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+
+        let mut replayed = 0;
+        for path in paths {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(pid) = name
+                .split_once('-')
+                .and_then(|(pid, _)| pid.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if pid == std::process::id() || process_alive(pid) {
+                continue;
+            }
+            // 書き込み途中で終了したエントリ
+            if name.ends_with(".tmp") {
+                fs::remove_file(&path)?;
+                continue;
+            }
+            let record = fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok())
+                .and_then(|value| from_json(&value));
+            match record {
+                Some(record) => {
+                    if let Err(e) = store.insert(&record) {
+                        // DB に書けなければ次の起動でもう一度試す
+                        return Err(io::Error::other(e));
+                    }
+                    replayed += 1;
+                }
+                None => warn!("ジャーナルを読み込めません: {}", path.display()),
+            }
+            fs::remove_file(&path)?;
+        }
+        Ok(replayed)
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // 0 や負の値になる PID は kill ではプロセスグループを指すので使わない
+    if pid == 0 || pid > i32::MAX as u32 {
+        return false;
+    }
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+// 途中で終了しても壊れたエントリを残さないよう、一時ファイルに書いてから置き換える
+fn rewrite(path: &Path, record: &ExecutionRecord) -> io::Result<()> {
+    let partial = path.with_extension("tmp");
+    let mut file = fs::File::create(&partial)?;
+    file.write_all(to_json(record).to_string().as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&partial, path)
+}
+
+fn to_json(record: &ExecutionRecord) -> Value {
+    json!({
+        "file_path": record.file_path,
+        "section": record.section,
+        "success": record.success,
+        "exit_code": record.exit_code,
+        "duration_ms": record.duration_ms,
+        "executed_at": record.executed_at,
+        "output_preview": record.output_preview,
+        "tool_version": record.environment.tool_version,
+        "os": record.environment.os,
+        "app_version": record.environment.app_version,
+        "todos_remaining": record.todos_remaining,
+        "score": record.score,
+        "content_hash": record.content_hash,
+        "error_signature": record.error_signature,
+        "language": record.language,
+        "coverage": record.coverage,
+    })
+}
+
+fn from_json(value: &Value) -> Option<ExecutionRecord> {
+    let text = |key: &str| value[key].as_str().map(str::to_string);
+    Some(ExecutionRecord {
+        id: 0,
+        file_path: text("file_path")?,
+        section: text("section")?,
+        success: value["success"].as_bool()?,
+        exit_code: value["exit_code"].as_i64().map(|code| code as i32),
+        duration_ms: value["duration_ms"].as_i64()?,
+        executed_at: value["executed_at"].as_i64()?,
+        output_preview: text("output_preview")?,
+        environment: Environment {
+            tool_version: text("tool_version"),
+            os: text("os")?,
+            app_version: text("app_version")?,
+        },
+        note: None,
+        todos_remaining: value["todos_remaining"].as_i64(),
+        score: value["score"].as_i64(),
+        content_hash: text("content_hash"),
+        error_signature: text("error_signature"),
+        language: text("language"),
+        coverage: value["coverage"].as_f64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_entries_left_by_finished_process() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal::new(tmp.path().join("journal"));
+        let record = ExecutionRecord {
+            id: 0,
+            file_path: "/work/a.go".to_string(),
+            section: "section1-basics".to_string(),
+            success: false,
+            exit_code: Some(1),
+            duration_ms: 120,
+            executed_at: 100,
+            output_preview: "panic: boom".to_string(),
+            environment: Environment {
+                tool_version: None,
+                os: "linux x86_64".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: None,
+            todos_remaining: Some(2),
+            score: Some(50),
+            content_hash: Some("abc".to_string()),
+            error_signature: None,
+            language: Some("Go".to_string()),
+            coverage: None,
+        };
+        // 採点の前に書き、点数が分かったら書き直す
+        let entry = journal
+            .write(&ExecutionRecord {
+                score: None,
+                ..record.clone()
+            })
+            .unwrap();
+        journal.rewrite(&entry, &record).unwrap();
+        let store = HistoryStore::open_in_memory().unwrap();
+
+        // 自分が書いたエントリはまだ保存中
+        assert_eq!(journal.replay(&store).unwrap(), 0);
+
+        // 終了したプロセスが残したエントリ
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let finished = child.id();
+        child.wait().unwrap();
+        let dir = tmp.path().join("journal");
+        fs::rename(&entry, dir.join(format!("{}-0.json", finished))).unwrap();
+        fs::write(dir.join(format!("{}-1.tmp", finished)), "{").unwrap();
+
+        assert_eq!(journal.replay(&store).unwrap(), 1);
+        let records = store.recent(10).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0],
+            ExecutionRecord {
+                id: records[0].id,
+                ..record
+            }
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::runner::RunOutcome;
+use std::path::Path;
+
+// grade コマンドの結果 (実行しなかった問題は outcome が None)
+pub struct GradedProblem {
+    pub path: String,
+    pub outcome: Option<RunOutcome>,
+}
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// CI の画面で表示できる JUnit XML (問題1つを1つの testcase にする)
+pub fn junit_xml(suite: &str, problems: &[GradedProblem]) -> String {
+    let failures = problems
+        .iter()
+        .filter(|p| p.outcome.as_ref().is_some_and(|o| !o.success))
+        .count();
+    let skipped = problems.iter().filter(|p| p.outcome.is_none()).count();
+    let time: f64 = problems
+        .iter()
+        .filter_map(|p| p.outcome.as_ref())
+        .map(|o| o.duration.as_secs_f64())
+        .sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites>\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        escape_xml(suite),
+        problems.len(),
+        failures,
+        skipped,
+        time
+    ));
+    for problem in problems {
+        // セクション (親ディレクトリ) を classname にする
+        let classname = Path::new(&problem.path)
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let open = format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape_xml(&problem.path),
+            escape_xml(&classname),
+            problem
+                .outcome
+                .as_ref()
+                .map_or(0.0, |o| o.duration.as_secs_f64())
+        );
+        match &problem.outcome {
+            Some(outcome) if outcome.success => xml.push_str(&format!("{} />\n", open)),
+            Some(outcome) => xml.push_str(&format!(
+                "{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                open,
+                escape_xml(outcome.output.lines().next().unwrap_or("失敗")),
+                escape_xml(&outcome.output)
+            )),
+            None => xml.push_str(&format!("{}>\n      <skipped />\n    </testcase>\n", open)),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+// GitHub Actions のワークフローコマンドでは % と改行をエスケープする
+fn escape_annotation(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+// file= や title= の値では , と : もエスケープする
+fn escape_property(text: &str) -> String {
+    escape_annotation(text)
+        .replace(',', "%2C")
+        .replace(':', "%3A")
+}
+
+// 失敗した問題ごとの GitHub Actions のエラー注釈
+pub fn github_annotations(problems: &[GradedProblem]) -> Vec<String> {
+    problems
+        .iter()
+        .filter_map(|problem| {
+            let outcome = problem.outcome.as_ref().filter(|o| !o.success)?;
+            Some(format!(
+                "::error file={},title={}::{}",
+                escape_property(&problem.path),
+                escape_property("採点: 失敗"),
+                escape_annotation(outcome.output.trim())
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn graded(path: &str, success: Option<bool>, output: &str) -> GradedProblem {
+        GradedProblem {
+            path: path.to_string(),
+            outcome: success.map(|success| RunOutcome {
+                path: PathBuf::from(path),
+                success,
+                duration: Duration::from_millis(250),
+                output: output.to_string(),
+                score: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let problems = [
+            graded("section1/a.go", Some(true), "ok"),
+            graded(
+                "section1/b.go",
+                Some(false),
+                "b.go:3: x < y & \"z\"\nexit status 1",
+            ),
+            graded("section2/c.py", None, ""),
+        ];
+
+        let xml = junit_xml("hw1", &problems);
+
+        assert!(xml.contains(
+            "<testsuite name=\"hw1\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"0.500\">"
+        ));
+        assert!(
+            xml.contains(
+                "<testcase name=\"section1/a.go\" classname=\"section1\" time=\"0.250\" />"
+            )
+        );
+        assert!(xml.contains("<failure message=\"b.go:3: x &lt; y &amp; &quot;z&quot;\">"));
+        assert!(xml.contains("<skipped />"));
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        let problems = [
+            graded("a.go", Some(true), "ok"),
+            graded("b.go", Some(false), "100% wrong\nexit status 1\n"),
+        ];
+
+        assert_eq!(
+            github_annotations(&problems),
+            ["::error file=b.go,title=採点%3A 失敗::100%25 wrong%0Aexit status 1"]
+        );
+    }
+}
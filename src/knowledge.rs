@@ -0,0 +1,137 @@
+use include_dir::{Dir, include_dir};
+use std::sync::LazyLock;
+
+// オフラインで使えるヒント集 (エラーメッセージの一部とトピックで引く)
+// 各ファイルの先頭に次の形式で検索用の情報を書く
+//   ---
+//   patterns: nil pointer dereference, invalid memory address
+//   topics: Pointer Basics, Pointer Usage
+//   ---
+static KNOWLEDGE_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/knowledge");
+
+static ENTRIES: LazyLock<Vec<Entry>> = LazyLock::new(|| {
+    KNOWLEDGE_DIR
+        .files()
+        .filter_map(|file| Entry::parse(file.contents_utf8()?))
+        .collect()
+});
+
+// 一度に表示する最大件数
+const MAX_RESULTS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub title: String,
+    // エラーメッセージに含まれる文字列 (大文字小文字は区別しない)
+    pub patterns: Vec<String>,
+    pub topics: Vec<String>,
+    // 前置きを除いた Markdown 本文
+    pub body: String,
+}
+
+impl Entry {
+    fn parse(text: &str) -> Option<Self> {
+        let rest = text.strip_prefix("---\n")?;
+        let (front, body) = rest.split_once("\n---\n")?;
+
+        let field = |name: &str| -> Vec<String> {
+            front
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let body = body.trim().to_string();
+        let title = body
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))?
+            .trim()
+            .to_string();
+        Some(Self {
+            title,
+            patterns: field("patterns"),
+            topics: field("topics"),
+            body,
+        })
+    }
+
+    fn pattern_hits(&self, text: &str) -> usize {
+        self.patterns
+            .iter()
+            .filter(|pattern| text.contains(&pattern.to_lowercase()))
+            .count()
+    }
+}
+
+pub fn entries() -> &'static [Entry] {
+    &ENTRIES
+}
+
+// エラーメッセージに一致するものを優先し、なければトピックで探す
+pub fn lookup(text: &str, topic: Option<&str>) -> Vec<&'static Entry> {
+    let text = text.to_lowercase();
+    let mut matched: Vec<(usize, &Entry)> = entries()
+        .iter()
+        .map(|entry| (entry.pattern_hits(&text), entry))
+        .filter(|(hits, _)| *hits > 0)
+        .collect();
+    matched.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.title.cmp(&b.1.title)));
+
+    if matched.is_empty()
+        && let Some(topic) = topic
+    {
+        return entries()
+            .iter()
+            .filter(|entry| entry.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)))
+            .take(MAX_RESULTS)
+            .collect();
+    }
+
+    matched
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+// エラーメッセージだけで探す (実行失敗時の一言ヒント用)
+pub fn lookup_error(output: &str) -> Option<&'static Entry> {
+    lookup(output, None).into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_entries_have_front_matter() {
+        assert_eq!(entries().len(), KNOWLEDGE_DIR.files().count());
+        assert!(entries().iter().all(|entry| !entry.patterns.is_empty()));
+    }
+
+    #[test]
+    fn test_lookup_by_error_signature() {
+        let output = "panic: runtime error: invalid memory address or nil pointer dereference\n[signal SIGSEGV]";
+
+        let entry = lookup_error(output).unwrap();
+
+        assert_eq!(entry.title, "nil ポインタの参照 (Go)");
+        assert!(entry.body.contains("new(T)"));
+    }
+
+    #[test]
+    fn test_lookup_falls_back_to_topic() {
+        let entries = lookup("どう書けばいい?", Some("maps"));
+
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].title.contains("マップ"));
+        assert!(lookup("no match", None).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// 問題へのリンク (例: learnapp://open/section3/problem05)
+pub const SCHEME: &str = "learnapp://";
+
+// "section3/problem05" または learnapp:// のリンクを (セクション, 問題) に分ける
+pub fn parse(target: &str) -> Option<(String, String)> {
+    let target = match target.strip_prefix(SCHEME) {
+        Some(rest) => rest.strip_prefix("open/").unwrap_or(rest),
+        None => target,
+    };
+    let (section, problem) = target.trim_matches('/').split_once('/')?;
+    if section.is_empty() || problem.is_empty() || problem.contains('/') {
+        return None;
+    }
+    Some((section.to_string(), problem.to_string()))
+}
+
+// "section3" は "section3" と "section3-functions" に一致する ("section30-..." には一致しない)
+pub fn matches_short_name(name: &str, short: &str, separator: char) -> bool {
+    name == short
+        || name
+            .strip_prefix(short)
+            .is_some_and(|rest| rest.starts_with(separator))
+}
+
+// dir 直下のセクションの短い名前に一致するディレクトリ
+pub fn section_dir(dir: &Path, section: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|name| matches_short_name(name, section, '-'))
+        })
+}
+
+// dir 以下からセクションと問題の短い名前に一致するファイルを探す
+pub fn resolve(dir: &Path, section: &str, problem: &str) -> Option<PathBuf> {
+    let section_dir = section_dir(dir, section)?;
+    let mut files: Vec<PathBuf> = std::fs::read_dir(section_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|stem| matches_short_name(stem, problem, '_'))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().next()
+}
+
+// 問題の短い名前 (例: section3/problem05、セクションのディレクトリにない場合は None)
+pub fn id_for(path: &Path) -> Option<String> {
+    let section = path.parent()?.file_name()?.to_str()?;
+    if !section.starts_with("section") {
+        return None;
+    }
+    let problem = path.file_stem()?.to_str()?;
+    let short =
+        |name: &str, separator: char| name.split(separator).next().unwrap_or(name).to_string();
+    Some(format!("{}/{}", short(section, '-'), short(problem, '_')))
+}
+
+// 問題ファイルへのリンク
+pub fn url_for(path: &Path) -> Option<String> {
+    Some(format!("{}open/{}", SCHEME, id_for(path)?))
+}
+
+// $VISUAL か $EDITOR (設定されていなければ None)
+fn editor() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+fn editor_not_set() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "$EDITOR (または $VISUAL) が設定されていません",
+    )
+}
+
+// $VISUAL か $EDITOR でファイルを開く ("code -w" のように引数があってもよい)
+pub fn launch_editor(path: &Path) -> io::Result<()> {
+    let editor = editor().ok_or_else(editor_not_set)?;
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let status = Command::new(program).args(parts).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} が失敗しました ({})",
+            program, status
+        )));
+    }
+    Ok(())
+}
+
+// エディタごとにファイルの指定した行を開く引数
+pub fn jump_args(program: &str, file: &Path, line: u32, column: Option<u32>) -> Vec<String> {
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(program)
+        .to_lowercase();
+    let file = file.display().to_string();
+    let position = match column {
+        Some(column) => format!("{}:{}:{}", file, line, column),
+        None => format!("{}:{}", file, line),
+    };
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => vec!["-g".to_string(), position],
+        "subl" | "zed" | "hx" => vec![position],
+        "idea" | "idea64" | "goland" | "pycharm" => {
+            vec!["--line".to_string(), line.to_string(), file]
+        }
+        // vi / vim / nvim / emacs / nano / micro など
+        _ => vec![format!("+{}", line), file],
+    }
+}
+
+// エラーの行をエディタで開く (終了は待たない、端末で動くエディタより GUI のエディタ向け)
+pub fn jump_to(path: &Path, line: u32, column: Option<u32>) -> io::Result<()> {
+    let editor = editor().ok_or_else(editor_not_set)?;
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    tokio::process::Command::new(program)
+        .args(parts)
+        .args(jump_args(program, path, line, column))
+        .spawn()?;
+    Ok(())
+}
+
+// ファイルの file:// URL
+pub fn file_url(path: &Path) -> String {
+    let path = path.display().to_string().replace('\\', "/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{}", path)
+    };
+    format!("file://{}", path.replace('%', "%25").replace(' ', "%20"))
+}
+
+// 対応している端末ではクリックで開ける OSC 8 のハイパーリンク
+pub fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        let expected = Some(("section3".to_string(), "problem05".to_string()));
+
+        assert_eq!(parse("section3/problem05"), expected);
+        assert_eq!(parse("learnapp://open/section3/problem05"), expected);
+        assert_eq!(parse("learnapp://section3/problem05/"), expected);
+        assert_eq!(parse("section3"), None);
+        assert_eq!(parse("a/b/c"), None);
+    }
+
+    #[test]
+    fn test_resolve_and_url_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        for section in ["section3-functions", "section30-extra"] {
+            std::fs::create_dir(tmp.path().join(section)).unwrap();
+        }
+        let closures = tmp.path().join("section3-functions/problem05_closures.go");
+        std::fs::write(&closures, "").unwrap();
+        std::fs::write(tmp.path().join("section30-extra/problem05_x.go"), "").unwrap();
+
+        assert_eq!(
+            resolve(tmp.path(), "section3", "problem05"),
+            Some(closures.clone())
+        );
+        assert_eq!(resolve(tmp.path(), "section3", "problem5"), None);
+        assert_eq!(
+            url_for(&closures).as_deref(),
+            Some("learnapp://open/section3/problem05")
+        );
+        assert_eq!(url_for(Path::new("/work/a.go")), None);
+    }
+
+    #[test]
+    fn test_jump_args_per_editor() {
+        let file = Path::new("/work/a.go");
+
+        assert_eq!(
+            jump_args("code", file, 12, Some(3)),
+            ["-g", "/work/a.go:12:3"]
+        );
+        assert_eq!(
+            jump_args("/usr/bin/nvim", file, 12, None),
+            ["+12", "/work/a.go"]
+        );
+        assert_eq!(
+            jump_args("goland", file, 12, None),
+            ["--line", "12", "/work/a.go"]
+        );
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        assert_eq!(
+            file_url(Path::new("/work/my dir/a.go")),
+            "file:///work/my%20dir/a.go"
+        );
+        assert_eq!(
+            hyperlink("file:///a.go", "a.go:3"),
+            "\x1b]8;;file:///a.go\x1b\\a.go:3\x1b]8;;\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::{Value, json};
+use std::error::Error;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+// LLM の呼び出しは利用者が環境変数で設定したときだけ行う (オプトイン)
+//   LEARN_LLM_ENDPOINT: OpenAI 互換の Chat Completions API の URL
+//   LEARN_LLM_API_KEY:  API キー (ローカルのサーバーなら省略可)
+//   LEARN_LLM_MODEL:    モデル名
+const ENDPOINT_VAR: &str = "LEARN_LLM_ENDPOINT";
+const API_KEY_VAR: &str = "LEARN_LLM_API_KEY";
+const MODEL_VAR: &str = "LEARN_LLM_MODEL";
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+pub type LlmResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmConfig {
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub model: String,
+}
+
+impl LlmConfig {
+    // 設定されていなければ None
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Some(Self {
+            endpoint: var(ENDPOINT_VAR)?,
+            api_key: var(API_KEY_VAR),
+            model: var(MODEL_VAR)?,
+        })
+    }
+
+    pub fn not_configured_message() -> String {
+        format!(
+            "LLM が設定されていません ({} と {} を設定してください)",
+            ENDPOINT_VAR, MODEL_VAR
+        )
+    }
+}
+
+// 1回の問い合わせで回答全体を受け取る (同期処理なので spawn_blocking から呼ぶ)
+pub fn complete(config: &LlmConfig, system: &str, user: &str) -> LlmResult<String> {
+    let response: Value = request(config, system, user, false)?
+        .body_mut()
+        .read_json()?;
+    message_content(&response).ok_or_else(|| "LLM の応答を解釈できません".into())
+}
+
+// 回答を少しずつ受け取り、受け取るたびに on_chunk を呼ぶ (戻り値は回答全体)
+pub fn stream(
+    config: &LlmConfig,
+    system: &str,
+    user: &str,
+    mut on_chunk: impl FnMut(&str),
+) -> LlmResult<String> {
+    let mut response = request(config, system, user, true)?;
+    let reader = BufReader::new(response.body_mut().as_reader());
+    let mut answer = String::new();
+
+    // Server-Sent Events: "data: {...}" の行が届き、"data: [DONE]" で終わる
+    for line in reader.lines() {
+        let line = line?;
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            continue;
+        };
+        if let Some(chunk) = event["choices"][0]["delta"]["content"].as_str() {
+            on_chunk(chunk);
+            answer.push_str(chunk);
+        }
+    }
+    Ok(answer)
+}
+
+fn request(
+    config: &LlmConfig,
+    system: &str,
+    user: &str,
+    stream: bool,
+) -> LlmResult<ureq::http::Response<ureq::Body>> {
+    let mut request = ureq::post(&config.endpoint)
+        .config()
+        .timeout_global(Some(TIMEOUT))
+        .build();
+    if let Some(api_key) = &config.api_key {
+        request = request.header("Authorization", format!("Bearer {}", api_key));
+    }
+    let response = request.send_json(json!({
+        "model": config.model,
+        "stream": stream,
+        "messages": [
+            {"role": "system", "content": system},
+            {"role": "user", "content": user},
+        ],
+    }))?;
+    Ok(response)
+}
+
+fn message_content(response: &Value) -> Option<String> {
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_content() {
+        let response = json!({
+            "choices": [{"message": {"role": "assistant", "content": "looks good"}}]
+        });
+
+        assert_eq!(message_content(&response).as_deref(), Some("looks good"));
+        assert_eq!(message_content(&json!({"error": "bad request"})), None);
+    }
+}
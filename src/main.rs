@@ -1,250 +1,781 @@
-use clap::Parser;
-use log::{error, info};
-use notify::{Event, EventKind, RecursiveMode, Result, Watcher};
-use std::collections::HashMap;
-use std::env;
-use std::path::PathBuf;
-use std::sync::mpsc;
-use std::time::{Duration, Instant};
-use tokio::process::Command;
-use which::which;
-
+mod anki;
+mod api;
+mod ask;
+mod assets;
+mod availability;
+mod backup;
+mod badge;
+mod cases;
+mod charts;
+mod chat;
+mod classroom;
+mod clipboard;
+mod commands;
+mod concurrency;
+mod config;
+mod coverage;
+mod data_dir;
+mod deploy;
+mod directive;
+mod dispatch;
+mod docs;
+mod doctor;
+mod dom;
+mod environment;
+mod events;
+mod exam;
+mod file_watcher;
+mod files;
+mod goal;
+mod grader;
+mod handler;
+mod history;
+mod hotkey;
+mod instance;
+mod journal;
+mod junit;
+mod knowledge;
+mod link;
+mod llm;
+mod mastery;
+mod metrics;
+mod mutation;
+mod normalize;
+mod notebook;
+mod outbox;
+mod privacy;
+mod problem;
+mod project;
+mod property;
+mod query;
+mod reflect;
+mod remote;
+mod report;
+mod rerun;
+mod review;
+mod runner;
+mod sandbox;
+mod search;
+mod session;
+mod similarity;
+mod snapshot;
+mod solve_time;
+mod sound;
+mod stacktrace;
+mod story;
+mod submit;
+mod summary;
+mod syntax;
+mod toolchain;
+mod upgrade;
+mod vscode;
+mod xp;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use clipboard::CopyTarget;
+use commands::config::{ConfigCommand, HandlersCommand};
+use commands::db::DbCommand;
+use commands::exam::ExamCommand;
+use commands::history::HistoryCommand;
+use commands::integrations::IntegrationsCommand;
+use commands::outbox::OutboxCommand;
+use commands::privacy::PrivacyCommand;
+use commands::report::ReportCommand;
+use commands::stats::StatsCommand;
+use commands::teacher::TeacherCommand;
+use commands::{assist, curriculum, db, grade, integrations, problems, stats, teacher, watch};
+use data_dir::DataDir;
+use log::error;
+use notify::Result;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// 問題ファイルを監視して、保存するたびに実行・採点する
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    dir: String,
-}
+    /// 監視するディレクトリ
+    #[arg(short, long, required = true)]
+    dir: Option<String>,
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // ログ設定
-    tracing_subscriber::fmt::init();
+    /// DB・ログの保存先 (未指定ならOS標準のデータディレクトリ)
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
 
-    if which("mise").is_err() {
-        error!("miseコマンドが見つかりません(必要な実行環境がインストールされていません)",);
-        std::process::exit(1);
-    }
+    /// 終了時に実行回数・失敗したファイル・学習時間を JSON で書き出す (監視と grade コマンド)
+    #[arg(long, global = true, value_name = "PATH")]
+    summary: Option<PathBuf>,
 
-    let args = Args::parse();
-    // 監視対象ディレクトリ
-    let watch_dir = PathBuf::from(&args.dir);
+    /// 実行するテスト名 (go test -run / pytest file::name)
+    #[arg(long)]
+    target: Option<String>,
 
-    let os_type = env::consts::OS;
+    /// テスト実行時にカバレッジを計測する (Pythonは pytest-cov が必要)
+    #[arg(long)]
+    coverage: bool,
 
-    // ディレクトリ存在確認
-    if !watch_dir.is_dir() {
-        error!("ディレクトリが存在しません: {}", watch_dir.display());
-        std::process::exit(1);
-    }
+    /// 成功した解答の行数・複雑度を表示する (learn:reference の模範解答と比較する)
+    #[arg(long)]
+    complexity: bool,
 
-    // イベントを受け取るチャンネル
-    let (tx, rx) = mpsc::channel::<Result<Event>>();
-    let mut watcher = notify::recommended_watcher(tx)?;
-    watcher.watch(&watch_dir, RecursiveMode::Recursive)?;
-
-    info!("監視を開始: {}", watch_dir.display());
-
-    let mut last_modified: HashMap<PathBuf, Instant> = HashMap::new();
-    let debounce_duration = Duration::from_millis(300);
-
-    for res in rx {
-        match res {
-            Ok(event) => {
-                for path in event.paths {
-                    if !path.is_file() {
-                        continue;
-                    }
-
-                    let now = Instant::now();
-                    let entry = last_modified.entry(path.clone()).or_insert(now);
-                    if now.duration_since(*entry) < debounce_duration {
-                        continue;
-                    }
-                    *entry = now;
-
-                    // windows: event.kind=Modify(Any)
-                    // Linux:   event.kind=Access(Open(Any))
-                    println!("event.kind={:?}, path={}", event.kind, path.display());
-
-                    match os_type {
-                        "linux" => {
-                            if let EventKind::Access(_) = event.kind {
-                                tokio::spawn(run_if_target_file(path));
-                            }
-                        }
-                        "windows" => {
-                            if let EventKind::Modify(_) = event.kind {
-                                tokio::spawn(run_if_target_file(path));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            Err(e) => error!("watch error: {:?}", e),
-        }
-    }
+    /// 成功した解答を LLM にレビューしてもらう (LEARN_LLM_* の設定が必要)
+    #[arg(long)]
+    review: bool,
 
-    Ok(())
-}
+    /// 失敗した直後の再実行をこの秒数待たせる (省略時 30秒、コードが実質的に変わっていればすぐ実行する)
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "30")]
+    reflect: Option<u64>,
 
-async fn run_if_target_file(path: PathBuf) {
-    let target_extensions = ["go", "py", "lua"];
+    /// 実行結果の効果音を鳴らさない
+    #[arg(long)]
+    silent: bool,
 
-    let extension = match path.extension().and_then(|s| s.to_str()) {
-        Some(ext) => ext,
-        None => {
-            error!("拡張子がありません: {}", path.display());
-            return;
-        }
-    };
+    /// 効果音の音量 (0 - 100、0 で消音)
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+    volume: u8,
 
-    if !target_extensions.contains(&extension) {
-        return;
-    }
+    /// 成功時に鳴らす音声ファイル (省略時は内蔵の効果音)
+    #[arg(long, value_name = "FILE")]
+    success_sound: Option<PathBuf>,
 
-    let command_name = match extension {
-        "go" => "go",
-        "py" => "python",
-        "lua" => "lua",
-        _ => return,
-    };
+    /// 失敗時に鳴らす音声ファイル (省略時は内蔵の効果音)
+    #[arg(long, value_name = "FILE")]
+    failure_sound: Option<PathBuf>,
 
-    if which(command_name).is_err() {
-        error!(
-            "コマンドが見つかりません: {} (必要な実行環境がインストールされていません)",
-            command_name
-        );
-        return;
-    }
+    /// 保存ごとの変更を記録する (playback コマンドで再生できる)
+    #[arg(long)]
+    snapshots: bool,
 
-    let mut command;
-
-    if extension == "go" {
-        // 実行環境存在チェック
-        command = Command::new("go");
-        command.arg("run").arg(&path);
-    } else if extension == "py" {
-        // 実行環境存在チェック
-        command = Command::new("python");
-        command.arg(&path);
-    } else {
-        return;
-    }
+    /// 実行履歴に残す出力の先頭部分の文字数
+    #[arg(long, default_value_t = history::PREVIEW_CHARS, value_name = "CHARS")]
+    preview_chars: usize,
 
-    println!("実行中: {}", path.display());
-
-    match command.output().await {
-        Ok(output) => {
-            if output.status.success() {
-                println!("✅ 成功: {}", path.display());
-                println!("=== 実行結果 ===============\n");
-                println!("{}", String::from_utf8_lossy(&output.stdout));
-                println!("\n===========================\n");
-            } else {
-                eprintln!("❌ 失敗: {}", path.display());
-                eprintln!("=== エラー ===============\n");
-                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
-                eprintln!("\n===========================\n");
-            }
-        }
-        Err(e) => eprintln!("実行エラー: {:?} ({})", e, path.display()),
-    }
-}
+    /// 出力の全文も圧縮して実行履歴に保存する (history show <番号> --full で表示できる)
+    #[arg(long)]
+    store_output: bool,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-    use tempfile::NamedTempFile;
+    /// この秒数以上ファイルイベントがなければ離席とみなす (セッションも自動で一時停止する)
+    #[arg(long, default_value_t = 300)]
+    idle_timeout: u64,
 
-    // 簡易ログを無効化する
-    fn init_logger() {
-        let _ = env_logger::builder().is_test(true).try_init();
-    }
+    /// go run / go test に付けるフラグ (例: "-race"、ファイル中の learn:flags が優先)
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    go_flags: Option<String>,
 
-    #[tokio::test]
-    async fn test_run_if_target_file_with_py_file() {
-        init_logger();
+    /// python に付けるオプション (例: "-X dev"、ファイル中の learn:flags が優先)
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    python_args: Option<String>,
 
-        // 一時Pythonファイル作成
-        let mut tmpfile = NamedTempFile::new().unwrap();
-        writeln!(tmpfile, "print('hello test')").unwrap();
-        let path = tmpfile.path().to_path_buf();
+    /// 失敗したらエラーの行を $VISUAL / $EDITOR で開く (code -g file:12, nvim +12 file など)
+    #[arg(long)]
+    jump_to_error: bool,
 
-        // 実行
-        run_if_target_file(path.clone()).await;
+    /// エラーの発生箇所をクリックで開けるリンク (OSC 8) で表示する
+    #[arg(long)]
+    hyperlinks: bool,
 
-        // ファイルはまだ存在するはず
-        assert!(path.exists());
-    }
+    /// 監視中に新しく作られた問題ファイルをすぐに実行する
+    #[arg(long)]
+    run_new: bool,
 
-    #[tokio::test]
-    async fn test_run_if_target_file_with_go_file() {
-        init_logger();
+    /// OS のファイル監視の代わりにポーリングで変更を検出する (NFS・SMB・一部のコンテナ向け)
+    #[arg(long)]
+    poll: bool,
 
-        // 一時Goファイル作成
-        let mut tmpfile = NamedTempFile::new().unwrap();
-        writeln!(
-            tmpfile,
-            "package main\nimport \"fmt\"\nfunc main() {{ fmt.Println(\"hello go test\") }}"
-        )
-        .unwrap();
-        let path = tmpfile.path().to_path_buf();
+    /// ポーリングの間隔 (ミリ秒)
+    #[arg(long, default_value_t = 1000, value_name = "MILLIS")]
+    poll_interval: u64,
 
-        run_if_target_file(path.clone()).await;
+    /// 同時に実行するファイル数
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u16).range(1..))]
+    workers: u16,
 
-        assert!(path.exists());
-    }
+    /// 実行待ちにできるファイル数 (超えたイベントは捨てる)
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u16).range(1..))]
+    queue_size: u16,
 
-    #[tokio::test]
-    async fn test_run_if_target_file_with_unsupported_extension() {
-        init_logger();
+    /// 同じディレクトリを監視中のプロセスを終了させて置き換える
+    #[arg(long)]
+    takeover: bool,
 
-        let mut tmpfile = NamedTempFile::new().unwrap();
-        writeln!(tmpfile, "echo unsupported").unwrap();
+    /// どのウィンドウにいても押せるショートカットキーで最後のファイルを再実行する (例: ctrl+alt+r、Linux の X11 のみ)
+    #[arg(long, value_name = "KEYS")]
+    hotkey: Option<String>,
 
-        // 一時ファイル名を.txtに変更
-        let path = tmpfile.path().with_extension("txt");
+    /// 保存したファイルを rsync で送り、ssh でリモートのマシンで実行する
+    #[arg(long, value_name = "USER@HOST:PATH")]
+    remote: Option<remote::Remote>,
 
-        // 実行（何も起きない）
-        run_if_target_file(path.clone()).await;
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
 
-        // 実行してもエラーにもならない（ただreturn）
-        assert!(path.exists() || !path.exists()); // 実行確認用ダミー
-    }
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// 問題ファイルのトピックからAnki用のカード(TSV)を書き出す
+    Anki {
+        /// 問題ファイルのあるディレクトリ
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// 出力先 (省略時は標準出力)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// バイナリに埋め込まれたGoのカリキュラムを書き出す
+    Init {
+        /// 書き出し先ディレクトリ
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// 既存のファイルも上書きする
+        #[arg(long)]
+        force: bool,
+    },
+    /// 同じ問題の提出ファイル同士の類似度を調べる
+    Similarity {
+        /// 提出物のディレクトリ (例: submissions/<学生名>/...)
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// 表示する類似度の下限 (0.0 - 1.0)
+        #[arg(long, default_value_t = 0.5)]
+        min_score: f64,
+    },
+    /// 講師向けの集計 (学生ごとの履歴DBからクラス全体のレポートを作る)
+    Teacher {
+        #[command(subcommand)]
+        action: TeacherCommand,
+    },
+    /// 実行環境と問題ごとのバージョン要件 (learn:requires) を確認する
+    Doctor {
+        /// 要件を確認する問題のディレクトリ
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+
+        /// 実行コマンドを探し直す (インストールした直後に、監視中のプロセスにも知らせる)
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// 実行履歴を新しい順に表示する
+    History {
+        /// 表示する件数
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+
+        /// この位置より古い記録を表示する (表示の最後に出る「続き」の位置)
+        #[arg(long)]
+        before: Option<history::Cursor>,
+
+        #[command(subcommand)]
+        action: Option<HistoryCommand>,
+    },
+    /// 時間制限付きの試験 (exam start <課題ディレクトリ> で開始、exam finish で終了)
+    Exam {
+        #[command(subcommand)]
+        action: ExamCommand,
+    },
+    /// 履歴DBのバックアップと復元
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+    /// 学校のデータ保持の方針に合わせて、匿名化した履歴を書き出す・コードや出力の内容を消す
+    Privacy {
+        #[command(subcommand)]
+        action: PrivacyCommand,
+    },
+    /// 送信待ちのレビューなどを表示する (省略時は一覧)
+    Outbox {
+        #[command(subcommand)]
+        action: Option<OutboxCommand>,
+    },
+    /// 学習のまとめを書き出す (メールでは送らない)
+    Report {
+        #[command(subcommand)]
+        action: ReportCommand,
+    },
+    /// --snapshots で記録した解答の変化を順に再生する
+    Playback {
+        /// 問題ファイル
+        problem: PathBuf,
+
+        /// 1回の変更ごとに待つミリ秒
+        #[arg(long, default_value_t = 500)]
+        delay: u64,
+
+        /// 変更箇所ではなく各時点のファイル全体を表示する
+        #[arg(long)]
+        full: bool,
+    },
+    /// 問題に取り組んだ経過 (保存・実行・メモ・解答時間) を Markdown にまとめる
+    Story {
+        /// 問題ファイル
+        problem: PathBuf,
+
+        /// 出力先 (省略時は標準出力)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 課題を提出する (採点レポートを作って履歴に記録する。--github なら push もする)
+    ///
+    /// 引数なしなら過去の提出を一覧表示する
+    Submit {
+        /// 課題のディレクトリ (GitHub Classroom のリポジトリ)
+        dir: Option<PathBuf>,
+
+        /// コミットして GitHub Classroom のリポジトリに push する (GITHUB_TOKEN があれば使う)
+        #[arg(long, requires = "dir")]
+        github: bool,
+
+        /// push 先のリモート
+        #[arg(long, default_value = "origin")]
+        remote: String,
+    },
+    /// 削除した問題ファイルを埋め込みのカリキュラムから作り直す
+    RestoreProblem {
+        /// "section3/problem05" または learnapp://open/section3/problem05
+        id: String,
+
+        /// 問題のあるディレクトリ
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// ファイルが残っていても元の問題で上書きする (解答は消える)
+        #[arg(long)]
+        force: bool,
+    },
+    /// カリキュラムが更新された問題に、新しい版の変更を解答を残したまま取り込む
+    UpgradeProblem {
+        /// "section3/problem05" または learnapp://open/section3/problem05
+        id: String,
+
+        /// 問題のあるディレクトリ
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// 問題をエディタ ($VISUAL / $EDITOR) で開く
+    Open {
+        /// "section3/problem05" または learnapp://open/section3/problem05
+        target: String,
+
+        /// 問題のあるディレクトリ
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// 直前の実行のエラー・出力・ファイルの内容をクリップボードにコピーする
+    Copy {
+        /// コピーするもの
+        #[arg(value_enum)]
+        what: CopyTarget,
+    },
+    /// 設定ファイル (データディレクトリの config.toml) と環境変数 LEARNAPP_* の設定
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// 拡張子ごとの実行方法 (組み込みと設定ファイルの [handlers])
+    Handlers {
+        #[command(subcommand)]
+        action: HandlersCommand,
+    },
+    /// エディタとの連携の設定を書き出す
+    Integrations {
+        #[command(subcommand)]
+        action: IntegrationsCommand,
+    },
+    /// 監視中のプロセスに最後に実行したファイルをもう一度実行させる (OS のショートカットキーに割り当てる用)
+    Rerun {
+        /// 監視しているディレクトリ (監視中のプロセスが1つだけなら省略できる)
+        dir: Option<PathBuf>,
+    },
+    /// 1つの問題ファイルだけを監視し、実行のたびに画面を消して問題文を上に表示する
+    Focus {
+        /// 問題ファイル
+        file: PathBuf,
+    },
+    /// ディレクトリ内の問題を1回ずつ実行して採点する (CI 向け、失敗があれば終了コード 1)
+    Grade {
+        /// 問題のあるディレクトリ
+        dir: PathBuf,
+
+        /// JUnit XML のレポートを書き出す
+        #[arg(long)]
+        junit: Option<PathBuf>,
+
+        /// 失敗した問題を GitHub Actions の注釈として出力する
+        #[arg(long)]
+        github_annotations: bool,
+    },
+    /// 解けた解答にバグを1つ入れてバグ探しの問題を作る (Go と Python)
+    Challenge {
+        /// 解けた問題ファイル
+        problem: PathBuf,
+
+        /// バグを入れた行を表示する
+        #[arg(long)]
+        hint: bool,
+    },
+    /// 問題を後回しにする (問題を省略すると保留中の問題を一覧表示する)
+    Skip {
+        /// 問題ファイル
+        problem: Option<PathBuf>,
+
+        /// 後回しにする理由
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// 保留を解除する
+        #[arg(long, requires = "problem")]
+        undo: bool,
+    },
+    /// 実行回数・成功率・学習時間と問題ごとの解答時間を表示する
+    Stats {
+        /// このタグが付いた問題の解答時間だけを表示する
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// この秒数以内に同じ内容のまま繰り返した実行を1回と数える (省略時 60秒)
+        #[arg(
+            long,
+            global = true,
+            value_name = "SECONDS",
+            num_args = 0..=1,
+            default_missing_value = "60"
+        )]
+        collapse_repeats: Option<u64>,
+
+        #[command(subcommand)]
+        action: Option<StatsCommand>,
+    },
+    /// 進み具合 (言語ごとに解き終えたセクション数、連続学習日数) を SVG バッジに書き出す
+    Badges {
+        /// 問題ファイルのあるディレクトリ
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// 書き出し先のディレクトリ
+        #[arg(short, long, default_value = "badges")]
+        out: PathBuf,
+    },
+    /// 問題のディレクトリを教室の PC や USB メモリに配る (user@host:/path または配り先のディレクトリ)
+    Deploy {
+        target: String,
+
+        /// 配る問題のディレクトリ
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+
+        /// データディレクトリの設定ファイルも配る (配り先の .learning-programming/config.toml)
+        #[arg(long)]
+        config: bool,
+    },
+    /// 問題を一覧表示する
+    Browse {
+        /// 問題ファイルのあるディレクトリ
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// このタグが付いた問題だけを表示する
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// 問題ファイルを全文検索する (例: search "type assertion" --dir example-go)
+    Search {
+        query: String,
+
+        /// 問題ファイルのあるディレクトリ (変更のあったファイルだけ索引し直す)
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// 表示する件数
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// 解答を LLM にレビューしてもらう (LEARN_LLM_* の設定が必要、同じ内容の結果はキャッシュする)
+    Review {
+        /// 解答ファイル
+        file: PathBuf,
+    },
+    /// 今取り組んでいる問題について LLM に質問する (LEARN_LLM_* の設定が必要)
+    Ask {
+        /// 質問 (省略すると過去の質問と回答を表示する)
+        question: Option<String>,
+
+        /// 対象の問題ファイル (省略時は最後に実行したファイル)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+    /// 直近のエラーと問題のトピックから、同梱のヒント集を表示する (オフラインで使える)
+    Hint {
+        /// 対象の問題ファイル (省略時は最後に実行したファイル)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+    /// 問題の内容・タグ・参考ドキュメントを表示する
+    Show {
+        /// 問題ファイル
+        problem: PathBuf,
+
+        /// learn:reference の模範解答も表示する (習熟度の記録に残る)
+        #[arg(long)]
+        solution: bool,
+    },
+    /// トピック (または問題ファイル) の参考ドキュメントをブラウザで開く
+    Docs {
+        /// トピック名 (例: "Goroutines") または問題ファイル
+        topic: String,
+
+        /// トピックの言語 (省略時は Go → Python の順に探す)
+        #[arg(long, value_enum)]
+        lang: Option<docs::Language>,
+
+        /// ブラウザで開かずにURLだけ表示する
+        #[arg(long)]
+        print: bool,
+    },
+    /// 問題にタグを付ける・外す (何も指定しなければ現在のタグを表示する)
+    Tag {
+        /// 問題ファイル
+        problem: PathBuf,
+
+        /// 追加するタグ (カンマ区切り)
+        #[arg(long, value_delimiter = ',')]
+        add: Vec<String>,
+
+        /// 外すタグ (カンマ区切り)
+        #[arg(long, value_delimiter = ',')]
+        remove: Vec<String>,
+    },
+    /// シェル補完スクリプトを標準出力に書き出す
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// manページ(roff形式)を標準出力に書き出す
+    Man,
+}
 
-    #[tokio::test]
-    async fn test_run_if_target_file_without_extension() {
-        init_logger();
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // ログ設定
+    let data_dir = DataDir::resolve(args.data_dir.as_deref());
+    init_logging(data_dir.as_ref().ok());
+
+    let data_dir = match data_dir {
+        Ok(data_dir) => data_dir,
+        Err(e) => {
+            error!("データディレクトリを作成できません: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let focus = match &args.command {
+        Some(Commands::Focus { file }) => Some(file.clone()),
+        Some(command) => {
+            if !kiosk_allows(command) && config::kiosk(&config::system_file()) {
+                error!(
+                    "キオスクモードでは使えません (監視・実行と問題の閲覧だけを使えます。解除は管理者が {} の kiosk を変更します)",
+                    config::system_file().display()
+                );
+                std::process::exit(1);
+            }
+            if let Err(e) = run_subcommand(command, &data_dir, args.summary.as_deref()).await {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => None,
+    };
 
-        // 一時ファイル名に拡張子なし
-        let tmpfile = NamedTempFile::new().unwrap();
-        let path = tmpfile.path().to_path_buf();
+    watch::watch(&args, &data_dir, focus).await
+}
 
-        // 実行
-        run_if_target_file(path.clone()).await;
+// キオスクモード (システムの設定ファイルの kiosk = true) でも使えるコマンド
+// 問題の生成・設定の変更・履歴の消去につながるものは使えない
+fn kiosk_allows(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Focus { .. }
+            | Commands::Rerun { .. }
+            | Commands::Grade { .. }
+            | Commands::Browse { .. }
+            | Commands::Search { .. }
+            | Commands::Show { .. }
+            | Commands::Open { .. }
+            | Commands::Hint { .. }
+            | Commands::Docs { .. }
+            | Commands::Doctor { .. }
+            | Commands::Handlers { .. }
+            | Commands::Completions { .. }
+            | Commands::Man
+            | Commands::Config {
+                action: ConfigCommand::Show { .. }
+                    | ConfigCommand::Get { .. }
+                    | ConfigCommand::Validate,
+            }
+    )
+}
 
-        // エラー出力が呼ばれるがクラッシュしない
-        assert!(path.exists());
+// サブコマンドを commands の各モジュールに振り分ける
+async fn run_subcommand(
+    command: &Commands,
+    data_dir: &DataDir,
+    summary: Option<&Path>,
+) -> commands::Result {
+    match command {
+        Commands::Anki { dir, output } => teacher::anki(dir, output.as_deref()),
+        Commands::Init { dir, force } => curriculum::init(dir, *force, data_dir),
+        Commands::Similarity { dir, min_score } => teacher::similarity(dir, *min_score),
+        Commands::Teacher { action } => teacher::teacher(action),
+        Commands::Doctor { dir, refresh } => {
+            commands::doctor::doctor(dir.as_deref(), *refresh, data_dir).await
+        }
+        Commands::History {
+            limit,
+            before,
+            action,
+        } => commands::history::history(*limit, *before, action.as_ref(), data_dir),
+        Commands::Privacy { action } => commands::privacy::privacy(action, data_dir),
+        Commands::Outbox { action } => commands::outbox::outbox(action.as_ref(), data_dir).await,
+        Commands::Report { action } => commands::report::report(action, data_dir),
+        Commands::Db { action } => db::db(action, data_dir),
+        Commands::Exam { action } => commands::exam::exam(action, data_dir),
+        Commands::Playback {
+            problem,
+            delay,
+            full,
+        } => commands::history::playback(problem, *delay, *full, data_dir).await,
+        Commands::Story { problem, output } => {
+            commands::history::story(problem, output.as_deref(), data_dir)
+        }
+        Commands::Submit {
+            dir,
+            github,
+            remote,
+        } => commands::submit::submit(dir.as_deref(), *github, remote, data_dir).await,
+        Commands::RestoreProblem { id, dir, force } => {
+            curriculum::restore_problem(id, dir, *force, data_dir)
+        }
+        Commands::UpgradeProblem { id, dir } => {
+            curriculum::upgrade_problem(id, dir, data_dir).await
+        }
+        Commands::Open { target, dir } => curriculum::open(target, dir),
+        Commands::Copy { what } => commands::history::copy(*what, data_dir),
+        // 監視として main で扱う
+        Commands::Focus { .. } => Ok(()),
+        Commands::Config { action } => commands::config::config(action, data_dir),
+        Commands::Handlers { action } => commands::config::handlers(action, data_dir),
+        Commands::Integrations { action } => integrations::integrations(action, data_dir).await,
+        Commands::Rerun { dir } => watch::rerun(dir.as_deref(), data_dir).await,
+        Commands::Grade {
+            dir,
+            junit,
+            github_annotations,
+        } => {
+            grade::grade(
+                dir,
+                junit.as_deref(),
+                *github_annotations,
+                summary,
+                data_dir,
+            )
+            .await
+        }
+        Commands::Challenge { problem, hint } => grade::challenge(problem, *hint, data_dir).await,
+        Commands::Skip {
+            problem,
+            reason,
+            undo,
+        } => problems::skip(problem.as_deref(), reason.as_deref(), *undo, data_dir),
+        Commands::Stats {
+            tag,
+            collapse_repeats,
+            action,
+        } => stats::stats(tag.as_deref(), *collapse_repeats, action.as_ref(), data_dir),
+        Commands::Deploy {
+            target,
+            dir,
+            config,
+        } => commands::deploy::deploy(target, dir, *config, data_dir).await,
+        Commands::Badges { dir, out } => problems::badges(dir, out, data_dir),
+        Commands::Browse { dir, tag } => problems::browse(dir, tag.as_deref(), data_dir),
+        Commands::Search { query, dir, limit } => problems::search(query, dir, *limit, data_dir),
+        Commands::Review { file } => assist::review(file, data_dir).await,
+        Commands::Ask { question, file } => {
+            assist::ask(question.as_deref(), file.as_deref(), data_dir).await
+        }
+        Commands::Hint { file } => assist::hint(file.as_deref(), data_dir),
+        Commands::Show { problem, solution } => problems::show(problem, *solution, data_dir),
+        Commands::Docs { topic, lang, print } => problems::docs(topic, *lang, *print),
+        Commands::Tag {
+            problem,
+            add,
+            remove,
+        } => problems::tag(problem, add, remove, data_dir),
+        Commands::Completions { shell } => {
+            let mut command = Args::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(*shell, &mut command, name, &mut std::io::stdout());
+            Ok(())
+        }
+        Commands::Man => {
+            clap_mangen::Man::new(Args::command()).render(&mut std::io::stdout())?;
+            Ok(())
+        }
     }
+}
 
-    #[tokio::test]
-    async fn test_run_if_target_file_command_not_found() {
-        init_logger();
+// 標準出力に加えて、データディレクトリ内のログファイルにも書き出す
+fn init_logging(data_dir: Option<&DataDir>) {
+    let file_layer = data_dir
+        .and_then(|data_dir| open_log_file(data_dir).ok())
+        .map(|file| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+        });
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(tracing_subscriber::fmt::layer())
+        .with(file_layer)
+        .init();
+}
 
-        // 存在しないコマンド (lua) を想定
-        let mut tmpfile = NamedTempFile::new().unwrap();
-        writeln!(tmpfile, "print('hi')").unwrap();
+fn open_log_file(data_dir: &DataDir) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.log_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Args::command().debug_assert();
+    }
 
-        // ".lua" の一時ファイルを実際に作成
-        let lua_path = tmpfile.path().with_extension("lua");
-        std::fs::copy(tmpfile.path(), &lua_path).unwrap();
+    #[test]
+    fn test_completions_include_subcommands() {
+        let mut command = Args::command();
+        let mut out = Vec::new();
 
-        // Lua が未インストール環境で実行しても panic せず return することを確認
-        run_if_target_file(lua_path.clone()).await;
+        clap_complete::generate(Shell::Bash, &mut command, "learning-programming", &mut out);
 
-        assert!(lua_path.exists());
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("completions"));
+        assert!(script.contains("--data-dir"));
     }
 }
@@ -0,0 +1,75 @@
+use crate::history::HintKind;
+
+// 解けた問題の習熟度 (どれだけ助けを借りたか)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mastery {
+    // 何も見ずに解いた
+    Independent,
+    // hint / ask を使って解いた
+    Hinted,
+    // 模範解答を見てから解いた
+    PeekedSolution,
+}
+
+impl Mastery {
+    pub fn classify(hints: &[HintKind]) -> Self {
+        if hints.contains(&HintKind::Solution) {
+            Mastery::PeekedSolution
+        } else if hints.is_empty() {
+            Mastery::Independent
+        } else {
+            Mastery::Hinted
+        }
+    }
+
+    // 習熟度スコアへの寄与 (0 - 100)
+    pub fn weight(self) -> u32 {
+        match self {
+            Mastery::Independent => 100,
+            Mastery::Hinted => 60,
+            Mastery::PeekedSolution => 20,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Mastery::Independent => "自力",
+            Mastery::Hinted => "ヒントあり",
+            Mastery::PeekedSolution => "解答参照",
+        }
+    }
+}
+
+// 解けた問題の習熟度の平均 (解けた問題がなければ None)
+pub fn score(masteries: &[Mastery]) -> Option<u32> {
+    if masteries.is_empty() {
+        return None;
+    }
+    let total: u32 = masteries.iter().map(|m| m.weight()).sum();
+    Some(total / masteries.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_score() {
+        let masteries = [
+            Mastery::classify(&[]),
+            Mastery::classify(&[HintKind::Hint, HintKind::Ask]),
+            Mastery::classify(&[HintKind::Ask, HintKind::Solution]),
+        ];
+
+        assert_eq!(
+            masteries,
+            [
+                Mastery::Independent,
+                Mastery::Hinted,
+                Mastery::PeekedSolution
+            ]
+        );
+        assert_eq!(score(&masteries), Some(60));
+        assert_eq!(score(&[]), None);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::syntax::{self, TokenKind};
+
+// 解答コードの簡単な指標
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    // 空行・コメント行を除いた行数
+    pub lines: usize,
+    // 循環的複雑度 (1 + 分岐の数)
+    pub complexity: usize,
+    // ブロックの最大の深さ (関数の本体を 0 とする)
+    pub max_nesting: usize,
+}
+
+// これを超えたら助言を出す
+const COMPLEXITY_LIMIT: usize = 10;
+const NESTING_LIMIT: usize = 3;
+// 模範解答の何倍の行数で助言を出すか
+const LINES_RATIO: usize = 2;
+
+const GO_BRANCHES: &[&str] = &["if", "for", "case"];
+const PYTHON_BRANCHES: &[&str] = &["if", "elif", "for", "while", "except", "case", "and", "or"];
+
+pub fn measure(source: &str, python: bool) -> Metrics {
+    let tokens = syntax::tokenize(source, python);
+
+    let mut lines: Vec<usize> = tokens.iter().map(|t| t.line).collect();
+    lines.dedup();
+
+    let branches = if python { PYTHON_BRANCHES } else { GO_BRANCHES };
+    let keywords = tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::Ident && branches.contains(&t.text.as_str()))
+        .count();
+    // Go の && と || は1文字ずつのトークンになる
+    let operators = tokens
+        .windows(2)
+        .filter(|pair| {
+            pair[0].kind == TokenKind::Punct
+                && pair[0].text == pair[1].text
+                && (pair[0].text == "&" || pair[0].text == "|")
+        })
+        .count();
+
+    Metrics {
+        lines: lines.len(),
+        complexity: 1 + keywords + if python { 0 } else { operators },
+        max_nesting: if python {
+            python_nesting(source)
+        } else {
+            go_nesting(&tokens)
+        },
+    }
+}
+
+fn go_nesting(tokens: &[syntax::Token]) -> usize {
+    let mut depth: usize = 0;
+    let mut max = 0;
+    for token in tokens.iter().filter(|t| t.kind == TokenKind::Punct) {
+        match token.text.as_str() {
+            "{" => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            "}" => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max.saturating_sub(1)
+}
+
+// インデントの段数で数える (def の中は1段引いて関数本体を 0 にそろえる)
+fn python_nesting(source: &str) -> usize {
+    let code_lines: Vec<&str> = source
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .collect();
+    let indents: Vec<usize> = code_lines
+        .iter()
+        .map(|l| l.len() - l.trim_start().len())
+        .collect();
+    let Some(unit) = indents.iter().copied().filter(|i| *i > 0).min() else {
+        return 0;
+    };
+    let max = indents.iter().max().copied().unwrap_or(0) / unit;
+    let has_def = code_lines
+        .iter()
+        .any(|l| l.trim_start().starts_with("def "));
+    if has_def { max.saturating_sub(1) } else { max }
+}
+
+// 成功した解答への控えめな助言
+pub fn feedback(mine: &Metrics, reference: Option<&Metrics>) -> Vec<String> {
+    let mut messages = Vec::new();
+    if let Some(reference) = reference
+        && mine.lines > reference.lines * LINES_RATIO
+    {
+        messages.push(format!(
+            "模範解答は{}行ですが、あなたの解答は{}行です。もっと短く書ける箇所がないか探してみましょう",
+            reference.lines, mine.lines
+        ));
+    }
+    if mine.complexity > COMPLEXITY_LIMIT {
+        messages.push(format!(
+            "分岐が多めです (複雑度 {})。処理を小さな関数に分けると読みやすくなります",
+            mine.complexity
+        ));
+    }
+    if mine.max_nesting > NESTING_LIMIT {
+        messages.push(format!(
+            "ブロックが{}段ネストしています。早期 return などで浅くできるかもしれません",
+            mine.max_nesting
+        ));
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_go() {
+        let source = "package main\n\n// コメント\nfunc main() {\n    for i := 0; i < 10; i++ {\n        if i%2 == 0 && i > 4 {\n            println(i)\n        }\n    }\n}\n";
+
+        let metrics = measure(source, false);
+
+        assert_eq!(
+            metrics,
+            Metrics {
+                lines: 8,
+                complexity: 4,
+                max_nesting: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_measure_python() {
+        let source = "def main():\n    for i in range(10):\n        if i % 2 == 0 and i > 4:\n            print(i)\n";
+
+        let metrics = measure(source, true);
+
+        assert_eq!(metrics.complexity, 4);
+        assert_eq!(metrics.max_nesting, 2);
+    }
+
+    #[test]
+    fn test_feedback_compares_with_reference() {
+        let mine = Metrics {
+            lines: 40,
+            complexity: 3,
+            max_nesting: 1,
+        };
+        let reference = Metrics {
+            lines: 8,
+            complexity: 2,
+            max_nesting: 1,
+        };
+
+        let messages = feedback(&mine, Some(&reference));
+
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("模範解答は8行"));
+        assert!(feedback(&mine, None).is_empty());
+    }
+}
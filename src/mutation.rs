@@ -0,0 +1,223 @@
+use crate::files;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// challenge: 解けた解答に小さなバグを1つ入れたもの (ミュータント) を作る
+// 比較の境界 (< と <=)・等号の反転・「+ 1」の削除 (off-by-one)・&& と || の入れ替えを試す
+// コメント・文字列・learn: の行は変えない
+
+// 採点で見つかるバグを探すときに試すミュータントの数 (1つごとに解答を実行する)
+pub const TRIES: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    // 変えた行 (1始まり)
+    pub line: usize,
+    pub source: String,
+}
+
+// 問題のディレクトリを作業用のディレクトリに複製する
+// (learn:case の入力や learn:reference の模範解答も同じ相対パスで見つかるように)
+pub fn copy_dir(dir: &Path, workspace: &Path) -> io::Result<()> {
+    for path in files::collect_files(dir)? {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        if relative.components().any(|c| c.as_os_str() == ".git") {
+            continue;
+        }
+        let destination = workspace.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&path, destination)?;
+    }
+    Ok(())
+}
+
+// 比較の演算子 (長いものから調べる)
+const COMPARISONS: [(&str, &str); 6] = [
+    ("<=", "<"),
+    (">=", ">"),
+    ("==", "!="),
+    ("!=", "=="),
+    ("<", "<="),
+    (">", ">="),
+];
+
+// Go と Python だけ (それ以外の言語は None)
+pub fn mutants(source: &str, extension: &str) -> Option<Vec<Mutant>> {
+    let python = match extension {
+        "go" => false,
+        "py" => true,
+        _ => return None,
+    };
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut mutants = Vec::new();
+    let mut in_block = false;
+    for (index, line) in lines.iter().enumerate() {
+        // Go のブロックコメントと Python の三重引用符の文字列は行単位で読み飛ばす
+        let delimiters = match python {
+            true => line.matches("\"\"\"").count() + line.matches("'''").count(),
+            false => line.matches("/*").count() + line.matches("*/").count(),
+        };
+        let skip = in_block || delimiters > 0;
+        in_block ^= delimiters % 2 == 1;
+        if skip {
+            continue;
+        }
+
+        let code = &line[..code_end(line, python)];
+        for (start, end, replacement) in replacements(code, python) {
+            let mut mutated = lines.clone();
+            let changed = format!("{}{}{}", &line[..start], replacement, &line[end..]);
+            mutated[index] = &changed;
+            mutants.push(Mutant {
+                line: index + 1,
+                source: mutated.concat(),
+            });
+        }
+    }
+    Some(mutants)
+}
+
+// コメントが始まる位置 (文字列の中の // や # は無視する)
+fn code_end(line: &str, python: bool) -> usize {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' || c == '`' => quote = Some(c),
+            None if python && c == '#' => return i,
+            None if !python && line[i..].starts_with("//") => return i,
+            None => {}
+        }
+    }
+    line.len()
+}
+
+// 文字列の外にある置き換え候補 (開始位置, 終了位置, 置き換える文字列)
+fn replacements(code: &str, python: bool) -> Vec<(usize, usize, &'static str)> {
+    let bytes = code.as_bytes();
+    let mut found = Vec::new();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if let Some(q) = quote {
+            if c == b'\\' {
+                i += 1;
+            } else if c == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        if c == b'"' || c == b'\'' || c == b'`' {
+            quote = Some(c);
+            i += 1;
+            continue;
+        }
+        if !code.is_char_boundary(i) {
+            i += 1;
+            continue;
+        }
+        let rest = &code[i..];
+        let prev = i.checked_sub(1).map(|p| bytes[p]);
+
+        if let Some((from, to)) = COMPARISONS.iter().find(|(from, _)| rest.starts_with(from)) {
+            let next = bytes.get(i + from.len()).copied();
+            // <<, >>, <-, ->, =>, := の一部は比較ではない
+            let operator = !matches!(prev, Some(b'<' | b'>' | b'-' | b'=' | b'!' | b':'))
+                && !matches!(next, Some(b'<' | b'>' | b'-' | b'='));
+            if operator {
+                found.push((i, i + from.len(), *to));
+            }
+            i += from.len();
+            continue;
+        }
+
+        let logic: &[(&str, &str)] = match python {
+            true => &[(" and ", " or "), (" or ", " and ")],
+            false => &[("&&", "||"), ("||", "&&")],
+        };
+        if let Some((from, to)) = logic.iter().find(|(from, _)| rest.starts_with(from)) {
+            found.push((i, i + from.len(), *to));
+            i += from.len();
+            continue;
+        }
+
+        if let Some(length) = plus_or_minus_one(code, i) {
+            found.push((i, i + length, ""));
+            i += length;
+            continue;
+        }
+        i += 1;
+    }
+    found
+}
+
+// 「値 + 1」「値 - 1」の「 + 1」の部分の長さ (単項のマイナス・+= 1・10 のような数は除く)
+fn plus_or_minus_one(code: &str, start: usize) -> Option<usize> {
+    let before = code[..start].trim_end_matches(' ');
+    if before.len() != start {
+        return None;
+    }
+    let operand = before
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == ')' || c == ']');
+    let rest = &code[start..];
+    let trimmed = rest.trim_start_matches(' ');
+    let after_operator = trimmed.strip_prefix(['+', '-'])?;
+    let after_one = after_operator.trim_start_matches(' ').strip_prefix('1')?;
+    let whole_number =
+        !after_one.starts_with(|c: char| c.is_alphanumeric() || c == '.' || c == '_');
+    (operand && whole_number).then(|| rest.len() - after_one.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mutants_change_one_operator_outside_comments_and_strings() {
+        let source = "package main\n\n// learn:check output 3\nfunc main() {\n\tfor i := 0; i < n-1; i++ {\n\t\tfmt.Println(\"a < b\", i == 2) // x < y\n\t}\n}\n";
+        let mutants = mutants(source, "go").unwrap();
+        let changed: Vec<(usize, &str)> = mutants
+            .iter()
+            .map(|m| (m.line, m.source.lines().nth(m.line - 1).unwrap()))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                (5, "\tfor i := 0; i <= n-1; i++ {"),
+                (5, "\tfor i := 0; i < n; i++ {"),
+                (6, "\t\tfmt.Println(\"a < b\", i != 2) // x < y"),
+            ]
+        );
+        assert!(super::mutants(source, "html").is_none());
+    }
+
+    #[test]
+    fn test_python_mutants_swap_logic_and_skip_docstrings() {
+        let source =
+            "def f(a, b):\n    \"\"\"a < b\"\"\"\n    return a > 0 and b >= 0\n\nx = -1\ny += 1\n";
+        let changed: Vec<String> = mutants(source, "py")
+            .unwrap()
+            .iter()
+            .map(|m| m.source.lines().nth(m.line - 1).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                "    return a >= 0 and b >= 0",
+                "    return a > 0 or b >= 0",
+                "    return a > 0 and b > 0",
+            ]
+        );
+    }
+}
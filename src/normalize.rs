@@ -0,0 +1,103 @@
+use crate::directive;
+use regex::Regex;
+use std::sync::LazyLock;
+
+// 問題ファイルの "learn:normalize <規則>" で定義する、出力を比べる前の正規化
+// time.Now() や map の順序のように実行ごとに変わる出力でも採点できるようにする
+// 例:
+//   // learn:normalize sort-lines                   (行を並べ替える)
+//   // learn:normalize strip-timestamps             (日付・時刻を <TIME> に置き換える)
+//   // learn:normalize replace 0x[0-9a-f]+ => <ADDR> (正規表現に一致する部分を置き換える)
+// 書いた順に適用する
+#[derive(Debug, Clone)]
+pub enum Rule {
+    SortLines,
+    StripTimestamps,
+    Replace(Regex, String),
+}
+
+// "2024-01-02 15:04:05.123 +0900 JST m=+0.000012345" や "15:04:05" など
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\d{4}[-/]\d{2}[-/]\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z| ?[+-]\d{2}:?\d{2})?(?: [A-Z]{2,5})?(?: m=[+-]\d+\.\d+)?)?|\b\d{1,2}:\d{2}:\d{2}(?:\.\d+)?\b",
+    )
+    .unwrap()
+});
+
+impl Rule {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value {
+            "sort-lines" => Some(Self::SortLines),
+            "strip-timestamps" => Some(Self::StripTimestamps),
+            _ => {
+                let (pattern, replacement) = value.strip_prefix("replace ")?.split_once("=>")?;
+                let regex = Regex::new(pattern.trim()).ok()?;
+                Some(Self::Replace(regex, replacement.trim().to_string()))
+            }
+        }
+    }
+
+    fn apply(&self, output: &str) -> String {
+        match self {
+            Self::SortLines => {
+                let mut lines: Vec<&str> = output.lines().collect();
+                lines.sort_unstable();
+                lines.join("\n")
+            }
+            Self::StripTimestamps => TIMESTAMP.replace_all(output, "<TIME>").into_owned(),
+            Self::Replace(regex, replacement) => {
+                regex.replace_all(output, replacement.as_str()).into_owned()
+            }
+        }
+    }
+}
+
+// 解釈できない learn:normalize (不正な正規表現など) は別に返す
+pub fn rules(source: &str) -> (Vec<Rule>, Vec<String>) {
+    let mut rules = Vec::new();
+    let mut invalid = Vec::new();
+    for value in directive::find_all(source, "normalize") {
+        match Rule::parse(&value) {
+            Some(rule) => rules.push(rule),
+            None => invalid.push(value),
+        }
+    }
+    (rules, invalid)
+}
+
+pub fn apply(rules: &[Rule], output: &str) -> String {
+    rules
+        .iter()
+        .fold(output.to_string(), |output, rule| rule.apply(&output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let source = "// learn:normalize sort-lines\n// learn:normalize replace id=\\d+ => id=N\n// learn:normalize replace ( => x\n// learn:normalize shuffle\n";
+
+        let (rules, invalid) = rules(source);
+
+        assert_eq!(rules.len(), 2);
+        assert!(matches!(rules[0], Rule::SortLines));
+        assert_eq!(invalid, ["replace ( => x", "shuffle"]);
+    }
+
+    #[test]
+    fn test_apply_in_order() {
+        let (rules, _) = rules(
+            "# learn:normalize strip-timestamps\n# learn:normalize replace id=\\d+ => id=N\n# learn:normalize sort-lines\n",
+        );
+        let output = "b id=12\nstarted at 2024-01-02 15:04:05.123456 +0900 JST m=+0.000012345\na id=3\ndone 09:30:00\n";
+
+        assert_eq!(
+            apply(&rules, output),
+            "a id=N\nb id=N\ndone <TIME>\nstarted at <TIME>"
+        );
+        assert_eq!(apply(&[], "x\n"), "x\n");
+    }
+}
@@ -0,0 +1,162 @@
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+// Jupyter Notebook (.ipynb) の読み取り
+// 実行は jupyter nbconvert --execute で行い、実行後のノートブックからセルごとの出力とエラーを取り出す
+// セルの番号はコードセルだけを数えた 1 からの番号
+
+// 実行したコードセルの出力
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellOutput {
+    pub number: usize,
+    // 標準出力と式の値 (text/plain)
+    pub text: String,
+    // "NameError: name 'x' is not defined" とトレースバック
+    pub error: Option<String>,
+}
+
+static ANSI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+// source / text は文字列か文字列の配列
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn code_cells(notebook: &Value) -> impl Iterator<Item = &Value> {
+    notebook["cells"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|cell| cell["cell_type"] == "code")
+}
+
+// コードセルのソースをつなげたもの (learn: ディレクティブや採点に使う、読めなければ空)
+pub fn code(raw: &str) -> String {
+    let Ok(notebook) = serde_json::from_str::<Value>(raw) else {
+        return String::new();
+    };
+    code_cells(&notebook)
+        .map(|cell| {
+            let source = text(&cell["source"]);
+            if source.ends_with('\n') {
+                source
+            } else {
+                source + "\n"
+            }
+        })
+        .collect()
+}
+
+// 実行後のノートブックからセルごとの出力を取り出す
+pub fn outputs(executed: &str) -> Option<Vec<CellOutput>> {
+    let notebook = serde_json::from_str::<Value>(executed).ok()?;
+    let cells = code_cells(&notebook)
+        .enumerate()
+        .map(|(index, cell)| {
+            let mut output = CellOutput {
+                number: index + 1,
+                text: String::new(),
+                error: None,
+            };
+            for item in cell["outputs"].as_array().into_iter().flatten() {
+                match item["output_type"].as_str() {
+                    Some("stream") if item["name"] == "stdout" => {
+                        output.text.push_str(&text(&item["text"]));
+                    }
+                    Some("execute_result") | Some("display_data") => {
+                        let plain = text(&item["data"]["text/plain"]);
+                        if !plain.is_empty() {
+                            output.text.push_str(&plain);
+                            if !plain.ends_with('\n') {
+                                output.text.push('\n');
+                            }
+                        }
+                    }
+                    Some("error") => {
+                        let traceback: Vec<String> = item["traceback"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(Value::as_str)
+                            .map(|line| ANSI.replace_all(line, "").into_owned())
+                            .collect();
+                        // 最後の行が例外の型とメッセージになるように並べる
+                        output.error = Some(format!(
+                            "{}\n{}: {}",
+                            traceback.join("\n"),
+                            item["ename"].as_str().unwrap_or_default(),
+                            item["evalue"].as_str().unwrap_or_default()
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+            output
+        })
+        .collect();
+    Some(cells)
+}
+
+// セルごとの出力を表示用の標準出力と標準エラーにまとめる
+pub fn render(cells: &[CellOutput]) -> (String, String) {
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    for cell in cells {
+        if !cell.text.is_empty() {
+            stdout.push_str(&format!("[セル {}]\n{}", cell.number, cell.text));
+        }
+        if let Some(error) = &cell.error {
+            stderr.push_str(&format!("[セル {}]\n{}\n", cell.number, error));
+        }
+    }
+    (stdout, stderr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXECUTED: &str = r##"{
+ "cells": [
+  {"cell_type": "markdown", "source": ["# 平均"]},
+  {"cell_type": "code", "source": ["# learn:check cell 1 3.0\n", "print(sum([2, 4]) / 2)"],
+   "outputs": [{"output_type": "stream", "name": "stdout", "text": ["3.0\n"]}]},
+  {"cell_type": "code", "source": "len([1, 2])",
+   "outputs": [{"output_type": "execute_result", "data": {"text/plain": ["2"]}}]},
+  {"cell_type": "code", "source": ["print(x)"],
+   "outputs": [{"output_type": "error", "ename": "NameError", "evalue": "name 'x' is not defined",
+                "traceback": ["\u001b[0;31mNameError\u001b[0m  Traceback (most recent call last)", "Cell In[3], line 1"]}]}
+ ],
+ "nbformat": 4
+}"##;
+
+    #[test]
+    fn test_code_joins_code_cells() {
+        assert_eq!(
+            code(EXECUTED),
+            "# learn:check cell 1 3.0\nprint(sum([2, 4]) / 2)\nlen([1, 2])\nprint(x)\n"
+        );
+        assert_eq!(code("not json"), "");
+    }
+
+    #[test]
+    fn test_outputs_and_render() {
+        let cells = outputs(EXECUTED).unwrap();
+
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0].text, "3.0\n");
+        assert_eq!(cells[1].text, "2\n");
+        let error = cells[2].error.as_deref().unwrap();
+        assert!(error.starts_with("NameError  Traceback"));
+        assert!(error.ends_with("NameError: name 'x' is not defined"));
+
+        let (stdout, stderr) = render(&cells);
+        assert_eq!(stdout, "[セル 1]\n3.0\n[セル 2]\n2\n");
+        assert!(stderr.starts_with("[セル 3]\n"));
+    }
+}
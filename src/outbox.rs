@@ -0,0 +1,283 @@
+use crate::chat::{self, QuietHours};
+use crate::config::ChatConfig;
+use crate::events::EventBus;
+use crate::history::{HistoryStore, Outbound};
+use crate::llm::LlmConfig;
+use crate::review;
+use log::{error, warn};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+// ネットワークに送るもの (LLM のレビュー・チャットへのまとめ) の待ち行列
+// 実行する側は履歴 DB の outbox に入れるだけで待たずに次へ進み、送るのは spawn_worker のタスクが行う
+// 送れなければ間隔を倍にしながら MAX_ATTEMPTS 回まで送り直し、CAPACITY 件たまったら新しいものは入れない
+
+// 待ち行列に入れておける数 (あきらめたものも数える。outbox retry か privacy purge で空ける)
+pub const CAPACITY: usize = 100;
+// 送り直す回数の上限と、最初に送り直すまでの秒数 (失敗するたびに倍にし、MAX_RETRY 秒で止める)
+const MAX_ATTEMPTS: i64 = 8;
+const FIRST_RETRY: i64 = 30;
+const MAX_RETRY: i64 = 6 * 60 * 60;
+// 1回に送る数
+const BATCH: usize = 10;
+// 実行が終わったとき以外にも、送り直す時刻になったものがないか確かめる間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    // 成功した解答のコードレビュー (成功したときのコードをレビューする)
+    Review {
+        file_path: String,
+        source: String,
+    },
+    // チャットの Webhook に送る1日のまとめ (quiet_hours の間は送らない)
+    Chat {
+        date: String,
+        webhook_url: String,
+        body: Value,
+        quiet_hours: Option<String>,
+    },
+}
+
+impl Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Review { .. } => "review",
+            Self::Chat { .. } => "chat",
+        }
+    }
+
+    fn payload(&self) -> Value {
+        match self {
+            Self::Review { file_path, source } => {
+                json!({"file_path": file_path, "source": source})
+            }
+            Self::Chat {
+                date,
+                webhook_url,
+                body,
+                quiet_hours,
+            } => json!({
+                "date": date,
+                "webhook_url": webhook_url,
+                "body": body,
+                "quiet_hours": quiet_hours,
+            }),
+        }
+    }
+
+    fn parse(outbound: &Outbound) -> Option<Self> {
+        let payload: Value = serde_json::from_str(&outbound.payload).ok()?;
+        let text = |key: &str| payload[key].as_str().map(str::to_string);
+        match outbound.kind.as_str() {
+            "review" => Some(Self::Review {
+                file_path: text("file_path")?,
+                source: text("source")?,
+            }),
+            "chat" => Some(Self::Chat {
+                date: text("date")?,
+                webhook_url: text("webhook_url")?,
+                body: payload["body"].clone(),
+                quiet_hours: text("quiet_hours"),
+            }),
+            _ => None,
+        }
+    }
+
+    // outbox の一覧に出す説明
+    pub fn describe(outbound: &Outbound) -> String {
+        match Self::parse(outbound) {
+            Some(Self::Review { file_path, .. }) => format!("レビュー {}", file_path),
+            // Webhook の URL は秘密なので出さない
+            Some(Self::Chat { date, .. }) => format!("チャットへのまとめ {}", date),
+            None => format!("{} (解釈できません)", outbound.kind),
+        }
+    }
+}
+
+// 待ち行列に入れる (いっぱいなら入れずに false)
+pub fn enqueue(store: &HistoryStore, message: &Message, now: i64) -> rusqlite::Result<bool> {
+    store.enqueue_outbound(
+        message.kind(),
+        &message.payload().to_string(),
+        now,
+        CAPACITY,
+    )
+}
+
+// failures 回続けて送れなかった後、次に送るまでの秒数 (None ならあきらめる)
+pub fn retry_delay(failures: i64) -> Option<i64> {
+    (failures < MAX_ATTEMPTS).then(|| {
+        FIRST_RETRY
+            .saturating_mul(1 << (failures - 1).clamp(0, 30))
+            .min(MAX_RETRY)
+    })
+}
+
+// 送る時刻になったものを送る (送れた数)
+// 履歴 DB のロックは送っている間は持たない
+pub async fn deliver_due(store: &Mutex<HistoryStore>, llm: Option<&LlmConfig>) -> usize {
+    let now = chrono::Utc::now().timestamp();
+    let (due, in_exam) = match store.lock() {
+        Ok(store) => (
+            store.due_outbound(now, BATCH).unwrap_or_else(|e| {
+                error!("送信待ちの記録を読み込めません: {}", e);
+                Vec::new()
+            }),
+            store.active_exam().ok().flatten().is_some(),
+        ),
+        Err(_) => return 0,
+    };
+
+    let mut sent = 0;
+    for outbound in due {
+        let result = match Message::parse(&outbound) {
+            Some(message) => deliver(store, llm, &message, in_exam).await,
+            None => Err(format!("{} の記録を解釈できません", outbound.kind)),
+        };
+        let Ok(store) = store.lock() else {
+            break;
+        };
+        let saved = match result {
+            Ok(true) => {
+                sent += 1;
+                store.finish_outbound(outbound.id)
+            }
+            // 試験中のレビューは試験が終わるまで、静かな時間帯のチャットは終わるまで送らない
+            Ok(false) => Ok(()),
+            Err(e) => {
+                let failures = outbound.attempts + 1;
+                let next = retry_delay(failures).map(|delay| now + delay);
+                match next {
+                    Some(_) => warn!(
+                        "{} を送れませんでした (後で送り直します): {}",
+                        Message::describe(&outbound),
+                        e
+                    ),
+                    None => error!(
+                        "{} を{}回送れなかったのであきらめます (outbox retry で送り直せます): {}",
+                        Message::describe(&outbound),
+                        failures,
+                        e
+                    ),
+                }
+                store.fail_outbound(outbound.id, &e, next)
+            }
+        };
+        if let Err(e) = saved {
+            error!("送信待ちの記録を更新できません: {}", e);
+        }
+    }
+    sent
+}
+
+// 送れたら true、まだ送らないなら false (試験中のレビュー・静かな時間帯のチャット)
+async fn deliver(
+    store: &Mutex<HistoryStore>,
+    llm: Option<&LlmConfig>,
+    message: &Message,
+    in_exam: bool,
+) -> Result<bool, String> {
+    match message {
+        Message::Review { .. } if in_exam => Ok(false),
+        Message::Review { file_path, source } => {
+            let config = llm.ok_or_else(LlmConfig::not_configured_message)?;
+            let result = review::run(config, Some(store), Path::new(file_path), source)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("🤖 {}", file_path);
+            review::print(&result);
+            Ok(true)
+        }
+        Message::Chat { quiet_hours, .. }
+            if quiet_hours
+                .as_deref()
+                .and_then(QuietHours::parse)
+                .is_some_and(|quiet| quiet.contains(chrono::Local::now().time())) =>
+        {
+            Ok(false)
+        }
+        Message::Chat {
+            webhook_url, body, ..
+        } => {
+            let (webhook_url, body) = (webhook_url.clone(), body.clone());
+            tokio::task::spawn_blocking(move || chat::post(&webhook_url, &body))
+                .await
+                .map_err(|e| e.to_string())??;
+            Ok(true)
+        }
+    }
+}
+
+// 監視している間、待ち行列を送り続けるタスク (実行が終わるたびと POLL_INTERVAL ごとに確かめる)
+// chat があれば、前日のまとめもここで待ち行列に入れる
+// 実行と同じ DB 接続を使わないよう、履歴 DB を開き直す
+pub fn spawn_worker(
+    db: PathBuf,
+    llm: Option<LlmConfig>,
+    chat: Option<ChatConfig>,
+    events: &EventBus,
+) -> tokio::task::JoinHandle<()> {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        let store = match HistoryStore::open(&db) {
+            Ok(store) => Mutex::new(store),
+            Err(e) => {
+                error!("履歴DBを開けません: {} (レビューなどは送られません)", e);
+                return;
+            }
+        };
+        loop {
+            if let (Some(chat), Ok(store)) = (&chat, store.lock())
+                && let Err(e) = chat::queue_yesterday(&store, chat, chrono::Local::now())
+            {
+                error!("チャットへのまとめを待ち行列に入れられません: {}", e);
+            }
+            deliver_due(&store, llm.as_ref()).await;
+            tokio::select! {
+                event = receiver.recv() => {
+                    if let Err(RecvError::Closed) = event {
+                        break;
+                    }
+                }
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_applies_backpressure_and_backoff() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let review = |n: usize| Message::Review {
+            file_path: format!("/work/{}.go", n),
+            source: "package main\n".to_string(),
+        };
+        for n in 0..CAPACITY {
+            assert!(enqueue(&store, &review(n), 100).unwrap());
+        }
+        assert!(!enqueue(&store, &review(CAPACITY), 100).unwrap());
+
+        let due = store.due_outbound(100, BATCH).unwrap();
+        assert_eq!(due.len(), BATCH);
+        assert_eq!(Message::parse(&due[0]), Some(review(0)));
+        store
+            .fail_outbound(due[0].id, "timeout", retry_delay(1).map(|d| 100 + d))
+            .unwrap();
+        store.finish_outbound(due[1].id).unwrap();
+        assert_eq!(store.due_outbound(100, 1).unwrap()[0].id, due[2].id);
+        assert_eq!(store.outbound().unwrap()[0].next_attempt_at, Some(130));
+
+        assert_eq!(retry_delay(1), Some(30));
+        assert_eq!(retry_delay(3), Some(120));
+        assert_eq!(retry_delay(MAX_ATTEMPTS - 1), Some(1920));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::classroom::problem_id;
+use crate::history::HistoryStore;
+use serde_json::{Value, json};
+
+// privacy export: 学校などに渡すための匿名化した実行履歴
+// 集計に使う値 (成否・時間・エラーの種類) だけを書き出し、コード・出力・メモ・ファイルの場所は含めない
+// ファイルはホームディレクトリなどの場所を落として問題の ID (section3/problem05) にする
+
+pub fn export(store: &HistoryStore, exported_at: i64) -> rusqlite::Result<Value> {
+    let executions: Vec<Value> = store
+        .executions_between(i64::MIN, i64::MAX)?
+        .iter()
+        .map(|record| {
+            json!({
+                "problem": problem_id(&record.file_path),
+                "section": record.section,
+                "language": record.language,
+                "success": record.success,
+                "exit_code": record.exit_code,
+                "duration_ms": record.duration_ms,
+                "executed_at": record.executed_at,
+                "score": record.score,
+                "coverage": record.coverage,
+                "todos_remaining": record.todos_remaining,
+                "error_signature": record.error_signature,
+                "os": record.environment.os,
+            })
+        })
+        .collect();
+    let solve_times: Vec<Value> = store
+        .solve_times()?
+        .iter()
+        .map(|solve| {
+            json!({
+                "problem": problem_id(&solve.file_path),
+                "section": solve.section,
+                "first_modified_at": solve.first_modified_at,
+                "solved_at": solve.solved_at,
+                "active_seconds": solve.active_seconds,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "exported_at": exported_at,
+        "executions": executions,
+        "solve_times": solve_times,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use crate::history::ExecutionRecord;
+
+    #[test]
+    fn test_export_leaves_out_content_and_paths() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let id = store
+            .insert(&ExecutionRecord {
+                id: 0,
+                file_path: "/home/alice/go/section1-basics/problem01_hello.go".to_string(),
+                section: "section1-basics".to_string(),
+                success: false,
+                exit_code: Some(1),
+                duration_ms: 120,
+                executed_at: 100,
+                output_preview: "secret output".to_string(),
+                environment: Environment {
+                    tool_version: None,
+                    os: "linux x86_64".to_string(),
+                    app_version: "0.1.0".to_string(),
+                },
+                note: None,
+                todos_remaining: None,
+                score: None,
+                content_hash: Some("abc".to_string()),
+                error_signature: Some("undefined: x".to_string()),
+                language: Some("Go".to_string()),
+                coverage: None,
+            })
+            .unwrap();
+        store.set_note(id, "secret note").unwrap();
+
+        let exported = export(&store, 200).unwrap();
+        let text = exported.to_string();
+        for hidden in ["alice", "secret", "abc"] {
+            assert!(!text.contains(hidden), "{}", text);
+        }
+        let execution = &exported["executions"][0];
+        assert_eq!(execution["problem"], "section1/problem01");
+        assert_eq!(execution["error_signature"], "undefined: x");
+        assert_eq!(exported["exported_at"], 200);
+    }
+}
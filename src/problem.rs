@@ -0,0 +1,126 @@
+// 問題ファイル先頭などのコメントに書かれたメタデータ
+// 例:
+//   // Problem: Goroutines Basic Practice
+//   // Topic: Goroutines
+//   // Difficulty: 1
+//   // Section: Goroutines and channels fundamentals
+//   // Syntax elements to practice: go keyword, goroutine creation
+//   // Tags: concurrency, interview
+//   // Docs: https://go.dev/tour/concurrency/1
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProblemHeader {
+    pub title: Option<String>,
+    pub topic: Option<String>,
+    pub difficulty: Option<u8>,
+    pub section: Option<String>,
+    pub syntax_elements: Vec<String>,
+    // 自由に付けられるタグ (小文字に揃える)
+    pub tags: Vec<String>,
+    // 参考ドキュメントのURL
+    pub docs: Vec<String>,
+    // "TODO: ..." コメントの本文
+    pub todos: Vec<String>,
+}
+
+pub fn parse(source: &str) -> ProblemHeader {
+    let mut header = ProblemHeader::default();
+
+    for comment in source.lines().filter_map(comment_text) {
+        let Some((key, value)) = comment.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+
+        match key.trim() {
+            "Problem" if header.title.is_none() => header.title = Some(value.to_string()),
+            "Topic" if header.topic.is_none() => header.topic = Some(value.to_string()),
+            "Difficulty" if header.difficulty.is_none() => header.difficulty = value.parse().ok(),
+            "Section" if header.section.is_none() => header.section = Some(value.to_string()),
+            "Syntax elements to practice" if header.syntax_elements.is_empty() => {
+                header.syntax_elements = split_list(value);
+            }
+            "Tags" if header.tags.is_empty() => {
+                header.tags = split_list(&value.to_lowercase());
+            }
+            "Docs" => header.docs.extend(split_list(value)),
+            "TODO" => header.todos.push(value.to_string()),
+            _ => {}
+        }
+    }
+
+    header
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// まだ残っている "TODO" コメントの数 (本文のないものも数える)
+pub fn count_todos(source: &str) -> usize {
+    source
+        .lines()
+        .filter_map(comment_text)
+        .filter(|comment| comment.starts_with("TODO"))
+        .count()
+}
+
+// "//" または "#" で始まる行のコメント本文
+fn comment_text(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("//")
+        .or_else(|| line.strip_prefix('#'))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_generated_problem_header() {
+        let source = "// Problem: Goroutines Basic Practice\n// Topic: Goroutines\n// Difficulty: 1\n// Docs: https://go.dev/tour/concurrency/1\n\npackage main\n\nfunc main() {\n// TODO: This is a basic level problem focusing on goroutines\n// Section: Goroutines and channels fundamentals\n// Syntax elements to practice: go keyword, goroutine creation\n}\n";
+
+        let header = parse(source);
+
+        assert_eq!(header.title.as_deref(), Some("Goroutines Basic Practice"));
+        assert_eq!(header.topic.as_deref(), Some("Goroutines"));
+        assert_eq!(header.difficulty, Some(1));
+        assert_eq!(
+            header.section.as_deref(),
+            Some("Goroutines and channels fundamentals")
+        );
+        assert_eq!(header.syntax_elements, ["go keyword", "goroutine creation"]);
+        assert_eq!(header.docs, ["https://go.dev/tour/concurrency/1"]);
+        assert_eq!(header.todos.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_python_comments() {
+        let source = "# Topic: Loops\n# Tags: Stdlib, interview\n# TODO: print numbers from 1 to 10\nfor i in range(10):\n    pass\n";
+
+        let header = parse(source);
+
+        assert_eq!(header.topic.as_deref(), Some("Loops"));
+        assert_eq!(header.tags, ["stdlib", "interview"]);
+        assert_eq!(header.todos, ["print numbers from 1 to 10"]);
+    }
+
+    #[test]
+    fn test_count_todos() {
+        let source = "// TODO: first\nfunc main() {\n    // TODO\n    x := \"TODO\"\n}\n";
+
+        assert_eq!(count_todos(source), 2);
+    }
+
+    #[test]
+    fn test_parse_without_header() {
+        assert_eq!(parse("print('hello')\n"), ProblemHeader::default());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// カリキュラム外のディレクトリを監視しているときのセクション名
+pub const PERSONAL_PROJECT_SECTION: &str = "personal-project";
+
+// ファイルが属しているプロジェクトの種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectType {
+    // go.mod があるディレクトリ配下
+    GoModule,
+    // pyproject.toml があるディレクトリ配下
+    PythonProject,
+    // 単体のファイル
+    Standalone,
+}
+
+// Python の問題を実行する環境 (問題ファイルに一番近いディレクトリのものを使う)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PythonEnv {
+    // .venv / venv の中のインタプリタ
+    Venv(PathBuf),
+    // uv.lock があるディレクトリ (uv run で実行する)
+    Uv(PathBuf),
+    // environment.yml の name (conda run で実行する)
+    Conda(String),
+}
+
+impl fmt::Display for PythonEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Venv(_) => write!(f, "venv"),
+            Self::Uv(_) => write!(f, "uv"),
+            Self::Conda(name) => write!(f, "conda {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectContext {
+    pub section: String,
+    pub project_type: ProjectType,
+    // go.mod / pyproject.toml が置かれているディレクトリ
+    pub project_root: Option<PathBuf>,
+    pub python_env: Option<PythonEnv>,
+}
+
+impl fmt::Display for ProjectContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.project_type {
+            ProjectType::GoModule => write!(f, "{} / go.mod", self.section)?,
+            ProjectType::PythonProject => write!(f, "{} / pyproject.toml", self.section)?,
+            ProjectType::Standalone => write!(f, "{}", self.section)?,
+        }
+        match &self.python_env {
+            Some(env) => write!(f, " / {}", env),
+            None => Ok(()),
+        }
+    }
+}
+
+// ファイルのパスからセクションとプロジェクトの種類を推定する
+pub fn detect(path: &Path) -> ProjectContext {
+    let section = detect_section(path).unwrap_or_else(|| PERSONAL_PROJECT_SECTION.to_string());
+
+    let marker = match path.extension().and_then(|s| s.to_str()) {
+        Some("go") => Some(("go.mod", ProjectType::GoModule)),
+        Some("py") => Some(("pyproject.toml", ProjectType::PythonProject)),
+        _ => None,
+    };
+
+    let found = marker.and_then(|(file_name, project_type)| {
+        path.ancestors()
+            .skip(1)
+            .find(|dir| dir.join(file_name).is_file())
+            .map(|dir| (project_type, dir.to_path_buf()))
+    });
+
+    let python_env = match path.extension().and_then(|s| s.to_str()) {
+        Some("py") => detect_python_env(path),
+        _ => None,
+    };
+
+    match found {
+        Some((project_type, root)) => ProjectContext {
+            section,
+            project_type,
+            project_root: Some(root),
+            python_env,
+        },
+        None => ProjectContext {
+            section,
+            project_type: ProjectType::Standalone,
+            project_root: None,
+            python_env,
+        },
+    }
+}
+
+// 問題ファイルから親ディレクトリへ順に .venv / venv、uv.lock、environment.yml を探す
+fn detect_python_env(path: &Path) -> Option<PythonEnv> {
+    let interpreter = if cfg!(windows) {
+        Path::new("Scripts").join("python.exe")
+    } else {
+        Path::new("bin").join("python")
+    };
+    path.ancestors().skip(1).find_map(|dir| {
+        if let Some(python) = [".venv", "venv"]
+            .iter()
+            .map(|name| dir.join(name).join(&interpreter))
+            .find(|python| python.is_file())
+        {
+            return Some(PythonEnv::Venv(python));
+        }
+        if dir.join("uv.lock").is_file() {
+            return Some(PythonEnv::Uv(dir.to_path_buf()));
+        }
+        ["environment.yml", "environment.yaml"]
+            .iter()
+            .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+            .and_then(|text| conda_env_name(&text))
+            .map(PythonEnv::Conda)
+    })
+}
+
+// environment.yml の先頭レベルの "name: datasci"
+fn conda_env_name(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("name:"))
+        .map(|name| name.trim().trim_matches(['"', '\'']).to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// "section3-functions" のようなディレクトリ名をセクションとみなす
+fn detect_section(path: &Path) -> Option<String> {
+    path.parent()?
+        .components()
+        .rev()
+        .filter_map(|c| c.as_os_str().to_str())
+        .find(|name| is_section_name(name))
+        .map(|name| name.to_string())
+}
+
+fn is_section_name(name: &str) -> bool {
+    name.strip_prefix("section")
+        .map(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_section_from_curriculum_path() {
+        let path = Path::new("example-go/section3-functions/problem01_function_basics.go");
+
+        let context = detect(path);
+
+        assert_eq!(context.section, "section3-functions");
+    }
+
+    #[test]
+    fn test_detect_personal_project_when_no_section() {
+        let path = Path::new("my-app/src/main.py");
+
+        let context = detect(path);
+
+        assert_eq!(context.section, PERSONAL_PROJECT_SECTION);
+        assert_eq!(context.project_type, ProjectType::Standalone);
+    }
+
+    #[test]
+    fn test_detect_go_module_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("go.mod"), "module example.com/app\n").unwrap();
+        fs::create_dir_all(tmp.path().join("cmd")).unwrap();
+        let path = tmp.path().join("cmd").join("main.go");
+
+        let context = detect(&path);
+
+        assert_eq!(context.project_type, ProjectType::GoModule);
+        assert_eq!(context.project_root.as_deref(), Some(tmp.path()));
+    }
+
+    #[test]
+    fn test_pyproject_is_ignored_for_go_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("pyproject.toml"), "[project]\n").unwrap();
+        let path = tmp.path().join("main.go");
+
+        let context = detect(&path);
+
+        assert_eq!(context.project_type, ProjectType::Standalone);
+    }
+
+    #[test]
+    fn test_detect_nearest_python_env() {
+        let tmp = tempfile::tempdir().unwrap();
+        let section = tmp.path().join("section9-data");
+        fs::create_dir_all(&section).unwrap();
+        fs::write(
+            tmp.path().join("environment.yml"),
+            "name: datasci\ndependencies:\n  - numpy\n",
+        )
+        .unwrap();
+        let path = section.join("problem01.py");
+
+        assert_eq!(
+            detect(&path).python_env,
+            Some(PythonEnv::Conda("datasci".to_string()))
+        );
+
+        // セクションの uv.lock の方が近い
+        fs::write(section.join("uv.lock"), "").unwrap();
+        assert_eq!(
+            detect(&path).python_env,
+            Some(PythonEnv::Uv(section.clone()))
+        );
+
+        let python = if cfg!(windows) {
+            section.join(".venv").join("Scripts").join("python.exe")
+        } else {
+            section.join(".venv").join("bin").join("python")
+        };
+        fs::create_dir_all(python.parent().unwrap()).unwrap();
+        fs::write(&python, "").unwrap();
+        let context = detect(&path);
+        assert_eq!(context.python_env, Some(PythonEnv::Venv(python)));
+        assert_eq!(context.to_string(), "section9-data / venv");
+
+        assert_eq!(detect(&section.join("main.go")).python_env, None);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::directive;
+use std::path::Path;
+
+// 問題ファイルの "learn:property <関数名>(<型>, ...)" で定義するプロパティテスト
+// ランダムな入力で解答の関数と learn:reference の模範解答の関数を呼び、結果が同じかを調べる
+// サンプルの入力だけに合わせた解答を見つけるためのもの (いまは Python のみ)
+// 例:
+//   # learn:property merge_sorted(list[int], list[int])
+//   # learn:property is_palindrome(str)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub function: String,
+    pub args: Vec<ArgType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgType {
+    Int,
+    Float,
+    Bool,
+    Str,
+    List(Box<ArgType>),
+}
+
+impl ArgType {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "bool" => Some(Self::Bool),
+            "str" => Some(Self::Str),
+            value => {
+                let item = value.strip_prefix("list[")?.strip_suffix(']')?;
+                Some(Self::List(Box::new(Self::parse(item)?)))
+            }
+        }
+    }
+
+    // ハーネスで値を作る Python の式 (size が大きいほど大きな値・長いリストになる)
+    fn generator(&self) -> String {
+        match self {
+            Self::Int => "gen_int(size)".to_string(),
+            Self::Float => "gen_float(size)".to_string(),
+            Self::Bool => "rng.random() < 0.5".to_string(),
+            Self::Str => "gen_str(size)".to_string(),
+            Self::List(item) => format!(
+                "[{} for _ in range(rng.randint(0, size))]",
+                item.generator()
+            ),
+        }
+    }
+}
+
+impl Property {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (function, args) = value.trim().strip_suffix(')')?.split_once('(')?;
+        let function = function.trim();
+        if function.is_empty() || !function.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        // list[...] の中にカンマは書けないので単純に区切る
+        let args = if args.trim().is_empty() {
+            Vec::new()
+        } else {
+            args.split(',')
+                .map(ArgType::parse)
+                .collect::<Option<Vec<_>>>()?
+        };
+        Some(Self {
+            function: function.to_string(),
+            args,
+        })
+    }
+}
+
+// 解釈できない learn:property は別に返す
+pub fn properties(source: &str) -> (Vec<Property>, Vec<String>) {
+    let mut properties = Vec::new();
+    let mut invalid = Vec::new();
+    for value in directive::find_all(source, "property") {
+        match Property::parse(&value) {
+            Some(property) => properties.push(property),
+            None => invalid.push(value),
+        }
+    }
+    (properties, invalid)
+}
+
+const PYTHON_HARNESS: &str = r#"import contextlib, copy, importlib.util, io, math, random, sys
+
+def load(name, path):
+    spec = importlib.util.spec_from_file_location(name, path)
+    module = importlib.util.module_from_spec(spec)
+    # 読み込み時の print は表示しない
+    with contextlib.redirect_stdout(io.StringIO()):
+        spec.loader.exec_module(module)
+    return module
+
+solution = load("solution", {{solution}})
+reference = load("reference", {{reference}})
+rng = random.Random({{seed}})
+
+def gen_int(size):
+    return rng.randint(-size * 10, size * 10)
+
+def gen_float(size):
+    return round(rng.uniform(-size * 10, size * 10), 3)
+
+def gen_str(size):
+    return "".join(rng.choice("abcxyz ") for _ in range(rng.randint(0, size)))
+
+def same(a, b):
+    if isinstance(a, float) or isinstance(b, float):
+        return isinstance(a, (int, float)) and isinstance(b, (int, float)) and math.isclose(a, b, rel_tol=1e-9, abs_tol=1e-9)
+    if isinstance(a, (list, tuple)) and isinstance(b, (list, tuple)):
+        return type(a) == type(b) and len(a) == len(b) and all(same(x, y) for x, y in zip(a, b))
+    return a == b
+
+def call(function, args):
+    try:
+        with contextlib.redirect_stdout(io.StringIO()):
+            return ("ok", function(*copy.deepcopy(args)))
+    except Exception as e:
+        return ("error", type(e).__name__ + ": " + str(e))
+
+# 小さな入力から試して、最初に見つかった反例を表示する
+for i in range({{cases}}):
+    size = 1 + i // 10
+    args = {{generate}}
+    expected = call(getattr(reference, {{function}}), args)
+    actual = call(getattr(solution, {{function}}), args)
+    if expected[0] != actual[0] or not same(expected[1], actual[1]):
+        print("FAIL " + {{function}} + "(" + ", ".join(repr(a) for a in args) + ")")
+        print("EXPECTED " + (repr(expected[1]) if expected[0] == "ok" else expected[1]))
+        print("ACTUAL " + (repr(actual[1]) if actual[0] == "ok" else actual[1]))
+        sys.exit(1)
+print("OK " + str({{cases}}))
+"#;
+
+// Python の文字列リテラル (パスに引用符やバックスラッシュが含まれていてもよいように)
+fn python_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// 解答と模範解答を読み込んでランダムな入力で比べる Python のスクリプト
+pub fn python_harness(
+    property: &Property,
+    solution: &Path,
+    reference: &Path,
+    cases: u32,
+    seed: u64,
+) -> String {
+    let generate = format!(
+        "[{}]",
+        property
+            .args
+            .iter()
+            .map(ArgType::generator)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    PYTHON_HARNESS
+        .replace(
+            "{{solution}}",
+            &python_string(&solution.display().to_string()),
+        )
+        .replace(
+            "{{reference}}",
+            &python_string(&reference.display().to_string()),
+        )
+        .replace("{{seed}}", &seed.to_string())
+        .replace("{{cases}}", &cases.to_string())
+        .replace("{{function}}", &python_string(&property.function))
+        .replace("{{generate}}", &generate)
+}
+
+// プロパティテストで見つかった、模範解答と結果が違う入力
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub call: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+// ハーネスの出力から反例を読み取る (通った場合は None)
+pub fn counterexample(output: &str) -> Option<Counterexample> {
+    let field = |prefix: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix))
+            .map(str::to_string)
+    };
+    Some(Counterexample {
+        call: field("FAIL ")?,
+        expected: field("EXPECTED ").unwrap_or_default(),
+        actual: field("ACTUAL ").unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_properties() {
+        let source = "# learn:property merge_sorted(list[int], list[int])\n# learn:property now()\n# learn:property f(dict)\n# learn:property broken\n";
+
+        let (properties, invalid) = properties(source);
+
+        assert_eq!(
+            properties,
+            [
+                Property {
+                    function: "merge_sorted".to_string(),
+                    args: vec![
+                        ArgType::List(Box::new(ArgType::Int)),
+                        ArgType::List(Box::new(ArgType::Int))
+                    ],
+                },
+                Property {
+                    function: "now".to_string(),
+                    args: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(invalid, ["f(dict)", "broken"]);
+    }
+
+    #[test]
+    fn test_python_harness_and_counterexample() {
+        let property = Property::parse("total(list[float], bool)").unwrap();
+
+        let harness = python_harness(
+            &property,
+            Path::new("/work/a.py"),
+            Path::new("/work/solutions/a.py"),
+            50,
+            7,
+        );
+
+        assert!(harness.contains("solution = load(\"solution\", \"/work/a.py\")"));
+        assert!(harness.contains(
+            "args = [[gen_float(size) for _ in range(rng.randint(0, size))], rng.random() < 0.5]"
+        ));
+        assert!(harness.contains("for i in range(50):"));
+        assert_eq!(counterexample("OK 50\n"), None);
+        assert_eq!(
+            counterexample("FAIL total([1.5], True)\nEXPECTED 1.5\nACTUAL 0\n"),
+            Some(Counterexample {
+                call: "total([1.5], True)".to_string(),
+                expected: "1.5".to_string(),
+                actual: "0".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,218 @@
+// stats query: 実行履歴を任意の観点でまとめて集計する
+// 例: stats query --group-by section,language --metric avg_time,success_rate --since 30d
+// 観点と指標は決まった SQL の式から選ぶだけなので、利用者の入力が SQL に入ることはない (期間はパラメータで渡す)
+
+// 集計の対象にする実行 (collapse_within 秒以内に同じ内容のまま繰り返した実行は最初の1回にまとめる)
+// 保存を連打しても試行回数や成功率が水増しされないように、--collapse-repeats で使う
+pub fn executions_source(collapse_within: Option<u64>) -> String {
+    match collapse_within {
+        None => "executions".to_string(),
+        Some(seconds) => format!(
+            "(SELECT * FROM (
+                SELECT *,
+                    LAG(content_hash) OVER run AS previous_hash,
+                    LAG(executed_at) OVER run AS previous_at
+                FROM executions
+                WINDOW run AS (PARTITION BY file_path ORDER BY executed_at, id))
+             WHERE NOT (content_hash IS NOT NULL AND content_hash IS previous_hash
+                AND executed_at - previous_at <= {}))",
+            seconds
+        ),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Dimension {
+    Section,
+    Language,
+    Problem,
+    Day,
+    Week,
+    Os,
+}
+
+impl Dimension {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Section => "section",
+            Self::Language => "COALESCE(language, 'その他')",
+            Self::Problem => "file_path",
+            Self::Day => "date(executed_at, 'unixepoch', 'localtime')",
+            Self::Week => "strftime('%Y-W%W', executed_at, 'unixepoch', 'localtime')",
+            Self::Os => "os",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Metric {
+    Runs,
+    #[value(name = "success_rate")]
+    SuccessRate,
+    #[value(name = "avg_time")]
+    AvgTime,
+    #[value(name = "max_time")]
+    MaxTime,
+    #[value(name = "avg_score")]
+    AvgScore,
+    Problems,
+}
+
+impl Metric {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Runs => "実行",
+            Self::SuccessRate => "成功率",
+            Self::AvgTime => "平均時間",
+            Self::MaxTime => "最長時間",
+            Self::AvgScore => "平均点",
+            Self::Problems => "問題数",
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Runs => "COUNT(*)",
+            Self::SuccessRate => "AVG(success) * 100.0",
+            Self::AvgTime => "AVG(duration_ms)",
+            Self::MaxTime => "MAX(duration_ms)",
+            Self::AvgScore => "AVG(score)",
+            Self::Problems => "COUNT(DISTINCT file_path)",
+        }
+    }
+
+    // 値がない (採点していない実行だけの平均点など) 場合は "-"
+    pub fn format(self, value: Option<f64>) -> String {
+        let Some(value) = value else {
+            return "-".to_string();
+        };
+        match self {
+            Self::Runs => format!("{}回", value as i64),
+            Self::Problems => format!("{}問", value as i64),
+            Self::SuccessRate => format!("{:.1}%", value),
+            Self::AvgScore => format!("{:.1}点", value),
+            Self::AvgTime | Self::MaxTime if value >= 1000.0 => {
+                format!("{:.1}s", value / 1000.0)
+            }
+            Self::AvgTime | Self::MaxTime => format!("{}ms", value.round() as i64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsQuery {
+    pub group_by: Vec<Dimension>,
+    pub metrics: Vec<Metric>,
+    // この時刻 (UNIX 秒) 以降の実行だけを集計する
+    pub since: Option<i64>,
+    // 同じ内容のまま繰り返した実行をまとめる間隔 (秒)
+    pub collapse_within: Option<u64>,
+}
+
+impl StatsQuery {
+    // ?1 に since (指定がなければ i64::MIN) を渡す
+    pub fn sql(&self) -> String {
+        let columns: Vec<&str> = self
+            .group_by
+            .iter()
+            .map(|dimension| dimension.sql())
+            .chain(self.metrics.iter().map(|metric| metric.sql()))
+            .collect();
+        let mut sql = format!(
+            "SELECT {} FROM {} WHERE executed_at >= ?1",
+            columns.join(", "),
+            executions_source(self.collapse_within)
+        );
+        if !self.group_by.is_empty() {
+            let positions: Vec<String> = (1..=self.group_by.len()).map(|i| i.to_string()).collect();
+            sql.push_str(&format!(
+                " GROUP BY {} ORDER BY {}",
+                positions.join(", "),
+                positions.join(", ")
+            ));
+        }
+        sql
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryRow {
+    pub keys: Vec<String>,
+    pub values: Vec<Option<f64>>,
+}
+
+// "30d" / "2w" / "12h" / "90m" を秒に直す
+pub fn parse_since(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.len().checked_sub(1)?);
+    let number: i64 = number.parse().ok().filter(|n| *n > 0)?;
+    let unit = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    number.checked_mul(unit)
+}
+
+// 1グループ1行 ("section1-basics / Go  実行 12回  成功率 75.0%")
+pub fn format_rows(query: &StatsQuery, rows: &[QueryRow]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let mut line = row.keys.join(" / ");
+        for (metric, value) in query.metrics.iter().zip(&row.values) {
+            if !line.is_empty() {
+                line.push_str("  ");
+            }
+            line.push_str(&format!("{} {}", metric.label(), metric.format(*value)));
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_and_since() {
+        let query = StatsQuery {
+            group_by: vec![Dimension::Section, Dimension::Day],
+            metrics: vec![Metric::Runs, Metric::SuccessRate],
+            since: None,
+            collapse_within: None,
+        };
+
+        assert_eq!(
+            query.sql(),
+            "SELECT section, date(executed_at, 'unixepoch', 'localtime'), COUNT(*), AVG(success) * 100.0 FROM executions WHERE executed_at >= ?1 GROUP BY 1, 2 ORDER BY 1, 2"
+        );
+        assert_eq!(parse_since("30d"), Some(30 * 86400));
+        assert_eq!(parse_since("12h"), Some(12 * 3600));
+        assert_eq!(parse_since("0d"), None);
+        assert_eq!(parse_since("30"), None);
+        assert_eq!(parse_since(""), None);
+    }
+
+    #[test]
+    fn test_format_rows() {
+        let query = StatsQuery {
+            group_by: vec![Dimension::Section, Dimension::Language],
+            metrics: vec![Metric::Runs, Metric::AvgTime, Metric::AvgScore],
+            since: None,
+            collapse_within: None,
+        };
+        let rows = [QueryRow {
+            keys: vec!["section1-basics".to_string(), "Go".to_string()],
+            values: vec![Some(12.0), Some(1530.0), None],
+        }];
+
+        assert_eq!(
+            format_rows(&query, &rows),
+            "section1-basics / Go  実行 12回  平均時間 1.5s  平均点 -\n"
+        );
+    }
+}
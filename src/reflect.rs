@@ -0,0 +1,129 @@
+use crate::syntax;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// リフレクトモード: 失敗した直後は同じファイルの再実行を少し待たせて、エラーを読むよう促す
+// コードが実質的に変わっていれば (空白・コメント以外のトークンが違えば) すぐに実行する
+pub struct ReflectGate {
+    delay: Duration,
+    failures: HashMap<PathBuf, Failure>,
+}
+
+struct Failure {
+    at: Instant,
+    fingerprint: Vec<String>,
+    // 待機中の実行がある
+    waiting: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Run,
+    // この時間待ってから実行する
+    Wait(Duration),
+    // 既に待機中の実行があるので今回は実行しない
+    AlreadyWaiting,
+}
+
+impl ReflectGate {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            failures: HashMap::new(),
+        }
+    }
+
+    pub fn check(&mut self, path: &Path, source: &str, now: Instant) -> Decision {
+        let Some(failure) = self.failures.get_mut(path) else {
+            return Decision::Run;
+        };
+        if failure.waiting {
+            return Decision::AlreadyWaiting;
+        }
+        let elapsed = now.duration_since(failure.at);
+        if elapsed >= self.delay || failure.fingerprint != fingerprint(path, source) {
+            return Decision::Run;
+        }
+        failure.waiting = true;
+        Decision::Wait(self.delay - elapsed)
+    }
+
+    // 待機が終わったら呼ぶ
+    pub fn finish_wait(&mut self, path: &Path) {
+        if let Some(failure) = self.failures.get_mut(path) {
+            failure.waiting = false;
+        }
+    }
+
+    pub fn record_failure(&mut self, path: &Path, source: &str, now: Instant) {
+        self.failures.insert(
+            path.to_path_buf(),
+            Failure {
+                at: now,
+                fingerprint: fingerprint(path, source),
+                waiting: false,
+            },
+        );
+    }
+
+    pub fn record_success(&mut self, path: &Path) {
+        self.failures.remove(path);
+    }
+}
+
+// 空白・コメントを除いたトークン列
+fn fingerprint(path: &Path, source: &str) -> Vec<String> {
+    let python = path.extension().is_some_and(|ext| ext == "py");
+    syntax::tokenize(source, python)
+        .into_iter()
+        .map(|token| token.text)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "package main\n\nfunc main() {\n    println(1)\n}\n";
+
+    #[test]
+    fn test_waits_when_only_whitespace_or_comments_changed() {
+        let path = Path::new("a.go");
+        let mut gate = ReflectGate::new(Duration::from_secs(10));
+        let failed_at = Instant::now();
+        gate.record_failure(path, SOURCE, failed_at);
+
+        let resaved = format!("// まだ直していない\n{}\n", SOURCE);
+        assert_eq!(
+            gate.check(path, &resaved, failed_at + Duration::from_secs(3)),
+            Decision::Wait(Duration::from_secs(7))
+        );
+        // 待機中に保存しても実行は1回だけ
+        assert_eq!(
+            gate.check(path, SOURCE, failed_at + Duration::from_secs(4)),
+            Decision::AlreadyWaiting
+        );
+    }
+
+    #[test]
+    fn test_runs_immediately_after_meaningful_change() {
+        let path = Path::new("a.go");
+        let mut gate = ReflectGate::new(Duration::from_secs(10));
+        let failed_at = Instant::now();
+        gate.record_failure(path, SOURCE, failed_at);
+
+        let fixed = SOURCE.replace("println(1)", "println(2)");
+        assert_eq!(
+            gate.check(path, &fixed, failed_at + Duration::from_secs(1)),
+            Decision::Run
+        );
+        assert_eq!(
+            gate.check(path, SOURCE, failed_at + Duration::from_secs(11)),
+            Decision::Run
+        );
+
+        gate.record_success(path);
+        assert_eq!(gate.check(path, SOURCE, failed_at), Decision::Run);
+    }
+}
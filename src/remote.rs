@@ -0,0 +1,191 @@
+use crate::files;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::process::Command;
+
+// --remote user@host:/path の監視
+// 保存のたびに監視しているディレクトリを rsync でリモートに送り、実行するコマンドを ssh 越しにリモートのディレクトリで実行する
+// 出力はローカルで受け取るので、採点・履歴・XP はローカルで監視しているときと同じ
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    // ssh の接続先 (user@host)
+    pub host: String,
+    // リモートの課題のディレクトリ
+    pub dir: String,
+    // ローカルで監視しているディレクトリ (絶対パス)
+    pub local_root: PathBuf,
+}
+
+impl FromStr for Remote {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some((host, dir)) if !host.is_empty() && !dir.is_empty() => Ok(Self {
+                host: host.to_string(),
+                dir: dir.trim_end_matches('/').to_string(),
+                local_root: PathBuf::new(),
+            }),
+            _ => Err(format!(
+                "リモートは user@host:/path の形で指定してください: {}",
+                value
+            )),
+        }
+    }
+}
+
+impl Remote {
+    pub fn with_local_root(self, local_root: &Path) -> Self {
+        Self {
+            local_root: local_root.to_path_buf(),
+            ..self
+        }
+    }
+
+    // 変更したファイルをリモートに送る (rsync は変わったファイルだけ送る)
+    pub async fn sync(&self) -> Result<(), String> {
+        let mut command = Command::new("rsync");
+        command.args(["-az", "--exclude", ".git/", "-e", "ssh -o BatchMode=yes"]);
+        // 仮想環境はマシンごとに作り直す
+        for dir in files::ENVIRONMENT_DIRS {
+            command.arg("--exclude").arg(format!("{}/", dir));
+        }
+        let output = command
+            .arg(format!("{}/", self.local_root.display()))
+            .arg(format!("{}:{}/", self.host, self.dir))
+            .output()
+            .await
+            .map_err(|e| format!("rsync を実行できません: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "リモートに送れません: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    // ローカルで実行するはずだったコマンドを、リモートの同じ場所で実行する ssh コマンドにする
+    pub fn wrap(&self, command: &Command) -> Command {
+        let command = command.as_std();
+        let dir = command
+            .get_current_dir()
+            .map(|dir| self.remote_path(&dir.display().to_string()))
+            .unwrap_or_else(|| self.dir.clone());
+        let mut script = format!("cd {} &&", shell_quote(&dir));
+        let envs: Vec<String> = command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some(format!(
+                    "{}={}",
+                    key.to_str()?,
+                    shell_quote(&self.remote_path(value?.to_str()?))
+                ))
+            })
+            .collect();
+        if !envs.is_empty() {
+            script.push_str(" env ");
+            script.push_str(&envs.join(" "));
+        }
+        for word in std::iter::once(command.get_program()).chain(command.get_args()) {
+            script.push(' ');
+            script.push_str(&shell_quote(&self.remote_path(&word.to_string_lossy())));
+        }
+
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes", &self.host, "--", &script]);
+        ssh
+    }
+
+    // 監視しているディレクトリの中のパスをリモートのパスにする (それ以外の値はそのまま)
+    pub fn remote_path(&self, value: &str) -> String {
+        if self.local_root.as_os_str().is_empty() {
+            return value.to_string();
+        }
+        match Path::new(value).strip_prefix(&self.local_root) {
+            Ok(relative) => std::iter::once(self.dir.clone())
+                .chain(
+                    relative
+                        .iter()
+                        .map(|name| name.to_string_lossy().into_owned()),
+                )
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => value.to_string(),
+        }
+    }
+}
+
+// シェルにそのまま渡せる文字列にする
+// ~/ で始まるパスはリモートのホームディレクトリに展開されるよう ~ を引用符の外に出す
+fn shell_quote(value: &str) -> String {
+    if value == "~" {
+        return value.to_string();
+    }
+    if let Some(rest) = value.strip_prefix("~/") {
+        return format!("~/{}", shell_quote(rest));
+    }
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+    {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_runs_in_remote_dir() {
+        let remote = "me@lab:/home/me/work/"
+            .parse::<Remote>()
+            .unwrap()
+            .with_local_root(Path::new("/home/local/work"));
+        assert_eq!(remote.host, "me@lab");
+        assert_eq!(remote.dir, "/home/me/work");
+        assert!("/home/me/work".parse::<Remote>().is_err());
+
+        let mut command = Command::new("go");
+        command
+            .arg("run")
+            .arg("/home/local/work/section1/it's.go")
+            .current_dir("/home/local/work/section1");
+        let ssh = remote.wrap(&command);
+        let args: Vec<String> = ssh
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-o",
+                "BatchMode=yes",
+                "me@lab",
+                "--",
+                r"cd /home/me/work/section1 && go run '/home/me/work/section1/it'\''s.go'",
+            ]
+        );
+
+        // ~ はリモートで展開させ、名前が同じ文字で始まるだけの別のディレクトリは書き換えない
+        let remote = "me@lab:~/work"
+            .parse::<Remote>()
+            .unwrap()
+            .with_local_root(Path::new("/home/local/work"));
+        let mut command = Command::new("python3");
+        command
+            .arg("/home/local/work/a b.py")
+            .arg("/home/local/workspace/data.txt")
+            .current_dir("/home/local/work");
+        let ssh = remote.wrap(&command);
+        assert_eq!(
+            ssh.as_std().get_args().last().unwrap(),
+            "cd ~/work && python3 ~/'work/a b.py' /home/local/workspace/data.txt"
+        );
+    }
+}
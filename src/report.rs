@@ -0,0 +1,283 @@
+use crate::badge;
+use crate::classroom::problem_id;
+use crate::history::HistoryStore;
+use crate::solve_time;
+use chrono::{Days, Local, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+// report weekly: 直近7日の学習のまとめ (解いた問題・連続記録・苦手なセクション・次にやること)
+// メールで送る機能はなく、Markdown か HTML をファイルか標準出力に書き出す
+
+// 苦手なセクションとみなすのに必要な実行回数
+const MIN_RUNS: i64 = 3;
+// 苦手なセクション・次にやることの最大数
+const WEAKEST: usize = 3;
+const PLAN: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Weekly {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub runs: i64,
+    pub successes: i64,
+    // 期間中に解けた問題と解答時間 (秒)
+    pub solved: Vec<(String, i64)>,
+    pub streak: u32,
+    pub study_seconds: i64,
+    // 成功率の低いセクション (セクション, 実行回数, 成功率 %)
+    pub weakest: Vec<(String, i64, f64)>,
+    pub plan: Vec<String>,
+}
+
+// today を最終日とする7日間をまとめる
+pub fn weekly(store: &HistoryStore, today: NaiveDate) -> rusqlite::Result<Weekly> {
+    let from = today - Days::new(6);
+    let timestamp = |day: NaiveDate| {
+        day.and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map_or(0, |t| t.timestamp())
+    };
+    let (since, until) = (timestamp(from), timestamp(today + Days::new(1)) - 1);
+
+    let executions = store.executions_between(since, until)?;
+    let mut sections: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    // 期間中に実行して一度も成功しなかった問題
+    let mut unfinished = BTreeSet::new();
+    let mut passed = BTreeSet::new();
+    for record in &executions {
+        let section = sections.entry(&record.section).or_default();
+        section.0 += 1;
+        section.1 += i64::from(record.success);
+        let id = problem_id(&record.file_path);
+        if record.success {
+            passed.insert(id.clone());
+            unfinished.remove(&id);
+        } else if !passed.contains(&id) {
+            unfinished.insert(id);
+        }
+    }
+
+    let solved: Vec<(String, i64)> = store
+        .solve_times()?
+        .into_iter()
+        .filter(|solve| (since..=until).contains(&solve.solved_at))
+        .map(|solve| (problem_id(&solve.file_path), solve.active_seconds))
+        .collect();
+    let (_, study_seconds) = store.progress_since(since)?;
+    let days: Vec<NaiveDate> = store
+        .study_days()?
+        .iter()
+        .filter_map(|day| day.parse().ok())
+        .collect();
+
+    let mut weakest: Vec<(String, i64, f64)> = sections
+        .into_iter()
+        .filter(|(_, (runs, _))| *runs >= MIN_RUNS)
+        .map(|(section, (runs, successes))| {
+            (
+                section.to_string(),
+                runs,
+                successes as f64 * 100.0 / runs as f64,
+            )
+        })
+        .filter(|(_, _, rate)| *rate < 100.0)
+        .collect();
+    weakest.sort_by(|a, b| a.2.total_cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+    weakest.truncate(WEAKEST);
+
+    let streak = badge::streak(&days, today);
+    let mut plan: Vec<String> = unfinished
+        .iter()
+        .map(|id| format!("{} を解き終える", id))
+        .collect();
+    plan.extend(
+        weakest.iter().map(|(section, _, rate)| {
+            format!("{} の問題を復習する (成功率 {:.1}%)", section, rate)
+        }),
+    );
+    plan.extend(
+        store
+            .deferred()?
+            .iter()
+            .map(|problem| format!("後回しにした {} に戻る", problem_id(&problem.file_path))),
+    );
+    if streak == 0 {
+        plan.push("1日1問ずつ実行して連続記録を作る".to_string());
+    }
+    plan.truncate(PLAN);
+
+    Ok(Weekly {
+        from,
+        to: today,
+        runs: executions.len() as i64,
+        successes: executions.iter().filter(|r| r.success).count() as i64,
+        solved,
+        streak,
+        study_seconds,
+        weakest,
+        plan,
+    })
+}
+
+fn duration(seconds: i64) -> String {
+    solve_time::format_duration(Duration::from_secs(seconds.max(0) as u64))
+}
+
+// 見出しと箇条書きの組 (Markdown と HTML で同じ内容を出す)
+fn contents(weekly: &Weekly) -> Vec<(&'static str, Vec<String>)> {
+    let rate = match weekly.runs {
+        0 => "-".to_string(),
+        runs => format!("{:.1}%", weekly.successes as f64 * 100.0 / runs as f64),
+    };
+    let or_none = |items: Vec<String>| match items.is_empty() {
+        true => vec!["なし".to_string()],
+        false => items,
+    };
+    vec![
+        (
+            "概要",
+            vec![
+                format!("解いた問題: {}問", weekly.solved.len()),
+                format!("連続記録: {}日", weekly.streak),
+                format!("学習時間: {}", duration(weekly.study_seconds)),
+                format!("実行回数: {}回 (成功率 {})", weekly.runs, rate),
+            ],
+        ),
+        (
+            "解いた問題",
+            or_none(
+                weekly
+                    .solved
+                    .iter()
+                    .map(|(id, seconds)| format!("{} ({})", id, duration(*seconds)))
+                    .collect(),
+            ),
+        ),
+        (
+            "苦手なセクション",
+            or_none(
+                weekly
+                    .weakest
+                    .iter()
+                    .map(|(section, runs, rate)| {
+                        format!("{} (実行 {}回、成功率 {:.1}%)", section, runs, rate)
+                    })
+                    .collect(),
+            ),
+        ),
+        ("来週やること", or_none(weekly.plan.clone())),
+    ]
+}
+
+fn title(weekly: &Weekly) -> String {
+    format!("週間レポート ({} 〜 {})", weekly.from, weekly.to)
+}
+
+pub fn render(weekly: &Weekly, format: Format) -> String {
+    match format {
+        Format::Markdown => {
+            let mut md = format!("# {}\n", title(weekly));
+            for (heading, items) in contents(weekly) {
+                md.push_str(&format!("\n## {}\n\n", heading));
+                for item in items {
+                    md.push_str(&format!("- {}\n", item));
+                }
+            }
+            md
+        }
+        Format::Html => {
+            let mut html = format!(
+                "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+                escape(&title(weekly))
+            );
+            for (heading, items) in contents(weekly) {
+                html.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape(heading)));
+                for item in items {
+                    html.push_str(&format!("<li>{}</li>\n", escape(&item)));
+                }
+                html.push_str("</ul>\n");
+            }
+            html.push_str("</body>\n</html>\n");
+            html
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use crate::history::{ExecutionRecord, SolveRecord};
+
+    #[test]
+    fn test_weekly_summarizes_last_seven_days() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let today = Local::now().date_naive();
+        let now = Local::now().timestamp();
+        let run = |file: &str, success: bool, executed_at: i64| ExecutionRecord {
+            id: 0,
+            file_path: format!("/work/section2-loops/{}", file),
+            section: "section2-loops".to_string(),
+            success,
+            exit_code: Some(i32::from(!success)),
+            duration_ms: 100,
+            executed_at,
+            output_preview: String::new(),
+            environment: Environment {
+                tool_version: None,
+                os: "linux x86_64".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: None,
+            todos_remaining: None,
+            score: None,
+            content_hash: None,
+            error_signature: None,
+            language: Some("Go".to_string()),
+            coverage: None,
+        };
+        store.insert(&run("problem01_sum.go", false, now)).unwrap();
+        store.insert(&run("problem01_sum.go", true, now)).unwrap();
+        store.insert(&run("problem02_fizz.go", false, now)).unwrap();
+        // 8日以上前の実行は含めない
+        store
+            .insert(&run("problem03_old.go", false, now - 8 * 86400))
+            .unwrap();
+        store
+            .record_solve_time(&SolveRecord {
+                file_path: "/work/section2-loops/problem01_sum.go".to_string(),
+                section: "section2-loops".to_string(),
+                first_modified_at: now - 300,
+                solved_at: now,
+                active_seconds: 300,
+            })
+            .unwrap();
+
+        let weekly = weekly(&store, today).unwrap();
+        assert_eq!((weekly.runs, weekly.successes), (3, 1));
+        assert_eq!(weekly.solved, [("section2/problem01".to_string(), 300)]);
+        assert_eq!(weekly.streak, 1);
+        assert_eq!(weekly.weakest.len(), 1);
+        assert_eq!(weekly.plan[0], "section2/problem02 を解き終える");
+
+        let md = render(&weekly, Format::Markdown);
+        assert!(md.contains("- 解いた問題: 1問\n"), "{}", md);
+        assert!(md.contains("- section2/problem01 (5分0秒)\n"));
+        let html = render(&weekly, Format::Html);
+        assert!(html.contains("<li>連続記録: 1日</li>"), "{}", html);
+    }
+}
@@ -0,0 +1,127 @@
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+// rerun コマンドから監視中のプロセスへの再実行の指示
+// 監視中のプロセスは 127.0.0.1 の空いているポートで待ち受け、ポートと合言葉をロックファイルに書く
+// rerun はロックファイルから生きているプロセスのポートを探して合言葉を送る
+// (シグナルと違い、プロセスが異常終了して PID が使い回されても別のプロセスを止めることがない)
+
+// 指示を受け取ってから返事をするまでの時間
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// 再実行を指示する先 (ロックファイルには "<ポート> <合言葉>" の1行で書く)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub port: u16,
+    pub token: String,
+}
+
+impl Endpoint {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (port, token) = line.trim().split_once(' ')?;
+        Some(Self {
+            port: port.parse().ok()?,
+            token: token.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.port, self.token)
+    }
+}
+
+// 待ち受けを始める (合言葉は OS の乱数で作り、ロックファイルを読めるユーザーだけが知っている)
+pub async fn bind() -> io::Result<(TcpListener, Endpoint)> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(io::Error::other)?;
+    let endpoint = Endpoint {
+        port: listener.local_addr()?.port(),
+        token: bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+    };
+    Ok((listener, endpoint))
+}
+
+// 合言葉が合っていれば on_rerun を呼ぶ (on_rerun が false を返したら待ち受けをやめる)
+pub fn spawn_server(
+    listener: TcpListener,
+    token: String,
+    mut on_rerun: impl FnMut() -> bool + Send + 'static,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            let mut line = String::new();
+            let read = reader.read_line(&mut line);
+            let accepted = matches!(tokio::time::timeout(TIMEOUT, read).await, Ok(Ok(_)))
+                && line.trim() == token;
+            let _ = writer
+                .write_all(if accepted { b"ok\n" } else { b"ng\n" })
+                .await;
+            if accepted && !on_rerun() {
+                break;
+            }
+        }
+    })
+}
+
+pub async fn request(endpoint: &Endpoint) -> io::Result<()> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(("127.0.0.1", endpoint.port)).await?;
+        stream
+            .write_all(format!("{}\n", endpoint.token).as_bytes())
+            .await?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).await?;
+        match reply.trim() {
+            "ok" => Ok(()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "再実行の指示を受け付けませんでした",
+            )),
+        }
+    };
+    tokio::time::timeout(TIMEOUT, exchange)
+        .await
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_request_needs_matching_token() {
+        let (listener, endpoint) = bind().await.unwrap();
+        assert_eq!(endpoint.token.len(), 32);
+        assert_ne!(bind().await.unwrap().1.token, endpoint.token);
+        assert_eq!(
+            Endpoint::parse(&endpoint.to_string()),
+            Some(endpoint.clone())
+        );
+
+        let reruns = Arc::new(AtomicUsize::new(0));
+        spawn_server(listener, endpoint.token.clone(), {
+            let reruns = reruns.clone();
+            move || {
+                reruns.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+        });
+
+        request(&endpoint).await.unwrap();
+        let wrong = Endpoint {
+            token: "guess".to_string(),
+            ..endpoint
+        };
+        assert!(request(&wrong).await.is_err());
+        assert_eq!(reruns.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::history::HistoryStore;
+use crate::llm::{self, LlmConfig, LlmResult};
+use crate::problem::{self, ProblemHeader};
+use log::error;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+
+const SYSTEM_PROMPT: &str = "You are a patient programming tutor reviewing a learner's exercise solution. \
+Reply in Japanese with a single JSON object: \
+{\"summary\": string, \"correctness\": [string], \"idiomatic\": [string]}. \
+\"correctness\" lists edge cases or bugs the learner should double-check; \
+\"idiomatic\" lists suggestions to make the code more idiomatic. Keep each item short.";
+
+// LLM によるレビュー結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Review {
+    pub summary: String,
+    // 正しさについての注意点
+    pub correctness: Vec<String>,
+    // より慣用的な書き方の提案
+    pub idiomatic: Vec<String>,
+}
+
+// 問題文 (ヘッダーのコメント) と解答を1つのプロンプトにまとめる
+pub fn user_prompt(header: &ProblemHeader, language: &str, source: &str) -> String {
+    let mut prompt = String::new();
+    if let Some(title) = &header.title {
+        prompt.push_str(&format!("Problem: {}\n", title));
+    }
+    if let Some(topic) = &header.topic {
+        prompt.push_str(&format!("Topic: {}\n", topic));
+    }
+    if let Some(section) = &header.section {
+        prompt.push_str(&format!("Section: {}\n", section));
+    }
+    for todo in &header.todos {
+        prompt.push_str(&format!("Task: {}\n", todo));
+    }
+    prompt.push_str(&format!("\n```{}\n{}\n```\n", language, source.trim_end()));
+    prompt
+}
+
+// 解答をレビューする (同じ内容・同じモデルなら履歴DBのキャッシュを使う)
+pub async fn run(
+    config: &LlmConfig,
+    history: Option<&Mutex<HistoryStore>>,
+    path: &Path,
+    source: &str,
+) -> LlmResult<Review> {
+    let language = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let prompt = user_prompt(&problem::parse(source), language, source);
+    let key = cache_key(&config.model, &prompt);
+
+    let cached = history
+        .and_then(|h| h.lock().ok())
+        .and_then(|store| store.cached_response(&key).ok().flatten());
+    if let Some(response) = cached {
+        return Ok(parse(&response));
+    }
+
+    let response = {
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || llm::complete(&config, SYSTEM_PROMPT, &prompt))
+            .await??
+    };
+
+    if let Some(store) = history.and_then(|h| h.lock().ok())
+        && let Err(e) = store.cache_response(&key, &response, chrono::Utc::now().timestamp())
+    {
+        error!("レビュー結果を保存できません: {}", e);
+    }
+    Ok(parse(&response))
+}
+
+// 同じ内容を何度もレビューに出さないためのキャッシュキー
+pub fn cache_key(model: &str, prompt: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(model)
+        .chain_update([0])
+        .chain_update(prompt)
+        .finalize();
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// JSON で返ってこなかった場合は応答全体を summary として扱う
+pub fn parse(response: &str) -> Review {
+    let body = response
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let Ok(value) = serde_json::from_str::<Value>(body) else {
+        return Review {
+            summary: response.trim().to_string(),
+            ..Default::default()
+        };
+    };
+
+    let list = |key: &str| {
+        value[key]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    Review {
+        summary: value["summary"].as_str().unwrap_or_default().to_string(),
+        correctness: list("correctness"),
+        idiomatic: list("idiomatic"),
+    }
+}
+
+pub fn print(review: &Review) {
+    println!("=== コードレビュー ===");
+    if !review.summary.is_empty() {
+        println!("{}", review.summary);
+    }
+    if !review.correctness.is_empty() {
+        println!("\n[正しさの確認]");
+        for item in &review.correctness {
+            println!("- {}", item);
+        }
+    }
+    if !review.idiomatic.is_empty() {
+        println!("\n[より良い書き方]");
+        for item in &review.idiomatic {
+            println!("- {}", item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_review_in_code_fence() {
+        let response = "```json\n{\"summary\": \"概ね良い\", \"correctness\": [\"空のスライス\"], \"idiomatic\": []}\n```";
+
+        assert_eq!(
+            parse(response),
+            Review {
+                summary: "概ね良い".to_string(),
+                correctness: vec!["空のスライス".to_string()],
+                idiomatic: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text_review() {
+        assert_eq!(parse(" 良いと思います \n").summary, "良いと思います");
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_model_and_prompt() {
+        let prompt = user_prompt(&ProblemHeader::default(), "go", "package main\n");
+
+        assert_eq!(cache_key("m", &prompt), cache_key("m", &prompt));
+        assert_ne!(cache_key("m", &prompt), cache_key("other", &prompt));
+        assert_eq!(cache_key("m", &prompt).len(), 64);
+    }
+}
@@ -0,0 +1,2434 @@
+use crate::api::{self, ApiRequest};
+use crate::assets;
+use crate::availability::Availability;
+use crate::cases::{self, CaseResult};
+use crate::concurrency;
+use crate::coverage;
+use crate::directive;
+use crate::dom;
+use crate::environment;
+use crate::events::{Event, EventBus};
+use crate::exam;
+use crate::grader::{self, CheckKind, Grade, Submission};
+use crate::handler::{self, Handler, Registry};
+use crate::history::{
+    self, ExecutionRecord, HintKind, HistoryStore, LastOutput, SolveRecord, XpEvent, XpReason,
+};
+use crate::journal::Journal;
+use crate::knowledge;
+use crate::link;
+use crate::llm::LlmConfig;
+use crate::metrics;
+use crate::normalize::{self, Rule};
+use crate::notebook;
+use crate::outbox;
+use crate::problem;
+use crate::project::{self, ProjectContext, ProjectType, PythonEnv};
+use crate::property::{self, Property};
+use crate::reflect::{Decision, ReflectGate};
+use crate::remote::Remote;
+use crate::review;
+use crate::sandbox;
+use crate::snapshot::SnapshotRecorder;
+use crate::solve_time::{self, SolveTracker};
+use crate::stacktrace::{self, Frame, RuntimeError};
+use crate::summary::RunStats;
+use crate::toolchain::{self, Requirement, Version};
+use crate::upgrade;
+use crate::xp::{self, Level};
+use log::{error, warn};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use which::which;
+
+// 各実行で共有する設定と状態
+#[derive(Default)]
+pub struct RunContext {
+    // 実行するテスト名 (ファイル中の learn:target より優先)
+    pub target: Option<String>,
+    // テスト実行時にカバレッジを計測する
+    pub coverage: bool,
+    // 成功した解答の複雑さを表示する
+    pub complexity: bool,
+    // 成功した解答を LLM にレビューしてもらう (--review かつ LLM の設定がある場合)
+    pub review: Option<LlmConfig>,
+    // 実行履歴 (DBを開けなかった場合は記録しない)
+    pub history: Option<Mutex<HistoryStore>>,
+    // 履歴に保存するまでの実行記録の控え
+    pub journal: Option<Journal>,
+    // 履歴に残す出力の先頭部分の文字数 (None なら history::PREVIEW_CHARS)
+    pub preview_chars: Option<usize>,
+    // 出力の全文も圧縮して履歴に保存する
+    pub store_output: bool,
+    // 問題ごとの解答時間の計測
+    pub solve_tracker: Mutex<SolveTracker>,
+    // --reflect のときだけ失敗直後の再実行を待たせる
+    pub reflect: Option<Mutex<ReflectGate>>,
+    // --snapshots のときだけ保存ごとの差分を記録する
+    pub snapshots: Option<Mutex<SnapshotRecorder>>,
+    // grade コマンドで一括実行している (変更なしでも実行する)
+    pub batch: bool,
+    // 起動してからの実行結果 (--summary 用)
+    pub stats: Mutex<RunStats>,
+    // 実行の開始・終了や問題が解けたことを知らせる先 (効果音などが受け取る)
+    pub events: EventBus,
+    // focus コマンドで1つのファイルに集中している
+    pub focus: bool,
+    // go run / go test に付けるフラグ (例: -race)
+    pub go_flags: Vec<String>,
+    // python に付けるインタプリタのオプション (例: -X dev)
+    pub python_args: Vec<String>,
+    // 設定ファイルの言語ごとの制限時間 (learn:timeout が優先)
+    pub go_timeout: Option<Duration>,
+    pub python_timeout: Option<Duration>,
+    // 拡張子ごとの実行方法 (設定ファイルの [handlers] を含む)
+    pub handlers: Registry,
+    // 実行コマンドがインストールされているかの確認結果の控え
+    pub availability: Mutex<Availability>,
+    // --remote ならリモートのマシンで実行する
+    pub remote: Option<Remote>,
+    // 失敗したらエラーの行をエディタで開く
+    pub jump_to_error: bool,
+    // エラーの発生箇所を OSC 8 のハイパーリンクで表示する
+    pub hyperlinks: bool,
+}
+
+// 1回の実行結果 (grade コマンドのレポート用)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub path: PathBuf,
+    pub success: bool,
+    pub duration: Duration,
+    // 成功時は標準出力、失敗時は標準エラー出力
+    pub output: String,
+    pub score: Option<u8>,
+}
+
+// 実行しなかった場合 (対象外・時間切れ・変更なしなど) は None
+pub async fn run_if_target_file(path: PathBuf, ctx: Arc<RunContext>) -> Option<RunOutcome> {
+    let extension = match path.extension().and_then(|s| s.to_str()) {
+        Some(ext) => ext,
+        None => {
+            error!("拡張子がありません: {}", path.display());
+            return None;
+        }
+    };
+
+    let handler = ctx.handlers.get(extension)?;
+    let built_in = handler.source == handler::Source::BuiltIn;
+
+    // go.mod / pyproject.toml がある場合はプロジェクトのルートで実行する
+    let context = project::detect(&path);
+    let file_path = match context.project_root {
+        Some(_) => std::path::absolute(&path).unwrap_or_else(|_| path.clone()),
+        None => path.clone(),
+    };
+
+    // 試験中は時間切れ後の実行を受け付けず、ヒントとレビューも出さない
+    let active_exam = ctx
+        .history
+        .as_ref()
+        .and_then(|h| h.lock().ok())
+        .and_then(|store| store.active_exam().ok().flatten());
+    if let Some(exam) = &active_exam {
+        let now = chrono::Utc::now().timestamp();
+        if exam::is_over(exam, now) && path_in(&path, &exam.assignment) {
+            println!("⏰ 試験時間は終了しています (exam finish で結果を確定してください)");
+            return None;
+        }
+        println!(
+            "⏱ 試験の残り時間: {}",
+            solve_time::format_duration(Duration::from_secs(
+                exam::remaining_seconds(exam, now) as u64
+            ))
+        );
+    }
+    let in_exam = active_exam.is_some();
+
+    let mut source = read_source(&path, extension).await;
+    record_snapshot(&ctx, &path, &source);
+    if !in_exam {
+        print_problem_update(&ctx, &path);
+    }
+
+    if !ctx.batch && unchanged_since_failure(&ctx, &path, &source, in_exam) {
+        return None;
+    }
+
+    let decision = ctx
+        .reflect
+        .as_ref()
+        .and_then(|gate| gate.lock().ok())
+        .map(|mut gate| gate.check(&path, &source, Instant::now()));
+    match decision {
+        Some(Decision::AlreadyWaiting) => return None,
+        Some(Decision::Wait(wait)) => {
+            reflect_countdown(wait).await;
+            if let Some(mut gate) = ctx.reflect.as_ref().and_then(|g| g.lock().ok()) {
+                gate.finish_wait(&path);
+            }
+            // 待っている間の変更も含めて実行する
+            source = read_source(&path, extension).await;
+        }
+        _ => {}
+    }
+
+    // learn:interpreter と learn:requires から実行するコマンドを選ぶ (要件を満たせない場合は実行しない)
+    // 設定ファイルのハンドラはそのコマンドで実行する (リモートで実行するときはリモートの PATH から探す)
+    let program = if ctx.remote.is_some() {
+        handler.command.clone()
+    } else if built_in {
+        resolve_program(
+            &source,
+            &handler.command,
+            &path,
+            &context,
+            &ctx.availability,
+        )
+        .await?
+    } else if is_installed(&ctx.availability, &handler.command) {
+        handler.command.clone()
+    } else {
+        if warn_missing(&ctx.availability, &handler.command) {
+            error!(
+                "コマンドが見つかりません: {} ([handlers.{}] の設定を確認してください。インストールしたら doctor --refresh で確認し直せます)",
+                handler.command, extension
+            );
+        }
+        return None;
+    };
+
+    // 実行対象のテスト (CLI引数 > ファイル中の learn:target)
+    // テストを指定して実行できないファイルではファイル全体を実行する
+    let run_target = ctx
+        .target
+        .clone()
+        .or_else(|| directive::find(&source, "target"))
+        .filter(|_| {
+            let supported = built_in && supports_target(extension, &path, &context);
+            if !supported {
+                warn!(
+                    "このファイルではテストを指定して実行できないため、ファイル全体を実行します: {} (Go は go.mod のあるディレクトリか _test.go のファイル、Python は pytest のテストだけ)",
+                    path.display()
+                );
+            }
+            supported
+        });
+
+    let (flags, timeout) = run_settings(&ctx, handler, &source, &context);
+
+    // learn:serve の問題はサーバーとして起動して learn:request を送る (テストを実行しているときは使わない)
+    let server = match directive::find(&source, "serve") {
+        Some(_) if ctx.remote.is_some() => {
+            warn!("リモートで実行するときは learn:serve を使えません (通常の実行をします)");
+            None
+        }
+        Some(value) if run_target.is_none() => match value.parse::<u16>() {
+            Ok(port) if port > 0 => Some(port),
+            _ => {
+                warn!("learn:serve を解釈できません: {}", value);
+                None
+            }
+        },
+        _ => None,
+    };
+    let (requests, invalid) = api::requests(&source);
+    for value in invalid {
+        warn!("learn:request を解釈できません: {}", value);
+    }
+
+    // 試験中は課題のファイルをネットワークから切り離して実行する
+    // (learn:serve の問題はリクエストを送れなくなり、--remote ではリモートのマシンで実行するので使わない)
+    let sandboxed = active_exam
+        .as_ref()
+        .is_some_and(|exam| path_in(&path, &exam.assignment))
+        && server.is_none()
+        && ctx.remote.is_none()
+        && sandbox::is_available();
+
+    let mut command = in_sandbox(
+        sandboxed,
+        on_remote(
+            &ctx,
+            handler_command(
+                handler,
+                &program,
+                &file_path,
+                &context,
+                run_target.as_deref(),
+                ctx.coverage,
+                &flags,
+            )?,
+        ),
+    );
+    if let Some(remote) = &ctx.remote
+        && let Err(e) = remote.sync().await
+    {
+        error!("{}", e);
+        return None;
+    }
+
+    if ctx.focus {
+        let run_number = ctx.stats.lock().map(|stats| stats.runs + 1).unwrap_or(1);
+        print_focus_header(&path, &source, run_target.as_deref(), run_number);
+    } else {
+        match &run_target {
+            Some(name) => println!("実行中: {} ({}) [{}]", path.display(), name, context),
+            None => println!("実行中: {} [{}]", path.display(), context),
+        }
+    }
+
+    ctx.events
+        .publish(Event::ExecutionStarted { path: path.clone() });
+    let started = Instant::now();
+    let (result, api_results) = match server {
+        Some(port) => match run_server(&mut command, port, &requests).await {
+            Ok((output, results)) => (Ok((output, None)), results),
+            Err(e) => (Err(e), Vec::new()),
+        },
+        None => (output_with_timeout(&mut command, timeout).await, Vec::new()),
+    };
+    let duration = started.elapsed();
+
+    match result {
+        Ok((output, timed_out)) => {
+            // ノートブックはセルごとの出力を取り出して通常の実行結果と同じように扱う
+            let (output, cells) = match extension {
+                "ipynb" => notebook_output(output),
+                _ => (output, Vec::new()),
+            };
+            // 失敗したときはエラー出力から例外の型と発生箇所を読み取る
+            let runtime_error = (!output.status.success())
+                .then(|| stacktrace::parse(extension, &String::from_utf8_lossy(&output.stderr)))
+                .flatten();
+            // 採点やレビューの途中で終了しても実行が失われないよう、先に記録を作ってジャーナルに書く
+            let mut record = ExecutionRecord {
+                error_signature: runtime_error.as_ref().map(RuntimeError::signature),
+                ..execution_record(
+                    &ctx,
+                    &path,
+                    &context,
+                    &output,
+                    duration,
+                    &source,
+                    environment::capture(&program).await,
+                )
+            };
+            let journal_entry = write_journal(&ctx, &record);
+            if output.status.success() {
+                println!("✅ 成功: {}", path.display());
+                println!("=== 実行結果 ===============\n");
+                println!("{}", String::from_utf8_lossy(&output.stdout));
+                println!("\n===========================\n");
+                print_coverage(extension, &output.stdout);
+                if ctx.complexity {
+                    print_metrics(extension, &source, &path);
+                }
+                if let Some(config) = ctx.review.as_ref().filter(|_| !in_exam) {
+                    queue_review(&ctx, config, &path, &source);
+                }
+            } else {
+                eprintln!("❌ 失敗: {}", path.display());
+                eprintln!("=== エラー ===============\n");
+                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+                eprintln!("\n===========================\n");
+                if let Some(error) = &runtime_error {
+                    let location = error
+                        .primary_frame()
+                        .map(|frame| (frame, frame_path(frame, &context)));
+                    print_runtime_error(error, location.as_ref(), ctx.hyperlinks);
+                    if ctx.jump_to_error
+                        && let Some((frame, path)) = &location
+                        && let Err(e) = link::jump_to(path, frame.line, frame.column)
+                    {
+                        error!("エディタでエラーの行を開けません: {}", e);
+                    }
+                }
+                if let Some(diagnosis) =
+                    concurrency::diagnose(&String::from_utf8_lossy(&output.stderr), timed_out)
+                {
+                    for (index, line) in concurrency::explain(&diagnosis).iter().enumerate() {
+                        println!("{}{}", if index == 0 { "🧵 " } else { "   " }, line);
+                    }
+                }
+                if !in_exam
+                    && let Some(entry) =
+                        knowledge::lookup_error(&String::from_utf8_lossy(&output.stderr))
+                {
+                    println!("💡 ヒント: {} (詳しくは hint コマンド)", entry.title);
+                }
+                print_coverage(extension, &output.stdout);
+            }
+            if let Some(mut gate) = ctx.reflect.as_ref().and_then(|g| g.lock().ok()) {
+                if output.status.success() {
+                    gate.record_success(&path);
+                } else {
+                    gate.record_failure(&path, &source, Instant::now());
+                }
+            }
+            let comparison = comparison_rules(&source);
+            let expected = reference_output(&ctx, &program, &path, &source, &flags, timeout).await;
+            let grade = grade_submission(
+                extension,
+                &source,
+                &output,
+                expected.as_deref(),
+                &comparison,
+                &cells,
+            );
+
+            // テストを実行しているときは入出力のケースは使わない
+            let (cases, invalid) = cases::cases(&source, path.parent().unwrap_or(Path::new(".")));
+            for value in invalid {
+                warn!("learn:case を解釈できません: {}", value);
+            }
+            if run_target.is_none() && !cases.is_empty() {
+                let results = run_cases(&cases, &comparison, timeout, || {
+                    handler_command(handler, &program, &file_path, &context, None, false, &flags)
+                        .map(|command| in_sandbox(sandboxed, on_remote(&ctx, command)))
+                })
+                .await;
+                println!(
+                    "=== テストケース: {}/{} ===",
+                    results.iter().filter(|result| result.passed).count(),
+                    results.len()
+                );
+                print!("{}", cases::format_table(&results));
+                record_case_results(&ctx, &path, &results);
+            }
+            if server.is_some() && !api_results.is_empty() {
+                println!(
+                    "=== API: {}/{} ===",
+                    api_results.iter().filter(|result| result.passed).count(),
+                    api_results.len()
+                );
+                print!("{}", cases::format_table(&api_results));
+                record_case_results(&ctx, &path, &api_results);
+            }
+
+            let (properties, invalid) = property::properties(&source);
+            for value in invalid {
+                warn!("learn:property を解釈できません: {}", value);
+            }
+            if output.status.success() && run_target.is_none() && !properties.is_empty() {
+                run_properties(
+                    extension,
+                    &path,
+                    &source,
+                    &properties,
+                    timeout,
+                    ctx.remote.as_ref(),
+                    |harness| {
+                        build_command("py", &program, harness, &context, None, false, &flags)
+                            .map(|command| in_sandbox(sandboxed, on_remote(&ctx, command)))
+                    },
+                )
+                .await;
+            }
+            let score = grade.map(|grade| grade.score);
+            record.score = score.map(i64::from);
+            // 先に書いたジャーナルには点数がないので、採点が終わったら書き直す
+            if let (Some(journal), Some(entry), Some(_)) = (&ctx.journal, &journal_entry, score)
+                && let Err(e) = journal.rewrite(entry, &record)
+            {
+                error!("ジャーナルに書き込めません: {}", e);
+            }
+            let outcome = RunOutcome {
+                path: path.clone(),
+                success: output.status.success(),
+                duration,
+                output: String::from_utf8_lossy(if output.status.success() {
+                    &output.stdout
+                } else {
+                    &output.stderr
+                })
+                .into_owned(),
+                score,
+            };
+            if let Ok(mut stats) = ctx.stats.lock() {
+                stats.record_run(&path.display().to_string(), outcome.success);
+            }
+            ctx.events.publish(Event::ExecutionFinished {
+                path: path.clone(),
+                success: outcome.success,
+                duration,
+            });
+            let difficulty = problem::parse(&source).difficulty;
+            record_execution(&ctx, &path, &output, record, journal_entry, difficulty);
+            Some(outcome)
+        }
+        Err(e) => {
+            eprintln!("実行エラー: {:?} ({})", e, path.display());
+            ctx.events.publish(Event::ExecutionFinished {
+                path: path.clone(),
+                success: false,
+                duration,
+            });
+            None
+        }
+    }
+}
+
+// challenge で作ったバグ入りの解答を、結果を表示せずに実行して採点する
+// 正常終了し、learn:check と learn:case がすべて通れば true (テストは指定せずファイル全体を実行する)
+// 終わらなくなった解答も失敗として扱えるよう、制限時間がなければ CHALLENGE_TIMEOUT で打ち切る
+pub async fn passes(path: &Path, ctx: &RunContext) -> Option<bool> {
+    let extension = path.extension().and_then(|s| s.to_str())?;
+    let handler = ctx.handlers.get(extension)?;
+    let context = project::detect(path);
+    let file_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let source = read_source(path, extension).await;
+    let program = match handler.source {
+        handler::Source::BuiltIn => {
+            resolve_program(&source, &handler.command, path, &context, &ctx.availability).await?
+        }
+        _ if is_installed(&ctx.availability, &handler.command) => handler.command.clone(),
+        _ => return None,
+    };
+    let (flags, timeout) = run_settings(ctx, handler, &source, &context);
+    let timeout = timeout.or(Some(CHALLENGE_TIMEOUT));
+
+    let mut command =
+        handler_command(handler, &program, &file_path, &context, None, false, &flags)?;
+    let (output, timed_out) = output_with_timeout(&mut command, timeout).await.ok()?;
+    let (output, cells) = match extension {
+        "ipynb" => notebook_output(output),
+        _ => (output, Vec::new()),
+    };
+    if !output.status.success() || timed_out.is_some() {
+        return Some(false);
+    }
+
+    let comparison = comparison_rules(&source);
+    let expected = reference_output(ctx, &program, path, &source, &flags, timeout).await;
+    let (checks, _) = grader::checks(&source);
+    let (rules, tolerance) = &comparison;
+    let graded = grader::grade(
+        &checks,
+        &Submission {
+            extension,
+            source: &source,
+            success: true,
+            stdout: &String::from_utf8_lossy(&output.stdout),
+            expected: expected.as_deref(),
+            normalize: rules,
+            tolerance: *tolerance,
+            cells: &cells,
+        },
+    )
+    .is_none_or(|grade| grade.score == 100);
+
+    let (cases, _) = cases::cases(&source, path.parent().unwrap_or(Path::new(".")));
+    let results = run_cases(&cases, &comparison, timeout, || {
+        handler_command(handler, &program, &file_path, &context, None, false, &flags)
+    })
+    .await;
+    Some(graded && results.iter().all(|result| result.passed))
+}
+
+// レビューは待ち行列に入れて outbox のタスクに任せ、LLM の応答を待たずに次の実行へ進む
+fn queue_review(ctx: &RunContext, config: &LlmConfig, path: &Path, source: &str) {
+    let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok()) else {
+        // 履歴 DB がなければ待ち行列に入れられないので、送り直さずにその場で依頼する (結果は待たない)
+        let (config, path, source) = (config.clone(), path.to_path_buf(), source.to_string());
+        tokio::spawn(async move {
+            match review::run(&config, None, &path, &source).await {
+                Ok(result) => review::print(&result),
+                Err(e) => error!("レビューに失敗しました: {}", e),
+            }
+        });
+        return;
+    };
+    let message = outbox::Message::Review {
+        file_path: std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string(),
+        source: source.to_string(),
+    };
+    match outbox::enqueue(&store, &message, chrono::Utc::now().timestamp()) {
+        Ok(true) => println!("🤖 レビューを依頼しました (届いたら表示します)"),
+        Ok(false) => warn!(
+            "送信待ちが{}件たまっているのでレビューを依頼しません (outbox で確認できます)",
+            outbox::CAPACITY
+        ),
+        Err(e) => error!("レビューを依頼できません: {}", e),
+    }
+}
+
+// 実行に付けるフラグと制限時間
+fn run_settings(
+    ctx: &RunContext,
+    handler: &Handler,
+    source: &str,
+    context: &ProjectContext,
+) -> (Vec<String>, Option<Duration>) {
+    let built_in = handler.source == handler::Source::BuiltIn;
+    let extension = handler.extension.as_str();
+    // 言語ごとのフラグ (ファイル中の learn:flags > CLI引数)
+    let mut flags = match directive::find(source, "flags") {
+        Some(flags) => split_flags(&flags),
+        None if !built_in => handler.args.clone(),
+        None => match extension {
+            "go" => ctx.go_flags.clone(),
+            "py" => ctx.python_args.clone(),
+            _ => Vec::new(),
+        },
+    };
+
+    // 並行処理の問題ではデータ競合を検出し、終わらない実行は短い時間で打ち切る
+    let concurrency =
+        built_in && extension == "go" && concurrency::is_concurrency_section(&context.section);
+    if concurrency && !flags.iter().any(|flag| flag == "-race") {
+        flags.push("-race".to_string());
+    }
+    let timeout = match directive::find(source, "timeout") {
+        Some(value) => match value.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 => Some(Duration::from_secs_f64(seconds)),
+            _ => {
+                warn!("learn:timeout を解釈できません: {}", value);
+                None
+            }
+        },
+        None => match extension {
+            "go" => ctx.go_timeout,
+            "py" => ctx.python_timeout,
+            _ => None,
+        }
+        .or(concurrency.then_some(concurrency::DEFAULT_TIMEOUT)),
+    };
+
+    (flags, timeout)
+}
+
+fn record_snapshot(ctx: &RunContext, path: &Path, source: &str) {
+    let Some(mut recorder) = ctx.snapshots.as_ref().and_then(|r| r.lock().ok()) else {
+        return;
+    };
+    let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok()) else {
+        return;
+    };
+    let file_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Err(e) = recorder.record(&store, &file_path, source, chrono::Utc::now().timestamp()) {
+        error!("スナップショットを保存できません: {}", e);
+    }
+}
+
+// 前回失敗したときと同じ内容なら実行せずに知らせる (回数は統計に残す)
+fn unchanged_since_failure(ctx: &RunContext, path: &Path, source: &str, in_exam: bool) -> bool {
+    let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok()) else {
+        return false;
+    };
+    let file_path = std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string();
+    let unchanged = match store.latest_execution(&file_path) {
+        Ok(Some(latest)) => {
+            !latest.success && latest.content_hash == Some(history::content_hash(source))
+        }
+        Ok(None) => false,
+        Err(e) => {
+            error!("実行履歴を読み込めません: {}", e);
+            false
+        }
+    };
+    if !unchanged {
+        return false;
+    }
+
+    if in_exam {
+        println!(
+            "🔁 前回失敗したときから変更されていません: {}",
+            path.display()
+        );
+    } else {
+        println!(
+            "🔁 前回失敗したときから変更されていません: {} (hint コマンドでヒントを見られます)",
+            path.display()
+        );
+    }
+    if let Err(e) = store.record_resubmission(&file_path, chrono::Utc::now().timestamp()) {
+        error!("再提出を記録できません: {}", e);
+    }
+    true
+}
+
+// 画面を消して問題文を一番上に表示し、その下に1行だけの実行ヘッダーを出す
+fn print_focus_header(path: &Path, source: &str, run_target: Option<&str>, run_number: usize) {
+    print!("\x1b[2J\x1b[H");
+    let header = problem::parse(source);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match (&header.title, &header.topic) {
+        (Some(title), Some(topic)) => println!("📌 {} [{}]", title, topic),
+        (Some(title), None) => println!("📌 {}", title),
+        _ => println!("📌 {}", file_name),
+    }
+    for todo in &header.todos {
+        println!("   TODO: {}", todo);
+    }
+    println!("{}", "─".repeat(40));
+    println!(
+        "▶ #{} {} {}{}",
+        run_number,
+        chrono::Local::now().format("%H:%M:%S"),
+        file_name,
+        run_target
+            .map(|name| format!(" ({})", name))
+            .unwrap_or_default()
+    );
+}
+
+// 残り秒数を1秒ごとに表示しながら待つ
+async fn reflect_countdown(wait: Duration) {
+    println!("🤔 コードが変わっていません。エラーメッセージをもう一度読んでみましょう");
+    let mut remaining = wait.as_secs_f64().ceil() as u64;
+    while remaining > 0 {
+        print!("\r   あと{}秒で実行します ", remaining);
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        remaining -= 1;
+    }
+    println!();
+}
+
+// 書き出したときからカリキュラムの問題が更新されていれば知らせる
+fn print_problem_update(ctx: &RunContext, path: &Path) {
+    let Some((section, problem)) = link::id_for(path).and_then(|id| link::parse(&id)) else {
+        return;
+    };
+    let Some((_, contents)) = assets::find_go_problem(&section, &problem) else {
+        return;
+    };
+    let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok()) else {
+        return;
+    };
+    let file_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let Ok(Some(recorded)) = store.problem_version(&file_path.display().to_string()) else {
+        return;
+    };
+    let latest = upgrade::problem_version(contents);
+    if recorded.version != latest.version {
+        println!(
+            "📦 この問題は新しい版があります (v{} → v{}): upgrade-problem {}/{} で解答に取り込めます",
+            recorded.version, latest.version, section, problem
+        );
+    }
+}
+
+fn path_in(path: &Path, dir: &str) -> bool {
+    std::path::absolute(path).is_ok_and(|path| path.starts_with(dir))
+}
+
+// 問題のソース (ノートブックはコードセルをつなげたもの)
+async fn read_source(path: &Path, extension: &str) -> String {
+    let raw = tokio::fs::read_to_string(path).await.unwrap_or_default();
+    match extension {
+        "ipynb" => notebook::code(&raw),
+        _ => raw,
+    }
+}
+
+// nbconvert が出力した実行後のノートブックを、セルごとの出力をまとめた実行結果にする
+// エラーになったセルがあれば失敗として扱う (読み取れなければそのまま返す)
+fn notebook_output(output: Output) -> (Output, Vec<String>) {
+    let Some(cells) = notebook::outputs(&String::from_utf8_lossy(&output.stdout)) else {
+        return (output, Vec::new());
+    };
+    let (stdout, stderr) = notebook::render(&cells);
+    let status = if cells.iter().any(|cell| cell.error.is_some()) {
+        exit_status(1)
+    } else {
+        output.status
+    };
+    let texts = cells.into_iter().map(|cell| cell.text).collect();
+    (
+        Output {
+            status,
+            stdout: stdout.into_bytes(),
+            stderr: stderr.into_bytes(),
+        },
+        texts,
+    )
+}
+
+fn exit_status(code: i32) -> std::process::ExitStatus {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        std::process::ExitStatus::from_raw(code << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        std::process::ExitStatus::from_raw(code as u32)
+    }
+}
+
+// 拡張子に対応する組み込みの実行コマンド
+pub fn command_for_extension(extension: &str) -> Option<&'static str> {
+    handler::BUILT_IN
+        .iter()
+        .find(|(built_in, _)| *built_in == extension)
+        .map(|(_, command)| *command)
+}
+
+// 実行履歴に記録する言語名
+pub fn language_for_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "go" => Some("Go"),
+        "py" | "ipynb" => Some("Python"),
+        "html" => Some("HTML"),
+        "lua" => Some("Lua"),
+        _ => None,
+    }
+}
+
+// コマンドがインストールされているか (確認結果の控えを使う)
+fn is_installed(availability: &Mutex<Availability>, command: &str) -> bool {
+    match availability.lock() {
+        Ok(mut availability) => availability
+            .find(command, chrono::Utc::now().timestamp())
+            .is_some(),
+        Err(_) => which(command).is_ok(),
+    }
+}
+
+// 見つからないことを初めて警告するときだけ true
+fn warn_missing(availability: &Mutex<Availability>, command: &str) -> bool {
+    availability
+        .lock()
+        .map(|mut availability| availability.warn_missing(command))
+        .unwrap_or(true)
+}
+
+// learn:interpreter で指定されたコマンド (見つからなければ python3.12 → python3 → python の順)
+// のうち、インストールされていて learn:requires を満たす最初のものを選ぶ
+// learn:interpreter がなければ問題の近くの仮想環境 (venv / uv / conda) を使う
+async fn resolve_program(
+    source: &str,
+    command_name: &str,
+    path: &Path,
+    context: &ProjectContext,
+    availability: &Mutex<Availability>,
+) -> Option<String> {
+    let requested = directive::find(source, "interpreter")
+        .map(|value| split_flags(&value))
+        .unwrap_or_default();
+    if requested.is_empty() {
+        match &context.python_env {
+            Some(PythonEnv::Venv(python)) => {
+                let program = python.display().to_string();
+                if let Some(requirement) = directive::find(source, "requires")
+                    && !toolchain_satisfied(&requirement, &program, path).await
+                {
+                    return None;
+                }
+                return Some(program);
+            }
+            // uv / conda は環境の中の python で実行する (コマンドの有無は build_command で確認する)
+            Some(PythonEnv::Uv(_) | PythonEnv::Conda(_)) => return Some(command_name.to_string()),
+            None => {}
+        }
+    }
+    let mut chain = toolchain::fallback_chain(&requested, command_name);
+    // ブラウザはディストリビューションによってコマンド名が違う
+    if command_name == "chromium" {
+        for name in dom::BROWSERS {
+            if !chain.iter().any(|c| c == name) {
+                chain.push(name.to_string());
+            }
+        }
+    }
+    let installed: Vec<&String> = chain
+        .iter()
+        .filter(|name| is_installed(availability, name))
+        .collect();
+    let Some(first) = installed.first() else {
+        // 同じ警告を保存のたびに繰り返さない
+        if warn_missing(availability, &chain.join(", ")) {
+            error!(
+                "コマンドが見つかりません: {} (必要な実行環境がインストールされていません。インストールしたら doctor --refresh で確認し直せます)",
+                chain.join(", ")
+            );
+        }
+        return None;
+    };
+
+    let program = match directive::find(source, "requires") {
+        Some(requirement) => {
+            let parsed = Requirement::parse(&requirement);
+            let mut chosen = None;
+            for name in &installed {
+                let version = environment::tool_version(name).await;
+                let matches = match (&parsed, version.as_deref().and_then(Version::find_in)) {
+                    (Some(parsed), Some(version)) => parsed.matches(&version),
+                    // 要件かバージョンを読めない場合は toolchain_satisfied で警告する
+                    _ => true,
+                };
+                if matches {
+                    chosen = Some(name.to_string());
+                    break;
+                }
+            }
+            // どれも満たさない場合は最初のコマンドで要件を満たさない理由を表示する
+            let program = chosen.unwrap_or_else(|| first.to_string());
+            if !toolchain_satisfied(&requirement, &program, path).await {
+                return None;
+            }
+            program
+        }
+        None => first.to_string(),
+    };
+
+    if let Some(wanted) = requested.first()
+        && *wanted != program
+    {
+        warn!(
+            "{} が見つからないか要件を満たさないため {} で実行します ({})",
+            wanted,
+            program,
+            path.display()
+        );
+    }
+    Some(program)
+}
+
+// インストールされているバージョンが要件を満たすか
+// バージョンを確認できない場合は警告だけ出して実行を続ける
+pub async fn toolchain_satisfied(requirement: &str, command_name: &str, path: &Path) -> bool {
+    let Some(requirement) = Requirement::parse(requirement) else {
+        warn!(
+            "learn:requires の書式が正しくありません: {} ({})",
+            requirement,
+            path.display()
+        );
+        return true;
+    };
+
+    let installed = environment::tool_version(command_name).await;
+    match installed.as_deref().and_then(Version::find_in) {
+        Some(version) if requirement.matches(&version) => true,
+        Some(version) => {
+            error!(
+                "{} の実行には {} が必要です (インストール済み: {} {})",
+                path.display(),
+                requirement,
+                command_name,
+                version
+            );
+            false
+        }
+        None => {
+            warn!(
+                "{} のバージョンを確認できません (要件: {})",
+                command_name, requirement
+            );
+            true
+        }
+    }
+}
+
+// 解答の行数・複雑度と、learn:reference で指定された模範解答との比較
+fn print_metrics(extension: &str, source: &str, path: &Path) {
+    let python = matches!(extension, "py" | "ipynb");
+    let mine = metrics::measure(source, python);
+    println!(
+        "=== 解答の指標: {}行, 複雑度 {}, ネスト {} ===",
+        mine.lines, mine.complexity, mine.max_nesting
+    );
+
+    // 模範解答のパスは問題ファイルからの相対パス
+    let reference = directive::find(source, "reference").and_then(|reference| {
+        let reference_path = path.parent().unwrap_or(Path::new(".")).join(&reference);
+        match std::fs::read_to_string(&reference_path) {
+            Ok(reference_source) => Some(metrics::measure(&reference_source, python)),
+            Err(e) => {
+                warn!(
+                    "模範解答を読み込めません: {} ({})",
+                    reference_path.display(),
+                    e
+                );
+                None
+            }
+        }
+    });
+
+    for message in metrics::feedback(&mine, reference.as_ref()) {
+        println!("💡 {}", message);
+    }
+}
+
+// learn:check が書かれていれば採点して結果を表示する
+fn grade_submission(
+    extension: &str,
+    source: &str,
+    output: &Output,
+    expected: Option<&str>,
+    (rules, tolerance): &(Vec<Rule>, Option<f64>),
+    cells: &[String],
+) -> Option<Grade> {
+    let (checks, invalid) = grader::checks(source);
+    for value in invalid {
+        warn!("learn:check を解釈できません: {}", value);
+    }
+
+    let grade = grader::grade(
+        &checks,
+        &Submission {
+            extension,
+            source,
+            success: output.status.success(),
+            stdout: &String::from_utf8_lossy(&output.stdout),
+            expected,
+            normalize: rules,
+            tolerance: *tolerance,
+            cells,
+        },
+    )?;
+    let dimensions: Vec<String> = grade
+        .by_dimension()
+        .iter()
+        .map(|(dimension, passed, total)| format!("{} {}/{}", dimension, passed, total))
+        .collect();
+    println!(
+        "=== 採点: {}/100 ({}) ===",
+        grade.score,
+        dimensions.join(", ")
+    );
+    for result in &grade.results {
+        println!(
+            "{} [{}] {}: {}",
+            if result.passed { "✅" } else { "❌" },
+            result.check.weight,
+            result.check.kind.dimension(),
+            result.check.kind
+        );
+    }
+    Some(grade)
+}
+
+// 出力を比べる前の正規化 (learn:normalize) と数値の許容誤差 (learn:tolerance)
+fn comparison_rules(source: &str) -> (Vec<Rule>, Option<f64>) {
+    let (rules, invalid) = normalize::rules(source);
+    for value in invalid {
+        warn!("learn:normalize を解釈できません: {}", value);
+    }
+    let tolerance = directive::find(source, "tolerance").and_then(|value| {
+        let tolerance = value.parse::<f64>().ok().filter(|t| *t >= 0.0);
+        if tolerance.is_none() {
+            warn!("learn:tolerance を解釈できません: {}", value);
+        }
+        tolerance
+    });
+    (rules, tolerance)
+}
+
+// learn:case のケースごとに入力を標準入力に渡して実行し、期待する出力と比べる
+// (timeout はケースごとの制限時間)
+async fn run_cases(
+    cases: &[cases::Case],
+    comparison: &(Vec<Rule>, Option<f64>),
+    timeout: Option<Duration>,
+    command: impl Fn() -> Option<Command>,
+) -> Vec<CaseResult> {
+    let (rules, tolerance) = comparison;
+    let mut results = Vec::new();
+    for case in cases {
+        let started = Instant::now();
+        let outcome = match (
+            tokio::fs::read(&case.input).await,
+            tokio::fs::read_to_string(&case.expected).await,
+        ) {
+            (Ok(input), Ok(expected)) => match run_with_input(command(), &input, timeout).await {
+                Ok((_, Some(limit))) => Err(format!(
+                    "制限時間 ({}秒) を超えたので打ち切りました",
+                    limit.as_secs_f64()
+                )),
+                Ok((output, None)) if !output.status.success() => {
+                    Err(format!("異常終了しました ({})", output.status))
+                }
+                Ok((output, None)) => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    if grader::matches_expected(&stdout, &expected, rules, *tolerance) {
+                        Ok(())
+                    } else {
+                        Err("出力が期待と違います".to_string())
+                    }
+                }
+                Err(e) => Err(format!("実行できません ({})", e)),
+            },
+            (Err(e), _) => Err(format!("入力を読み込めません ({})", e)),
+            (_, Err(e)) => Err(format!("期待する出力を読み込めません ({})", e)),
+        };
+        results.push(CaseResult {
+            name: case.name.clone(),
+            passed: outcome.is_ok(),
+            duration_ms: started.elapsed().as_millis() as i64,
+            detail: outcome.err(),
+        });
+    }
+    results
+}
+
+// timeout を過ぎたら打ち切る (打ち切った場合は制限時間も返す)
+// go run はビルドしたプログラムを子プロセスで動かすので、プロセスグループごと終了させる
+async fn output_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<(Output, Option<Duration>)> {
+    let Some(limit) = timeout else {
+        return Ok((command.output().await?, None));
+    };
+
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = read_pipe(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read_pipe(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let (status, timed_out) = match tokio::time::timeout(limit, child.wait()).await {
+        Ok(status) => (status?, None),
+        Err(_) => (kill_group(&mut child).await?, Some(limit)),
+    };
+    Ok((
+        Output {
+            status,
+            stdout: stdout.await.unwrap_or_default(),
+            stderr: stderr.await.unwrap_or_default(),
+        },
+        timed_out,
+    ))
+}
+
+// 終了を待たずにパイプの内容を読み続ける (パイプが詰まって子プロセスが止まらないように)
+fn read_pipe(
+    pipe: Option<Box<dyn tokio::io::AsyncRead + Send + Unpin>>,
+) -> tokio::task::JoinHandle<Vec<u8>> {
+    tokio::spawn(async move {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = tokio::io::AsyncReadExt::read_to_end(&mut pipe, &mut buffer).await;
+        }
+        buffer
+    })
+}
+
+// process_group(0) で起動した子プロセスをグループごと終了させる
+async fn kill_group(child: &mut Child) -> std::io::Result<std::process::ExitStatus> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--"])
+            .arg(format!("-{}", pid))
+            .status()
+            .await;
+    }
+    let _ = child.kill().await;
+    child.wait().await
+}
+
+// learn:serve の問題: プログラムをサーバーとして起動し、ポートが開いたら learn:request を順に送り、
+// 最後にサーバーを終了させる (すべての応答が期待どおりなら成功)
+async fn run_server(
+    command: &mut Command,
+    port: u16,
+    requests: &[ApiRequest],
+) -> std::io::Result<(Output, Vec<CaseResult>)> {
+    // 前の実行のサーバーなどが残っていると、そちらにリクエストを送ってしまう
+    if tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .is_ok()
+    {
+        return Err(std::io::Error::other(format!(
+            "ポート {} はすでに使われています",
+            port
+        )));
+    }
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = read_pipe(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read_pipe(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let ready = wait_for_port(&mut child, port).await?;
+    let mut results = Vec::new();
+    if ready {
+        for request in requests {
+            let started = Instant::now();
+            let sent = request.clone();
+            let outcome = match tokio::task::spawn_blocking(move || api::send(port, &sent)).await {
+                Ok(Ok(response)) => request.verify(&response),
+                Ok(Err(e)) => Err(format!("リクエストに失敗しました ({})", e)),
+                Err(e) => Err(format!("リクエストに失敗しました ({})", e)),
+            };
+            results.push(CaseResult {
+                name: request.to_string(),
+                passed: outcome.is_ok(),
+                duration_ms: started.elapsed().as_millis() as i64,
+                detail: outcome.err(),
+            });
+        }
+    }
+
+    // 先に終了していた場合 (コンパイルエラーなど) はその終了コードを使う
+    let exited = child.try_wait()?;
+    let status = match exited {
+        Some(status) => status,
+        None => {
+            kill_group(&mut child).await?;
+            let passed = ready && results.iter().all(|result| result.passed);
+            exit_status(if passed { 0 } else { 1 })
+        }
+    };
+    let mut stderr = stderr.await.unwrap_or_default();
+    if !ready && exited.is_none() {
+        stderr.extend_from_slice(
+            format!(
+                "{}秒以内にポート {} で接続を受け付けませんでした\n",
+                api::STARTUP_TIMEOUT.as_secs(),
+                port
+            )
+            .as_bytes(),
+        );
+    }
+    Ok((
+        Output {
+            status,
+            stdout: stdout.await.unwrap_or_default(),
+            stderr,
+        },
+        results,
+    ))
+}
+
+// ポートが開くまで待つ (先にプロセスが終了した場合と時間切れの場合は false)
+async fn wait_for_port(child: &mut Child, port: u16) -> std::io::Result<bool> {
+    let deadline = Instant::now() + api::STARTUP_TIMEOUT;
+    while Instant::now() < deadline {
+        if child.try_wait()?.is_some() {
+            return Ok(false);
+        }
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return Ok(true);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(false)
+}
+
+// input を標準入力に渡して実行する
+// timeout を過ぎたらプロセスグループごと打ち切る (打ち切った場合は制限時間も返す)
+async fn run_with_input(
+    command: Option<Command>,
+    input: &[u8],
+    timeout: Option<Duration>,
+) -> std::io::Result<(Output, Option<Duration>)> {
+    let mut command = command.ok_or_else(|| std::io::Error::other("実行コマンドがありません"))?;
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = read_pipe(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read_pipe(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_vec();
+        // 入力を読まずに終了・停止するプログラムもあるので、書き込みは待たずに失敗も無視する
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+    }
+
+    let (status, timed_out) = match timeout {
+        Some(limit) => match tokio::time::timeout(limit, child.wait()).await {
+            Ok(status) => (status?, None),
+            Err(_) => (kill_group(&mut child).await?, Some(limit)),
+        },
+        None => (child.wait().await?, None),
+    };
+    Ok((
+        Output {
+            status,
+            stdout: stdout.await.unwrap_or_default(),
+            stderr: stderr.await.unwrap_or_default(),
+        },
+        timed_out,
+    ))
+}
+
+// challenge で制限時間の指定がない解答を打ち切るまでの時間 (比較の演算子を変えると終わらなくなることがある)
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// プロパティテストで試す入力の数と乱数のシード (反例を再現できるように固定する)
+const PROPERTY_CASES: u32 = 100;
+const PROPERTY_SEED: u64 = 1;
+
+// learn:property ごとにハーネスを作り、ランダムな入力で模範解答と結果を比べる
+// command はハーネスのファイルを実行するコマンドを作る (timeout は learn:property ごとの制限時間)
+async fn run_properties(
+    extension: &str,
+    path: &Path,
+    source: &str,
+    properties: &[Property],
+    timeout: Option<Duration>,
+    remote: Option<&Remote>,
+    command: impl Fn(&Path) -> Option<Command>,
+) {
+    if extension != "py" {
+        warn!("learn:property は Python の問題だけで使えます");
+        return;
+    }
+    let Some(reference) = directive::find(source, "reference") else {
+        warn!("learn:property には learn:reference が必要です");
+        return;
+    };
+    let reference_path = path.parent().unwrap_or(Path::new(".")).join(&reference);
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let (mut solution, mut reference) = (absolute(path), absolute(&reference_path));
+    // --remote では解答と模範解答をリモートの場所で読み込み、ハーネスは標準入力で渡す
+    if let Some(remote) = remote {
+        let on_remote =
+            |path: &Path| PathBuf::from(remote.remote_path(&path.display().to_string()));
+        (solution, reference) = (on_remote(&solution), on_remote(&reference));
+    }
+
+    for property in properties {
+        let harness = property::python_harness(
+            property,
+            &solution,
+            &reference,
+            PROPERTY_CASES,
+            PROPERTY_SEED,
+        );
+        let result = match remote {
+            Some(_) => {
+                let Some(command) = command(Path::new("-")) else {
+                    return;
+                };
+                run_with_input(Some(command), harness.as_bytes(), timeout).await
+            }
+            None => {
+                // 監視しているディレクトリの外に置く (ハーネスの作成で実行が始まらないように)
+                let harness_path = std::env::temp_dir().join(format!(
+                    "learn-property-{}.py",
+                    &history::content_hash(&harness)[..16]
+                ));
+                if let Err(e) = tokio::fs::write(&harness_path, &harness).await {
+                    error!("プロパティテストのハーネスを作成できません: {}", e);
+                    return;
+                }
+                let Some(command) = command(&harness_path) else {
+                    return;
+                };
+                let result = run_with_input(Some(command), &[], timeout).await;
+                let _ = tokio::fs::remove_file(&harness_path).await;
+                result
+            }
+        };
+
+        let output = match result {
+            Ok((_, Some(limit))) => {
+                println!(
+                    "❌ プロパティテスト: {} が制限時間 ({}秒) を超えたので打ち切りました",
+                    property.function,
+                    limit.as_secs_f64()
+                );
+                continue;
+            }
+            Ok((output, None)) => output,
+            Err(e) => {
+                error!("プロパティテストを実行できません: {}", e);
+                return;
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if output.status.success() {
+            println!(
+                "✅ プロパティテスト: {} ({}件の入力で模範解答と一致)",
+                property.function, PROPERTY_CASES
+            );
+        } else if let Some(example) = property::counterexample(&stdout) {
+            println!(
+                "❌ プロパティテスト: {} の結果が模範解答と違います",
+                property.function
+            );
+            println!("   入力: {}", example.call);
+            println!("   期待: {}", example.expected);
+            println!("   実際: {}", example.actual);
+        } else {
+            warn!(
+                "プロパティテストに失敗しました: {} ({})",
+                property.function,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+}
+
+fn record_case_results(ctx: &RunContext, path: &Path, results: &[CaseResult]) {
+    let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok()) else {
+        return;
+    };
+    let file_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Err(e) = store.record_case_results(
+        &file_path.display().to_string(),
+        results,
+        chrono::Utc::now().timestamp(),
+    ) {
+        error!("テストケースの結果を保存できません: {}", e);
+    }
+}
+
+// learn:check reference-output の期待する出力 (learn:reference の模範解答を実行した標準出力)
+// 模範解答の内容ごとに履歴 DB にキャッシュするので、テンプレートが変わったときだけ実行し直す
+async fn reference_output(
+    ctx: &RunContext,
+    program: &str,
+    path: &Path,
+    source: &str,
+    flags: &[String],
+    timeout: Option<Duration>,
+) -> Option<String> {
+    let (checks, _) = grader::checks(source);
+    if !checks
+        .iter()
+        .any(|check| check.kind == CheckKind::ReferenceOutput)
+    {
+        return None;
+    }
+    let Some(reference) = directive::find(source, "reference") else {
+        warn!("learn:check reference-output には learn:reference が必要です");
+        return None;
+    };
+
+    let reference_path = path.parent().unwrap_or(Path::new(".")).join(&reference);
+    let reference_source = match tokio::fs::read_to_string(&reference_path).await {
+        Ok(reference_source) => reference_source,
+        Err(e) => {
+            warn!(
+                "模範解答を読み込めません: {} ({})",
+                reference_path.display(),
+                e
+            );
+            return None;
+        }
+    };
+    let hash = history::content_hash(&reference_source);
+    if let Some(stdout) = ctx
+        .history
+        .as_ref()
+        .and_then(|h| h.lock().ok())
+        .and_then(|store| store.reference_output(&hash).ok().flatten())
+    {
+        return Some(stdout);
+    }
+
+    let extension = reference_path.extension().and_then(|s| s.to_str())?;
+    let context = project::detect(&reference_path);
+    let file_path = match context.project_root {
+        Some(_) => std::path::absolute(&reference_path).unwrap_or_else(|_| reference_path.clone()),
+        None => reference_path.clone(),
+    };
+    let handler = ctx.handlers.get(extension)?;
+    let command = on_remote(
+        ctx,
+        handler_command(handler, program, &file_path, &context, None, false, flags)?,
+    );
+    println!(
+        "模範解答を実行して期待する出力を作成します: {}",
+        reference_path.display()
+    );
+    let output = match run_with_input(Some(command), &[], timeout).await {
+        Ok((_, Some(limit))) => {
+            warn!(
+                "模範解答が制限時間 ({}秒) を超えたので打ち切りました: {}",
+                limit.as_secs_f64(),
+                reference_path.display()
+            );
+            return None;
+        }
+        Ok((output, None)) if output.status.success() => output,
+        Ok((output, None)) => {
+            warn!(
+                "模範解答の実行に失敗しました: {} ({})",
+                reference_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return None;
+        }
+        Err(e) => {
+            warn!(
+                "模範解答を実行できません: {} ({})",
+                reference_path.display(),
+                e
+            );
+            return None;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if let Some(store) = ctx.history.as_ref().and_then(|h| h.lock().ok())
+        && let Err(e) = store.save_reference_output(&hash, &stdout, chrono::Utc::now().timestamp())
+    {
+        error!("模範解答の出力を保存できません: {}", e);
+    }
+    Some(stdout)
+}
+
+// 履歴に保存する実行記録 (エラーの種類と採点の結果は呼び出し側で入れる)
+fn execution_record(
+    ctx: &RunContext,
+    path: &Path,
+    context: &ProjectContext,
+    output: &Output,
+    duration: Duration,
+    source: &str,
+    environment: environment::Environment,
+) -> ExecutionRecord {
+    let text = if output.status.success() {
+        &output.stdout
+    } else {
+        &output.stderr
+    };
+    let extension = path.extension().and_then(|s| s.to_str());
+    ExecutionRecord {
+        id: 0,
+        file_path: std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string(),
+        section: context.section.clone(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        duration_ms: duration.as_millis() as i64,
+        executed_at: chrono::Utc::now().timestamp(),
+        output_preview: history::preview(
+            &String::from_utf8_lossy(text),
+            ctx.preview_chars.unwrap_or(history::PREVIEW_CHARS),
+        ),
+        environment,
+        note: None,
+        todos_remaining: Some(problem::count_todos(source) as i64),
+        score: None,
+        content_hash: Some(history::content_hash(source)),
+        error_signature: None,
+        language: extension
+            .and_then(language_for_extension)
+            .map(str::to_string),
+        coverage: extension.filter(|_| ctx.coverage).and_then(|extension| {
+            coverage::parse(extension, &String::from_utf8_lossy(&output.stdout))
+        }),
+    }
+}
+
+// 履歴に保存しない場合は書かない
+fn write_journal(ctx: &RunContext, record: &ExecutionRecord) -> Option<PathBuf> {
+    let journal = ctx.journal.as_ref().filter(|_| ctx.history.is_some())?;
+    match journal.write(record) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            error!("ジャーナルに書き込めません: {}", e);
+            None
+        }
+    }
+}
+
+// 実行結果を履歴に保存する (保存できたらジャーナルから消す)
+fn record_execution(
+    ctx: &RunContext,
+    path: &Path,
+    output: &Output,
+    record: ExecutionRecord,
+    journal_entry: Option<PathBuf>,
+    difficulty: Option<u8>,
+) {
+    let Some(history) = &ctx.history else {
+        return;
+    };
+    let success = record.success;
+
+    // 最初に成功したときの作業時間を解答時間として保存する
+    let solve_time = match ctx.solve_tracker.lock() {
+        Ok(mut tracker) if success => tracker.finish(path),
+        _ => None,
+    };
+
+    let Ok(store) = history.lock() else {
+        return;
+    };
+    match store.insert(&record) {
+        Ok(id) => {
+            if let (Some(journal), Some(entry)) = (&ctx.journal, &journal_entry) {
+                journal.remove(entry);
+            }
+            if ctx.store_output
+                && let Err(e) = store.save_full_output(id, &output.stdout, &output.stderr)
+            {
+                error!("出力の全文を保存できません: {}", e);
+            }
+        }
+        // ジャーナルに残った記録は次に起動したときに保存する
+        Err(e) => error!("履歴を保存できません: {}", e),
+    }
+    // copy コマンドで使う全文の出力
+    let last_output = LastOutput {
+        file_path: record.file_path.clone(),
+        executed_at: record.executed_at,
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    };
+    if let Err(e) = store.save_last_output(&last_output) {
+        error!("出力を保存できません: {}", e);
+    }
+    // 削除された問題が作り直されて実行されたらリセット扱いを外す
+    if let Err(e) = store.clear_removed(&record.file_path) {
+        error!("削除済みの記録を更新できません: {}", e);
+    }
+
+    // ひな形の TODO がどれだけ残っているか
+    match store.todo_total(&record.file_path) {
+        Ok(Some(total)) if total > 0 => {
+            println!(
+                "TODO: 残り {} / {}",
+                record.todos_remaining.unwrap_or_default(),
+                total
+            )
+        }
+        Ok(_) => {}
+        Err(e) => error!("TODO の記録を読み込めません: {}", e),
+    }
+
+    // 後回しにしていた問題が解けたら保留を解除する
+    if success {
+        match store.undefer(&record.file_path) {
+            Ok(true) => println!("⏭ 保留していた問題を解きました: {}", record.file_path),
+            Ok(false) => {}
+            Err(e) => error!("保留を解除できません: {}", e),
+        }
+    }
+
+    if let Some(solve_time) = solve_time {
+        let solve_record = SolveRecord {
+            file_path: record.file_path.clone(),
+            section: record.section.clone(),
+            first_modified_at: solve_time.first_modified_at,
+            solved_at: record.executed_at,
+            active_seconds: solve_time.active.as_secs() as i64,
+        };
+        match store.record_solve_time(&solve_record) {
+            Ok(true) => println!(
+                "⏱ 解答時間: {}",
+                solve_time::format_duration(solve_time.active)
+            ),
+            Ok(false) => {}
+            Err(e) => error!("解答時間を保存できません: {}", e),
+        }
+    }
+
+    // カリキュラムの問題を初めて解いたら XP を付与する
+    if success
+        && record.section != project::PERSONAL_PROJECT_SECTION
+        && let Some(xp) = award_xp(&store, &record, difficulty)
+    {
+        ctx.events.publish(Event::ProblemPassed {
+            path: path.to_path_buf(),
+            xp,
+        });
+    }
+}
+
+fn award_xp(store: &HistoryStore, record: &ExecutionRecord, difficulty: Option<u8>) -> Option<i64> {
+    let before = match store.total_xp() {
+        Ok(total) => total,
+        Err(e) => {
+            error!("XP を読み込めません: {}", e);
+            return None;
+        }
+    };
+    let event = |reason, xp| XpEvent {
+        id: 0,
+        file_path: record.file_path.clone(),
+        reason,
+        xp,
+        awarded_at: record.executed_at,
+    };
+    let mut events = vec![event(XpReason::Solve, xp::solve_xp(difficulty))];
+    // 失敗せず、模範解答も見ずに解けた
+    let first_try = store
+        .failure_count(&record.file_path)
+        .is_ok_and(|failures| failures == 0)
+        && store
+            .hints_used(&record.file_path, record.executed_at)
+            .is_ok_and(|hints| !hints.contains(&HintKind::Solution));
+    if first_try {
+        events.push(event(XpReason::FirstTry, xp::first_try_bonus(difficulty)));
+    }
+
+    match store.award_xp(&events) {
+        Ok(true) => {
+            let gained: Vec<String> = events
+                .iter()
+                .map(|event| format!("+{} XP ({})", event.xp, event.reason.label()))
+                .collect();
+            println!("✨ {}", gained.join(" "));
+            let xp = events.iter().map(|e| e.xp).sum::<i64>();
+            let level = Level::from_xp(before + xp);
+            if level.level > Level::from_xp(before).level {
+                println!("🎉 レベルアップ! {}", level);
+            } else {
+                println!("{}", level);
+            }
+            Some(xp)
+        }
+        Ok(false) => None,
+        Err(e) => {
+            error!("XP を保存できません: {}", e);
+            None
+        }
+    }
+}
+
+// エラー出力のパスは実行したディレクトリからの相対パスのことがある (Go のコンパイルエラーなど)
+fn frame_path(frame: &Frame, context: &ProjectContext) -> PathBuf {
+    let path = PathBuf::from(&frame.file);
+    if path.is_absolute() {
+        return path;
+    }
+    match &context.project_root {
+        Some(root) => root.join(path),
+        None => std::path::absolute(&path).unwrap_or(path),
+    }
+}
+
+// "💥 NameError: name 'x' is not defined (a.py:3 main)"
+fn print_runtime_error(
+    error: &RuntimeError,
+    location: Option<&(&Frame, PathBuf)>,
+    hyperlinks: bool,
+) {
+    let location = location
+        .map(|(frame, path)| {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| frame.file.clone());
+            let position = format!("{}:{}", file, frame.line);
+            let position = if hyperlinks {
+                link::hyperlink(&link::file_url(path), &position)
+            } else {
+                position
+            };
+            let function = frame
+                .function
+                .as_ref()
+                .map(|function| format!(" {}", function))
+                .unwrap_or_default();
+            format!(" ({}{})", position, function)
+        })
+        .unwrap_or_default();
+    if error.message.is_empty() {
+        eprintln!("💥 {}{}", error.kind, location);
+    } else {
+        eprintln!("💥 {}: {}{}", error.kind, error.message, location);
+    }
+}
+
+fn print_coverage(extension: &str, stdout: &[u8]) {
+    if let Some(percent) = coverage::parse(extension, &String::from_utf8_lossy(stdout)) {
+        println!("📊 カバレッジ: {:.1}%", percent);
+    }
+}
+
+// "-race -v" のように空白区切りで指定されたフラグ
+pub fn split_flags(flags: &str) -> Vec<String> {
+    flags.split_whitespace().map(str::to_string).collect()
+}
+
+// uv / conda の環境では環境の中の python で実行する
+// (learn:interpreter で別のコマンドが選ばれた場合や、uv / conda が見つからない場合はそのまま実行する)
+fn python_command(program: &str, context: &ProjectContext) -> Command {
+    let (tool, args): (&str, Vec<String>) = match &context.python_env {
+        Some(PythonEnv::Uv(dir)) if program == "python" => (
+            "uv",
+            vec![
+                "run".to_string(),
+                "--project".to_string(),
+                dir.display().to_string(),
+                "python".to_string(),
+            ],
+        ),
+        Some(PythonEnv::Conda(name)) if program == "python" => (
+            "conda",
+            ["run", "-n", name, "--no-capture-output", "python"]
+                .map(str::to_string)
+                .to_vec(),
+        ),
+        _ => return Command::new(program),
+    };
+    if which(tool).is_err() {
+        warn!(
+            "{} が見つからないため {} の環境を使わずに実行します",
+            tool,
+            context
+                .python_env
+                .as_ref()
+                .map(|env| env.to_string())
+                .unwrap_or_default()
+        );
+        return Command::new(program);
+    }
+    let mut command = Command::new(tool);
+    command.args(args);
+    command
+}
+
+// --remote ならリモートの同じ場所で実行する ssh コマンドにする
+fn on_remote(ctx: &RunContext, command: Command) -> Command {
+    match &ctx.remote {
+        Some(remote) => remote.wrap(&command),
+        None => command,
+    }
+}
+
+// 試験中はネットワークから切り離して実行する
+fn in_sandbox(sandboxed: bool, command: Command) -> Command {
+    match sandboxed {
+        true => sandbox::wrap(&command),
+        false => command,
+    }
+}
+
+// 設定ファイルのハンドラは「コマンド 引数... ファイル」をプロジェクトのルートで実行する
+fn handler_command(
+    handler: &Handler,
+    program: &str,
+    path: &Path,
+    context: &ProjectContext,
+    run_target: Option<&str>,
+    coverage: bool,
+    flags: &[String],
+) -> Option<Command> {
+    if handler.source == handler::Source::BuiltIn {
+        return build_command(
+            &handler.extension,
+            program,
+            path,
+            context,
+            run_target,
+            coverage,
+            flags,
+        );
+    }
+    let mut command = Command::new(program);
+    command.args(flags).arg(path);
+    if let Some(root) = &context.project_root {
+        command.current_dir(root);
+    }
+    Some(command)
+}
+
+// テストを指定して実行できるか (go test -run はモジュールならパッケージ単位、それ以外は _test.go のファイルだけ)
+fn supports_target(extension: &str, path: &Path, context: &ProjectContext) -> bool {
+    match extension {
+        "py" => true,
+        "go" => {
+            context.project_type == ProjectType::GoModule
+                || path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with("_test.go"))
+        }
+        _ => false,
+    }
+}
+
+// 拡張子と実行対象から実行コマンドを組み立てる
+// run_target が指定されている場合はファイル全体ではなくテストだけを実行する (できないファイルでは無視する)
+// flags は Go なら go run / go test のフラグ、Python ならインタプリタのオプション
+// program は実行するコマンド (learn:interpreter で選んだ python3.12 など)
+fn build_command(
+    extension: &str,
+    program: &str,
+    path: &Path,
+    context: &ProjectContext,
+    run_target: Option<&str>,
+    coverage: bool,
+    flags: &[String],
+) -> Option<Command> {
+    let mut command = match extension {
+        // ノートブックはすべてのセルを実行し (エラーのセルがあっても続ける)、実行後のノートブックを標準出力に出す
+        "ipynb" => {
+            let mut command = Command::new(program);
+            command
+                .arg("nbconvert")
+                .args([
+                    "--to",
+                    "notebook",
+                    "--execute",
+                    "--allow-errors",
+                    "--stdout",
+                ])
+                .args(flags)
+                .arg(path);
+            if let Some(root) = &context.project_root {
+                command.current_dir(root);
+            }
+            return Some(command);
+        }
+        // HTML はヘッドレスブラウザで開き、スクリプトを実行した後の DOM を標準出力に出す
+        // (--virtual-time-budget でタイマーや読み込み後の処理が終わるまで待つ)
+        "html" => {
+            let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+            let mut command = Command::new(program);
+            command
+                .args([
+                    "--headless",
+                    "--disable-gpu",
+                    "--virtual-time-budget=3000",
+                    "--dump-dom",
+                ])
+                .args(flags)
+                .arg(link::file_url(&path));
+            return Some(command);
+        }
+        // Lua はインタプリタにファイルを渡すだけ (テストを指定する方法はない)
+        "lua" => {
+            let mut command = Command::new(program);
+            command.args(flags).arg(path);
+            if let Some(root) = &context.project_root {
+                command.current_dir(root);
+            }
+            return Some(command);
+        }
+        "go" => Command::new(program),
+        "py" => {
+            let mut command = python_command(program, context);
+            command.args(flags);
+            command
+        }
+        _ => return None,
+    };
+
+    if let Some(root) = &context.project_root {
+        command.current_dir(root);
+    }
+
+    let Some(name) = run_target.filter(|_| supports_target(extension, path, context)) else {
+        match extension {
+            "go" => command.arg("run").args(flags).arg(path),
+            _ => command.arg(path),
+        };
+        return Some(command);
+    };
+
+    if extension == "py" {
+        command.arg("-m").arg("pytest");
+        if coverage {
+            command.arg("--cov").arg("--cov-report=term");
+        }
+        command.arg(format!("{}::{}", path.display(), name));
+        return Some(command);
+    }
+
+    command.arg("test").args(flags);
+    if coverage {
+        command.arg("-cover");
+    }
+    command.arg("-run").arg(name);
+
+    // モジュール内ではパッケージ単位でテストする
+    if context.project_type == ProjectType::GoModule {
+        command.arg(".");
+        if let Some(dir) = path.parent() {
+            command.current_dir(dir);
+        }
+    } else {
+        command.arg(path);
+    }
+
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    // 簡易ログを無効化する
+    fn init_logger() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_with_py_file() {
+        init_logger();
+
+        // 一時Pythonファイル作成
+        let mut tmpfile = NamedTempFile::new().unwrap();
+        writeln!(tmpfile, "print('hello test')").unwrap();
+        let path = tmpfile.path().to_path_buf();
+
+        // 実行
+        run_if_target_file(path.clone(), Arc::default()).await;
+
+        // ファイルはまだ存在するはず
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_with_go_file() {
+        init_logger();
+
+        // 一時Goファイル作成
+        let mut tmpfile = NamedTempFile::new().unwrap();
+        writeln!(
+            tmpfile,
+            "package main\nimport \"fmt\"\nfunc main() {{ fmt.Println(\"hello go test\") }}"
+        )
+        .unwrap();
+        let path = tmpfile.path().to_path_buf();
+
+        run_if_target_file(path.clone(), Arc::default()).await;
+
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_with_unsupported_extension() {
+        init_logger();
+
+        let mut tmpfile = NamedTempFile::new().unwrap();
+        writeln!(tmpfile, "echo unsupported").unwrap();
+
+        // 一時ファイル名を.txtに変更
+        let path = tmpfile.path().with_extension("txt");
+
+        // 実行（何も起きない）
+        run_if_target_file(path.clone(), Arc::default()).await;
+
+        // 実行してもエラーにもならない（ただreturn）
+        assert!(path.exists() || !path.exists()); // 実行確認用ダミー
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_without_extension() {
+        init_logger();
+
+        // 一時ファイル名に拡張子なし
+        let tmpfile = NamedTempFile::new().unwrap();
+        let path = tmpfile.path().to_path_buf();
+
+        // 実行
+        run_if_target_file(path.clone(), Arc::default()).await;
+
+        // エラー出力が呼ばれるがクラッシュしない
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_command_not_found() {
+        init_logger();
+
+        // 存在しないコマンド (lua) を想定
+        let mut tmpfile = NamedTempFile::new().unwrap();
+        writeln!(tmpfile, "print('hi')").unwrap();
+
+        // ".lua" の一時ファイルを実際に作成
+        let lua_path = tmpfile.path().with_extension("lua");
+        std::fs::copy(tmpfile.path(), &lua_path).unwrap();
+
+        // Lua が未インストール環境で実行しても panic せず return することを確認
+        run_if_target_file(lua_path.clone(), Arc::default()).await;
+
+        assert!(lua_path.exists());
+    }
+
+    fn command_args(command: &Command) -> Vec<String> {
+        command
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn standalone() -> ProjectContext {
+        ProjectContext {
+            section: project::PERSONAL_PROJECT_SECTION.to_string(),
+            project_type: ProjectType::Standalone,
+            project_root: None,
+            python_env: None,
+        }
+    }
+
+    #[test]
+    fn test_build_command_runs_go_test_with_target() {
+        let path = Path::new("sum_test.go");
+
+        let command =
+            build_command("go", "go", path, &standalone(), Some("TestSum"), false, &[]).unwrap();
+
+        assert_eq!(
+            command_args(&command),
+            ["test", "-run", "TestSum", "sum_test.go"]
+        );
+    }
+
+    #[test]
+    fn test_build_command_ignores_target_go_test_cannot_run() {
+        // go.mod がなければ go test -run は _test.go のファイルにしか使えない
+        let command = build_command(
+            "go",
+            "go",
+            Path::new("main.go"),
+            &standalone(),
+            Some("TestSum"),
+            false,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(command_args(&command), ["run", "main.go"]);
+
+        let module = ProjectContext {
+            project_type: ProjectType::GoModule,
+            project_root: Some(PathBuf::from("/work/calc")),
+            ..standalone()
+        };
+        let command = build_command(
+            "go",
+            "go",
+            Path::new("/work/calc/sum.go"),
+            &module,
+            Some("TestSum"),
+            false,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(command_args(&command), ["test", "-run", "TestSum", "."]);
+
+        let command = build_command(
+            "lua",
+            "lua",
+            Path::new("a.lua"),
+            &standalone(),
+            Some("test_a"),
+            false,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(command_args(&command), ["a.lua"]);
+    }
+
+    #[test]
+    fn test_build_command_runs_pytest_with_target() {
+        let path = Path::new("test_sum.py");
+
+        let command = build_command(
+            "py",
+            "python",
+            path,
+            &standalone(),
+            Some("test_sum"),
+            false,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            command_args(&command),
+            ["-m", "pytest", "test_sum.py::test_sum"]
+        );
+    }
+
+    #[test]
+    fn test_build_command_without_target_runs_file() {
+        let path = Path::new("main.go");
+
+        let command = build_command("go", "go", path, &standalone(), None, false, &[]).unwrap();
+
+        assert_eq!(command_args(&command), ["run", "main.go"]);
+
+        let command = build_command(
+            "lua",
+            "lua",
+            Path::new("a.lua"),
+            &standalone(),
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(command_args(&command), ["a.lua"]);
+        // 組み込みのハンドラはどれも実行コマンドを組み立てられる
+        for (extension, program) in handler::BUILT_IN {
+            let path = Path::new("a").with_extension(extension);
+            assert!(
+                build_command(extension, program, &path, &standalone(), None, false, &[]).is_some(),
+                "{}",
+                extension
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_command_dumps_dom_for_html() {
+        let path = Path::new("/work/web/index.html");
+        let flags = ["--no-sandbox".to_string()];
+
+        let command =
+            build_command("html", "chromium", path, &standalone(), None, false, &flags).unwrap();
+
+        assert_eq!(
+            command_args(&command),
+            [
+                "--headless",
+                "--disable-gpu",
+                "--virtual-time-budget=3000",
+                "--dump-dom",
+                "--no-sandbox",
+                "file:///work/web/index.html"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_command_with_coverage() {
+        let go = build_command(
+            "go",
+            "go",
+            Path::new("a_test.go"),
+            &standalone(),
+            Some("TestA"),
+            true,
+            &[],
+        );
+        let py = build_command(
+            "py",
+            "python",
+            Path::new("test_a.py"),
+            &standalone(),
+            Some("test_a"),
+            true,
+            &[],
+        );
+
+        assert_eq!(
+            command_args(&go.unwrap()),
+            ["test", "-cover", "-run", "TestA", "a_test.go"]
+        );
+        assert_eq!(
+            command_args(&py.unwrap()),
+            [
+                "-m",
+                "pytest",
+                "--cov",
+                "--cov-report=term",
+                "test_a.py::test_a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_command_with_flags() {
+        let go = build_command(
+            "go",
+            "go",
+            Path::new("a_test.go"),
+            &standalone(),
+            Some("TestA"),
+            false,
+            &split_flags("-race"),
+        );
+        let py = build_command(
+            "py",
+            "python",
+            Path::new("main.py"),
+            &standalone(),
+            None,
+            false,
+            &split_flags("-X dev"),
+        );
+
+        assert_eq!(
+            command_args(&go.unwrap()),
+            ["test", "-race", "-run", "TestA", "a_test.go"]
+        );
+        assert_eq!(command_args(&py.unwrap()), ["-X", "dev", "main.py"]);
+    }
+
+    #[test]
+    fn test_build_command_ignores_coverage_without_target() {
+        let command = build_command(
+            "py",
+            "python",
+            Path::new("main.py"),
+            &standalone(),
+            None,
+            true,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(command_args(&command), ["main.py"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_records_history() {
+        init_logger();
+
+        let mut tmpfile = tempfile::Builder::new().suffix(".py").tempfile().unwrap();
+        writeln!(tmpfile, "print('recorded')").unwrap();
+        let ctx = Arc::new(RunContext {
+            history: Some(Mutex::new(HistoryStore::open_in_memory().unwrap())),
+            ..Default::default()
+        });
+
+        run_if_target_file(tmpfile.path().to_path_buf(), ctx.clone()).await;
+
+        let records = ctx
+            .history
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .recent(10)
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].success);
+        assert_eq!(records[0].output_preview, "recorded");
+        assert!(records[0].environment.tool_version.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_toolchain_satisfied() {
+        let path = Path::new("problem.py");
+
+        assert!(toolchain_satisfied("python>=3", "python", path).await);
+        assert!(!toolchain_satisfied("python>=99", "python", path).await);
+        // 書式が不正な場合は実行を止めない
+        assert!(toolchain_satisfied("python>=latest", "python", path).await);
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_skips_unsatisfied_requirement() {
+        init_logger();
+
+        let mut tmpfile = tempfile::Builder::new().suffix(".py").tempfile().unwrap();
+        writeln!(tmpfile, "# learn:requires python>=99\nprint('never')").unwrap();
+        let ctx = Arc::new(RunContext {
+            history: Some(Mutex::new(HistoryStore::open_in_memory().unwrap())),
+            ..Default::default()
+        });
+
+        run_if_target_file(tmpfile.path().to_path_buf(), ctx.clone()).await;
+
+        let records = ctx
+            .history
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .recent(10)
+            .unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_first_passing_run_records_solve_time() {
+        init_logger();
+
+        let mut tmpfile = tempfile::Builder::new().suffix(".py").tempfile().unwrap();
+        writeln!(tmpfile, "print('solved')").unwrap();
+        let path = tmpfile.path().to_path_buf();
+        let ctx = Arc::new(RunContext {
+            history: Some(Mutex::new(HistoryStore::open_in_memory().unwrap())),
+            ..Default::default()
+        });
+        ctx.solve_tracker
+            .lock()
+            .unwrap()
+            .touch(&path, Instant::now());
+
+        run_if_target_file(path.clone(), ctx.clone()).await;
+        run_if_target_file(path.clone(), ctx.clone()).await;
+
+        let store = ctx.history.as_ref().unwrap().lock().unwrap();
+        assert_eq!(store.solve_times().unwrap().len(), 1);
+        assert_eq!(store.summary(None).unwrap().executions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_if_target_file_records_score() {
+        init_logger();
+
+        let mut tmpfile = tempfile::Builder::new().suffix(".py").tempfile().unwrap();
+        writeln!(
+            tmpfile,
+            "# learn:check 60 output 6\n# learn:check 40 code sum(\nprint(1 + 2 + 3)"
+        )
+        .unwrap();
+        let ctx = Arc::new(RunContext {
+            history: Some(Mutex::new(HistoryStore::open_in_memory().unwrap())),
+            ..Default::default()
+        });
+
+        run_if_target_file(tmpfile.path().to_path_buf(), ctx.clone()).await;
+
+        let store = ctx.history.as_ref().unwrap().lock().unwrap();
+        assert_eq!(store.recent(1).unwrap()[0].score, Some(60));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_cases_stops_at_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("in.txt"), "").unwrap();
+        std::fs::write(dir.path().join("out.txt"), "").unwrap();
+        let case = cases::Case {
+            name: "loop".to_string(),
+            input: dir.path().join("in.txt"),
+            expected: dir.path().join("out.txt"),
+        };
+
+        // 終わらないケースも制限時間で打ち切って次に進む
+        let started = Instant::now();
+        let results = run_cases(
+            &[case],
+            &(Vec::new(), None),
+            Some(Duration::from_millis(200)),
+            || {
+                let mut command = Command::new("sh");
+                command.args(["-c", "sleep 30"]);
+                Some(command)
+            },
+        )
+        .await;
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(!results[0].passed);
+        assert!(results[0].detail.as_deref().unwrap().contains("制限時間"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+// 試験中の実行をネットワークから切り離すサンドボックス
+// Linux では unshare でネットワークの名前空間を分け、外部と通信できない状態で問題を実行する
+// (管理者権限は要らない。使えない環境では通常どおり実行する)
+
+// このマシンでサンドボックスを使えるか (最初の1回だけ確かめる)
+pub fn is_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(probe)
+}
+
+#[cfg(target_os = "linux")]
+fn probe() -> bool {
+    std::process::Command::new("unshare")
+        .args(["--net", "--map-root-user", "true"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn probe() -> bool {
+    false
+}
+
+// コマンドをネットワークのない名前空間で実行するコマンドにする (作業ディレクトリと環境変数は引き継ぐ)
+pub fn wrap(command: &Command) -> Command {
+    let command = command.as_std();
+    let mut sandboxed = Command::new("unshare");
+    sandboxed
+        .args(["--net", "--map-root-user", "--"])
+        .arg(command.get_program())
+        .args(command.get_args());
+    if let Some(dir) = command.get_current_dir() {
+        sandboxed.current_dir(dir);
+    }
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => sandboxed.env(key, value),
+            None => sandboxed.env_remove(key),
+        };
+    }
+    sandboxed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_keeps_args_dir_and_env() {
+        let mut command = Command::new("go");
+        command
+            .args(["run", "a.go"])
+            .current_dir("/work")
+            .env("GOFLAGS", "-mod=mod");
+
+        let wrapped = wrap(&command);
+        let wrapped = wrapped.as_std();
+        assert_eq!(wrapped.get_program(), "unshare");
+        assert_eq!(
+            wrapped
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>(),
+            ["--net", "--map-root-user", "--", "go", "run", "a.go"]
+        );
+        assert_eq!(
+            wrapped.get_current_dir(),
+            Some(std::path::Path::new("/work"))
+        );
+        assert_eq!(wrapped.get_envs().count(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::files;
+use crate::handler::Registry;
+use crate::history::HistoryStore;
+use crate::problem::{self, ProblemHeader};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// ディレクトリ配下の問題ファイルを全文検索の索引に反映する
+// 前回から更新されていないファイルは読み直さないので、2回目以降の検索はすぐに終わる
+// 対象は実行方法が決まる (組み込みか設定ファイルのハンドラがある) ファイル、戻り値は索引し直したファイル数
+pub fn update_index(
+    store: &HistoryStore,
+    handlers: &Registry,
+    dir: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    let dir = std::path::absolute(dir)?;
+    let indexed = store.indexed_problems()?;
+    let mut seen = HashSet::new();
+    let mut updated = 0;
+
+    for path in files::collect_files(&dir)? {
+        if !handlers.is_runnable(&path) {
+            continue;
+        }
+
+        let file_path = path.display().to_string();
+        seen.insert(file_path.clone());
+        if indexed.get(&file_path) == Some(&modified_at(&path)?) {
+            continue;
+        }
+        index_file(store, &path)?;
+        updated += 1;
+    }
+
+    // 削除されたファイルを索引から外す
+    for file_path in indexed.keys() {
+        if Path::new(file_path).starts_with(&dir) && !seen.contains(file_path) {
+            store.remove_problem(file_path)?;
+        }
+    }
+
+    Ok(updated)
+}
+
+fn modified_at(path: &Path) -> std::io::Result<i64> {
+    Ok(fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default())
+}
+
+// 1つのファイルを索引に登録する (監視中に作られたファイルにも使う)
+pub fn index_file(store: &HistoryStore, path: &Path) -> Result<ProblemHeader, Box<dyn Error>> {
+    let source = fs::read_to_string(path)?;
+    let header = problem::parse(&source);
+    store.index_problem(
+        &path.display().to_string(),
+        modified_at(path)?,
+        header.title.as_deref().unwrap_or_default(),
+        header.topic.as_deref().unwrap_or_default(),
+        &source,
+    )?;
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_index_skips_unchanged_and_drops_removed_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = HistoryStore::open_in_memory().unwrap();
+        fs::write(
+            tmp.path().join("problem01.go"),
+            "// Problem: Type Assertion Practice\n// Topic: Interfaces\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("problem02.py"), "# Topic: Loops\n").unwrap();
+        fs::write(tmp.path().join("notes.txt"), "type assertion").unwrap();
+
+        let handlers = Registry::default();
+        assert_eq!(update_index(&store, &handlers, tmp.path()).unwrap(), 2);
+        assert_eq!(update_index(&store, &handlers, tmp.path()).unwrap(), 0);
+        assert_eq!(store.search_problems("assertion", 10).unwrap().len(), 1);
+
+        fs::remove_file(tmp.path().join("problem01.go")).unwrap();
+        update_index(&store, &handlers, tmp.path()).unwrap();
+        assert!(store.search_problems("assertion", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+// 連続して作業していた1区間 (一時停止から再開までは別の区間になる)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecord {
+    // UNIX時間
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+impl SessionRecord {
+    pub fn active_seconds(&self) -> i64 {
+        (self.ended_at - self.started_at).max(0)
+    }
+}
+
+struct Active {
+    started_at: i64,
+    last_event_at: i64,
+    last_event: Instant,
+}
+
+// 学習セッションの計測
+// 一定時間ファイルイベントがなければ自動で一時停止し、次のイベントで再開する
+pub struct SessionClock {
+    idle_timeout: Duration,
+    active: Option<Active>,
+}
+
+impl SessionClock {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            active: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    // ファイルイベントを受け取るたびに呼ぶ
+    // 放置されていたセッションがあれば一時停止して、その区間を返す
+    pub fn touch(&mut self, now: Instant, unix_time: i64) -> Option<SessionRecord> {
+        let paused = self.pause_if_idle(now);
+        let active = self.active.get_or_insert(Active {
+            started_at: unix_time,
+            last_event_at: unix_time,
+            last_event: now,
+        });
+        active.last_event_at = unix_time;
+        active.last_event = now;
+        paused
+    }
+
+    // 最後のイベントから idle_timeout 以上経っていれば一時停止する
+    pub fn pause_if_idle(&mut self, now: Instant) -> Option<SessionRecord> {
+        let idle = self
+            .active
+            .as_ref()
+            .is_some_and(|a| now.saturating_duration_since(a.last_event) >= self.idle_timeout);
+        if idle { self.pause() } else { None }
+    }
+
+    // 放置していた時間は含めず、最後のイベントまでを1区間とする
+    pub fn pause(&mut self) -> Option<SessionRecord> {
+        self.active.take().map(|a| SessionRecord {
+            started_at: a.started_at,
+            ended_at: a.last_event_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_session_is_paused_at_last_event() {
+        let mut clock = SessionClock::new(Duration::from_secs(300));
+        let start = Instant::now();
+
+        assert_eq!(clock.touch(start, 1000), None);
+        assert_eq!(clock.touch(start + Duration::from_secs(60), 1060), None);
+        assert_eq!(clock.pause_if_idle(start + Duration::from_secs(120)), None);
+
+        // 一晩放置
+        let paused = clock.pause_if_idle(start + Duration::from_secs(8 * 3600));
+        assert_eq!(
+            paused,
+            Some(SessionRecord {
+                started_at: 1000,
+                ended_at: 1060
+            })
+        );
+        assert!(!clock.is_active());
+    }
+
+    #[test]
+    fn test_next_event_resumes_new_session() {
+        let mut clock = SessionClock::new(Duration::from_secs(300));
+        let start = Instant::now();
+        clock.touch(start, 1000);
+
+        // 一時停止の確認前にイベントが来ても、放置していた区間は分けて記録する
+        let paused = clock.touch(start + Duration::from_secs(3600), 4600);
+
+        assert_eq!(paused.map(|s| s.active_seconds()), Some(0));
+        assert!(clock.is_active());
+        assert_eq!(
+            clock.pause(),
+            Some(SessionRecord {
+                started_at: 4600,
+                ended_at: 4600
+            })
+        );
+    }
+}
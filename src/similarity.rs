@@ -0,0 +1,269 @@
+use crate::files;
+use crate::syntax::{self, TokenKind};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// k-gram のトークン数
+const K: usize = 5;
+// winnowing のウィンドウ幅
+const WINDOW: usize = 4;
+// 一致箇所をまとめるときに許容する行の隙間
+const LINE_GAP: usize = 2;
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+// 2つの提出ファイルの比較結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    // 0.0 - 1.0 (フィンガープリントの Jaccard 係数)
+    pub score: f64,
+    // 一致した行範囲 (左の開始行, 左の終了行, 右の開始行, 右の終了行)
+    pub regions: Vec<(usize, usize, usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+// ファイルのフィンガープリント (ハッシュ -> 出現行)
+#[derive(Debug, Default)]
+struct Fingerprint {
+    hashes: HashMap<u64, usize>,
+}
+
+// ディレクトリ配下の提出物を同じファイル名(=同じ問題)ごとに比較し、類似度の高い順に返す
+pub fn analyze(dir: &Path, min_score: f64) -> io::Result<Vec<Match>> {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for path in files::collect_files(dir)? {
+        if !matches!(
+            path.extension().and_then(|s| s.to_str()),
+            Some("go") | Some("py")
+        ) {
+            continue;
+        }
+        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+            groups.entry(name.to_string()).or_default().push(path);
+        }
+    }
+
+    let mut matches = Vec::new();
+    for paths in groups.values() {
+        let fingerprints = paths
+            .iter()
+            .map(|path| Ok(fingerprint(path, &fs::read_to_string(path)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        for i in 0..paths.len() {
+            for j in i + 1..paths.len() {
+                let result = compare(&paths[i], &fingerprints[i], &paths[j], &fingerprints[j]);
+                if result.score >= min_score {
+                    matches.push(result);
+                }
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(matches)
+}
+
+fn compare(left: &Path, a: &Fingerprint, right: &Path, b: &Fingerprint) -> Match {
+    let left_set: HashSet<u64> = a.hashes.keys().copied().collect();
+    let right_set: HashSet<u64> = b.hashes.keys().copied().collect();
+    let shared: Vec<u64> = left_set.intersection(&right_set).copied().collect();
+    let union = left_set.union(&right_set).count();
+
+    let score = if union == 0 {
+        0.0
+    } else {
+        shared.len() as f64 / union as f64
+    };
+
+    let mut lines: Vec<(usize, usize)> = shared
+        .iter()
+        .map(|hash| (a.hashes[hash], b.hashes[hash]))
+        .collect();
+    lines.sort_unstable();
+
+    Match {
+        left: left.to_path_buf(),
+        right: right.to_path_buf(),
+        score,
+        regions: merge_regions(&lines),
+    }
+}
+
+// 隣接する一致行をまとめて行範囲にする
+fn merge_regions(lines: &[(usize, usize)]) -> Vec<(usize, usize, usize, usize)> {
+    let mut regions: Vec<(usize, usize, usize, usize)> = Vec::new();
+    for &(l, r) in lines {
+        match regions.last_mut() {
+            Some(last)
+                if l <= last.1 + LINE_GAP && r + LINE_GAP >= last.3 && r <= last.3 + LINE_GAP =>
+            {
+                last.1 = last.1.max(l);
+                last.2 = last.2.min(r);
+                last.3 = last.3.max(r);
+            }
+            _ => regions.push((l, l, r, r)),
+        }
+    }
+    regions
+}
+
+fn fingerprint(path: &Path, source: &str) -> Fingerprint {
+    let python = path.extension().and_then(|s| s.to_str()) == Some("py");
+    let tokens = tokenize(source, python);
+    if tokens.len() < K {
+        return Fingerprint::default();
+    }
+
+    let grams: Vec<(u64, usize)> = tokens
+        .windows(K)
+        .map(|gram| (hash_gram(gram), gram[0].line))
+        .collect();
+
+    // 各ウィンドウで最小のハッシュを選ぶ (winnowing)
+    let mut fingerprint = Fingerprint::default();
+    for window in grams.windows(WINDOW.min(grams.len())) {
+        if let Some(&(hash, line)) = window.iter().min_by_key(|(hash, _)| *hash) {
+            fingerprint.hashes.entry(hash).or_insert(line);
+        }
+    }
+    fingerprint
+}
+
+// FNV-1a (実行ごとに結果が変わらないハッシュ)
+fn hash_gram(gram: &[Token]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for token in gram {
+        for byte in token.text.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+// 変数名の付け替えや空白・コメントの違いを無視できるようにトークン化する
+fn tokenize(source: &str, python: bool) -> Vec<Token> {
+    let keywords = if python { PYTHON_KEYWORDS } else { GO_KEYWORDS };
+    syntax::tokenize(source, python)
+        .into_iter()
+        .map(|token| {
+            let text = match token.kind {
+                TokenKind::Ident if keywords.contains(&token.text.as_str()) => token.text,
+                TokenKind::Ident => "I".to_string(),
+                TokenKind::Str => "S".to_string(),
+                TokenKind::Number => "N".to_string(),
+                TokenKind::Punct => token.text,
+            };
+            Token {
+                text,
+                line: token.line,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLUTION: &str = "package main\n\nimport \"fmt\"\n\nfunc sum(values []int) int {\n    total := 0\n    for _, v := range values {\n        total += v\n    }\n    return total\n}\n\nfunc main() {\n    fmt.Println(sum([]int{1, 2, 3}))\n}\n";
+
+    const RENAMED: &str = "package main\n\nimport \"fmt\"\n\n// 合計を返す\nfunc add(xs []int) int {\n    acc := 0\n    for _, x := range xs {\n        acc += x\n    }\n    return acc\n}\n\nfunc main() {\n    fmt.Println(add([]int{4, 5, 6}))\n}\n";
+
+    const DIFFERENT: &str = "package main\n\nimport \"fmt\"\n\ntype Stack struct {\n    items []string\n}\n\nfunc (s *Stack) Push(v string) {\n    s.items = append(s.items, v)\n}\n\nfunc main() {\n    s := &Stack{}\n    s.Push(\"a\")\n    if len(s.items) > 0 {\n        fmt.Println(\"ok\")\n    }\n}\n";
+
+    fn write_submission(root: &Path, student: &str, source: &str) {
+        let dir = root.join(student).join("section3-functions");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("problem01_function_basics.go"), source).unwrap();
+    }
+
+    #[test]
+    fn test_renamed_copy_is_detected() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_submission(tmp.path(), "alice", SOLUTION);
+        write_submission(tmp.path(), "bob", RENAMED);
+
+        let matches = analyze(tmp.path(), 0.0).unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].score, 1.0);
+        assert!(!matches[0].regions.is_empty());
+    }
+
+    #[test]
+    fn test_results_are_ranked_by_score() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_submission(tmp.path(), "alice", SOLUTION);
+        write_submission(tmp.path(), "bob", RENAMED);
+        write_submission(tmp.path(), "carol", DIFFERENT);
+
+        let matches = analyze(tmp.path(), 0.0).unwrap();
+
+        assert_eq!(matches.len(), 3);
+        assert!(matches[0].score > matches[1].score);
+        assert!(matches[0].left.starts_with(tmp.path().join("alice")));
+        assert!(matches[0].right.starts_with(tmp.path().join("bob")));
+    }
+
+    #[test]
+    fn test_min_score_filters_unrelated_submissions() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_submission(tmp.path(), "alice", SOLUTION);
+        write_submission(tmp.path(), "carol", DIFFERENT);
+
+        let matches = analyze(tmp.path(), 0.5).unwrap();
+
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_ignores_comments_and_names() {
+        let a = tokenize("x = 1  # comment\n", true);
+        let b = tokenize("total = 42\n", true);
+
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::history::HistoryStore;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// 保存ごとの変更 (前回の内容との差分)
+// 先頭と末尾の共通する行を除き、間の行の置き換えとして記録する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta {
+    // 置き換えが始まる行 (0 始まり)
+    pub start: usize,
+    // 削除した行 (改行を含む)
+    pub removed: Vec<String>,
+    // 追加した行 (改行を含む)
+    pub inserted: Vec<String>,
+}
+
+// 記録された1回分の変更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub taken_at: i64,
+    pub delta: Delta,
+}
+
+fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+// 変更がなければ None
+pub fn delta(old: &str, new: &str) -> Option<Delta> {
+    if old == new {
+        return None;
+    }
+    let old = lines(old);
+    let new = lines(new);
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let owned = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect();
+    Some(Delta {
+        start: prefix,
+        removed: owned(&old[prefix..old.len() - suffix]),
+        inserted: owned(&new[prefix..new.len() - suffix]),
+    })
+}
+
+pub fn apply(text: &str, delta: &Delta) -> String {
+    let lines = lines(text);
+    let start = delta.start.min(lines.len());
+    let end = (start + delta.removed.len()).min(lines.len());
+
+    let mut result = lines[..start].concat();
+    result.push_str(&delta.inserted.concat());
+    result.push_str(&lines[end..].concat());
+    result
+}
+
+// 記録された変更を順に当てた各時点の内容
+pub fn replay(snapshots: &[Snapshot]) -> Vec<String> {
+    let mut text = String::new();
+    snapshots
+        .iter()
+        .map(|snapshot| {
+            text = apply(&text, &snapshot.delta);
+            text.clone()
+        })
+        .collect()
+}
+
+// --snapshots のときに保存ごとの差分を履歴DBに記録する
+#[derive(Default)]
+pub struct SnapshotRecorder {
+    // ファイルごとの最後に記録した内容
+    latest: HashMap<PathBuf, String>,
+}
+
+impl SnapshotRecorder {
+    pub fn record(
+        &mut self,
+        store: &HistoryStore,
+        file_path: &Path,
+        source: &str,
+        taken_at: i64,
+    ) -> rusqlite::Result<()> {
+        let key = file_path.display().to_string();
+        if !self.latest.contains_key(file_path) {
+            // 前回の起動時までの記録から最後の内容を復元する
+            let previous = replay(&store.snapshots(&key)?).pop().unwrap_or_default();
+            self.latest.insert(file_path.to_path_buf(), previous);
+        }
+
+        let previous = &self.latest[file_path];
+        if let Some(delta) = delta(previous, source) {
+            store.record_snapshot(&key, taken_at, &delta)?;
+            self.latest
+                .insert(file_path.to_path_buf(), source.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_keeps_only_changed_lines() {
+        let old = "package main\n\nfunc main() {\n}\n";
+        let new = "package main\n\nfunc main() {\n    println(1)\n}\n";
+
+        let delta = delta(old, new).unwrap();
+
+        assert_eq!(
+            delta,
+            Delta {
+                start: 3,
+                removed: vec![],
+                inserted: vec!["    println(1)\n".to_string()],
+            }
+        );
+        assert_eq!(apply(old, &delta), new);
+        assert_eq!(super::delta(new, new), None);
+    }
+
+    #[test]
+    fn test_replay_and_recorder_round_trip() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let path = Path::new("/work/a.py");
+        let versions = ["print(1)\n", "x = 1\nprint(x)\n", "x = 2\nprint(x)"];
+
+        let mut recorder = SnapshotRecorder::default();
+        for (i, version) in versions.iter().enumerate() {
+            recorder.record(&store, path, version, i as i64).unwrap();
+        }
+        // 変更がなければ記録しない
+        recorder.record(&store, path, versions[2], 10).unwrap();
+
+        let snapshots = store.snapshots("/work/a.py").unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(replay(&snapshots), versions);
+
+        // 再起動後も続きから記録できる
+        let mut recorder = SnapshotRecorder::default();
+        recorder.record(&store, path, "x = 3\n", 20).unwrap();
+        assert_eq!(
+            replay(&store.snapshots("/work/a.py").unwrap())[3],
+            "x = 3\n"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// ファイルイベントがこれ以上途切れたら離席とみなす (既定値)
+pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(300);
+
+// 問題ごとの「最初の変更から最初に成功するまで」の作業時間を計測する
+// 一定時間以上ファイルイベントがない区間(離席など)は作業時間に含めない
+pub struct SolveTracker {
+    idle_threshold: Duration,
+    timers: HashMap<PathBuf, Timer>,
+}
+
+struct Timer {
+    // 最初に変更を検知した時刻 (UNIX時間)
+    first_modified_at: i64,
+    last_event: Instant,
+    active: Duration,
+}
+
+// 成功時に確定した解答時間
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveTime {
+    pub first_modified_at: i64,
+    pub active: Duration,
+}
+
+impl SolveTracker {
+    pub fn new(idle_threshold: Duration) -> Self {
+        Self {
+            idle_threshold,
+            timers: HashMap::new(),
+        }
+    }
+
+    // ファイルイベントを受け取るたびに呼ぶ
+    pub fn touch(&mut self, path: &Path, now: Instant) {
+        let timer = self.timers.entry(path.to_path_buf()).or_insert(Timer {
+            first_modified_at: chrono::Utc::now().timestamp(),
+            last_event: now,
+            active: Duration::ZERO,
+        });
+
+        let gap = now.saturating_duration_since(timer.last_event);
+        if gap <= self.idle_threshold {
+            timer.active += gap;
+        }
+        timer.last_event = now;
+    }
+
+    // ファイル (またはディレクトリ) の名前変更・移動後も計測を続ける
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<PathBuf> = self
+            .timers
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+        for path in moved {
+            let Ok(rest) = path.strip_prefix(from) else {
+                continue;
+            };
+            // join("") だと末尾に区切り文字が付くのでファイル自体の場合はそのまま使う
+            let renamed = if rest.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(rest)
+            };
+            if let Some(timer) = self.timers.remove(&path) {
+                self.timers.insert(renamed, timer);
+            }
+        }
+    }
+
+    // 成功した問題の計測を終了して結果を返す (計測していなければ None)
+    pub fn finish(&mut self, path: &Path) -> Option<SolveTime> {
+        self.timers.remove(path).map(|timer| SolveTime {
+            first_modified_at: timer.first_modified_at,
+            active: timer.active,
+        })
+    }
+}
+
+impl Default for SolveTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_THRESHOLD)
+    }
+}
+
+// "1時間2分3秒" 形式
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if hours > 0 {
+        format!("{}時間{}分{}秒", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}分{}秒", minutes, seconds)
+    } else {
+        format!("{}秒", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_time_excludes_idle_gaps() {
+        let mut tracker = SolveTracker::new(Duration::from_secs(300));
+        let path = Path::new("problem01.go");
+        let start = Instant::now();
+
+        tracker.touch(path, start);
+        tracker.touch(path, start + Duration::from_secs(60));
+        // 2時間の離席
+        tracker.touch(path, start + Duration::from_secs(60 + 7200));
+        tracker.touch(path, start + Duration::from_secs(60 + 7200 + 30));
+
+        let solve_time = tracker.finish(path).unwrap();
+        assert_eq!(solve_time.active, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn test_finish_only_once() {
+        let mut tracker = SolveTracker::new(Duration::from_secs(300));
+        let path = Path::new("problem01.py");
+        tracker.touch(path, Instant::now());
+
+        assert!(tracker.finish(path).is_some());
+        assert!(tracker.finish(path).is_none());
+    }
+
+    #[test]
+    fn test_rename_keeps_timer() {
+        let mut tracker = SolveTracker::new(Duration::from_secs(300));
+        let start = Instant::now();
+        tracker.touch(Path::new("section1/a.go"), start);
+
+        tracker.rename(Path::new("section1/a.go"), Path::new("section1/hello.go"));
+        tracker.rename(Path::new("section1"), Path::new("section01"));
+        tracker.touch(
+            Path::new("section01/hello.go"),
+            start + Duration::from_secs(40),
+        );
+
+        let solve_time = tracker.finish(Path::new("section01/hello.go")).unwrap();
+        assert_eq!(solve_time.active, Duration::from_secs(40));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42秒");
+        assert_eq!(format_duration(Duration::from_secs(750)), "12分30秒");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1時間2分3秒");
+    }
+}
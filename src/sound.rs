@@ -0,0 +1,277 @@
+use log::warn;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use which::which;
+
+// 実行結果を知らせる効果音
+// 音声ライブラリには依存せず、OS に付属のプレイヤーで WAV を再生する (見つからなければベルを鳴らす)
+// (rodio などは Linux で ALSA の開発用ヘッダーが必要になり、ビルドできない環境があるため使わない)
+pub struct Sounds {
+    success: Sound,
+    failure: Sound,
+}
+
+// 再生するファイルと、プレイヤーに指定する音量 (ファイルに音量を反映済みなら None)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sound {
+    path: PathBuf,
+    volume: Option<u8>,
+}
+
+const SAMPLE_RATE: u32 = 22050;
+
+// (周波数 Hz, 長さ ms)
+const SUCCESS_NOTES: &[(f32, u32)] = &[(1047.0, 90), (1319.0, 140)];
+const FAILURE_NOTES: &[(f32, u32)] = &[(392.0, 140), (262.0, 220)];
+
+impl Sounds {
+    // 音量 (0 - 100) を反映した効果音をデータディレクトリに用意する
+    // success / failure を指定した場合、16bit PCM の WAV なら音量を反映した複製を作り、
+    // それ以外の形式はそのまま再生してプレイヤーに音量を指定する (afplay / paplay / pw-play のみ)
+    pub fn prepare(
+        dir: &Path,
+        volume: u8,
+        success: Option<&Path>,
+        failure: Option<&Path>,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let generated = |name: &str, notes| -> io::Result<Sound> {
+            let path = dir.join(format!("{}-{}.wav", name, volume));
+            if !path.exists() {
+                std::fs::write(&path, tone_wav(notes, volume))?;
+            }
+            Ok(Sound { path, volume: None })
+        };
+        let custom = |name: &str, path: &Path| -> io::Result<Sound> {
+            let scaled = std::fs::read(path)
+                .ok()
+                .and_then(|wav| scale_wav(&wav, volume));
+            let Some(scaled) = scaled else {
+                return Ok(Sound {
+                    path: path.to_path_buf(),
+                    volume: Some(volume),
+                });
+            };
+            let copy = dir.join(format!("{}-custom-{}.wav", name, volume));
+            std::fs::write(&copy, scaled)?;
+            Ok(Sound {
+                path: copy,
+                volume: None,
+            })
+        };
+        Ok(Self {
+            success: match success {
+                Some(path) => custom("success", path)?,
+                None => generated("success", SUCCESS_NOTES)?,
+            },
+            failure: match failure {
+                Some(path) => custom("failure", path)?,
+                None => generated("failure", FAILURE_NOTES)?,
+            },
+        })
+    }
+
+    // 再生の終了は待たない
+    pub fn play(&self, success: bool) {
+        let sound = if success {
+            &self.success
+        } else {
+            &self.failure
+        };
+        let Some(mut command) = player_command(&sound.path, sound.volume) else {
+            print!("\x07");
+            return;
+        };
+        let result = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Err(e) = result {
+            warn!("効果音を再生できません: {}", e);
+        }
+    }
+}
+
+// volume はファイルに反映できなかったときだけ指定する (aplay と Windows では指定できない)
+fn player_command(path: &Path, volume: Option<u8>) -> Option<Command> {
+    let ratio = volume.map(|volume| f32::from(volume.min(100)) / 100.0);
+    match std::env::consts::OS {
+        "macos" => {
+            let mut command = Command::new("afplay");
+            if let Some(ratio) = ratio {
+                command.arg("-v").arg(ratio.to_string());
+            }
+            command.arg(path);
+            Some(command)
+        }
+        "windows" => {
+            let mut command = Command::new("powershell");
+            command.arg("-NoProfile").arg("-Command").arg(format!(
+                "(New-Object Media.SoundPlayer '{}').PlaySync()",
+                path.display().to_string().replace('\'', "''")
+            ));
+            Some(command)
+        }
+        _ => ["paplay", "aplay", "pw-play"]
+            .into_iter()
+            .find(|player| which(player).is_ok())
+            .map(|player| {
+                let mut command = Command::new(player);
+                match (player, ratio) {
+                    ("aplay", _) => {
+                        command.arg("-q");
+                    }
+                    // paplay は 65536 が 100%
+                    ("paplay", Some(ratio)) => {
+                        command.arg(format!("--volume={}", (ratio * 65536.0) as u32));
+                    }
+                    ("pw-play", Some(ratio)) => {
+                        command.arg(format!("--volume={}", ratio));
+                    }
+                    _ => {}
+                }
+                command.arg(path);
+                command
+            }),
+    }
+}
+
+// 16bit PCM の WAV の振幅を volume (0 - 100) 倍にする (それ以外の形式なら None)
+fn scale_wav(wav: &[u8], volume: u8) -> Option<Vec<u8>> {
+    if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut scaled = wav.to_vec();
+    let mut pcm16 = false;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let size = u32::from_le_bytes(wav[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8..(offset + 8 + size).min(wav.len());
+        match id {
+            // 1 は PCM、0xFFFE は拡張形式
+            b"fmt " if body.len() >= 16 => {
+                let format = u16::from_le_bytes([wav[body.start], wav[body.start + 1]]);
+                let bits = u16::from_le_bytes([wav[body.start + 14], wav[body.start + 15]]);
+                pcm16 = matches!(format, 1 | 0xFFFE) && bits == 16;
+            }
+            b"data" if pcm16 => {
+                for sample in scaled[body].chunks_exact_mut(2) {
+                    let value = i32::from(i16::from_le_bytes([sample[0], sample[1]]));
+                    let value = (value * i32::from(volume.min(100)) / 100) as i16;
+                    sample.copy_from_slice(&value.to_le_bytes());
+                }
+                return Some(scaled);
+            }
+            _ => {}
+        }
+        // チャンクは偶数バイトにそろえられている
+        offset += 8 + size + size % 2;
+    }
+    None
+}
+
+// 正弦波をつないだ 16bit モノラルの WAV
+pub fn tone_wav(notes: &[(f32, u32)], volume: u8) -> Vec<u8> {
+    let amplitude = f32::from(volume.min(100)) / 100.0 * f32::from(i16::MAX) * 0.8;
+    let mut samples: Vec<i16> = Vec::new();
+    for &(frequency, millis) in notes {
+        let count = SAMPLE_RATE * millis / 1000;
+        // 端でプツッと鳴らないように前後 5ms をフェードさせる
+        let fade = (SAMPLE_RATE / 200).min(count / 2).max(1);
+        for i in 0..count {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let envelope = (i.min(count - 1 - i) as f32 / fade as f32).min(1.0);
+            let value = (2.0 * std::f32::consts::PI * frequency * t).sin() * amplitude * envelope;
+            samples.push(value as i16);
+        }
+    }
+
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, モノラル
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_wav_header_and_volume() {
+        let wav = tone_wav(&[(440.0, 100)], 50);
+
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        // 22050Hz で 100ms = 2205 サンプル
+        let data_len = u32::from_le_bytes(wav[40..44].try_into().unwrap());
+        assert_eq!(data_len, 2205 * 2);
+        assert_eq!(wav.len(), 44 + data_len as usize);
+
+        let peak = |wav: &[u8]| {
+            wav[44..]
+                .chunks(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]).unsigned_abs())
+                .max()
+                .unwrap()
+        };
+        assert!(peak(&wav) > 10000);
+        assert_eq!(peak(&tone_wav(&[(440.0, 100)], 0)), 0);
+    }
+
+    #[test]
+    fn test_prepare_applies_volume_to_custom_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let custom = tmp.path().join("ok.wav");
+        std::fs::write(&custom, tone_wav(&[(440.0, 100)], 100)).unwrap();
+        let mp3 = tmp.path().join("ng.mp3");
+        std::fs::write(&mp3, b"ID3").unwrap();
+
+        let sounds = Sounds::prepare(tmp.path(), 30, Some(&custom), None).unwrap();
+
+        // WAV は音量を反映した複製を再生する
+        assert_eq!(sounds.success.volume, None);
+        assert!(sounds.success.path.ends_with("success-custom-30.wav"));
+        let peak = |path: &Path| {
+            std::fs::read(path).unwrap()[44..]
+                .chunks(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]).unsigned_abs())
+                .max()
+                .unwrap()
+        };
+        assert_eq!(
+            u32::from(peak(&sounds.success.path)),
+            u32::from(peak(&custom)) * 30 / 100
+        );
+        assert!(sounds.failure.path.ends_with("failure-30.wav"));
+        assert!(sounds.failure.path.exists());
+
+        // それ以外の形式はプレイヤーに音量を指定する
+        let sounds = Sounds::prepare(tmp.path(), 30, None, Some(&mp3)).unwrap();
+        assert_eq!(
+            sounds.failure,
+            Sound {
+                path: mp3,
+                volume: Some(30)
+            }
+        );
+    }
+}
@@ -0,0 +1,303 @@
+// 実行時エラー (Go の panic・コンパイルエラー、Python の Traceback) を構造化する
+// 表示・よくあるエラーの集計・エディタでのエラー行へのジャンプに使う
+
+// エラーの発生箇所 (内側の呼び出しが先)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub function: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    // 例外の型 (NameError など)、または "panic" / "runtime error" / "compile error"
+    pub kind: String,
+    pub message: String,
+    pub frames: Vec<Frame>,
+}
+
+impl RuntimeError {
+    // 標準ライブラリを除いた一番内側の発生箇所 (なければ一番内側)
+    pub fn primary_frame(&self) -> Option<&Frame> {
+        let is_library = |frame: &&Frame| {
+            frame.file.starts_with('<')
+                || ["/go/src/", "/lib/python", "site-packages", "\\Lib\\"]
+                    .iter()
+                    .any(|dir| frame.file.contains(dir))
+        };
+        self.frames
+            .iter()
+            .find(|frame| !is_library(frame))
+            .or_else(|| self.frames.first())
+    }
+
+    // 同じ種類のエラーをまとめるためのキー (数値と引用符の中身を伏せる)
+    pub fn signature(&self) -> String {
+        let mut normalized = String::new();
+        let mut quote = None;
+        let mut previous_digit = false;
+        for c in self.message.chars() {
+            match quote {
+                Some(q) if c == q => {
+                    normalized.push(c);
+                    quote = None;
+                }
+                Some(_) => {}
+                None if c == '\'' || c == '"' => {
+                    normalized.push(c);
+                    normalized.push('_');
+                    quote = Some(c);
+                }
+                None if c.is_ascii_digit() => {
+                    if !previous_digit {
+                        normalized.push('N');
+                    }
+                }
+                None => normalized.push(c),
+            }
+            previous_digit = quote.is_none() && c.is_ascii_digit();
+        }
+        if normalized.is_empty() {
+            self.kind.clone()
+        } else {
+            format!("{}: {}", self.kind, normalized)
+        }
+    }
+}
+
+// 拡張子ごとにエラー出力を読み取る (読み取れなければ None)
+pub fn parse(extension: &str, output: &str) -> Option<RuntimeError> {
+    match extension {
+        "go" => parse_go(output),
+        // ノートブックのエラーも最後の行が "NameError: ..." になる
+        "py" | "ipynb" => parse_python(output),
+        _ => None,
+    }
+}
+
+pub fn parse_go(output: &str) -> Option<RuntimeError> {
+    let lines: Vec<&str> = output.lines().collect();
+
+    if let Some(index) = lines
+        .iter()
+        .position(|line| line.starts_with("panic: ") || line.starts_with("fatal error: "))
+    {
+        let (kind, message) = lines[index].split_once(": ").unwrap_or_default();
+        let (kind, message) = match message.strip_prefix("runtime error: ") {
+            Some(message) => ("runtime error", message),
+            None => (kind, message),
+        };
+        let message = message.trim_end_matches(" [recovered]");
+
+        // 最初の goroutine (panic した goroutine) のスタックだけを読む
+        let mut frames = Vec::new();
+        let mut function = None;
+        for line in lines[index + 1..]
+            .iter()
+            .skip_while(|line| !line.starts_with("goroutine "))
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+        {
+            match line.strip_prefix('\t') {
+                Some(location) => {
+                    // "/work/main.go:8 +0x1d"
+                    let location = location.split(" +0x").next().unwrap_or(location);
+                    if let Some((file, line)) = location.rsplit_once(':')
+                        && let Ok(line) = line.parse()
+                    {
+                        frames.push(Frame {
+                            file: file.to_string(),
+                            line,
+                            column: None,
+                            function: function.take(),
+                        });
+                    }
+                }
+                None => {
+                    // "main.divide(...)" → "main.divide"
+                    let name = line.rsplit_once('(').map_or(*line, |(name, _)| name);
+                    function = Some(name.to_string());
+                }
+            }
+        }
+        return Some(RuntimeError {
+            kind: kind.to_string(),
+            message: message.to_string(),
+            frames,
+        });
+    }
+
+    // "./main.go:4:2: undefined: fmt"
+    let errors: Vec<(Frame, &str)> = lines
+        .iter()
+        .filter_map(|line| {
+            let (file, rest) = line.split_once(".go:")?;
+            let mut parts = rest.splitn(3, ':');
+            let line = parts.next()?.parse().ok()?;
+            let column = parts.next()?.parse().ok()?;
+            let message = parts.next()?.trim();
+            Some((
+                Frame {
+                    file: format!("{}.go", file),
+                    line,
+                    column: Some(column),
+                    function: None,
+                },
+                message,
+            ))
+        })
+        .collect();
+    let message = errors.first()?.1.to_string();
+    Some(RuntimeError {
+        kind: "compile error".to_string(),
+        message,
+        frames: errors.into_iter().map(|(frame, _)| frame).collect(),
+    })
+}
+
+pub fn parse_python(output: &str) -> Option<RuntimeError> {
+    let mut frames = Vec::new();
+    let mut exception = None;
+
+    for line in output.lines() {
+        // 例外の連鎖では最後の Traceback を使う
+        if line.starts_with("Traceback (most recent call last):") {
+            frames.clear();
+            exception = None;
+            continue;
+        }
+
+        // '  File "/work/a.py", line 3, in main' (SyntaxError では ", in ..." がない)
+        if let Some(rest) = line.trim_start().strip_prefix("File \"")
+            && let Some((file, rest)) = rest.split_once("\", line ")
+        {
+            let (line, function) = match rest.split_once(", in ") {
+                Some((line, function)) => (line, Some(function.to_string())),
+                None => (rest, None),
+            };
+            if let Ok(line) = line.trim().parse() {
+                frames.push(Frame {
+                    file: file.to_string(),
+                    line,
+                    column: None,
+                    function,
+                });
+            }
+            continue;
+        }
+
+        // "NameError: name 'x' is not defined" / "KeyboardInterrupt"
+        if !line.starts_with(char::is_whitespace) && !line.is_empty() {
+            let (kind, message) = line.split_once(": ").unwrap_or((line, ""));
+            let is_name = kind
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+                && kind.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_lowercase());
+            if is_name {
+                exception = Some((kind.to_string(), message.to_string()));
+            }
+        }
+    }
+
+    let (kind, message) = exception?;
+    // Traceback は外側の呼び出しが先なので内側が先になるように並べ替える
+    frames.reverse();
+    Some(RuntimeError {
+        kind,
+        message,
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_go_panic() {
+        let output = "panic: runtime error: index out of range [5] with length 3
+
+goroutine 1 [running]:
+main.get(...)
+\t/work/section1/main.go:8
+main.main()
+\t/work/section1/main.go:12 +0x1d
+exit status 2
+";
+
+        let error = parse("go", output).unwrap();
+
+        assert_eq!(error.kind, "runtime error");
+        assert_eq!(error.message, "index out of range [5] with length 3");
+        assert_eq!(error.frames.len(), 2);
+        let frame = error.primary_frame().unwrap();
+        assert_eq!(frame.file, "/work/section1/main.go");
+        assert_eq!(frame.line, 8);
+        assert_eq!(frame.function.as_deref(), Some("main.get"));
+        assert_eq!(
+            error.signature(),
+            "runtime error: index out of range [N] with length N"
+        );
+    }
+
+    #[test]
+    fn test_parse_go_compile_error() {
+        let output = "# command-line-arguments\n./main.go:4:2: undefined: fmt\n./main.go:9:1: missing return\n";
+
+        let error = parse_go(output).unwrap();
+
+        assert_eq!(error.kind, "compile error");
+        assert_eq!(error.message, "undefined: fmt");
+        assert_eq!(
+            error.frames[0],
+            Frame {
+                file: "./main.go".to_string(),
+                line: 4,
+                column: Some(2),
+                function: None,
+            }
+        );
+        assert_eq!(error.frames.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_python_traceback() {
+        let output = r#"Traceback (most recent call last):
+  File "/work/a.py", line 5, in <module>
+    main()
+  File "/work/a.py", line 2, in main
+    print(x)
+          ^
+NameError: name 'x' is not defined
+"#;
+
+        let error = parse("py", output).unwrap();
+
+        assert_eq!(error.kind, "NameError");
+        assert_eq!(error.message, "name 'x' is not defined");
+        let frame = error.primary_frame().unwrap();
+        assert_eq!((frame.line, frame.function.as_deref()), (2, Some("main")));
+        assert_eq!(error.signature(), "NameError: name '_' is not defined");
+    }
+
+    #[test]
+    fn test_parse_python_syntax_error_and_library_frames() {
+        let syntax = "  File \"/work/a.py\", line 1\n    print(\n         ^\nSyntaxError: '(' was never closed\n";
+        let error = parse_python(syntax).unwrap();
+        assert_eq!(error.kind, "SyntaxError");
+        assert_eq!(error.primary_frame().unwrap().line, 1);
+
+        let library = r#"Traceback (most recent call last):
+  File "/work/a.py", line 3, in <module>
+    json.loads("x")
+  File "/usr/lib/python3.12/json/__init__.py", line 346, in loads
+    return _default_decoder.decode(s)
+json.decoder.JSONDecodeError: Expecting value: line 1 column 1 (char 0)
+"#;
+        let error = parse_python(library).unwrap();
+        assert_eq!(error.kind, "json.decoder.JSONDecodeError");
+        assert_eq!(error.primary_frame().unwrap().file, "/work/a.py");
+    }
+}
@@ -0,0 +1,220 @@
+use crate::history::{ExecutionRecord, HintKind, SolveRecord};
+use crate::link;
+use crate::mastery::Mastery;
+use crate::problem::ProblemHeader;
+use crate::snapshot::Snapshot;
+use crate::solve_time;
+use std::path::Path;
+use std::time::Duration;
+
+// 1つの問題に取り組んだ記録 (story コマンドで Markdown にまとめる)
+pub struct Story<'a> {
+    pub file_path: &'a str,
+    pub header: &'a ProblemHeader,
+    // 現在のコード
+    pub source: &'a str,
+    // コードブロックの言語名 (拡張子)
+    pub language: &'a str,
+    pub executions: &'a [ExecutionRecord],
+    pub snapshots: &'a [Snapshot],
+    pub solve: Option<&'a SolveRecord>,
+    pub hints: &'a [HintKind],
+}
+
+// 経過の1行 (保存と実行を時刻順に並べる)
+enum Event<'a> {
+    Save(&'a Snapshot),
+    Run(&'a ExecutionRecord),
+}
+
+impl Event<'_> {
+    fn at(&self) -> i64 {
+        match self {
+            Event::Save(snapshot) => snapshot.taken_at,
+            Event::Run(record) => record.executed_at,
+        }
+    }
+}
+
+fn time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+pub fn render(story: &Story) -> String {
+    let mut md = String::new();
+    let title = story.header.title.as_deref().unwrap_or(story.file_path);
+    md.push_str(&format!("# {}\n\n", title));
+
+    md.push_str(&format!("- ファイル: `{}`\n", story.file_path));
+    if let Some(url) = link::url_for(Path::new(story.file_path)) {
+        md.push_str(&format!("- リンク: [エディタで開く]({})\n", url));
+    }
+    if let Some(topic) = &story.header.topic {
+        md.push_str(&format!("- トピック: {}\n", topic));
+    }
+    match story.solve {
+        Some(solve) => md.push_str(&format!(
+            "- 解答時間: {} ({}に解決)\n",
+            solve_time::format_duration(Duration::from_secs(solve.active_seconds.max(0) as u64)),
+            time(solve.solved_at)
+        )),
+        None => md.push_str("- 解答時間: 未解決\n"),
+    }
+    let successes = story.executions.iter().filter(|r| r.success).count();
+    md.push_str(&format!(
+        "- 実行回数: {} (成功 {})\n",
+        story.executions.len(),
+        successes
+    ));
+    if story.solve.is_some() {
+        md.push_str(&format!(
+            "- 習熟度: {}\n",
+            Mastery::classify(story.hints).label()
+        ));
+    }
+
+    let mut events: Vec<Event> = story
+        .snapshots
+        .iter()
+        .map(Event::Save)
+        .chain(story.executions.iter().map(Event::Run))
+        .collect();
+    // 同じ時刻なら保存を先に並べる
+    events.sort_by_key(|event| (event.at(), matches!(event, Event::Run(_))));
+
+    if !events.is_empty() {
+        md.push_str("\n## 経過\n\n");
+    }
+    for event in &events {
+        match event {
+            Event::Save(snapshot) => md.push_str(&format!(
+                "- {} 保存 (+{} -{}行)\n",
+                time(snapshot.taken_at),
+                snapshot.delta.inserted.len(),
+                snapshot.delta.removed.len()
+            )),
+            Event::Run(record) => {
+                let result = if record.success {
+                    "✅ 成功".to_string()
+                } else {
+                    // 失敗したときは出力の1行目 (たいていエラーの要約)
+                    format!(
+                        "❌ 失敗: `{}`",
+                        record.output_preview.lines().next().unwrap_or_default()
+                    )
+                };
+                let score = record
+                    .score
+                    .map(|score| format!(" ({}/100)", score))
+                    .unwrap_or_default();
+                md.push_str(&format!(
+                    "- {} {}{}\n",
+                    time(record.executed_at),
+                    result,
+                    score
+                ));
+                if let Some(note) = &record.note {
+                    md.push_str(&format!("  - 📝 {}\n", note));
+                }
+            }
+        }
+    }
+
+    md.push_str(&format!(
+        "\n## 最終的なコード\n\n```{}\n{}\n```\n",
+        story.language,
+        story.source.trim_end()
+    ));
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use crate::snapshot::Delta;
+
+    fn run(executed_at: i64, success: bool, output: &str, note: Option<&str>) -> ExecutionRecord {
+        ExecutionRecord {
+            id: 0,
+            file_path: "/work/a.go".to_string(),
+            section: "section1-basics".to_string(),
+            success,
+            exit_code: Some(if success { 0 } else { 2 }),
+            duration_ms: 10,
+            executed_at,
+            output_preview: output.to_string(),
+            environment: Environment {
+                tool_version: None,
+                os: "linux x86_64".to_string(),
+                app_version: "0.1.0".to_string(),
+            },
+            note: note.map(str::to_string),
+            todos_remaining: None,
+            score: None,
+            content_hash: None,
+            error_signature: None,
+            language: None,
+            coverage: None,
+        }
+    }
+
+    #[test]
+    fn test_render_story() {
+        let header = ProblemHeader {
+            title: Some("Hello World".to_string()),
+            ..Default::default()
+        };
+        let executions = [
+            run(
+                110,
+                false,
+                "./a.go:4:2: undefined: fmt\nexit status 1",
+                None,
+            ),
+            run(130, true, "hello", Some("import を忘れていた")),
+        ];
+        let snapshots = [Snapshot {
+            taken_at: 120,
+            delta: Delta {
+                start: 1,
+                removed: vec![],
+                inserted: vec!["import \"fmt\"\n".to_string()],
+            },
+        }];
+        let solve = SolveRecord {
+            file_path: "/work/a.go".to_string(),
+            section: "section1-basics".to_string(),
+            first_modified_at: 100,
+            solved_at: 130,
+            active_seconds: 30,
+        };
+
+        let md = render(&Story {
+            file_path: "/work/a.go",
+            header: &header,
+            source: "package main\n",
+            language: "go",
+            executions: &executions,
+            snapshots: &snapshots,
+            solve: Some(&solve),
+            hints: &[HintKind::Hint],
+        });
+
+        assert!(md.starts_with("# Hello World\n"));
+        assert!(md.contains("- 実行回数: 2 (成功 1)\n"));
+        assert!(md.contains("- 習熟度: ヒントあり\n"));
+        let failed = md.find("❌ 失敗: `./a.go:4:2: undefined: fmt`").unwrap();
+        let saved = md.find("保存 (+1 -0行)").unwrap();
+        let passed = md.find("✅ 成功").unwrap();
+        assert!(failed < saved && saved < passed);
+        assert!(md.contains("  - 📝 import を忘れていた\n"));
+        assert!(md.ends_with("```go\npackage main\n```\n"));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::session::SessionRecord;
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+// 起動してからの実行結果の集計 (--summary で終了時に JSON として書き出す)
+#[derive(Debug, Default)]
+pub struct RunStats {
+    pub runs: usize,
+    pub successes: usize,
+    // 最後の実行が失敗したままのファイル
+    pub failing: BTreeSet<String>,
+    pub sessions: usize,
+    pub study_seconds: i64,
+    pub queue: QueueStats,
+}
+
+// 監視中の実行待ちの集計
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    // 実行待ちに積んだ数
+    pub queued: usize,
+    // 同じファイルのイベントとしてまとめた数
+    pub coalesced: usize,
+    // 実行待ちがいっぱいで捨てた数
+    pub dropped: usize,
+    pub max_depth: usize,
+}
+
+impl RunStats {
+    pub fn record_run(&mut self, file_path: &str, success: bool) {
+        self.runs += 1;
+        if success {
+            self.successes += 1;
+            self.failing.remove(file_path);
+        } else {
+            self.failing.insert(file_path.to_string());
+        }
+    }
+
+    pub fn record_session(&mut self, session: &SessionRecord) {
+        self.sessions += 1;
+        self.study_seconds += session.active_seconds();
+    }
+
+    // mode は "watch" または "grade"
+    pub fn to_json(&self, mode: &str, started_at: i64, ended_at: i64) -> Value {
+        json!({
+            "mode": mode,
+            "started_at": started_at,
+            "ended_at": ended_at,
+            "runs": self.runs,
+            "successes": self.successes,
+            "failures": self.runs - self.successes,
+            "failing_files": self.failing,
+            "session": {
+                "count": self.sessions,
+                "active_seconds": self.study_seconds,
+            },
+            "queue": {
+                "queued": self.queue.queued,
+                "coalesced": self.queue.coalesced,
+                "dropped": self.queue.dropped,
+                "max_depth": self.queue.max_depth,
+            },
+        })
+    }
+}
+
+pub fn write(path: &Path, summary: &Value) -> std::io::Result<()> {
+    std::fs::write(path, format!("{:#}\n", summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_json() {
+        let mut stats = RunStats::default();
+        stats.record_run("a.go", false);
+        stats.record_run("b.go", false);
+        // 後で成功したファイルは失敗に数えない
+        stats.record_run("a.go", true);
+        stats.record_session(&SessionRecord {
+            started_at: 100,
+            ended_at: 400,
+        });
+
+        let summary = stats.to_json("watch", 50, 500);
+
+        assert_eq!(summary["runs"], 3);
+        assert_eq!(summary["failures"], 2);
+        assert_eq!(summary["failing_files"], json!(["b.go"]));
+        assert_eq!(summary["session"]["active_seconds"], 300);
+    }
+}
@@ -0,0 +1,187 @@
+// Go / Python のソースをトークンに分ける (コメントは読み飛ばす)
+// 類似度チェックと構文チェックで共有する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,
+    Str,
+    Number,
+    Punct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub line: usize,
+}
+
+pub fn tokenize(source: &str, python: bool) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if (python && c == '#') || (!python && c == '/' && next == Some('/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if !python && c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' || c == '`' {
+            let start = i;
+            let start_line = line;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && c != '`' {
+                    i += 1;
+                } else if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token {
+                kind: TokenKind::Str,
+                text: chars[start..i.min(chars.len())].iter().collect(),
+                line: start_line,
+            });
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Ident,
+                text: chars[start..i].iter().collect(),
+                line,
+            });
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Number,
+                text: chars[start..i].iter().collect(),
+                line,
+            });
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Punct,
+                text: c.to_string(),
+                line,
+            });
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+// 構文チェックで使える名前 (これ以外はキーワード・識別子そのものとして探す)
+//   for-range   : Go の for ... range / Python の for ... in
+//   index-loop  : Go の for i := 0; ...; i++ (3つの節を持つ for)
+//   goroutine   : Go の go 文
+//   method      : Go のレシーバ付き関数 / Python のクラス内の def
+//   comprehension: Python の内包表記
+pub fn uses(source: &str, python: bool, construct: &str) -> bool {
+    let tokens = tokenize(source, python);
+    let is = |i: usize, text: &str| {
+        tokens
+            .get(i)
+            .is_some_and(|t| t.kind != TokenKind::Str && t.text == text)
+    };
+
+    // Python の for 文 (内包表記の for と区別する)
+    let starts_line = |i: usize| i == 0 || tokens[i - 1].line != tokens[i].line;
+
+    match construct {
+        "for-range" if python => (0..tokens.len()).any(|i| {
+            is(i, "for") && starts_line(i) && statement(&tokens, i, python).any(|t| t.text == "in")
+        }),
+        "for-range" => (0..tokens.len())
+            .any(|i| is(i, "for") && statement(&tokens, i, python).any(|t| t.text == "range")),
+        "index-loop" if !python => (0..tokens.len())
+            .any(|i| is(i, "for") && statement(&tokens, i, python).any(|t| t.text == ";")),
+        "goroutine" if !python => (0..tokens.len()).any(|i| is(i, "go")),
+        "method" if python => tokens
+            .iter()
+            .any(|t| t.text == "def" && t.line > 1 && indented(source, t.line)),
+        "method" => (0..tokens.len()).any(|i| is(i, "func") && is(i + 1, "(")),
+        "comprehension" if python => (0..tokens.len()).any(|i| {
+            is(i, "for")
+                && !starts_line(i)
+                && tokens[..i]
+                    .iter()
+                    .rev()
+                    .take_while(|t| t.line == tokens[i].line)
+                    .any(|t| t.text == "[" || t.text == "{" || t.text == "(")
+        }),
+        _ => tokens
+            .iter()
+            .any(|t| t.kind == TokenKind::Ident && t.text == construct),
+    }
+}
+
+// i 番目のトークンから文の終わり (Go は "{"、Python は ":") までのトークン
+fn statement(tokens: &[Token], i: usize, python: bool) -> impl Iterator<Item = &Token> {
+    let end = if python { ":" } else { "{" };
+    tokens[i + 1..]
+        .iter()
+        .take_while(move |t| !(t.kind == TokenKind::Punct && t.text == end))
+}
+
+fn indented(source: &str, line: usize) -> bool {
+    source
+        .lines()
+        .nth(line - 1)
+        .is_some_and(|l| l.starts_with(char::is_whitespace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE_LOOP: &str = "package main\n\nfunc main() {\n    // for i := 0; i < 3; i++\n    for _, v := range []int{1, 2} {\n        println(v)\n    }\n}\n";
+
+    const INDEX_LOOP: &str = "package main\n\nfunc main() {\n    xs := []int{1, 2}\n    for i := 0; i < len(xs); i++ {\n        println(xs[i], \"range\")\n    }\n}\n";
+
+    #[test]
+    fn test_go_loop_constructs() {
+        assert!(uses(RANGE_LOOP, false, "for-range"));
+        // コメント中のループは数えない
+        assert!(!uses(RANGE_LOOP, false, "index-loop"));
+
+        assert!(uses(INDEX_LOOP, false, "index-loop"));
+        // 文字列中の "range" は数えない
+        assert!(!uses(INDEX_LOOP, false, "for-range"));
+        assert!(!uses(INDEX_LOOP, false, "goto"));
+    }
+
+    #[test]
+    fn test_python_constructs() {
+        let source =
+            "class A:\n    def total(self, xs):\n        return sum([x * 2 for x in xs])\n";
+
+        assert!(uses(source, true, "method"));
+        assert!(uses(source, true, "comprehension"));
+        assert!(!uses(source, true, "for-range"));
+        assert!(uses(source, true, "sum"));
+    }
+}
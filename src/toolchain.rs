@@ -0,0 +1,225 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// "1.22.1" のようなバージョン番号
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u32>);
+
+impl Version {
+    // 文字列中で最初に現れる "数字.数字" 形式の部分を読む
+    // 例: "go version go1.22.1 linux/amd64" -> 1.22.1, "Python 3.12.2" -> 3.12.2
+    pub fn find_in(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+        let mut start = 0;
+        while start < bytes.len() {
+            if bytes[start].is_ascii_digit() {
+                let end = text[start..]
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .map(|i| start + i)
+                    .unwrap_or(text.len());
+                let candidate = text[start..end].trim_end_matches('.');
+                if candidate.contains('.') {
+                    return Self::parse(candidate);
+                }
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        None
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let parts = text
+            .trim()
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        (!parts.is_empty()).then_some(Self(parts))
+    }
+}
+
+impl Ord for Version {
+    // "1.21" と "1.21.0" は等しいとみなす
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| {
+                let a = self.0.get(i).copied().unwrap_or(0);
+                let b = other.0.get(i).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+}
+
+// "go>=1.21" / "python >=3.10, <3.13" のようなバージョン要件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    raw: String,
+    constraints: Vec<(Op, Version)>,
+}
+
+impl Requirement {
+    pub fn parse(text: &str) -> Option<Self> {
+        // 先頭の言語名 (go / python) は読み飛ばす
+        let body = text.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace());
+
+        let constraints = body
+            .split(',')
+            .map(|part| {
+                let part = part.trim();
+                let (op, rest) = [
+                    (">=", Op::Ge),
+                    ("<=", Op::Le),
+                    ("==", Op::Eq),
+                    (">", Op::Gt),
+                    ("<", Op::Lt),
+                    ("=", Op::Eq),
+                ]
+                .iter()
+                .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (*op, rest)))?;
+                Some((op, Version::parse(rest)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!constraints.is_empty()).then(|| Self {
+            raw: text.trim().to_string(),
+            constraints,
+        })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.constraints.iter().all(|(op, required)| match op {
+            Op::Ge => version >= required,
+            Op::Gt => version > required,
+            Op::Le => version <= required,
+            Op::Lt => version < required,
+            Op::Eq => version == required,
+        })
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+// learn:interpreter で指定されたコマンドと、見つからない場合に順に試すコマンド
+// 例: ["python3.12"], "python" -> python3.12, python3, python
+pub fn fallback_chain(requested: &[String], default: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        if !name.is_empty() && !chain.iter().any(|c| c == name) {
+            chain.push(name.to_string());
+        }
+    };
+    for name in requested {
+        // 末尾のバージョンを ".12" → "3" の順に外していく
+        let mut name = name.as_str();
+        loop {
+            push(name);
+            let shorter = match name.rsplit_once('.') {
+                Some((base, minor)) if minor.chars().all(|c| c.is_ascii_digit()) => base,
+                _ => name.trim_end_matches(|c: char| c.is_ascii_digit()),
+            };
+            if shorter == name {
+                break;
+            }
+            name = shorter;
+        }
+    }
+    push(default);
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_version_in_tool_output() {
+        assert_eq!(
+            Version::find_in("go version go1.22.1 linux/amd64"),
+            Version::parse("1.22.1")
+        );
+        assert_eq!(Version::find_in("Python 3.12.2"), Version::parse("3.12.2"));
+        assert_eq!(Version::find_in("no version here 42"), None);
+    }
+
+    #[test]
+    fn test_version_ordering_ignores_trailing_zero() {
+        assert_eq!(
+            Version::parse("1.21")
+                .unwrap()
+                .cmp(&Version::parse("1.21.0").unwrap()),
+            Ordering::Equal
+        );
+        assert!(Version::parse("1.9").unwrap() < Version::parse("1.21").unwrap());
+    }
+
+    #[test]
+    fn test_requirement_matches_range() {
+        let requirement = Requirement::parse("python >=3.10, <3.13").unwrap();
+
+        assert!(requirement.matches(&Version::parse("3.12.2").unwrap()));
+        assert!(!requirement.matches(&Version::parse("3.9.18").unwrap()));
+        assert!(!requirement.matches(&Version::parse("3.13.0").unwrap()));
+        assert!(
+            Requirement::parse("go==1.21")
+                .unwrap()
+                .matches(&Version::parse("1.21.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_requirement_parse_errors() {
+        assert_eq!(Requirement::parse("go"), None);
+        assert_eq!(Requirement::parse("go>=one"), None);
+    }
+
+    #[test]
+    fn test_fallback_chain() {
+        assert_eq!(
+            fallback_chain(&["python3.12".to_string()], "python"),
+            ["python3.12", "python3", "python"]
+        );
+        assert_eq!(
+            fallback_chain(&["pypy3".to_string(), "python3.8".to_string()], "python"),
+            ["pypy3", "pypy", "python3.8", "python3", "python"]
+        );
+        assert_eq!(fallback_chain(&[], "go"), ["go"]);
+    }
+}
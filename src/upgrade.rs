@@ -0,0 +1,128 @@
+use crate::assets;
+use crate::history::{self, HistoryStore, ProblemVersion};
+use std::io;
+use std::path::Path;
+use tokio::process::Command;
+
+// カリキュラムの問題の版 (内容のハッシュの先頭 8 文字)
+// init / restore-problem で書き出したときの版と内容を履歴 DB に残しておき、
+// 新しいバイナリでカリキュラムの問題が変わっていれば upgrade-problem で解答に取り込めるようにする
+
+pub fn problem_version(template: &[u8]) -> ProblemVersion {
+    let template = String::from_utf8_lossy(template).into_owned();
+    ProblemVersion {
+        version: history::content_hash(&template)[..8].to_string(),
+        template,
+    }
+}
+
+// dir 以下に書き出されたまま (カリキュラムと同じ内容) の問題の版を記録する
+// 記録が変わった問題の数を返す (解答を書き始めた問題は元の版が分からないので記録しない)
+pub fn record_extracted(store: &HistoryStore, dir: &Path, now: i64) -> rusqlite::Result<usize> {
+    let mut recorded = 0;
+    for (path, contents) in assets::go_curriculum_files() {
+        let path = dir.join(path);
+        if std::fs::read(&path).ok().as_deref() != Some(contents) {
+            continue;
+        }
+        let file_path = std::path::absolute(&path)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let version = problem_version(contents);
+        if store.problem_version(&file_path)?.as_ref() != Some(&version) {
+            store.record_problem_version(&file_path, &version, now)?;
+            recorded += 1;
+        }
+    }
+    Ok(recorded)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub text: String,
+    // 衝突した箇所の数 (衝突した箇所には <<<<<<< / >>>>>>> の印が入る)
+    pub conflicts: usize,
+}
+
+// 解答 (mine) に、元の版 (base) から新しい版 (latest) への変更を 3-way マージで取り込む
+// マージは git merge-file に任せる
+pub async fn merge(mine: &str, base: &str, latest: &str) -> io::Result<Merge> {
+    // 監視しているディレクトリの外に置く (作成で実行が始まらないように)
+    let dir = std::env::temp_dir();
+    let file = |name: &str| dir.join(format!("learn-merge-{}-{}", std::process::id(), name));
+    let files = [file("mine"), file("base"), file("latest")];
+    for (path, text) in files.iter().zip([mine, base, latest]) {
+        tokio::fs::write(path, text).await?;
+    }
+
+    let output = Command::new("git")
+        .args(["merge-file", "-p"])
+        .args(["-L", "解答", "-L", "元の版", "-L", "新しい版"])
+        .args(&files)
+        .output()
+        .await;
+    for path in &files {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+
+    // 終了コードは衝突の数 (エラーのときは負の値)
+    let output = output?;
+    match output.status.code() {
+        Some(conflicts @ 0..=127) => Ok(Merge {
+            text: String::from_utf8_lossy(&output.stdout).into_owned(),
+            conflicts: conflicts as usize,
+        }),
+        _ => Err(io::Error::other(format!(
+            "git merge-file に失敗しました: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_merge_keeps_solution_and_takes_template_changes() {
+        let base = "// Title: 合計\npackage main\n\nfunc sum() int {\n\t// TODO\n\treturn 0\n}\n";
+        let latest =
+            "// Title: 配列の合計\npackage main\n\nfunc sum() int {\n\t// TODO\n\treturn 0\n}\n";
+        let mine = "// Title: 合計\npackage main\n\nfunc sum() int {\n\treturn 1 + 2\n}\n";
+
+        let merged = merge(mine, base, latest).await.unwrap();
+
+        assert_eq!(merged.conflicts, 0);
+        assert_eq!(
+            merged.text,
+            "// Title: 配列の合計\npackage main\n\nfunc sum() int {\n\treturn 1 + 2\n}\n"
+        );
+
+        let latest = base.replace("return 0", "return -1");
+        let merged = merge(mine, base, &latest).await.unwrap();
+        assert_eq!(merged.conflicts, 1);
+        assert!(merged.text.contains("<<<<<<< 解答"));
+        assert!(merged.text.contains(">>>>>>> 新しい版"));
+    }
+
+    #[test]
+    fn test_record_extracted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = HistoryStore::open_in_memory().unwrap();
+        assets::extract_go_curriculum(tmp.path(), false).unwrap();
+        let solution = tmp
+            .path()
+            .join("section1-basics")
+            .join("problem01_variables.go");
+        std::fs::write(&solution, "// my solution\n").unwrap();
+
+        let recorded = record_extracted(&store, tmp.path(), 100).unwrap();
+
+        assert_eq!(recorded, assets::go_curriculum_files().len() - 1);
+        let file_path = |path: &Path| std::path::absolute(path).unwrap().display().to_string();
+        assert_eq!(store.problem_version(&file_path(&solution)).unwrap(), None);
+        // 2回目は変わっていないので記録しない
+        assert_eq!(record_extracted(&store, tmp.path(), 200).unwrap(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::files;
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// integrations vscode init: 課題のディレクトリに VS Code の設定 (.vscode/) を書き出す
+// タスクから監視・集中モード・再実行・採点を起動でき、launch.json で開いているファイルをデバッグできる
+
+// 実行ファイル名 (PATH にある前提)
+const PROGRAM: &str = env!("CARGO_PKG_NAME");
+
+fn task(label: &str, args: &[&str]) -> Value {
+    json!({
+        "label": label,
+        "type": "process",
+        "command": PROGRAM,
+        "args": args,
+        "problemMatcher": [],
+        "presentation": {"reveal": "always", "panel": "dedicated"},
+    })
+}
+
+pub fn tasks() -> Value {
+    let mut watch = task("学習: 監視を開始", &["--dir", "${workspaceFolder}"]);
+    watch["isBackground"] = json!(true);
+    // Ctrl+Shift+B で開いているファイルの集中モードを始める
+    let mut focus = task("学習: このファイルに集中", &["focus", "${file}"]);
+    focus["group"] = json!({"kind": "build", "isDefault": true});
+    focus["isBackground"] = json!(true);
+    let mut rerun = task("学習: 最後のファイルを再実行", &["rerun"]);
+    rerun["presentation"] = json!({"reveal": "silent"});
+    json!({
+        "version": "2.0.0",
+        "tasks": [
+            watch,
+            focus,
+            rerun,
+            task("学習: 採点", &["grade", "${workspaceFolder}"]),
+        ],
+    })
+}
+
+pub fn launch() -> Value {
+    json!({
+        "version": "0.2.0",
+        "configurations": [
+            {
+                "name": "Go: このファイルをデバッグ",
+                "type": "go",
+                "request": "launch",
+                "mode": "debug",
+                "program": "${file}",
+            },
+            {
+                "name": "Python: このファイルをデバッグ",
+                "type": "debugpy",
+                "request": "launch",
+                "program": "${file}",
+                "console": "integratedTerminal",
+            },
+        ],
+    })
+}
+
+// 仮想環境などのディレクトリは VS Code でも監視しない (大量のファイルで監視の上限に達しないように)
+// 自動保存は保存のたびに実行されるので、フォーカスを外したときだけにする
+pub fn settings() -> Value {
+    let exclude: serde_json::Map<String, Value> = files::ENVIRONMENT_DIRS
+        .iter()
+        .map(|dir| (format!("**/{}/**", dir), json!(true)))
+        .collect();
+    json!({
+        "files.watcherExclude": exclude,
+        "files.autoSave": "onFocusChange",
+    })
+}
+
+pub fn extensions() -> Value {
+    json!({"recommendations": ["golang.go", "ms-python.python", "ms-python.debugpy"]})
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct InitSummary {
+    pub written: Vec<PathBuf>,
+    // 既にあったので書き出さなかったファイル
+    pub skipped: Vec<PathBuf>,
+}
+
+// dir/.vscode/ に書き出す (force でなければ既存のファイルは上書きしない)
+pub fn init(dir: &Path, force: bool) -> io::Result<InitSummary> {
+    let vscode = dir.join(".vscode");
+    fs::create_dir_all(&vscode)?;
+    let mut summary = InitSummary::default();
+    for (name, contents) in [
+        ("tasks.json", tasks()),
+        ("launch.json", launch()),
+        ("settings.json", settings()),
+        ("extensions.json", extensions()),
+    ] {
+        let path = vscode.join(name);
+        if path.exists() && !force {
+            summary.skipped.push(path);
+            continue;
+        }
+        fs::write(&path, format!("{:#}\n", contents))?;
+        summary.written.push(path);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_keeps_existing_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let vscode = tmp.path().join(".vscode");
+        fs::create_dir_all(&vscode).unwrap();
+        fs::write(vscode.join("settings.json"), "{}").unwrap();
+
+        let summary = init(tmp.path(), false).unwrap();
+
+        assert_eq!(summary.written.len(), 3);
+        assert_eq!(summary.skipped, [vscode.join("settings.json")]);
+        assert_eq!(
+            fs::read_to_string(vscode.join("settings.json")).unwrap(),
+            "{}"
+        );
+        let tasks: Value =
+            serde_json::from_str(&fs::read_to_string(vscode.join("tasks.json")).unwrap()).unwrap();
+        assert_eq!(tasks["tasks"][1]["args"], json!(["focus", "${file}"]));
+
+        let summary = init(tmp.path(), true).unwrap();
+        assert_eq!(summary.written.len(), 4);
+        let settings: Value =
+            serde_json::from_str(&fs::read_to_string(vscode.join("settings.json")).unwrap())
+                .unwrap();
+        assert_eq!(settings["files.watcherExclude"]["**/.venv/**"], json!(true));
+    }
+}
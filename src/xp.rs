@@ -0,0 +1,85 @@
+use std::fmt;
+
+// 経験値 (XP) とレベル
+// 問題を初めて解くと難易度に応じた XP を得る。失敗せず、模範解答も見ずに解けたら一発で成功したボーナスが付く
+// 付与した XP は理由ごとに履歴DBの xp_events に記録する (stats xp で確認できる)
+
+// 難易度1あたりの XP (難易度の書かれていない問題と難易度0の問題は難易度1とみなす)
+// 難易度の段階 (1〜5 や 1〜10) は問題を書く側が決めるので、上限は設けない (u8 なので溢れない)
+const XP_PER_DIFFICULTY: i64 = 10;
+
+pub fn solve_xp(difficulty: Option<u8>) -> i64 {
+    XP_PER_DIFFICULTY * i64::from(difficulty.unwrap_or(1).max(1))
+}
+
+// 一発で成功したボーナス (解答の XP の半分)
+pub fn first_try_bonus(difficulty: Option<u8>) -> i64 {
+    solve_xp(difficulty) / 2
+}
+
+// レベル L になるのに必要な累計 XP は 50 * L * (L - 1) (Lv.2 は 100、Lv.3 は 300、Lv.4 は 600)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub level: u32,
+    pub total_xp: i64,
+    // 次のレベルまでに必要な XP
+    pub remaining: i64,
+}
+
+fn threshold(level: u32) -> i64 {
+    50 * i64::from(level) * (i64::from(level) - 1)
+}
+
+impl Level {
+    pub fn from_xp(total_xp: i64) -> Self {
+        let mut level = 1;
+        while threshold(level + 1) <= total_xp {
+            level += 1;
+        }
+        Self {
+            level,
+            total_xp,
+            remaining: threshold(level + 1) - total_xp.max(0),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Lv.{} (XP {}, 次のレベルまで {})",
+            self.level, self.total_xp, self.remaining
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xp_and_level() {
+        assert_eq!(solve_xp(None), 10);
+        assert_eq!(solve_xp(Some(3)), 30);
+        assert_eq!(solve_xp(Some(9)), 90);
+        assert_eq!(solve_xp(Some(0)), 10);
+        assert_eq!(first_try_bonus(Some(3)), 15);
+
+        assert_eq!(Level::from_xp(0).level, 1);
+        assert_eq!(Level::from_xp(99).level, 1);
+        assert_eq!(Level::from_xp(100).level, 2);
+        assert_eq!(
+            Level::from_xp(420),
+            Level {
+                level: 3,
+                total_xp: 420,
+                remaining: 180,
+            }
+        );
+        assert_eq!(
+            Level::from_xp(420).to_string(),
+            "Lv.3 (XP 420, 次のレベルまで 180)"
+        );
+    }
+}